@@ -1,25 +1,19 @@
 // benches/benchmark.rs
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
-use opti_radar::target_processor::{find_targets, ransac_fit_lines, levenberg_marquardt_optimize, Line};
-use opti_radar::data_generator::generate_data;
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use opti_radar::target_processor::{find_targets, find_targets_with_config, find_targets_jlinkage, find_targets_clustered, find_targets_batch, ransac_fit_lines, levenberg_marquardt_optimize, levenberg_marquardt_optimize_detailed, dogleg_optimize, linear_triangulate, FindTargetsConfig, Line, Line32, LmConfig, RansacConfig, ResidualModel};
+use opti_radar::line_soa::LineSoa;
+use opti_radar::data_generator::{generate_data_from_config, GeneratorConfig, ScenarioPreset};
+use opti_radar::evaluation::match_targets;
+use opti_radar::locator::TargetLocator;
 use nalgebra::{Point3, Vector3};
-use rand::{thread_rng, Rng};
+use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
+use std::ops::ControlFlow;
 
 /// 基准测试函数，用于测量 find_targets 的性能。
 fn bench_find_targets(c: &mut Criterion) {
     // 数据生成只进行一次，避免在基准测试循环中重复执行
-    let (_, all_data) = generate_data(
-        10,            // num_targets
-        (-500.0, 500.0),
-        (-500.0, 500.0),
-        (50.0, 150.0),
-        (5, 10),       // num_stations_per_target_range
-        (100.0, 500.0),
-        (10.0, 30.0),
-        1.0,           // pos_noise_std
-        0.5,           // alt_noise_std
-        0.005,         // angle_noise_std
-    );
+    // 默认参数：10 个目标，每个目标 5~10 个测量站
+    let (_, all_data) = generate_data_from_config(&GeneratorConfig::default());
 
     let threshold = 20.0;
     let min_lines = 3;
@@ -34,37 +28,313 @@ fn bench_find_targets(c: &mut Criterion) {
     });
 }
 
+/// 测量数 × 目标数的扩展性：测量数 100 / 1000 / 10000，目标数 1 / 10 / 50，
+/// 每个目标的测量站数为 测量数 / 目标数（至少 3，因此 50 个目标时实际为 150 条测量），
+/// 基准名称中为实际测量数。数据与 RANSAC 均使用固定种子，不同运行之间可比。
+fn bench_find_targets_scaling(c: &mut Criterion) {
+    let mut group = c.benchmark_group("find_targets_scaling");
+    group.sample_size(10);
+    for num_targets in [1, 10, 50] {
+        for num_measurements in [100, 1_000, 10_000] {
+            let per_target = (num_measurements / num_targets).max(3);
+            let generator = GeneratorConfig::builder()
+                .num_targets(num_targets)
+                .target_x_range(-2000.0, 2000.0)
+                .target_y_range(-2000.0, 2000.0)
+                .num_stations_per_target_range(per_target, per_target)
+                .seed(42)
+                .build()
+                .unwrap();
+            let (_, data) = generate_data_from_config(&generator);
+            let mut config = FindTargetsConfig::new(20.0, 3);
+            config.ransac.seed = Some(1);
+
+            group.throughput(Throughput::Elements(data.len() as u64));
+            group.bench_with_input(
+                BenchmarkId::new(format!("{}_targets", num_targets), data.len()),
+                &data,
+                |b, data| {
+                    b.iter(|| black_box(find_targets_with_config(black_box(data), &config)));
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+/// 批量处理 100 帧合成数据（每帧 5 个目标 × 20 个测量站）；启用 `parallel` 特性时比较不同线程数
+fn bench_find_targets_batch(c: &mut Criterion) {
+    let frames: Vec<_> = (0..100u64)
+        .map(|frame| {
+            let generator = GeneratorConfig::builder()
+                .num_targets(5)
+                .num_stations_per_target_range(20, 20)
+                .seed(1000 + frame)
+                .build()
+                .unwrap();
+            generate_data_from_config(&generator).1
+        })
+        .collect();
+    let mut config = FindTargetsConfig::new(20.0, 3);
+    config.ransac.seed = Some(1);
+
+    let mut group = c.benchmark_group("find_targets_batch");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(frames.len() as u64));
+    #[cfg(feature = "parallel")]
+    {
+        let available = std::thread::available_parallelism().map_or(1, |n| n.get());
+        for threads in [1, 2, 4, 8].into_iter().filter(|&threads| threads <= available) {
+            let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+            group.bench_with_input(BenchmarkId::new("threads", threads), &frames, |b, frames| {
+                b.iter(|| pool.install(|| black_box(find_targets_batch(black_box(frames), &config))));
+            });
+        }
+    }
+    #[cfg(not(feature = "parallel"))]
+    group.bench_with_input(BenchmarkId::new("sequential", frames.len()), &frames, |b, frames| {
+        b.iter(|| black_box(find_targets_batch(black_box(frames), &config)));
+    });
+    group.finish();
+}
+
+/// 大规模输入：10 个目标 × 2000 个测量站（20000 条测量），RANSAC 上限 500 次。
+/// 逐轮提取时剩余光线以索引传入 RANSAC，不再逐轮复制。
+fn bench_find_targets_large(c: &mut Criterion) {
+    let generator = GeneratorConfig::builder()
+        .num_targets(10)
+        .target_x_range(-2000.0, 2000.0)
+        .target_y_range(-2000.0, 2000.0)
+        .num_stations_per_target_range(2000, 2000)
+        .seed(42)
+        .build()
+        .unwrap();
+    let (_, data) = generate_data_from_config(&generator);
+    let mut config = FindTargetsConfig::new(20.0, 3);
+    config.ransac.seed = Some(1);
+    config.ransac.max_iterations = 500;
+
+    let mut group = c.benchmark_group("find_targets_large");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(data.len() as u64));
+    group.bench_function(BenchmarkId::new("10_targets", data.len()), |b| {
+        b.iter(|| black_box(find_targets_with_config(black_box(&data), &config)));
+    });
+    group.finish();
+}
+
+/// 进度回调的开销：2000 条测量（5 个目标 × 400 个测量站），不设回调与每 100 次迭代调用一次空回调的对比
+fn bench_progress_overhead(c: &mut Criterion) {
+    let generator = GeneratorConfig::builder()
+        .num_targets(5)
+        .num_stations_per_target_range(400, 400)
+        .seed(42)
+        .build()
+        .unwrap();
+    let (_, data) = generate_data_from_config(&generator);
+    let mut config = FindTargetsConfig::new(20.0, 3);
+    config.ransac.seed = Some(1);
+    let with_callback = config.clone().with_progress(|_| ControlFlow::Continue(()));
+
+    let mut group = c.benchmark_group("progress_overhead");
+    group.sample_size(20);
+    group.throughput(Throughput::Elements(data.len() as u64));
+    for (name, config) in [("none", &config), ("noop_callback", &with_callback)] {
+        group.bench_with_input(BenchmarkId::new(name, data.len()), config, |b, config| {
+            b.iter(|| black_box(find_targets_with_config(black_box(&data), config)));
+        });
+    }
+    group.finish();
+}
+
+/// 50000 条测量（25 个目标 × 2000 个测量站，固定种子）下内点统计逐条检验与网格索引的对比，两者结果相同
+fn bench_spatial_index(c: &mut Criterion) {
+    let generator = GeneratorConfig::builder()
+        .num_targets(25)
+        .target_x_range(-2000.0, 2000.0)
+        .target_y_range(-2000.0, 2000.0)
+        .num_stations_per_target_range(2000, 2000)
+        .seed(42)
+        .build()
+        .unwrap();
+    let (_, data) = generate_data_from_config(&generator);
+    let mut config = FindTargetsConfig::new(20.0, 3);
+    config.ransac.seed = Some(1);
+
+    let mut group = c.benchmark_group("spatial_index");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(data.len() as u64));
+    for (name, cutoff) in [("brute_force", usize::MAX), ("grid", 0)] {
+        config.ransac.spatial_index_cutoff = cutoff;
+        group.bench_with_input(BenchmarkId::new(name, data.len()), &config, |b, config| {
+            b.iter(|| black_box(find_targets_with_config(black_box(&data), config)));
+        });
+    }
+    group.finish();
+}
+
+/// 增量定位与批处理的对比：已求解的 10 个目标之外新到 5 条测量后重新求解。
+fn bench_incremental_locator(c: &mut Criterion) {
+    let generator = GeneratorConfig::builder().seed(3).build().unwrap();
+    let (_, all_data) = generate_data_from_config(&generator);
+    let mut config = FindTargetsConfig::new(20.0, 3);
+    config.ransac.seed = Some(1);
+
+    let (previous, delta) = all_data.split_at(all_data.len() - 5);
+    let mut locator = TargetLocator::new(config.clone());
+    for m in previous {
+        locator.add_measurement(m.clone()).unwrap();
+    }
+    locator.solve();
+
+    c.bench_function("find_targets_batch_after_5_new", |b| {
+        b.iter(|| {
+            let located = find_targets_with_config(black_box(&all_data), black_box(&config));
+            black_box(located);
+        });
+    });
+    c.bench_function("target_locator_incremental_after_5_new", |b| {
+        b.iter_batched(
+            || locator.clone(),
+            |mut locator| {
+                for m in delta {
+                    locator.add_measurement(m.clone()).unwrap();
+                }
+                black_box(locator.solve().len());
+            },
+            BatchSize::SmallInput,
+        );
+    });
+}
+
 /// 基准测试函数，用于测量 ransac_fit_lines 的性能。
 fn bench_ransac(c: &mut Criterion) {
-    // 准备测试数据
+    // 准备测试数据：10 条交于目标的光线（带少量噪声）+ 5 条随机方向的杂波
+    let mut rng = StdRng::seed_from_u64(1);
+    let target = Point3::new(10.0, 20.0, 30.0);
     let mut lines = Vec::new();
     for _ in 0..10 {
-        let start = Point3::new(0.0, 0.0, 0.0);
-        let direction = Vector3::new(1.0, 0.0, 0.0).normalize();
-        lines.push(Line { start, direction });
+        let start = Point3::new(rng.gen_range(-100.0..100.0), rng.gen_range(-100.0..100.0), 0.0);
+        let aim = target + Vector3::new(rng.gen_range(-0.2..0.2), rng.gen_range(-0.2..0.2), rng.gen_range(-0.2..0.2));
+        lines.push(Line::new(start, aim - start));
     }
     for _ in 0..5 {
-        let start = Point3::new(50.0, 50.0, 50.0);
-        let direction = Vector3::new(0.0, 1.0, 0.0).normalize();
-        lines.push(Line { start, direction });
+        let start = Point3::new(rng.gen_range(-100.0..100.0), rng.gen_range(-100.0..100.0), 0.0);
+        let direction = Vector3::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0), rng.gen_range(0.1..1.0));
+        lines.push(Line::new(start, direction));
     }
-    let ransac_iterations = 100;
-    let ransac_threshold = 1.0;
-    let min_lines = 3;
+    let mut config = RansacConfig::new(100, 1.0, 3);
+    config.seed = Some(1);
+
+    // 打印一次实际迭代次数和内点数，便于核对自适应终止
+    let (result, stats) = ransac_fit_lines(&lines, &config);
+    println!(
+        "ransac_fit_lines: iterations_run = {}, inliers = {:?}",
+        stats.iterations_run,
+        result.map(|(_, inliers)| inliers.len())
+    );
 
     c.bench_function("ransac_fit_lines", |b| {
         b.iter(|| {
-            let result = ransac_fit_lines(
-                black_box(&lines),
-                black_box(ransac_iterations),
-                black_box(ransac_threshold),
-                black_box(min_lines),
-            );
+            let result = ransac_fit_lines(black_box(&lines), black_box(&config));
+            black_box(result);
+        });
+    });
+}
+
+/// 大规模输入下的 RANSAC：3000 条光线，其中 90% 为杂波，迭代跑满上限。
+/// 分别以默认特性和 `--features parallel` 运行可比较并行加速比。
+fn bench_ransac_large(c: &mut Criterion) {
+    let mut rng = thread_rng();
+    let target = Point3::new(10.0, 20.0, 30.0);
+    let mut lines = Vec::new();
+    for _ in 0..300 {
+        let start = Point3::new(rng.gen_range(-500.0..500.0), rng.gen_range(-500.0..500.0), 0.0);
+        lines.push(Line::new(start, target - start));
+    }
+    for _ in 0..2700 {
+        let start = Point3::new(rng.gen_range(-500.0..500.0), rng.gen_range(-500.0..500.0), 0.0);
+        let direction = Vector3::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0), rng.gen_range(0.1..1.0));
+        lines.push(Line::new(start, direction));
+    }
+    let mut config = RansacConfig::new(1000, 1.0, 3);
+    config.seed = Some(1);
+
+    c.bench_function("ransac_fit_lines_3000_lines", |b| {
+        b.iter(|| {
+            let result = ransac_fit_lines(black_box(&lines), black_box(&config));
+            black_box(result);
+        });
+    });
+}
+
+/// 10000 条光线、固定 50 次迭代的 RANSAC，内点统计为主要开销。
+/// 以 `--features parallel` 运行时超过 `parallel_cutoff` 的内点统计会并行执行。
+fn bench_inliers_10000(c: &mut Criterion) {
+    let mut rng = thread_rng();
+    let target = Point3::new(10.0, 20.0, 30.0);
+    let lines: Vec<_> = (0..10_000)
+        .map(|i| {
+            let start = Point3::new(rng.gen_range(-500.0..500.0), rng.gen_range(-500.0..500.0), 0.0);
+            if i % 10 == 0 {
+                Line::new(start, target - start)
+            } else {
+                Line::new(start, Vector3::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0), rng.gen_range(0.1..1.0)))
+            }
+        })
+        .collect();
+    let mut config = RansacConfig::new(50, 1.0, 3);
+    config.seed = Some(1);
+    config.confidence = 1.0; // 跑满迭代次数
+
+    c.bench_function("ransac_inliers_10000_lines", |b| {
+        b.iter(|| {
+            let result = ransac_fit_lines(black_box(&lines), black_box(&config));
             black_box(result);
         });
     });
 }
 
+/// 100000 条光线到一个候选点的残差距离：逐条对 `Line` 计算与用 `LineSoa` 按列计算，
+/// 分别对全部光线（连续索引）和三分之二的光线（零散索引，如贪心提取移除部分光线之后）计算。
+/// 以 `--features simd` 运行时后者使用 f64x4 显式 SIMD；连续索引的标量列式循环编译器本身已能自动向量化，
+/// 显式 SIMD 的收益主要在零散索引上。
+fn bench_residuals_100000(c: &mut Criterion) {
+    let mut rng = StdRng::seed_from_u64(11);
+    let lines: Vec<Line> = (0..100_000)
+        .map(|_| {
+            let start = Point3::new(rng.gen_range(-5000.0..5000.0), rng.gen_range(-5000.0..5000.0), 0.0);
+            Line::new(start, Vector3::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0), rng.gen_range(0.1..1.0)))
+        })
+        .collect();
+    let soa = LineSoa::new(&lines);
+    let point = Point3::new(100.0, -200.0, 300.0);
+
+    let mut group = c.benchmark_group("residuals_100000");
+    for (name, indices) in [
+        ("all", (0..lines.len()).collect::<Vec<_>>()),
+        ("two_thirds", (0..lines.len()).filter(|i| i % 3 != 0).collect()),
+    ] {
+        group.throughput(Throughput::Elements(indices.len() as u64));
+        group.bench_with_input(BenchmarkId::new("line_by_line", name), &indices, |b, indices| {
+            let mut out = Vec::with_capacity(indices.len());
+            b.iter(|| {
+                out.clear();
+                out.extend(indices.iter().map(|&i| black_box(&lines)[i].distance_to_point(&point)));
+                black_box(&out);
+            });
+        });
+        group.bench_with_input(BenchmarkId::new("soa", name), &indices, |b, indices| {
+            let mut out = Vec::with_capacity(indices.len());
+            b.iter(|| {
+                black_box(&soa).residuals(indices, &point, ResidualModel::Metric, false, &mut out);
+                black_box(&out);
+            });
+        });
+    }
+    group.finish();
+}
+
 /// 基准测试函数，用于测量 levenberg_marquardt_optimize 的性能。
 fn bench_lm(c: &mut Criterion) {
     // 准备一组基准数据，模拟RANSAC筛选出的内点
@@ -80,7 +350,7 @@ fn bench_lm(c: &mut Criterion) {
             rng.gen_range(25.0..35.0),
         );
         let direction = (true_position - start).normalize();
-        lines.push(Line { start, direction });
+        lines.push(Line::new(start, direction));
     }
 
     let initial_guess = Point3::new(9.0, 19.0, 29.0);
@@ -98,8 +368,153 @@ fn bench_lm(c: &mut Criterion) {
             black_box(result);
         });
     });
+
+    // 200 条光线，体现法方程累加随光线数量的扩展性
+    let mut many_lines = Vec::new();
+    for _ in 0..200 {
+        let start = Point3::new(
+            rng.gen_range(-100.0..100.0),
+            rng.gen_range(-100.0..100.0),
+            rng.gen_range(0.0..5.0),
+        );
+        many_lines.push(Line::new(start, true_position - start));
+    }
+    c.bench_function("levenberg_marquardt_optimize_200_lines", |b| {
+        b.iter(|| {
+            let result = levenberg_marquardt_optimize(
+                black_box(&many_lines),
+                black_box(initial_guess),
+                black_box(iterations),
+                black_box(initial_lambda),
+            );
+            black_box(result);
+        });
+    });
+
+    // 同一场景下的闭式线性三角定位，用于与 LM 对比
+    c.bench_function("linear_triangulate", |b| {
+        b.iter(|| {
+            let result = linear_triangulate(black_box(&lines));
+            black_box(result);
+        });
+    });
+}
+
+/// 同一 10 条光线场景（同 `bench_lm`，固定种子）下 LM 与 dogleg 的对比
+fn bench_lm_vs_dogleg(c: &mut Criterion) {
+    let mut rng = StdRng::seed_from_u64(7);
+    let true_position = Point3::new(10.0, 20.0, 30.0);
+    let lines: Vec<Line> = (0..10)
+        .map(|_| {
+            let start = Point3::new(rng.gen_range(0.0..5.0), rng.gen_range(15.0..25.0), rng.gen_range(25.0..35.0));
+            Line::new(start, true_position - start)
+        })
+        .collect();
+    let initial_guess = Point3::new(9.0, 19.0, 29.0);
+    let config = LmConfig::default();
+
+    let mut group = c.benchmark_group("optimizer");
+    group.bench_function("lm", |b| {
+        b.iter(|| black_box(levenberg_marquardt_optimize_detailed(black_box(&lines), initial_guess, &config)))
+    });
+    group.bench_function("dogleg", |b| {
+        b.iter(|| black_box(dogleg_optimize(black_box(&lines), initial_guess, &config)))
+    });
+    group.finish();
+}
+
+/// 10 个密集目标（`DenseMultiTarget` 放大到 60 m × 60 m，固定种子）下顺序 RANSAC 与 J-linkage 的对比
+fn bench_sequential_vs_jlinkage(c: &mut Criterion) {
+    let generator = GeneratorConfig {
+        num_targets: 10,
+        target_x_range: (-30.0, 30.0),
+        target_y_range: (-30.0, 30.0),
+        seed: Some(11000),
+        ..GeneratorConfig::preset(ScenarioPreset::DenseMultiTarget)
+    };
+    let (_, data) = generate_data_from_config(&generator);
+    let mut config = FindTargetsConfig::new(5.0, 3);
+    config.ransac.seed = Some(1);
+
+    let mut group = c.benchmark_group("multi_model");
+    group.sample_size(20);
+    group.bench_function("sequential_ransac", |b| {
+        b.iter(|| black_box(find_targets_with_config(black_box(&data), &config)))
+    });
+    group.bench_function("jlinkage", |b| {
+        b.iter(|| black_box(find_targets_jlinkage(black_box(&data), &config)))
+    });
+    group.finish();
+}
+
+/// 5000 条测量（100 个目标 × 50 个测量站，固定种子）下顺序 RANSAC 与交会点 DBSCAN 预分组的对比，
+/// 运行前打印两者的召回率（匹配门限为 RANSAC 阈值）
+fn bench_sequential_vs_clustered(c: &mut Criterion) {
+    let generator = GeneratorConfig::builder()
+        .num_targets(100)
+        .target_x_range(-2000.0, 2000.0)
+        .target_y_range(-2000.0, 2000.0)
+        .num_stations_per_target_range(50, 50)
+        .seed(42)
+        .build()
+        .unwrap();
+    let (true_targets, data) = generate_data_from_config(&generator);
+    let mut config = FindTargetsConfig::new(20.0, 3);
+    config.ransac.seed = Some(1);
+    for (name, located) in [
+        ("sequential_ransac", find_targets_with_config(&data, &config)),
+        ("dbscan_clustered", find_targets_clustered(&data, &config)),
+    ] {
+        let recall = match_targets(&true_targets, &located, config.ransac.threshold).recall();
+        println!("{}：{} 条测量，定位 {} 个目标，召回率 {:.3}", name, data.len(), located.len(), recall);
+    }
+
+    let mut group = c.benchmark_group("pre_clustering");
+    group.sample_size(10);
+    group.bench_function("sequential_ransac", |b| {
+        b.iter(|| black_box(find_targets_with_config(black_box(&data), &config)))
+    });
+    group.bench_function("dbscan_clustered", |b| {
+        b.iter(|| black_box(find_targets_clustered(black_box(&data), &config)))
+    });
+    group.finish();
+}
+
+/// 同一组光线分别以 f64 与 f32 运行 LM 和 RANSAC，比较两种标量类型的吞吐量
+fn bench_f32_vs_f64(c: &mut Criterion) {
+    let mut rng = StdRng::seed_from_u64(5);
+    let target = Point3::new(10.0, 20.0, 30.0);
+    let mut lines: Vec<Line> = (0..50)
+        .map(|_| {
+            let start = Point3::new(rng.gen_range(-100.0..100.0), rng.gen_range(-100.0..100.0), 0.0);
+            let aim = target + Vector3::new(rng.gen_range(-0.2..0.2), rng.gen_range(-0.2..0.2), rng.gen_range(-0.2..0.2));
+            Line::new(start, aim - start)
+        })
+        .collect();
+    for _ in 0..50 {
+        let start = Point3::new(rng.gen_range(-100.0..100.0), rng.gen_range(-100.0..100.0), 0.0);
+        let direction = Vector3::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0), rng.gen_range(0.1..1.0));
+        lines.push(Line::new(start, direction));
+    }
+    let lines32: Vec<Line32> = lines.iter().map(Line::cast).collect();
+    let inliers = &lines[..50];
+    let inliers32 = &lines32[..50];
+    let initial_guess = Point3::new(9.0, 19.0, 29.0);
+    let mut config = RansacConfig::new(200, 1.0, 3);
+    config.seed = Some(1);
+
+    let mut group = c.benchmark_group("scalar_type");
+    group.bench_function("lm/f64", |b| {
+        b.iter(|| black_box(levenberg_marquardt_optimize(black_box(inliers), initial_guess, 200, 0.001)))
+    });
+    group.bench_function("lm/f32", |b| {
+        b.iter(|| black_box(levenberg_marquardt_optimize(black_box(inliers32), initial_guess.cast::<f32>(), 200, 0.001)))
+    });
+    group.bench_function("ransac/f64", |b| b.iter(|| black_box(ransac_fit_lines(black_box(&lines), &config))));
+    group.bench_function("ransac/f32", |b| b.iter(|| black_box(ransac_fit_lines(black_box(&lines32), &config))));
+    group.finish();
 }
 
 // 定义基准测试组和主函数
-criterion_group!(benches, bench_find_targets, bench_ransac, bench_lm);
+criterion_group!(benches, bench_find_targets, bench_find_targets_scaling, bench_find_targets_large, bench_progress_overhead, bench_spatial_index, bench_find_targets_batch, bench_incremental_locator, bench_ransac, bench_ransac_large, bench_inliers_10000, bench_residuals_100000, bench_lm, bench_lm_vs_dogleg, bench_sequential_vs_jlinkage, bench_sequential_vs_clustered, bench_f32_vs_f64);
 criterion_main!(benches);
\ No newline at end of file
@@ -0,0 +1,39 @@
+// examples/trace.rs
+//
+// 展示 `trace` 特性的结构化输出：对一个小的模拟场景运行 find_targets，
+// 打印 find_targets / round / ransac_fit_lines / levenberg_marquardt_optimize 各层 span 中的事件。
+//
+//     cargo run --example trace --features trace
+//
+// 设置环境变量 OPTI_RADAR_TRACE=trace 时额外输出每个 LM 步的接受/拒绝及阻尼系数。
+
+use opti_radar::data_generator::{generate_scenario, GeneratorConfig};
+use opti_radar::target_processor::{find_targets_with_diagnostics, FindTargetsConfig};
+use tracing_subscriber::filter::LevelFilter;
+
+fn main() {
+    let level = match std::env::var("OPTI_RADAR_TRACE").as_deref() {
+        Ok("trace") => LevelFilter::TRACE,
+        _ => LevelFilter::DEBUG,
+    };
+    tracing_subscriber::fmt().with_max_level(level).with_target(false).init();
+
+    let generator = GeneratorConfig::builder()
+        .num_targets(3)
+        .target_x_range(-1000.0, 1000.0)
+        .target_y_range(-1000.0, 1000.0)
+        .num_stations_per_target_range(4, 6)
+        .angle_noise_std(0.001)
+        .clutter_fraction(0.1)
+        .seed(7)
+        .build()
+        .unwrap();
+    let scenario = generate_scenario(&generator);
+
+    let mut config = FindTargetsConfig::new(20.0, 3);
+    config.ransac.seed = Some(7);
+    let (located, diagnostics) = find_targets_with_diagnostics(&scenario.measurements, &config);
+
+    println!("真实目标 {} 个，定位到 {} 个", scenario.true_targets.len(), located.len());
+    println!("{}", diagnostics);
+}
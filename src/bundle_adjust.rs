@@ -0,0 +1,391 @@
+// src/bundle_adjust.rs
+
+use crate::error::OptiRadarError;
+use crate::target_processor::{LmConfig, LocatedTarget, Measurement, ResidualModel};
+use nalgebra::{DMatrix, DVector, Matrix3, Point3, Vector3};
+use std::collections::HashMap;
+
+// --- 测量站位置与目标位置的联合平差 ---
+//
+// 测量站上报的位置（例如 GPS）误差达数米时，会使它参与的所有目标产生系统偏差。
+// 多个目标由同一组测量站观测时，这一误差可观测：以 `find_targets` 的结果为初值，
+// 对全部目标位置与每个测量站的位置修正量联合运行 LM，修正量带有指向上报位置的先验。
+// 未知数为 3 × (目标数 + 测量站数)，法方程按稠密矩阵求解，适用于数十个目标和测量站的规模。
+
+/// 联合平差参数
+#[derive(Debug, Clone)]
+pub struct BundleAdjustConfig {
+    pub measurement_std: f64, // 单条光线残差的标准差，单位同 `lm.residual_model`（米或弧度）
+    pub station_std_m: f64,   // 测量站上报位置各轴的标准差（米），作为位置修正量的先验
+    pub lm: LmConfig,         // 迭代次数、阻尼、残差模型及收敛判据；`robust_loss` 不使用
+}
+
+impl BundleAdjustConfig {
+    /// 以角度残差（弧度）创建参数，其余 LM 参数取默认值
+    pub fn new(measurement_std_rad: f64, station_std_m: f64) -> Self {
+        BundleAdjustConfig {
+            measurement_std: measurement_std_rad,
+            station_std_m,
+            lm: LmConfig {
+                residual_model: ResidualModel::Angular,
+                ..LmConfig::default()
+            },
+        }
+    }
+}
+
+/// 平差后的测量站
+#[derive(Debug, Clone)]
+pub struct StationEstimate {
+    pub station_id: String,
+    pub reported_position: Point3<f64>,   // 上报位置（该测量站第一条参与平差的测量的起点）
+    pub position: Point3<f64>,            // 平差后的位置
+    pub covariance: Option<Matrix3<f64>>, // 位置协方差，法方程奇异时为 None
+    pub num_observations: usize,          // 参与平差的光线数
+}
+
+impl StationEstimate {
+    /// 位置修正量：平差后位置减去上报位置
+    pub fn correction(&self) -> Vector3<f64> {
+        self.position - self.reported_position
+    }
+}
+
+/// 联合平差的结果
+#[derive(Debug, Clone)]
+pub struct BundleAdjustment {
+    pub stations: Vec<StationEstimate>, // 按测量站首次出现的顺序
+    pub targets: Vec<LocatedTarget>,    // 顺序同输入；位置、协方差及残差已更新
+    pub iterations_used: usize,
+    pub initial_cost: f64, // 初值处的代价（观测项与先验项之和，已按标准差归一化）
+    pub final_cost: f64,
+    pub converged: bool,
+}
+
+/// 一条参与平差的观测
+struct Observation {
+    target: usize,
+    station: Option<usize>, // 没有测量站标识的光线起点固定
+    measurement: usize,
+}
+
+/// 以 `targets`（通常为 `find_targets` 的结果）为初值，联合平差目标位置与测量站位置
+///
+/// 观测为各目标的内点测量（`inlier_indices` 为 `measurements` 中的索引），按 `station_id`
+/// 归组：同一测量站的全部光线共用一个位置修正量 δ，光线起点取测量自身的起点加 δ。
+/// 代价为 Σ w‖r / σₘ‖² + Σ ‖δ / σₛ‖²，r 为 `lm.residual_model` 的残差，w 为测量权重。
+/// 协方差为收敛点处法矩阵的逆，假设 `measurement_std` 与 `station_std_m` 准确。
+///
+/// 测量站与目标整体平移或缩放时光线方向不变，这些自由度只能由先验约束，因此修正后的
+/// 测量站位置仍保留上报误差的平均值。`measurement_std` 或 `station_std_m` 不是正的有限值时
+/// 返回 `InvalidParameter`。
+pub fn bundle_adjust(
+    measurements: &[Measurement],
+    targets: &[LocatedTarget],
+    config: &BundleAdjustConfig,
+) -> Result<BundleAdjustment, OptiRadarError> {
+    for (name, value) in [("measurement_std", config.measurement_std), ("station_std_m", config.station_std_m)] {
+        if !(value.is_finite() && value > 0.0) {
+            return Err(OptiRadarError::InvalidParameter { name, value });
+        }
+    }
+
+    // 观测及测量站编号
+    let mut station_lookup: HashMap<&str, usize> = HashMap::new();
+    let mut stations: Vec<StationEstimate> = Vec::new();
+    let mut observations = Vec::new();
+    for (t, target) in targets.iter().enumerate() {
+        for &i in &target.inlier_indices {
+            let m = &measurements[i];
+            m.try_into_line()?;
+            let station = m.station_id.as_deref().map(|id| {
+                *station_lookup.entry(id).or_insert_with(|| {
+                    stations.push(StationEstimate {
+                        station_id: id.to_string(),
+                        reported_position: Point3::new(m.x, m.y, m.z),
+                        position: Point3::new(m.x, m.y, m.z),
+                        covariance: None,
+                        num_observations: 0,
+                    });
+                    stations.len() - 1
+                })
+            });
+            if let Some(s) = station {
+                stations[s].num_observations += 1;
+            }
+            observations.push(Observation { target: t, station, measurement: i });
+        }
+    }
+
+    let num_targets = targets.len();
+    let mut positions: Vec<Point3<f64>> = targets.iter().map(|t| t.position).collect();
+    let mut corrections = vec![Vector3::zeros(); stations.len()];
+    let problem = Problem { measurements, observations: &observations, config, num_targets };
+
+    let mut lambda = config.lm.initial_lambda;
+    let initial_cost = problem.cost(&positions, &corrections);
+    let mut cost = initial_cost;
+    let mut iterations_used = 0;
+    let mut converged = false;
+    while iterations_used < config.lm.iterations {
+        iterations_used += 1;
+        let (h, g) = problem.normal_equations(&positions, &corrections);
+        let mut damped = h.clone();
+        for k in 0..damped.nrows() {
+            damped[(k, k)] += lambda * h[(k, k)].max(1e-12);
+        }
+        let Some(step) = damped.cholesky().map(|c| c.solve(&(-&g))) else {
+            lambda *= 10.0;
+            if lambda > config.lm.max_lambda {
+                break;
+            }
+            continue;
+        };
+
+        let new_positions: Vec<_> = positions
+            .iter()
+            .enumerate()
+            .map(|(t, p)| p + step.fixed_rows::<3>(3 * t).into_owned())
+            .collect();
+        let new_corrections: Vec<_> = corrections
+            .iter()
+            .enumerate()
+            .map(|(s, c)| c + step.fixed_rows::<3>(3 * (num_targets + s)).into_owned())
+            .collect();
+        let new_cost = problem.cost(&new_positions, &new_corrections);
+        if new_cost < cost {
+            let relative_decrease = (cost - new_cost) / cost;
+            positions = new_positions;
+            corrections = new_corrections;
+            cost = new_cost;
+            lambda *= 0.1;
+            if step.norm() < config.lm.xtol || relative_decrease < config.lm.ftol {
+                converged = true;
+                break;
+            }
+        } else {
+            if step.norm() < config.lm.xtol {
+                converged = true;
+                break;
+            }
+            lambda *= 10.0;
+            if lambda > config.lm.max_lambda {
+                break;
+            }
+        }
+    }
+
+    // 收敛点处法矩阵的逆即为协方差
+    let (h, _) = problem.normal_equations(&positions, &corrections);
+    let covariance = h.cholesky().map(|c| c.inverse());
+    let block = |k: usize| covariance.as_ref().map(|cov| cov.fixed_view::<3, 3>(3 * k, 3 * k).into_owned());
+
+    for (s, station) in stations.iter_mut().enumerate() {
+        station.position = station.reported_position + corrections[s];
+        station.covariance = block(num_targets + s);
+    }
+    let mut adjusted = targets.to_vec();
+    for (t, target) in adjusted.iter_mut().enumerate() {
+        target.position = positions[t];
+        target.covariance = block(t);
+        target.residuals = observations
+            .iter()
+            .filter(|o| o.target == t)
+            .map(|o| {
+                let line = problem.line(o, &corrections);
+                ResidualModel::Metric.residual(&line, &positions[t], config.lm.ray_mode).norm()
+            })
+            .collect();
+        let mut total_error_sq = 0.0;
+        let mut total_weight = 0.0;
+        for (&i, r) in target.inlier_indices.iter().zip(&target.residuals) {
+            total_error_sq += measurements[i].weight * r * r;
+            total_weight += measurements[i].weight;
+        }
+        if total_weight > 0.0 {
+            target.avg_error_dist_m = (total_error_sq / total_weight).sqrt();
+        }
+        target.max_residual_m = target.residuals.iter().copied().fold(0.0, f64::max);
+    }
+
+    Ok(BundleAdjustment {
+        stations,
+        targets: adjusted,
+        iterations_used,
+        initial_cost,
+        final_cost: cost,
+        converged,
+    })
+}
+
+/// 平差问题：观测与参数布局（先全部目标，后全部测量站修正量，各占 3 个未知数）
+struct Problem<'a> {
+    measurements: &'a [Measurement],
+    observations: &'a [Observation],
+    config: &'a BundleAdjustConfig,
+    num_targets: usize,
+}
+
+impl Problem<'_> {
+    /// 观测对应的光线，起点已加上测量站修正量
+    fn line(&self, observation: &Observation, corrections: &[Vector3<f64>]) -> crate::target_processor::Line {
+        let m = &self.measurements[observation.measurement];
+        let mut line = m.try_into_line().expect("观测在 bundle_adjust 开始时已校验");
+        if let Some(s) = observation.station {
+            line.start += corrections[s];
+        }
+        line
+    }
+
+    fn cost(&self, positions: &[Point3<f64>], corrections: &[Vector3<f64>]) -> f64 {
+        let model = self.config.lm.residual_model;
+        let observation_cost: f64 = self
+            .observations
+            .iter()
+            .map(|o| {
+                let line = self.line(o, corrections);
+                let r = model.residual(&line, &positions[o.target], self.config.lm.ray_mode) / self.config.measurement_std;
+                line.weight * r.norm_squared()
+            })
+            .sum();
+        let prior_cost: f64 = corrections.iter().map(|c| (c / self.config.station_std_m).norm_squared()).sum();
+        observation_cost + prior_cost
+    }
+
+    /// 高斯-牛顿法方程 H = JᵀJ、g = Jᵀr（均已按标准差与权重归一化）
+    fn normal_equations(&self, positions: &[Point3<f64>], corrections: &[Vector3<f64>]) -> (DMatrix<f64>, DVector<f64>) {
+        let n = 3 * (self.num_targets + corrections.len());
+        let mut h = DMatrix::zeros(n, n);
+        let mut g = DVector::zeros(n);
+        let model = self.config.lm.residual_model;
+        let ray_mode = self.config.lm.ray_mode;
+        for o in self.observations {
+            let line = self.line(o, corrections);
+            let p = &positions[o.target];
+            let scale = line.weight.sqrt() / self.config.measurement_std;
+            let r = model.residual(&line, p, ray_mode) * scale;
+            // 残差只依赖 p - start，对起点修正量的雅可比为对目标位置雅可比的相反数
+            let j = model.jacobian(&line, p, ray_mode) * scale;
+            let jtj = j.transpose() * j;
+            let jtr = j.transpose() * r;
+            let t = 3 * o.target;
+            add_block(&mut h, t, t, &jtj);
+            add_segment(&mut g, t, &jtr);
+            if let Some(s) = o.station {
+                let s = 3 * (self.num_targets + s);
+                add_block(&mut h, s, s, &jtj);
+                add_block(&mut h, t, s, &(-jtj));
+                add_block(&mut h, s, t, &(-jtj));
+                add_segment(&mut g, s, &(-jtr));
+            }
+        }
+        let prior_information = 1.0 / (self.config.station_std_m * self.config.station_std_m);
+        for (k, c) in corrections.iter().enumerate() {
+            let s = 3 * (self.num_targets + k);
+            add_block(&mut h, s, s, &(Matrix3::identity() * prior_information));
+            add_segment(&mut g, s, &(c * prior_information));
+        }
+        (h, g)
+    }
+}
+
+fn add_block(h: &mut DMatrix<f64>, row: usize, col: usize, block: &Matrix3<f64>) {
+    let mut view = h.fixed_view_mut::<3, 3>(row, col);
+    view += block;
+}
+
+fn add_segment(g: &mut DVector<f64>, row: usize, segment: &Vector3<f64>) {
+    let mut view = g.fixed_rows_mut::<3>(row);
+    view += segment;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::target_processor::{find_targets_with_config, FindTargetsConfig};
+    use rand::prelude::*;
+    use rand_distr::{Distribution, Normal};
+
+    #[test]
+    fn test_bundle_adjust_reduces_target_error() {
+        // 8 个测量站环绕 10 个目标，上报位置带 5 米误差，测向噪声 0.05 毫弧度
+        let mut rng = StdRng::seed_from_u64(3);
+        let station_noise = Normal::new(0.0, 5.0).unwrap();
+        let angle_noise = Normal::new(0.0, 5e-5).unwrap();
+        let true_stations: Vec<Point3<f64>> = (0..8)
+            .map(|k| {
+                let angle = k as f64 * std::f64::consts::TAU / 8.0;
+                Point3::new(3000.0 * angle.cos(), 3000.0 * angle.sin(), rng.gen_range(0.0..50.0))
+            })
+            .collect();
+        let reported: Vec<Point3<f64>> = true_stations
+            .iter()
+            .map(|s| s + Vector3::from_fn(|_, _| station_noise.sample(&mut rng)))
+            .collect();
+        let true_targets: Vec<Point3<f64>> = (0..10)
+            .map(|_| Point3::new(rng.gen_range(-1500.0..1500.0), rng.gen_range(-1500.0..1500.0), rng.gen_range(300.0..1000.0)))
+            .collect();
+
+        let mut measurements = Vec::new();
+        for target in &true_targets {
+            for (k, station) in true_stations.iter().enumerate() {
+                let d = (target - station).normalize();
+                let perturbed = d + Vector3::from_fn(|_, _| angle_noise.sample(&mut rng));
+                let r = reported[k];
+                measurements.push(
+                    Measurement::new(r.x, r.y, r.z, perturbed.x, perturbed.y, perturbed.z)
+                        .with_station_id(format!("S{}", k)),
+                );
+            }
+        }
+
+        let mut config = FindTargetsConfig::new(30.0, 3);
+        config.ransac.seed = Some(1);
+        config.ransac.max_iterations = 500;
+        let located = find_targets_with_config(&measurements, &config);
+        assert_eq!(located.len(), true_targets.len());
+        let mean_error = |targets: &[LocatedTarget]| -> f64 {
+            let total: f64 = targets
+                .iter()
+                .map(|t| true_targets.iter().map(|p| (t.position - p).norm()).fold(f64::INFINITY, f64::min))
+                .sum();
+            total / targets.len() as f64
+        };
+
+        let result = bundle_adjust(&measurements, &located, &BundleAdjustConfig::new(5e-5, 5.0)).unwrap();
+        assert!(result.converged);
+        assert!(result.final_cost < result.initial_cost);
+        let before = mean_error(&located);
+        let after = mean_error(&result.targets);
+        assert!(after < 0.8 * before, "{} -> {}", before, after);
+
+        // 测量站位置同样更接近真值（整体平移不可观测，比较去除平均偏移后的误差）
+        assert_eq!(result.stations.len(), 8);
+        let offset = |positions: &dyn Fn(usize) -> Point3<f64>| -> Vector3<f64> {
+            (0..8).map(|k| positions(k) - true_stations[k]).sum::<Vector3<f64>>() / 8.0
+        };
+        let relative_error = |positions: &dyn Fn(usize) -> Point3<f64>| -> f64 {
+            let mean = offset(positions);
+            (0..8).map(|k| (positions(k) - true_stations[k] - mean).norm()).sum::<f64>() / 8.0
+        };
+        let station_index = |k: usize| result.stations.iter().position(|s| s.station_id == format!("S{}", k)).unwrap();
+        let before = relative_error(&|k| reported[k]);
+        let after = relative_error(&|k| result.stations[station_index(k)].position);
+        assert!(after < 0.5 * before, "{} -> {}", before, after);
+        for station in &result.stations {
+            let cov = station.covariance.unwrap();
+            assert!(cov.symmetric_eigenvalues().min() > 0.0);
+            assert!(cov.symmetric_eigenvalues().max() < 25.0 + 1e-9);
+            assert_eq!(station.num_observations, 10);
+        }
+    }
+
+    #[test]
+    fn test_bundle_adjust_rejects_invalid_config() {
+        let config = BundleAdjustConfig::new(0.0, 5.0);
+        assert!(matches!(
+            bundle_adjust(&[], &[], &config),
+            Err(OptiRadarError::InvalidParameter { name: "measurement_std", .. })
+        ));
+    }
+}
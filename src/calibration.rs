@@ -0,0 +1,407 @@
+// src/calibration.rs
+
+use crate::error::OptiRadarError;
+use crate::target_processor::{find_targets_with_config, FindTargetsConfig, LmConfig, Measurement};
+use nalgebra::{DMatrix, DVector, Matrix3, Point3, Vector3};
+use std::collections::HashMap;
+
+// --- 测量站测向偏差的标定 ---
+//
+// 测量站的指北误差等固定测向偏差对该站的所有光线相同，RANSAC 无法将其剔除，
+// 会使该站参与的每个目标产生系统偏移。多个目标由同一组测量站观测时，偏差可与目标位置
+// 联合估计：以定位结果为初值，对全部目标位置与每个测量站的方位角（及俯仰角）偏差运行 LM，
+// 然后用修正后的方向重新定位，重复若干轮。
+
+/// 偏差标定参数
+#[derive(Debug, Clone)]
+pub struct BiasCalibrationConfig {
+    pub estimate_elevation: bool, // 是否同时估计俯仰角偏差；为 false 时只估计方位角偏差
+    pub passes: usize,            // 定位与偏差估计交替的轮数，至少为 1
+    pub lm: LmConfig,             // 联合估计的迭代次数、阻尼及收敛判据；残差模型、射线模式与鲁棒损失不使用
+}
+
+impl Default for BiasCalibrationConfig {
+    fn default() -> Self {
+        BiasCalibrationConfig {
+            estimate_elevation: true,
+            passes: 3,
+            lm: LmConfig::default(),
+        }
+    }
+}
+
+/// 单个测量站的测向偏差估计（弧度）
+///
+/// 角度约定同 `Measurement::from_az_el`：方位角从 +Y 顺时针转向 +X，俯仰角向上为正；
+/// 偏差为测量值减真实值，修正时从测量方向中减去。
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct BiasEstimate {
+    pub azimuth_rad: f64,
+    pub elevation_rad: f64,                // 不估计俯仰角时为 0
+    pub azimuth_std_rad: Option<f64>,      // 方位角偏差的标准差，法方程奇异时为 None
+    pub elevation_std_rad: Option<f64>,    // 俯仰角偏差的标准差，不估计俯仰角或法方程奇异时为 None
+    pub num_observations: usize,           // 最后一轮参与估计的光线数
+}
+
+impl BiasEstimate {
+    /// 从测量方向中扣除偏差，返回单位方向向量
+    pub fn correct(&self, direction: &Vector3<f64>) -> Vector3<f64> {
+        let (azimuth, elevation) = az_el(direction);
+        unit_direction(azimuth - self.azimuth_rad, elevation - self.elevation_rad)
+    }
+}
+
+/// 按测量站标识扣除测向偏差，没有标识或标识不在 `biases` 中的测量保持不变
+pub fn apply_bias_corrections(measurements: &[Measurement], biases: &HashMap<String, BiasEstimate>) -> Vec<Measurement> {
+    measurements
+        .iter()
+        .map(|m| {
+            let mut corrected = m.clone();
+            if let Some(bias) = m.station_id.as_ref().and_then(|id| biases.get(id)) {
+                let direction = bias.correct(&Vector3::new(m.direction_x, m.direction_y, m.direction_z));
+                corrected.direction_x = direction.x;
+                corrected.direction_y = direction.y;
+                corrected.direction_z = direction.z;
+            }
+            corrected
+        })
+        .collect()
+}
+
+/// 以默认参数（同时估计俯仰角，交替 3 轮）标定各测量站的测向偏差
+///
+/// 见 `calibrate_station_biases_with`。
+pub fn calibrate_station_biases(
+    measurements: &[Measurement],
+    targets_config: &FindTargetsConfig,
+) -> Result<HashMap<String, BiasEstimate>, OptiRadarError> {
+    calibrate_station_biases_with(measurements, targets_config, &BiasCalibrationConfig::default())
+}
+
+/// 标定各测量站的测向偏差，结果以测量站标识为键
+///
+/// 每一轮用当前偏差修正测量方向，按 `targets_config` 定位，再以定位结果为初值，
+/// 对全部目标位置与各测量站的偏差联合运行 LM（残差为目标方向与修正后测量方向之差），
+/// 得到新的偏差。只有带 `station_id` 且属于某个目标内点的测量参与估计；
+/// 一个测量站至少需要观测两个目标，其偏差才能与目标位置区分。
+/// 标准差按残差估计的测向噪声换算。得到偏差后，用 `apply_bias_corrections`
+/// 修正测量并重新定位。`passes` 为 0 时返回 `InvalidParameter`。
+pub fn calibrate_station_biases_with(
+    measurements: &[Measurement],
+    targets_config: &FindTargetsConfig,
+    config: &BiasCalibrationConfig,
+) -> Result<HashMap<String, BiasEstimate>, OptiRadarError> {
+    if config.passes == 0 {
+        return Err(OptiRadarError::InvalidParameter {
+            name: "passes",
+            value: 0.0,
+        });
+    }
+
+    let mut estimates: HashMap<String, BiasEstimate> = HashMap::new();
+    for _ in 0..config.passes {
+        let corrected = apply_bias_corrections(measurements, &estimates);
+        let targets = find_targets_with_config(&corrected, targets_config);
+
+        // 观测及测量站编号
+        let mut station_ids: Vec<String> = Vec::new();
+        let mut station_lookup: HashMap<&str, usize> = HashMap::new();
+        let mut observations = Vec::new();
+        for (t, target) in targets.iter().enumerate() {
+            for &i in &target.inlier_indices {
+                let Some(id) = measurements[i].station_id.as_deref() else {
+                    continue;
+                };
+                let s = *station_lookup.entry(id).or_insert_with(|| {
+                    station_ids.push(id.to_string());
+                    station_ids.len() - 1
+                });
+                observations.push(Observation::new(&measurements[i], t, s));
+            }
+        }
+        if observations.is_empty() {
+            break;
+        }
+
+        let problem = Problem {
+            observations: &observations,
+            num_targets: targets.len(),
+            estimate_elevation: config.estimate_elevation,
+        };
+        let mut params = DVector::zeros(problem.num_params(station_ids.len()));
+        for (t, target) in targets.iter().enumerate() {
+            params.fixed_rows_mut::<3>(3 * t).copy_from(&target.position.coords);
+        }
+        for (s, id) in station_ids.iter().enumerate() {
+            let bias = estimates.get(id).copied().unwrap_or_default();
+            let k = problem.bias_offset(s);
+            params[k] = bias.azimuth_rad;
+            if config.estimate_elevation {
+                params[k + 1] = bias.elevation_rad;
+            }
+        }
+
+        let params = problem.solve(params, &config.lm);
+        let (residual, jacobian) = problem.linearize(&params);
+        let dof = (2 * observations.len()).saturating_sub(params.len()).max(1);
+        let noise_variance = residual.norm_squared() / dof as f64;
+        let covariance = (jacobian.transpose() * &jacobian).cholesky().map(|c| c.inverse() * noise_variance);
+        let std = |k: usize| covariance.as_ref().map(|cov| cov[(k, k)].max(0.0).sqrt());
+
+        let previous = std::mem::take(&mut estimates);
+        let mut max_change: f64 = 0.0;
+        for (s, id) in station_ids.into_iter().enumerate() {
+            let k = problem.bias_offset(s);
+            let estimate = BiasEstimate {
+                azimuth_rad: params[k],
+                elevation_rad: if config.estimate_elevation { params[k + 1] } else { 0.0 },
+                azimuth_std_rad: std(k),
+                elevation_std_rad: if config.estimate_elevation { std(k + 1) } else { None },
+                num_observations: observations.iter().filter(|o| o.station == s).count(),
+            };
+            let old = previous.get(&id).copied().unwrap_or_default();
+            max_change = max_change
+                .max((estimate.azimuth_rad - old.azimuth_rad).abs())
+                .max((estimate.elevation_rad - old.elevation_rad).abs());
+            estimates.insert(id, estimate);
+        }
+        if max_change < config.lm.xtol {
+            break;
+        }
+    }
+    Ok(estimates)
+}
+
+/// 一条参与估计的光线
+struct Observation {
+    start: Point3<f64>,
+    azimuth: f64,   // 测量的原始方位角
+    elevation: f64, // 测量的原始俯仰角
+    scale: f64,     // 权重的平方根
+    target: usize,
+    station: usize,
+}
+
+impl Observation {
+    fn new(m: &Measurement, target: usize, station: usize) -> Self {
+        let (azimuth, elevation) = az_el(&Vector3::new(m.direction_x, m.direction_y, m.direction_z));
+        Observation {
+            start: Point3::new(m.x, m.y, m.z),
+            azimuth,
+            elevation,
+            scale: m.weight.sqrt(),
+            target,
+            station,
+        }
+    }
+}
+
+/// 联合估计问题：参数先为全部目标位置（各 3 个），后为各测量站偏差（各 1 或 2 个）
+struct Problem<'a> {
+    observations: &'a [Observation],
+    num_targets: usize,
+    estimate_elevation: bool,
+}
+
+impl Problem<'_> {
+    fn biases_per_station(&self) -> usize {
+        if self.estimate_elevation {
+            2
+        } else {
+            1
+        }
+    }
+
+    fn num_params(&self, num_stations: usize) -> usize {
+        3 * self.num_targets + self.biases_per_station() * num_stations
+    }
+
+    fn bias_offset(&self, station: usize) -> usize {
+        3 * self.num_targets + self.biases_per_station() * station
+    }
+
+    /// 残差 r = 目标单位方向 − 修正后的测量方向（小角度下模长即夹角），及其雅可比
+    fn linearize(&self, params: &DVector<f64>) -> (DVector<f64>, DMatrix<f64>) {
+        let m = self.observations.len();
+        let mut residual = DVector::zeros(3 * m);
+        let mut jacobian = DMatrix::zeros(3 * m, params.len());
+        for (row, o) in self.observations.iter().enumerate() {
+            let t = 3 * o.target;
+            let k = self.bias_offset(o.station);
+            let azimuth = o.azimuth - params[k];
+            let elevation = if self.estimate_elevation { o.elevation - params[k + 1] } else { o.elevation };
+
+            let offset = params.fixed_rows::<3>(t) - o.start.coords;
+            let range = offset.norm().max(f64::EPSILON);
+            let u = offset / range;
+            let r = (u - unit_direction(azimuth, elevation)) * o.scale;
+            residual.fixed_rows_mut::<3>(3 * row).copy_from(&r);
+
+            let d_point = (Matrix3::identity() - u * u.transpose()) * (o.scale / range);
+            jacobian.fixed_view_mut::<3, 3>(3 * row, t).copy_from(&d_point);
+            // 修正角 = 测量角 − 偏差，故残差对偏差的导数为方向对角度的导数
+            let (sin_az, cos_az) = azimuth.sin_cos();
+            let (sin_el, cos_el) = elevation.sin_cos();
+            let d_azimuth = Vector3::new(cos_el * cos_az, -cos_el * sin_az, 0.0) * o.scale;
+            jacobian.fixed_view_mut::<3, 1>(3 * row, k).copy_from(&d_azimuth);
+            if self.estimate_elevation {
+                let d_elevation = Vector3::new(-sin_el * sin_az, -sin_el * cos_az, cos_el) * o.scale;
+                jacobian.fixed_view_mut::<3, 1>(3 * row, k + 1).copy_from(&d_elevation);
+            }
+        }
+        (residual, jacobian)
+    }
+
+    /// LM 迭代，阻尼为 λ·diag(JᵀJ)
+    fn solve(&self, mut params: DVector<f64>, lm: &LmConfig) -> DVector<f64> {
+        let mut lambda = lm.initial_lambda;
+        let (mut residual, mut jacobian) = self.linearize(&params);
+        let mut cost = residual.norm_squared();
+        for _ in 0..lm.iterations {
+            let h = jacobian.transpose() * &jacobian;
+            let g = jacobian.transpose() * &residual;
+            let mut damped = h.clone();
+            for k in 0..damped.nrows() {
+                damped[(k, k)] += lambda * h[(k, k)].max(1e-12);
+            }
+            let Some(step) = damped.cholesky().map(|c| c.solve(&(-&g))) else {
+                lambda *= 10.0;
+                if lambda > lm.max_lambda {
+                    break;
+                }
+                continue;
+            };
+
+            let candidate = &params + &step;
+            let (new_residual, new_jacobian) = self.linearize(&candidate);
+            let new_cost = new_residual.norm_squared();
+            if new_cost < cost {
+                let relative_decrease = (cost - new_cost) / cost;
+                params = candidate;
+                residual = new_residual;
+                jacobian = new_jacobian;
+                cost = new_cost;
+                lambda *= 0.1;
+                if step.norm() < lm.xtol || relative_decrease < lm.ftol {
+                    break;
+                }
+            } else {
+                if step.norm() < lm.xtol {
+                    break;
+                }
+                lambda *= 10.0;
+                if lambda > lm.max_lambda {
+                    break;
+                }
+            }
+        }
+        params
+    }
+}
+
+/// 方向向量的方位角与俯仰角（弧度）
+fn az_el(direction: &Vector3<f64>) -> (f64, f64) {
+    let norm = direction.norm();
+    (direction.x.atan2(direction.y), (direction.z / norm).clamp(-1.0, 1.0).asin())
+}
+
+/// 方位角/俯仰角对应的单位方向，约定同 `Measurement::from_az_el`
+fn unit_direction(azimuth: f64, elevation: f64) -> Vector3<f64> {
+    let (sin_az, cos_az) = azimuth.sin_cos();
+    let (sin_el, cos_el) = elevation.sin_cos();
+    Vector3::new(cos_el * sin_az, cos_el * cos_az, sin_el)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_correct_inverts_bias() {
+        let bias = BiasEstimate {
+            azimuth_rad: 0.03,
+            elevation_rad: -0.01,
+            ..Default::default()
+        };
+        let truth = unit_direction(1.2, 0.3);
+        let measured = unit_direction(1.2 + 0.03, 0.3 - 0.01) * 5.0;
+        assert!((bias.correct(&measured) - truth).norm() < 1e-12);
+
+        let mut biases = HashMap::new();
+        biases.insert("S0".to_string(), bias);
+        let data = [
+            Measurement::new(0.0, 0.0, 0.0, measured.x, measured.y, measured.z).with_station_id("S0"),
+            Measurement::new(0.0, 0.0, 0.0, measured.x, measured.y, measured.z).with_station_id("S1"),
+            Measurement::new(0.0, 0.0, 0.0, measured.x, measured.y, measured.z),
+        ];
+        let corrected = apply_bias_corrections(&data, &biases);
+        assert!((corrected[0].direction_x - truth.x).abs() < 1e-12);
+        for (c, m) in corrected.iter().zip(&data).skip(1) {
+            assert_eq!((c.direction_x, c.direction_y, c.direction_z), (m.direction_x, m.direction_y, m.direction_z));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "simulation")]
+    fn test_recovers_injected_biases() {
+        use crate::data_generator::{generate_scenario, GeneratorConfig, StationLayout};
+        use crate::evaluation::match_targets;
+
+        // 8 个共享测量站各带 ±20 毫弧度方位角、±10 毫弧度俯仰角偏差，测向噪声 0.5 毫弧度
+        let generator = GeneratorConfig::builder()
+            .num_targets(10)
+            .target_x_range(-800.0, 800.0)
+            .target_y_range(-800.0, 800.0)
+            .target_z_range(200.0, 600.0)
+            .station_dist_range(500.0, 1500.0)
+            .station_z_range(0.0, 20.0)
+            .pos_noise_std(0.0)
+            .alt_noise_std(0.0)
+            .angle_noise_std(5e-4)
+            .azimuth_bias_range(-0.02, 0.02)
+            .elevation_bias_range(-0.01, 0.01)
+            .station_layout(StationLayout::Shared {
+                num_stations: 8,
+                detection_probability: 1.0,
+            })
+            .seed(11)
+            .build()
+            .unwrap();
+        let scenario = generate_scenario(&generator);
+
+        let mut config = FindTargetsConfig::new(40.0, 3);
+        config.ransac.max_iterations = 500;
+        config.ransac.seed = Some(11);
+        let biases = calibrate_station_biases(&scenario.measurements, &config).unwrap();
+        assert_eq!(biases.len(), 8);
+        for (m, truth) in scenario.measurements.iter().zip(&scenario.station_biases) {
+            let estimate = &biases[m.station_id.as_ref().unwrap()];
+            assert!((estimate.azimuth_rad - truth.azimuth_rad).abs() < 1e-3, "{:?} {:?}", estimate, truth);
+            assert!((estimate.elevation_rad - truth.elevation_rad).abs() < 1e-3, "{:?} {:?}", estimate, truth);
+            assert!(estimate.azimuth_std_rad.unwrap() < 1e-3);
+        }
+
+        // 修正后重新定位，误差明显减小
+        let mean_error = |data: &[Measurement]| {
+            let located = find_targets_with_config(data, &config);
+            let result = match_targets(&scenario.true_targets, &located, f64::INFINITY);
+            assert_eq!(result.matches.len(), scenario.true_targets.len());
+            result.mean_error().unwrap()
+        };
+        let before = mean_error(&scenario.measurements);
+        let after = mean_error(&apply_bias_corrections(&scenario.measurements, &biases));
+        assert!(after < 0.1 * before, "{} -> {}", before, after);
+    }
+
+    #[test]
+    fn test_rejects_zero_passes() {
+        let config = BiasCalibrationConfig {
+            passes: 0,
+            ..Default::default()
+        };
+        assert!(matches!(
+            calibrate_station_biases_with(&[], &FindTargetsConfig::new(1.0, 3), &config),
+            Err(OptiRadarError::InvalidParameter { name: "passes", .. })
+        ));
+    }
+}
@@ -0,0 +1,204 @@
+// src/coords.rs
+
+use nalgebra::{Matrix3, Point3, Vector3};
+
+// --- WGS84 大地坐标、ECEF 与 ENU 之间的转换 ---
+//
+// 本 crate 其余部分均在局部 ENU 坐标系（x 东、y 北、z 天，单位米）下计算。
+// 测量站以经纬高给出时，先转换到 ECEF，再转换到以某个参考点为原点的 ENU；
+// 与平面近似不同，相距数十公里以上时仍保持毫米级精度。
+
+/// WGS84 椭球长半轴（米）
+pub const WGS84_A: f64 = 6_378_137.0;
+/// WGS84 椭球扁率
+pub const WGS84_F: f64 = 1.0 / 298.257_223_563;
+/// WGS84 第一偏心率的平方 e² = f(2 - f)
+pub const WGS84_E2: f64 = WGS84_F * (2.0 - WGS84_F);
+
+/// ecef_to_geodetic 的最大迭代次数；地表附近 3 次以内即收敛到亚毫米
+const MAX_GEODETIC_ITERATIONS: usize = 10;
+
+/// WGS84 大地坐标
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Geodetic {
+    pub latitude: f64,  // 纬度（弧度），北纬为正
+    pub longitude: f64, // 经度（弧度），东经为正
+    pub altitude: f64,  // 椭球高（米）
+}
+
+impl Geodetic {
+    /// 由弧度制的纬度/经度创建
+    pub fn new(latitude: f64, longitude: f64, altitude: f64) -> Self {
+        Geodetic { latitude, longitude, altitude }
+    }
+
+    /// 由角度制的纬度/经度创建
+    pub fn from_degrees(latitude_deg: f64, longitude_deg: f64, altitude: f64) -> Self {
+        Geodetic::new(latitude_deg.to_radians(), longitude_deg.to_radians(), altitude)
+    }
+
+    /// 纬度（度）
+    pub fn latitude_deg(&self) -> f64 {
+        self.latitude.to_degrees()
+    }
+
+    /// 经度（度）
+    pub fn longitude_deg(&self) -> f64 {
+        self.longitude.to_degrees()
+    }
+}
+
+/// 卯酉圈曲率半径 N(φ)
+fn prime_vertical_radius(sin_lat: f64) -> f64 {
+    WGS84_A / (1.0 - WGS84_E2 * sin_lat * sin_lat).sqrt()
+}
+
+/// 大地坐标 → ECEF（米）
+pub fn geodetic_to_ecef(geodetic: &Geodetic) -> Point3<f64> {
+    let (sin_lat, cos_lat) = geodetic.latitude.sin_cos();
+    let (sin_lon, cos_lon) = geodetic.longitude.sin_cos();
+    let n = prime_vertical_radius(sin_lat);
+    let h = geodetic.altitude;
+    Point3::new(
+        (n + h) * cos_lat * cos_lon,
+        (n + h) * cos_lat * sin_lon,
+        (n * (1.0 - WGS84_E2) + h) * sin_lat,
+    )
+}
+
+/// ECEF → 大地坐标
+///
+/// 对纬度做不动点迭代，椭球高按 h = p cosφ + z sinφ − a²/N 计算，
+/// 在两极（p = 0）附近同样稳定。位于地心时返回纬度 0、高度 −a。
+pub fn ecef_to_geodetic(ecef: &Point3<f64>) -> Geodetic {
+    let p = ecef.x.hypot(ecef.y);
+    let longitude = ecef.y.atan2(ecef.x);
+    let mut latitude = ecef.z.atan2(p * (1.0 - WGS84_E2));
+    for _ in 0..MAX_GEODETIC_ITERATIONS {
+        let n = prime_vertical_radius(latitude.sin());
+        let next = (ecef.z + WGS84_E2 * n * latitude.sin()).atan2(p);
+        let converged = (next - latitude).abs() < 1e-15;
+        latitude = next;
+        if converged {
+            break;
+        }
+    }
+    let (sin_lat, cos_lat) = latitude.sin_cos();
+    let n = prime_vertical_radius(sin_lat);
+    let altitude = p * cos_lat + ecef.z * sin_lat - WGS84_A * WGS84_A / n;
+    Geodetic { latitude, longitude, altitude }
+}
+
+/// ECEF → 以 `origin` 为原点的 ENU 的旋转矩阵（各行依次为东、北、天方向）
+fn ecef_to_enu_rotation(origin: &Geodetic) -> Matrix3<f64> {
+    let (sin_lat, cos_lat) = origin.latitude.sin_cos();
+    let (sin_lon, cos_lon) = origin.longitude.sin_cos();
+    Matrix3::new(
+        -sin_lon,
+        cos_lon,
+        0.0,
+        -sin_lat * cos_lon,
+        -sin_lat * sin_lon,
+        cos_lat,
+        cos_lat * cos_lon,
+        cos_lat * sin_lon,
+        sin_lat,
+    )
+}
+
+/// ECEF 点 → 以 `origin` 为原点的 ENU 坐标（米）
+pub fn ecef_to_enu(ecef: &Point3<f64>, origin: &Geodetic) -> Point3<f64> {
+    Point3::from(ecef_to_enu_rotation(origin) * (ecef - geodetic_to_ecef(origin)))
+}
+
+/// 以 `origin` 为原点的 ENU 坐标 → ECEF 点
+pub fn enu_to_ecef(enu: &Point3<f64>, origin: &Geodetic) -> Point3<f64> {
+    geodetic_to_ecef(origin) + ecef_to_enu_rotation(origin).transpose() * enu.coords
+}
+
+/// ECEF 方向向量 → `origin` 处的 ENU 方向（只旋转，不平移）
+pub fn ecef_vector_to_enu(vector: &Vector3<f64>, origin: &Geodetic) -> Vector3<f64> {
+    ecef_to_enu_rotation(origin) * vector
+}
+
+/// `origin` 处的 ENU 方向 → ECEF 方向向量
+pub fn enu_vector_to_ecef(vector: &Vector3<f64>, origin: &Geodetic) -> Vector3<f64> {
+    ecef_to_enu_rotation(origin).transpose() * vector
+}
+
+/// 大地坐标 → 以 `origin` 为原点的 ENU 坐标
+pub fn geodetic_to_enu(geodetic: &Geodetic, origin: &Geodetic) -> Point3<f64> {
+    ecef_to_enu(&geodetic_to_ecef(geodetic), origin)
+}
+
+/// 以 `origin` 为原点的 ENU 坐标 → 大地坐标
+pub fn enu_to_geodetic(enu: &Point3<f64>, origin: &Geodetic) -> Geodetic {
+    ecef_to_geodetic(&enu_to_ecef(enu, origin))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_vectors() {
+        // 赤道与本初子午线交点、北极
+        let p = geodetic_to_ecef(&Geodetic::from_degrees(0.0, 0.0, 0.0));
+        assert!((p - Point3::new(WGS84_A, 0.0, 0.0)).norm() < 1e-9);
+        let pole = geodetic_to_ecef(&Geodetic::from_degrees(90.0, 0.0, 100.0));
+        assert!((pole - Point3::new(0.0, 0.0, 6_356_752.314_245 + 100.0)).norm() < 1e-3);
+        let g = ecef_to_geodetic(&pole);
+        assert!((g.latitude_deg() - 90.0).abs() < 1e-12);
+        assert!((g.altitude - 100.0).abs() < 1e-6);
+
+        // 北纬 45°、东经 90°、高 1000 米的参考值
+        let p = geodetic_to_ecef(&Geodetic::from_degrees(45.0, 90.0, 1000.0));
+        assert!(p.x.abs() < 1e-6);
+        assert!((p.y - 4_518_297.985_630).abs() < 1e-3, "{}", p.y);
+        assert!((p.z - 4_488_055.515_647_106).abs() < 1e-3, "{}", p.z);
+    }
+
+    #[test]
+    fn test_ecef_round_trip() {
+        // 覆盖两极附近、南半球、日界线与高空
+        for &(lat, lon, alt) in &[
+            (0.0, 0.0, 0.0),
+            (31.2304, 121.4737, 12.0),
+            (-33.8688, 151.2093, 58.0),
+            (89.9999, -179.9999, 3000.0),
+            (-89.5, 10.0, -50.0),
+            (52.0, -0.1, 400_000.0),
+        ] {
+            let geodetic = Geodetic::from_degrees(lat, lon, alt);
+            let ecef = geodetic_to_ecef(&geodetic);
+            let back = ecef_to_geodetic(&ecef);
+            assert!((geodetic_to_ecef(&back) - ecef).norm() < 1e-3, "({}, {}, {})", lat, lon, alt);
+            assert!((back.latitude - geodetic.latitude).abs() < 1e-10);
+            assert!((back.altitude - alt).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_enu_axes_and_round_trip() {
+        let origin = Geodetic::from_degrees(40.0, 116.0, 50.0);
+        assert!(geodetic_to_enu(&origin, &origin).coords.norm() < 1e-6);
+
+        // 正北约 1 角分（≈1.85 公里）的点：北向为正、东向约为零、因地球曲率略低于原点
+        let north = geodetic_to_enu(&Geodetic::from_degrees(40.0 + 1.0 / 60.0, 116.0, 50.0), &origin);
+        assert!(north.x.abs() < 1e-6);
+        assert!((north.y - 1851.0).abs() < 5.0, "{}", north.y);
+        assert!(north.z < 0.0 && north.z > -1.0, "{}", north.z);
+
+        // 天向为 ECEF 中的椭球法线
+        let up = enu_vector_to_ecef(&Vector3::z(), &origin);
+        let normal = geodetic_to_ecef(&Geodetic::from_degrees(40.0, 116.0, 51.0)) - geodetic_to_ecef(&origin);
+        assert!((up - normal).norm() < 1e-6);
+        assert!((ecef_vector_to_enu(&up, &origin) - Vector3::z()).norm() < 1e-12);
+
+        // 100 公里外的点往返
+        let enu = Point3::new(70_000.0, -70_000.0, 2_000.0);
+        let back = geodetic_to_enu(&enu_to_geodetic(&enu, &origin), &origin);
+        assert!((back - enu).norm() < 1e-3);
+    }
+}
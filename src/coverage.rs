@@ -0,0 +1,302 @@
+// src/coverage.rs
+
+use crate::crlb::{compute_crlb, crlb_position_sigma};
+use crate::error::OptiRadarError;
+use crate::evaluation::interpolated_percentile;
+use crate::target_processor::{geometry_dop, Line};
+use nalgebra::{DMatrix, Point3};
+use std::io::{self, Write};
+
+// --- 布站覆盖图 ---
+//
+// 部署前评估候选站址：在固定高度的水平网格上，假设目标位于每个网格点、每个测量站各测一次方向，
+// 计算 CRLB 给出的 1σ 定位误差下界与几何精度因子（DOP）。两者都只取决于几何，与定位算法无关。
+
+/// `CoverageMap::to_csv` 的表头
+pub const COVERAGE_CSV_HEADER: &str = "x,y,z,crlb_sigma_m,gdop";
+
+/// 覆盖图网格：覆盖 `x_range` × `y_range`（含两端，可以退化为一行或一列），间距 `resolution` 米，
+/// 目标高度 `altitude`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GridSpec {
+    pub x_range: (f64, f64),
+    pub y_range: (f64, f64),
+    pub resolution: f64,
+    pub altitude: f64,
+}
+
+impl GridSpec {
+    pub fn new(x_range: (f64, f64), y_range: (f64, f64), resolution: f64, altitude: f64) -> Self {
+        GridSpec {
+            x_range,
+            y_range,
+            resolution,
+            altitude,
+        }
+    }
+
+    /// 检查范围有序且有限、间距为正、高度有限
+    pub fn validate(&self) -> Result<(), OptiRadarError> {
+        if !(self.resolution.is_finite() && self.resolution > 0.0) {
+            return Err(OptiRadarError::InvalidParameter {
+                name: "resolution",
+                value: self.resolution,
+            });
+        }
+        if !self.altitude.is_finite() {
+            return Err(OptiRadarError::InvalidParameter {
+                name: "altitude",
+                value: self.altitude,
+            });
+        }
+        for (name, (min, max)) in [("x_range", self.x_range), ("y_range", self.y_range)] {
+            if !(min.is_finite() && max.is_finite() && min <= max) {
+                return Err(OptiRadarError::InvalidRange { name, min, max });
+            }
+        }
+        Ok(())
+    }
+
+    /// 各列的 x 坐标，升序
+    pub fn xs(&self) -> Vec<f64> {
+        axis(self.x_range, self.resolution)
+    }
+
+    /// 各行的 y 坐标，升序
+    pub fn ys(&self) -> Vec<f64> {
+        axis(self.y_range, self.resolution)
+    }
+}
+
+/// 从 min 起每隔 step 取一点，不超过 max（含两端）
+fn axis((min, max): (f64, f64), step: f64) -> Vec<f64> {
+    let count = ((max - min) / step + 1e-9).floor() as usize + 1;
+    (0..count).map(|i| min + i as f64 * step).collect()
+}
+
+/// 覆盖图的指标
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoverageMetric {
+    /// CRLB 的 1σ 位置误差下界（米），见 `crlb::crlb_position_sigma`
+    CrlbSigma,
+    /// 几何精度因子，见 `target_processor::geometry_dop` 的 `total`
+    Gdop,
+}
+
+/// 网格上各点的预测精度，见 `evaluate_grid`
+///
+/// 矩阵的行对应 `ys`、列对应 `xs`：`crlb_sigmas[(行, 列)]` 为目标位于 (xs[列], ys[行], altitude) 时的值。
+/// 几何退化（少于 2 个测量站或与目标共线）的点为无穷大。
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoverageMap {
+    pub xs: Vec<f64>,
+    pub ys: Vec<f64>,
+    pub altitude: f64,
+    pub crlb_sigmas: DMatrix<f64>,
+    pub gdops: DMatrix<f64>,
+}
+
+impl CoverageMap {
+    /// 指定指标的二维数组
+    pub fn values(&self, metric: CoverageMetric) -> &DMatrix<f64> {
+        match metric {
+            CoverageMetric::CrlbSigma => &self.crlb_sigmas,
+            CoverageMetric::Gdop => &self.gdops,
+        }
+    }
+
+    /// 网格上的最小值
+    pub fn min(&self, metric: CoverageMetric) -> f64 {
+        self.values(metric).iter().copied().fold(f64::INFINITY, f64::min)
+    }
+
+    /// 网格上的最大值
+    pub fn max(&self, metric: CoverageMetric) -> f64 {
+        self.values(metric).iter().copied().fold(f64::NEG_INFINITY, f64::max)
+    }
+
+    /// 网格上的第 `percent` 百分位数（0~100，相邻次序统计量间线性插值）
+    ///
+    /// 例如 `percentile(CoverageMetric::CrlbSigma, 90.0)` 为 90% 的网格点都能达到的误差下界。
+    pub fn percentile(&self, metric: CoverageMetric, percent: f64) -> Result<f64, OptiRadarError> {
+        if !(0.0..=100.0).contains(&percent) {
+            return Err(OptiRadarError::InvalidParameter {
+                name: "percent",
+                value: percent,
+            });
+        }
+        let mut values: Vec<f64> = self.values(metric).iter().copied().collect();
+        values.sort_by(f64::total_cmp);
+        Ok(interpolated_percentile(&values, percent))
+    }
+
+    /// 按行依次给出各网格点的目标位置与两项指标 (位置, CRLB 1σ, GDOP)
+    pub fn points(&self) -> impl Iterator<Item = (Point3<f64>, f64, f64)> + '_ {
+        self.ys.iter().enumerate().flat_map(move |(row, &y)| {
+            self.xs.iter().enumerate().map(move |(col, &x)| {
+                (
+                    Point3::new(x, y, self.altitude),
+                    self.crlb_sigmas[(row, col)],
+                    self.gdops[(row, col)],
+                )
+            })
+        })
+    }
+
+    /// 写为 CSV，表头为 `COVERAGE_CSV_HEADER`，每个网格点一行（按行，先 x 后 y）
+    pub fn to_csv<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "{}", COVERAGE_CSV_HEADER)?;
+        for (point, sigma, gdop) in self.points() {
+            writeln!(writer, "{},{},{},{},{}", point.x, point.y, point.z, sigma, gdop)?;
+        }
+        Ok(())
+    }
+}
+
+/// 在 `grid` 的每个网格点上计算 `stations` 的 CRLB 1σ 位置误差下界和 GDOP
+///
+/// `sigma` 为垂直于视线的每个方向上的测角标准差（弧度），含义同 `crlb::compute_crlb`；GDOP 与 σ 无关。
+/// 网格参数无效、`sigma` 为负或非有限值、测量站坐标含非有限值时返回错误。
+pub fn evaluate_grid(stations: &[Point3<f64>], grid: &GridSpec, sigma: f64) -> Result<CoverageMap, OptiRadarError> {
+    grid.validate()?;
+    if !(sigma.is_finite() && sigma >= 0.0) {
+        return Err(OptiRadarError::InvalidParameter { name: "sigma", value: sigma });
+    }
+    if stations.iter().any(|p| !p.iter().all(|v| v.is_finite())) {
+        return Err(OptiRadarError::NonFiniteStation);
+    }
+    let (xs, ys) = (grid.xs(), grid.ys());
+    let target = |row: usize, col: usize| Point3::new(xs[col], ys[row], grid.altitude);
+    let crlb_sigmas = DMatrix::from_fn(ys.len(), xs.len(), |row, col| {
+        crlb_position_sigma(&compute_crlb(stations, &target(row, col), sigma))
+    });
+    let gdops = DMatrix::from_fn(ys.len(), xs.len(), |row, col| {
+        // 与目标重合的测量站不提供方向
+        let lines: Vec<Line> = stations
+            .iter()
+            .filter_map(|station| Line::from_points(*station, target(row, col)).ok())
+            .collect();
+        geometry_dop(&lines).total
+    });
+    Ok(CoverageMap {
+        xs,
+        ys,
+        altitude: grid.altitude,
+        crlb_sigmas,
+        gdops,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square_stations() -> Vec<Point3<f64>> {
+        vec![
+            Point3::new(-500.0, -500.0, 10.0),
+            Point3::new(500.0, -500.0, 10.0),
+            Point3::new(500.0, 500.0, 10.0),
+            Point3::new(-500.0, 500.0, 10.0),
+        ]
+    }
+
+    #[test]
+    fn test_evaluate_grid_shape_and_values() {
+        let stations = square_stations();
+        let grid = GridSpec::new((-1000.0, 1000.0), (-500.0, 1000.0), 100.0, 150.0);
+        let map = evaluate_grid(&stations, &grid, 0.001).unwrap();
+        assert_eq!((map.xs.len(), map.ys.len()), (21, 16));
+        assert_eq!(map.crlb_sigmas.shape(), (16, 21));
+        assert_eq!(map.gdops.shape(), (16, 21));
+        assert_eq!((map.xs[20], map.ys[15]), (1000.0, 1000.0));
+        for (point, sigma, gdop) in map.points() {
+            assert_eq!(point.z, 150.0);
+            assert_eq!(sigma, crlb_position_sigma(&compute_crlb(&stations, &point, 0.001)));
+            let lines: Vec<Line> = stations.iter().map(|s| Line::from_points(*s, point).unwrap()).collect();
+            assert_eq!(gdop, geometry_dop(&lines).total);
+        }
+
+        let line = evaluate_grid(&stations, &GridSpec::new((0.0, 0.0), (0.0, 250.0), 100.0, 150.0), 0.001).unwrap();
+        assert_eq!(line.crlb_sigmas.shape(), (3, 1));
+    }
+
+    #[test]
+    fn test_coverage_inside_hull_beats_outside() {
+        let map = evaluate_grid(
+            &square_stations(),
+            &GridSpec::new((-3000.0, 3000.0), (-3000.0, 3000.0), 250.0, 150.0),
+            0.001,
+        )
+        .unwrap();
+        let (mut inside, mut outside) = (Vec::new(), Vec::new());
+        for (point, sigma, gdop) in map.points() {
+            if point.x.abs() < 400.0 && point.y.abs() < 400.0 {
+                inside.push((sigma, gdop));
+            } else if point.x.abs() > 2000.0 || point.y.abs() > 2000.0 {
+                outside.push((sigma, gdop));
+            }
+        }
+        let worst_inside = inside.iter().fold((0.0f64, 0.0f64), |a, b| (a.0.max(b.0), a.1.max(b.1)));
+        let best_outside = outside.iter().fold((f64::INFINITY, f64::INFINITY), |a, b| (a.0.min(b.0), a.1.min(b.1)));
+        assert!(worst_inside.0 < best_outside.0, "{:?} vs {:?}", worst_inside, best_outside);
+        assert!(worst_inside.1 < best_outside.1, "{:?} vs {:?}", worst_inside, best_outside);
+    }
+
+    #[test]
+    fn test_coverage_statistics_and_csv() {
+        let map = evaluate_grid(
+            &square_stations(),
+            &GridSpec::new((-1000.0, 1000.0), (-1000.0, 1000.0), 500.0, 100.0),
+            0.002,
+        )
+        .unwrap();
+        let metric = CoverageMetric::CrlbSigma;
+        let mut sorted: Vec<f64> = map.crlb_sigmas.iter().copied().collect();
+        sorted.sort_by(f64::total_cmp);
+        assert_eq!(map.min(metric), sorted[0]);
+        assert_eq!(map.max(metric), sorted[24]);
+        assert_eq!(map.percentile(metric, 0.0).unwrap(), sorted[0]);
+        assert_eq!(map.percentile(metric, 50.0).unwrap(), sorted[12]);
+        assert_eq!(map.percentile(metric, 100.0).unwrap(), sorted[24]);
+        let p55 = map.percentile(metric, 55.0).unwrap();
+        assert!((p55 - (sorted[13] + 0.2 * (sorted[14] - sorted[13]))).abs() < 1e-12);
+        assert!(map.percentile(metric, 101.0).is_err());
+        assert!(map.percentile(metric, f64::NAN).is_err());
+        assert!(map.min(CoverageMetric::Gdop) > 0.0);
+
+        let mut buffer = Vec::new();
+        map.to_csv(&mut buffer).unwrap();
+        let text = String::from_utf8(buffer).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 26);
+        assert_eq!(lines[0], COVERAGE_CSV_HEADER);
+        let first: Vec<f64> = lines[1].split(',').map(|v| v.parse().unwrap()).collect();
+        assert_eq!(first[..3], [-1000.0, -1000.0, 100.0]);
+        assert_eq!(first[3], map.crlb_sigmas[(0, 0)]);
+        assert_eq!(first[4], map.gdops[(0, 0)]);
+    }
+
+    #[test]
+    fn test_evaluate_grid_rejects_invalid_input() {
+        let stations = square_stations();
+        let ok = GridSpec::new((0.0, 1.0), (0.0, 1.0), 1.0, 0.0);
+        assert!(evaluate_grid(&stations, &ok, 0.001).is_ok());
+        for grid in [
+            GridSpec { x_range: (1.0, 0.0), ..ok },
+            GridSpec { y_range: (0.0, f64::INFINITY), ..ok },
+            GridSpec { resolution: 0.0, ..ok },
+            GridSpec { altitude: f64::NAN, ..ok },
+        ] {
+            assert!(evaluate_grid(&stations, &grid, 0.001).is_err(), "{:?}", grid);
+        }
+        assert!(evaluate_grid(&stations, &ok, -0.001).is_err());
+        assert_eq!(
+            evaluate_grid(&[Point3::new(f64::NAN, 0.0, 0.0)], &ok, 0.001),
+            Err(OptiRadarError::NonFiniteStation)
+        );
+        // 单个测量站无法定位：所有点为无穷大
+        let single = evaluate_grid(&stations[..1], &ok, 0.001).unwrap();
+        assert!(single.min(CoverageMetric::CrlbSigma).is_infinite());
+        assert!(single.min(CoverageMetric::Gdop).is_infinite());
+    }
+}
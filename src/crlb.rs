@@ -0,0 +1,158 @@
+// src/crlb.rs
+
+use crate::coverage::{evaluate_grid, GridSpec};
+use crate::error::OptiRadarError;
+use crate::target_processor::is_well_conditioned;
+use nalgebra::{Matrix3, Point3};
+
+// --- 纯测向定位的克拉美-罗下界（CRLB） ---
+//
+// 每个测量站对目标测得一个方向，方向误差在垂直于视线的两个方向上各为标准差 σ（弧度）的独立正态噪声，
+// 相当于目标在垂直于视线的平面内有标准差 r·σ 的位置误差（r 为测量站到目标的距离）。
+// 因此 Fisher 信息矩阵 J = Σ (I − d dᵀ) / (r σ)²，任何无偏估计的位置协方差不小于 J⁻¹。
+// 下界只取决于布站几何与测角精度，与定位算法无关，可在部署前评估候选站址
+// （网格上的覆盖图见 `coverage::evaluate_grid`）；测量站位置误差与测向偏差不计入。
+
+/// 目标位于 `target` 时，`station_positions` 的每个测量站各测一次方向所能达到的位置协方差下界（米²）
+///
+/// `angular_sigma` 为垂直于视线的每个方向上的测角标准差（弧度）。`GeneratorConfig::angle_noise_std`
+/// 是偏离角的均方根，对应 `angular_sigma = angle_noise_std / √2`。与目标重合的测量站不提供信息。
+/// 信息矩阵奇异（少于 2 个测量站，或所有测量站与目标共线）时各元素为无穷大。
+pub fn compute_crlb(station_positions: &[Point3<f64>], target: &Point3<f64>, angular_sigma: f64) -> Matrix3<f64> {
+    // 先按 σ = 1 累加信息矩阵，求逆后再乘 σ²，σ = 0 时下界为零
+    let information: Matrix3<f64> = station_positions
+        .iter()
+        .filter_map(|station| {
+            let offset = target - station;
+            let range_squared = offset.norm_squared();
+            (range_squared > 0.0).then(|| {
+                let direction = offset / range_squared.sqrt();
+                (Matrix3::identity() - direction * direction.transpose()) / range_squared
+            })
+        })
+        .sum();
+    match information.try_inverse().filter(|_| is_well_conditioned(&information)) {
+        Some(inverse) => inverse * (angular_sigma * angular_sigma),
+        None => Matrix3::repeat(f64::INFINITY),
+    }
+}
+
+/// 协方差下界对应的 1σ 位置误差（米）：√tr(C)，即三维定位误差均方根的下界
+pub fn crlb_position_sigma(covariance: &Matrix3<f64>) -> f64 {
+    covariance.trace().max(0.0).sqrt()
+}
+
+/// 水平网格上各点的 1σ 位置误差下界，用于绘制覆盖图，见 `compute_crlb_grid`
+#[derive(Debug, Clone, PartialEq)]
+pub struct CrlbGrid {
+    pub xs: Vec<f64>,     // 各列的 x 坐标，升序
+    pub ys: Vec<f64>,     // 各行的 y 坐标，升序
+    pub z: f64,           // 目标高度
+    pub sigmas: Vec<f64>, // 按行存储：sigmas[行 * xs.len() + 列] 对应目标位于 (xs[列], ys[行], z)
+}
+
+impl CrlbGrid {
+    /// 第 `row` 行、第 `col` 列的 1σ 位置误差下界
+    pub fn at(&self, col: usize, row: usize) -> f64 {
+        self.sigmas[row * self.xs.len() + col]
+    }
+
+    /// 按行依次给出各网格点的目标位置与误差下界
+    pub fn points(&self) -> impl Iterator<Item = (Point3<f64>, f64)> + '_ {
+        self.ys
+            .iter()
+            .flat_map(move |&y| self.xs.iter().map(move |&x| Point3::new(x, y, self.z)))
+            .zip(self.sigmas.iter().copied())
+    }
+}
+
+/// 在高度 `z` 的水平网格上逐点计算 `compute_crlb` 的 1σ 位置误差下界
+///
+/// 网格覆盖 `x_range` × `y_range`（含两端，可以退化为一行或一列），间距为 `step` 米。
+/// 只取 `coverage::evaluate_grid` 的 CRLB 一项，参数检查与其相同：范围无序或含非有限值、`step` 不为正、
+/// `z` 或 `angular_sigma` 无效、测量站坐标含非有限值时返回错误。
+pub fn compute_crlb_grid(
+    station_positions: &[Point3<f64>],
+    x_range: (f64, f64),
+    y_range: (f64, f64),
+    z: f64,
+    step: f64,
+    angular_sigma: f64,
+) -> Result<CrlbGrid, OptiRadarError> {
+    let map = evaluate_grid(station_positions, &GridSpec::new(x_range, y_range, step, z), angular_sigma)?;
+    let sigmas = map.points().map(|(_, sigma, _)| sigma).collect();
+    Ok(CrlbGrid {
+        xs: map.xs,
+        ys: map.ys,
+        z,
+        sigmas,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crlb_symmetric_layout() {
+        // 四个测量站在目标四周同一高度、距离均为 r：信息矩阵为 diag(2, 2, 4) / (rσ)²
+        let (r, sigma) = (1000.0, 0.002);
+        let target = Point3::new(10.0, 20.0, 100.0);
+        let stations: Vec<Point3<f64>> = [(r, 0.0), (-r, 0.0), (0.0, r), (0.0, -r)]
+            .iter()
+            .map(|&(dx, dy)| Point3::new(target.x + dx, target.y + dy, target.z))
+            .collect();
+        let covariance = compute_crlb(&stations, &target, sigma);
+        let scale = (r * sigma).powi(2);
+        let expected = Matrix3::from_diagonal(&nalgebra::Vector3::new(0.5, 0.5, 0.25)) * scale;
+        assert!((covariance - expected).norm() < 1e-12 * scale);
+        assert!((crlb_position_sigma(&covariance) - (1.25 * scale).sqrt()).abs() < 1e-12);
+
+        // 下界与 σ² 成正比，与目标重合的测量站被忽略
+        let mut with_coincident = stations.clone();
+        with_coincident.push(target);
+        let doubled = compute_crlb(&with_coincident, &target, 2.0 * sigma);
+        assert!((doubled - covariance * 4.0).norm() < 1e-12 * scale);
+        assert_eq!(compute_crlb(&stations, &target, 0.0), Matrix3::zeros());
+    }
+
+    #[test]
+    fn test_crlb_degenerate_geometry() {
+        let target = Point3::new(0.0, 0.0, 100.0);
+        let single = [Point3::new(500.0, 0.0, 0.0)];
+        assert!(crlb_position_sigma(&compute_crlb(&single, &target, 0.001)).is_infinite());
+        // 测量站都在过目标的同一直线上：沿该直线的位置不可观测
+        let collinear = [Point3::new(0.0, 0.0, 0.0), Point3::new(0.0, 0.0, 50.0), Point3::new(0.0, 0.0, 300.0)];
+        assert!(crlb_position_sigma(&compute_crlb(&collinear, &target, 0.001)).is_infinite());
+        assert!(crlb_position_sigma(&compute_crlb(&[], &target, 0.001)).is_infinite());
+    }
+
+    #[test]
+    fn test_crlb_grid() {
+        // 两个测量站在 x 轴上：基线附近的交会角小，误差下界远大于基线中垂线上的点
+        let stations = [Point3::new(-500.0, 0.0, 0.0), Point3::new(500.0, 0.0, 0.0)];
+        let grid = compute_crlb_grid(&stations, (-1000.0, 1000.0), (100.0, 1000.0), 50.0, 100.0, 0.001).unwrap();
+        assert_eq!(grid.xs.len(), 21);
+        assert_eq!(grid.ys.len(), 10);
+        assert_eq!(grid.sigmas.len(), 210);
+        assert_eq!((grid.xs[20], grid.ys[9]), (1000.0, 1000.0));
+        for (point, sigma) in grid.points() {
+            assert_eq!(sigma, crlb_position_sigma(&compute_crlb(&stations, &point, 0.001)));
+        }
+        // 第 5 行 y = 600，第 10 列 x = 0（中垂线），第 20 列 x = 1000（基线延长线附近）
+        assert!(grid.at(20, 5) > 2.0 * grid.at(10, 5), "{} vs {}", grid.at(20, 5), grid.at(10, 5));
+
+        let line = compute_crlb_grid(&stations, (0.0, 0.0), (0.0, 250.0), 50.0, 100.0, 0.001).unwrap();
+        assert_eq!((line.xs.len(), line.ys.len()), (1, 3));
+
+        assert!(compute_crlb_grid(&stations, (1.0, 0.0), (0.0, 1.0), 0.0, 1.0, 0.001).is_err());
+        assert!(compute_crlb_grid(&stations, (0.0, 1.0), (0.0, 1.0), 0.0, 0.0, 0.001).is_err());
+        assert!(compute_crlb_grid(&stations, (0.0, 1.0), (0.0, 1.0), f64::NAN, 1.0, 0.001).is_err());
+        assert!(compute_crlb_grid(&stations, (0.0, 1.0), (0.0, 1.0), 0.0, 1.0, -0.001).is_err());
+        let bad_station = [Point3::new(f64::NAN, 0.0, 0.0)];
+        assert_eq!(
+            compute_crlb_grid(&bad_station, (0.0, 1.0), (0.0, 1.0), 0.0, 1.0, 0.001),
+            Err(OptiRadarError::NonFiniteStation)
+        );
+    }
+}
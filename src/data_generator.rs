@@ -1,29 +1,1420 @@
 // src/data_generator.rs
 
-use crate::target_processor::Measurement;
-use nalgebra::{Point3, Vector3};
+use crate::error::OptiRadarError;
+use crate::target_processor::{find_targets_with_config, FindTargetsConfig, LocatedTarget, Measurement};
+use nalgebra::{Point3, Unit, UnitQuaternion, Vector3};
 use rand::prelude::*;
-use std::f64::consts::PI;
+use rand_distr::{Distribution, Normal};
+use std::f64::consts::{FRAC_PI_2, PI};
+#[cfg(feature = "serde")]
+use std::fs::File;
+#[cfg(feature = "serde")]
+use std::io::{self, BufReader, BufWriter, Write};
+#[cfg(feature = "serde")]
+use std::path::Path;
+
+/// 测量噪声的分布
+///
+/// 各分布下噪声参数均为标准差；标准差为 0（或非正）时不加噪声。
+/// 方向噪声为绕随机垂直轴的旋转，旋转角服从所选分布，因此测量方向与真实方向的夹角
+/// 均方根等于 `angle_noise_std`（弧度），与方向的朝向无关。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum NoiseModel {
+    /// 正态分布 N(0, σ²)
+    #[default]
+    Gaussian,
+    /// 均匀分布 U(-√3σ, √3σ)，标准差同为 σ
+    Uniform,
+    /// 旧的方向噪声模型，仅用于对比：方向各分量加 N(0, σ²) 噪声后重新单位化，
+    /// 实际角度误差约为 √2σ 且依赖于方向的朝向。位置噪声同 `Gaussian`
+    Componentwise,
+}
+
+impl NoiseModel {
+    /// 按给定标准差抽取一个噪声样本
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R, std: f64) -> f64 {
+        if std.is_nan() || std <= 0.0 {
+            return 0.0;
+        }
+        match self {
+            NoiseModel::Gaussian | NoiseModel::Componentwise => Normal::new(0.0, std).unwrap().sample(rng),
+            NoiseModel::Uniform => {
+                let half_width = 3f64.sqrt() * std;
+                rng.gen_range(-half_width..half_width)
+            }
+        }
+    }
+}
+
+/// 测量站布局
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StationLayout {
+    /// 每个目标各自生成一组独立的测量站（数量与距离见 `GeneratorConfig`），测量不带测量站标识
+    #[default]
+    PerTarget,
+    /// 在目标区域（向外扩展最大测站距离）内生成 `num_stations` 个共享测量站，
+    /// 每个测量站以 `detection_probability` 的概率观测到每个目标
+    Shared {
+        num_stations: usize,
+        detection_probability: f64,
+    },
+    /// 使用给定的共享测量站位置（例如实际部署的站址），观测规则同 `Shared`；
+    /// 忽略 `num_stations_per_target_range` 与 `station_dist_range`（后者仍决定杂波的分布范围）。
+    /// `angle_noise_stds` 非空时与 `positions` 一一对应，给出各测量站的测角噪声标准差（弧度），
+    /// 为空时按 `angle_noise_std`/`angle_noise_std_range`
+    Fixed {
+        positions: Vec<Point3<f64>>,
+        detection_probability: f64,
+        #[cfg_attr(feature = "serde", serde(default))]
+        angle_noise_stds: Vec<f64>,
+    },
+    /// 沿给定折线（例如一条道路）按长度均匀部署 `num_stations` 个共享测量站，与目标位置无关，观测规则同 `Shared`。
+    /// 站位的高度在折线顶点间线性插值（忽略 `station_z_range`），并在水平面内垂直于所在线段
+    /// 偏移 ±`lateral_jitter_m` 米内的均匀随机量。用于模拟测量站共线、横向几何很差的部署
+    Polyline {
+        vertices: Vec<Point3<f64>>,
+        num_stations: usize,
+        lateral_jitter_m: f64,
+        detection_probability: f64,
+    },
+}
+
+/// 检测概率随测量站到目标距离的衰减
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DetectionFalloff {
+    /// 与距离无关
+    #[default]
+    None,
+    /// 指数衰减：p = p0 · exp(−距离 / range_m)
+    Exponential { range_m: f64 },
+    /// 距离超过 range_m 时无法检测，以内为 p0
+    MaxRange { range_m: f64 },
+}
+
+impl DetectionFalloff {
+    /// 距离为 `range`（米）时相对基础检测概率的比例
+    pub fn factor(&self, range: f64) -> f64 {
+        match *self {
+            DetectionFalloff::None => 1.0,
+            DetectionFalloff::Exponential { range_m } => (-range / range_m).exp(),
+            DetectionFalloff::MaxRange { range_m } => {
+                if range <= range_m {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+}
+
+/// 测量站的系统性测向偏差（弧度），同一测量站的所有测量偏差相同
+///
+/// 角度约定同 `Measurement::from_az_el`：方位角从 +Y 顺时针转向 +X，俯仰角向上为正。
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StationBias {
+    pub azimuth_rad: f64,
+    pub elevation_rad: f64,
+}
+
+impl StationBias {
+    /// 将偏差叠加到单位方向向量上
+    pub fn apply(&self, direction: &Vector3<f64>) -> Vector3<f64> {
+        if self.azimuth_rad == 0.0 && self.elevation_rad == 0.0 {
+            return *direction;
+        }
+        let azimuth = direction.x.atan2(direction.y) + self.azimuth_rad;
+        let elevation = direction.z.clamp(-1.0, 1.0).asin() + self.elevation_rad;
+        let horizontal = elevation.cos();
+        Vector3::new(horizontal * azimuth.sin(), horizontal * azimuth.cos(), elevation.sin())
+    }
+}
+
+/// 常用评估场景的预设参数，由 `GeneratorConfig::preset` 展开为完整配置
+///
+/// 各预设的精度范围为集成测试（`tests/accuracy.rs`）中的 Monte Carlo 结果，
+/// 误差为匹配目标的平均定位误差，匹配率为匹配目标数占真实目标数的比例。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ScenarioPreset {
+    /// 一般场景：3 个目标分布在 4 km × 4 km 区域，每个目标 3~5 个测量站（0.5~2 km），
+    /// 测角噪声 3 mrad。RANSAC 阈值 20 米时平均误差约 5 米（测试上限 20 米），匹配率不低于 80%
+    Standard,
+    /// 远距离：3 个目标高 500~1000 米，测量站距目标 3~8 km，测角噪声 1 mrad。
+    /// 1 mrad 在该距离下已偏离目标 3~8 米，宜用角度残差（阈值 0.004 弧度），
+    /// 此时匹配率不低于 90%，平均误差约 4 米（测试上限 30 米）；距离残差（阈值 5 米）会丢失光线
+    LongRange,
+    /// 密集多目标：3 个目标挤在 20 m × 20 m × 20 m 内，每个目标 3~5 个近距离测量站（50~200 米），
+    /// 测角噪声 0.6 mrad。RANSAC 阈值 5 米时平均误差约 1.5 米（测试上限 100 米），匹配率不低于 50%；
+    /// 光线互相混淆，可开启 `reassignment_passes` 或 `em` 改善
+    DenseMultiTarget,
+    /// 高噪声：2 个目标，每个目标 10~20 个测量站（100~500 米），位置噪声 5.8 米、
+    /// 测角噪声 12 mrad。距离残差阈值 50 米或角度残差阈值 0.15 弧度时平均误差约 5 米（测试上限 100 米），
+    /// 匹配率不低于 70%
+    HighNoise,
+    /// 稀疏测量站：3 个目标，每个目标仅 2~3 个近距离测量站（50~200 米），测角噪声 1.2 mrad。
+    /// RANSAC 阈值 10 米时平均误差约 1 米（测试上限 150 米），匹配率不低于 60%；
+    /// 只有 2 条光线的目标无法剔除离群光线，误差波动较大
+    SparseStations,
+}
+
+impl ScenarioPreset {
+    /// 全部预设
+    pub const ALL: [ScenarioPreset; 5] = [
+        ScenarioPreset::Standard,
+        ScenarioPreset::LongRange,
+        ScenarioPreset::DenseMultiTarget,
+        ScenarioPreset::HighNoise,
+        ScenarioPreset::SparseStations,
+    ];
+}
+
+/// 数据生成参数
+///
+/// 推荐通过 `GeneratorConfig::builder()` 构造，`build` 会校验各范围；
+/// 直接修改字段时可调用 `validate` 检查。反序列化时缺省的字段取默认值。
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct GeneratorConfig {
+    pub num_targets: usize,                           // 目标数量
+    pub target_x_range: (f64, f64),                   // 目标 x 坐标范围
+    pub target_y_range: (f64, f64),                   // 目标 y 坐标范围
+    pub target_z_range: (f64, f64),                   // 目标 z 坐标范围
+    pub num_stations_per_target_range: (usize, usize), // 每个目标的测量站数量范围（含两端）
+    pub station_dist_range: (f64, f64),               // 测量站与目标的水平距离范围
+    pub station_z_range: (f64, f64),                  // 测量站海拔范围
+    pub pos_noise_std: f64,                           // 测量站水平位置噪声标准差
+    pub alt_noise_std: f64,                           // 测量站海拔噪声标准差
+    pub angle_noise_std: f64,                         // 测量方向角度噪声标准差（弧度）
+    pub angle_noise_std_range: Option<(f64, f64)>,    // 设置时每个测量站的测角噪声标准差从该范围均匀抽取，取代 angle_noise_std
+    pub noise_model: NoiseModel,                      // 噪声分布
+    pub azimuth_bias_range: (f64, f64),               // 每个测量站方位角偏差的均匀分布范围（弧度），默认无偏差
+    pub elevation_bias_range: (f64, f64),             // 每个测量站俯仰角偏差的均匀分布范围（弧度），默认无偏差
+    pub clutter_fraction: f64,                        // 杂波（虚警）测量占全部测量的比例，[0, 1)
+    pub station_layout: StationLayout,                // 测量站布局，默认每个目标独立测量站
+    pub detection_probability: f64,                   // 每对测量站-目标的基础检测概率 p0，默认 1.0（不漏检）
+    pub detection_falloff: DetectionFalloff,          // 检测概率随距离的衰减，默认不衰减
+    pub azimuth_step_rad: f64,                        // 测向输出的方位角量化步长（弧度），0 为不量化
+    pub elevation_step_rad: f64,                      // 测向输出的俯仰角量化步长（弧度），0 为不量化
+    pub correlated_angle_noise_std: f64,              // 每个测量站缓变相关测角误差的标准差（弧度），0 为不加，见 `correlated_angle_noise`
+    pub correlated_noise_length: f64,                 // 相关测角误差的相关长度（该站的连续测量条数），0 为各条独立
+    pub seed: Option<u64>,                            // 随机种子，None 时每次调用随机取种
+}
+
+impl Default for GeneratorConfig {
+    /// 与基准测试相同的场景：10 个目标，每个目标 5~10 个测量站
+    fn default() -> Self {
+        GeneratorConfig {
+            num_targets: 10,
+            target_x_range: (-500.0, 500.0),
+            target_y_range: (-500.0, 500.0),
+            target_z_range: (50.0, 150.0),
+            num_stations_per_target_range: (5, 10),
+            station_dist_range: (100.0, 500.0),
+            station_z_range: (10.0, 30.0),
+            pos_noise_std: 1.0,
+            alt_noise_std: 0.5,
+            angle_noise_std: 0.005,
+            angle_noise_std_range: None,
+            noise_model: NoiseModel::Gaussian,
+            azimuth_bias_range: (0.0, 0.0),
+            elevation_bias_range: (0.0, 0.0),
+            clutter_fraction: 0.0,
+            station_layout: StationLayout::PerTarget,
+            detection_probability: 1.0,
+            detection_falloff: DetectionFalloff::None,
+            azimuth_step_rad: 0.0,
+            elevation_step_rad: 0.0,
+            correlated_angle_noise_std: 0.0,
+            correlated_noise_length: 0.0,
+            seed: None,
+        }
+    }
+}
+
+/// 校验偏差范围：有限且 min ≤ max（相等时为固定偏差）
+fn check_bias_range(name: &'static str, (min, max): (f64, f64)) -> Result<(), OptiRadarError> {
+    if min.is_finite() && max.is_finite() && min <= max {
+        Ok(())
+    } else {
+        Err(OptiRadarError::InvalidRange { name, min, max })
+    }
+}
+
+/// 校验 (min, max) 范围：有限且 min < max
+fn check_range(name: &'static str, (min, max): (f64, f64)) -> Result<(), OptiRadarError> {
+    if min.is_finite() && max.is_finite() && min < max {
+        Ok(())
+    } else {
+        Err(OptiRadarError::InvalidRange { name, min, max })
+    }
+}
+
+/// 校验噪声标准差：有限且非负
+fn check_std(name: &'static str, value: f64) -> Result<(), OptiRadarError> {
+    if value.is_finite() && value >= 0.0 {
+        Ok(())
+    } else {
+        Err(OptiRadarError::InvalidParameter { name, value })
+    }
+}
+
+impl GeneratorConfig {
+    /// 以默认参数开始构造
+    pub fn builder() -> GeneratorConfigBuilder {
+        GeneratorConfigBuilder {
+            config: GeneratorConfig::default(),
+        }
+    }
+
+    /// 预设评估场景的完整配置（未设种子），其余参数取默认值
+    pub fn preset(preset: ScenarioPreset) -> GeneratorConfig {
+        let builder = GeneratorConfig::builder();
+        let builder = match preset {
+            ScenarioPreset::Standard => builder
+                .num_targets(3)
+                .target_x_range(-2000.0, 2000.0)
+                .target_y_range(-2000.0, 2000.0)
+                .target_z_range(50.0, 200.0)
+                .num_stations_per_target_range(3, 5)
+                .station_dist_range(500.0, 2000.0)
+                .station_z_range(30.0, 70.0)
+                .pos_noise_std(2.9)
+                .alt_noise_std(1.2)
+                .angle_noise_std(0.003),
+            ScenarioPreset::LongRange => builder
+                .num_targets(3)
+                .target_x_range(-500.0, 500.0)
+                .target_y_range(-500.0, 500.0)
+                .target_z_range(500.0, 1000.0)
+                .num_stations_per_target_range(4, 6)
+                .station_dist_range(3000.0, 8000.0)
+                .station_z_range(10.0, 50.0)
+                .pos_noise_std(1.0)
+                .alt_noise_std(0.5)
+                .angle_noise_std(0.001),
+            ScenarioPreset::DenseMultiTarget => builder
+                .num_targets(3)
+                .target_x_range(-10.0, 10.0)
+                .target_y_range(-10.0, 10.0)
+                .target_z_range(10.0, 30.0)
+                .num_stations_per_target_range(3, 5)
+                .station_dist_range(50.0, 200.0)
+                .station_z_range(5.0, 15.0)
+                .pos_noise_std(0.3)
+                .alt_noise_std(0.3)
+                .angle_noise_std(0.0006),
+            ScenarioPreset::HighNoise => builder
+                .num_targets(2)
+                .target_x_range(-500.0, 500.0)
+                .target_y_range(-500.0, 500.0)
+                .target_z_range(20.0, 100.0)
+                .num_stations_per_target_range(10, 20)
+                .station_dist_range(100.0, 500.0)
+                .station_z_range(10.0, 30.0)
+                .pos_noise_std(5.8)
+                .alt_noise_std(2.9)
+                .angle_noise_std(0.012),
+            ScenarioPreset::SparseStations => builder
+                .num_targets(3)
+                .target_x_range(-200.0, 200.0)
+                .target_y_range(-200.0, 200.0)
+                .target_z_range(10.0, 50.0)
+                .num_stations_per_target_range(2, 3)
+                .station_dist_range(50.0, 200.0)
+                .station_z_range(5.0, 15.0)
+                .pos_noise_std(0.6)
+                .alt_noise_std(0.3)
+                .angle_noise_std(0.0012),
+        };
+        builder.build().expect("预设参数有效")
+    }
+
+    /// 检查各范围有序、噪声标准差非负
+    pub fn validate(&self) -> Result<(), OptiRadarError> {
+        check_range("target_x_range", self.target_x_range)?;
+        check_range("target_y_range", self.target_y_range)?;
+        check_range("target_z_range", self.target_z_range)?;
+        let (min_stations, max_stations) = self.num_stations_per_target_range;
+        if min_stations > max_stations {
+            return Err(OptiRadarError::InvalidRange {
+                name: "num_stations_per_target_range",
+                min: min_stations as f64,
+                max: max_stations as f64,
+            });
+        }
+        check_range("station_dist_range", self.station_dist_range)?;
+        check_range("station_z_range", self.station_z_range)?;
+        check_std("pos_noise_std", self.pos_noise_std)?;
+        check_std("alt_noise_std", self.alt_noise_std)?;
+        check_std("angle_noise_std", self.angle_noise_std)?;
+        if let Some((min, max)) = self.angle_noise_std_range {
+            check_bias_range("angle_noise_std_range", (min, max))?;
+            check_std("angle_noise_std_range", min)?;
+        }
+        check_bias_range("azimuth_bias_range", self.azimuth_bias_range)?;
+        check_bias_range("elevation_bias_range", self.elevation_bias_range)?;
+        check_std("azimuth_step_rad", self.azimuth_step_rad)?;
+        check_std("elevation_step_rad", self.elevation_step_rad)?;
+        check_std("correlated_angle_noise_std", self.correlated_angle_noise_std)?;
+        check_std("correlated_noise_length", self.correlated_noise_length)?;
+        if !(0.0..1.0).contains(&self.clutter_fraction) {
+            return Err(OptiRadarError::InvalidParameter {
+                name: "clutter_fraction",
+                value: self.clutter_fraction,
+            });
+        }
+        match &self.station_layout {
+            StationLayout::PerTarget => {}
+            StationLayout::Shared { detection_probability, .. }
+            | StationLayout::Fixed { detection_probability, .. }
+            | StationLayout::Polyline { detection_probability, .. } => {
+                if !(0.0..=1.0).contains(detection_probability) {
+                    return Err(OptiRadarError::InvalidParameter {
+                        name: "detection_probability",
+                        value: *detection_probability,
+                    });
+                }
+            }
+        }
+        if let StationLayout::Fixed {
+            positions,
+            angle_noise_stds,
+            ..
+        } = &self.station_layout
+        {
+            if positions.iter().any(|p| !p.iter().all(|v| v.is_finite())) {
+                return Err(OptiRadarError::NonFiniteStation);
+            }
+            if !angle_noise_stds.is_empty() && angle_noise_stds.len() != positions.len() {
+                return Err(OptiRadarError::InvalidParameter {
+                    name: "angle_noise_stds.len",
+                    value: angle_noise_stds.len() as f64,
+                });
+            }
+            for &std in angle_noise_stds {
+                check_std("angle_noise_stds", std)?;
+            }
+        }
+        if let StationLayout::Polyline {
+            vertices,
+            lateral_jitter_m,
+            ..
+        } = &self.station_layout
+        {
+            if vertices.len() < 2 {
+                return Err(OptiRadarError::InvalidParameter {
+                    name: "vertices.len",
+                    value: vertices.len() as f64,
+                });
+            }
+            if vertices.iter().any(|p| !p.iter().all(|v| v.is_finite())) {
+                return Err(OptiRadarError::NonFiniteStation);
+            }
+            let length = polyline_length(vertices);
+            if length <= 0.0 {
+                return Err(OptiRadarError::InvalidParameter {
+                    name: "polyline_length",
+                    value: length,
+                });
+            }
+            check_std("lateral_jitter_m", *lateral_jitter_m)?;
+        }
+        if !(0.0..=1.0).contains(&self.detection_probability) {
+            return Err(OptiRadarError::InvalidParameter {
+                name: "detection_probability",
+                value: self.detection_probability,
+            });
+        }
+        match self.detection_falloff {
+            DetectionFalloff::None => {}
+            DetectionFalloff::Exponential { range_m } | DetectionFalloff::MaxRange { range_m } => {
+                if !(range_m.is_finite() && range_m > 0.0) {
+                    return Err(OptiRadarError::InvalidParameter {
+                        name: "detection_falloff.range_m",
+                        value: range_m,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 测量站与目标相距 `range`（米）时的检测概率（不含共享布局的 `detection_probability`）
+    pub fn detection_probability_at(&self, range: f64) -> f64 {
+        self.detection_probability * self.detection_falloff.factor(range)
+    }
+}
+
+/// `GeneratorConfig` 的构造器，未设置的参数取默认值
+#[derive(Debug, Clone)]
+pub struct GeneratorConfigBuilder {
+    config: GeneratorConfig,
+}
+
+impl GeneratorConfigBuilder {
+    pub fn num_targets(mut self, num_targets: usize) -> Self {
+        self.config.num_targets = num_targets;
+        self
+    }
+
+    pub fn target_x_range(mut self, min: f64, max: f64) -> Self {
+        self.config.target_x_range = (min, max);
+        self
+    }
+
+    pub fn target_y_range(mut self, min: f64, max: f64) -> Self {
+        self.config.target_y_range = (min, max);
+        self
+    }
+
+    pub fn target_z_range(mut self, min: f64, max: f64) -> Self {
+        self.config.target_z_range = (min, max);
+        self
+    }
+
+    /// 每个目标的测量站数量范围（含两端，允许 min == max）
+    pub fn num_stations_per_target_range(mut self, min: usize, max: usize) -> Self {
+        self.config.num_stations_per_target_range = (min, max);
+        self
+    }
+
+    pub fn station_dist_range(mut self, min: f64, max: f64) -> Self {
+        self.config.station_dist_range = (min, max);
+        self
+    }
+
+    pub fn station_z_range(mut self, min: f64, max: f64) -> Self {
+        self.config.station_z_range = (min, max);
+        self
+    }
+
+    pub fn pos_noise_std(mut self, std: f64) -> Self {
+        self.config.pos_noise_std = std;
+        self
+    }
+
+    pub fn alt_noise_std(mut self, std: f64) -> Self {
+        self.config.alt_noise_std = std;
+        self
+    }
+
+    pub fn angle_noise_std(mut self, std: f64) -> Self {
+        self.config.angle_noise_std = std;
+        self
+    }
+
+    /// 每个测量站的测角噪声标准差从 [min, max) 均匀抽取（min = max 时固定为 min）
+    pub fn angle_noise_std_range(mut self, min: f64, max: f64) -> Self {
+        self.config.angle_noise_std_range = Some((min, max));
+        self
+    }
+
+    pub fn noise_model(mut self, noise_model: NoiseModel) -> Self {
+        self.config.noise_model = noise_model;
+        self
+    }
+
+    /// 杂波测量占全部测量的比例，须在 [0, 1) 内
+    pub fn azimuth_bias_range(mut self, min: f64, max: f64) -> Self {
+        self.config.azimuth_bias_range = (min, max);
+        self
+    }
+
+    pub fn elevation_bias_range(mut self, min: f64, max: f64) -> Self {
+        self.config.elevation_bias_range = (min, max);
+        self
+    }
+
+    pub fn clutter_fraction(mut self, fraction: f64) -> Self {
+        self.config.clutter_fraction = fraction;
+        self
+    }
+
+    pub fn station_layout(mut self, layout: StationLayout) -> Self {
+        self.config.station_layout = layout;
+        self
+    }
+
+    pub fn detection_probability(mut self, probability: f64) -> Self {
+        self.config.detection_probability = probability;
+        self
+    }
+
+    pub fn detection_falloff(mut self, falloff: DetectionFalloff) -> Self {
+        self.config.detection_falloff = falloff;
+        self
+    }
+
+    /// 测向输出的方位角/俯仰角量化步长（弧度），0 为不量化
+    pub fn angle_quantization(mut self, azimuth_step_rad: f64, elevation_step_rad: f64) -> Self {
+        self.config.azimuth_step_rad = azimuth_step_rad;
+        self.config.elevation_step_rad = elevation_step_rad;
+        self
+    }
+
+    /// 每个测量站叠加在白噪声之上的缓变相关测角误差（大气折射、平台晃动等）
+    ///
+    /// 误差的两个角分量（水平横向、俯仰）各为 AR(1) 过程，按该测量站的测量次序推进，
+    /// 相邻两条的相关系数为 exp(−1 / `length`)；平稳时偏离角的均方根为 `std`（弧度），
+    /// 因此与 `angle_noise_std` 的平方和即总测角方差。`length` 为 0 时各条测量的误差独立
+    pub fn correlated_angle_noise(mut self, std: f64, length: f64) -> Self {
+        self.config.correlated_angle_noise_std = std;
+        self.config.correlated_noise_length = length;
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.config.seed = Some(seed);
+        self
+    }
+
+    /// 校验参数并生成配置，范围无序等错误返回 `OptiRadarError`
+    pub fn build(self) -> Result<GeneratorConfig, OptiRadarError> {
+        self.config.validate()?;
+        Ok(self.config)
+    }
+}
+
+/// 模拟场景：真实目标、测量站、测量及每条测量的真值关联
+///
+/// `measurements`、`station_positions`、`station_biases`、`angle_noise_stds`、`correlated_errors` 与 `labels`
+/// 按索引一一对应。
+/// 启用 `serde` 特性时可用 `save_json`/`load_json` 保存为 JSON 文件，以便复现实验或附在问题报告中。
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Scenario {
+    pub true_targets: Vec<Point3<f64>>,      // 目标的真实、无噪声位置
+    pub station_positions: Vec<Point3<f64>>, // 每条测量对应测量站的真实、无噪声位置
+    pub measurements: Vec<Measurement>,      // 带噪声的测量
+    pub station_biases: Vec<StationBias>,    // 每条测量对应测量站的测向偏差，杂波为零
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub angle_noise_stds: Vec<Option<f64>>,  // 每条测量对应测量站的测角噪声标准差（弧度），杂波为 None
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub correlated_errors: Vec<StationBias>, // 每条测量实际叠加的相关测角误差（方位角/俯仰角），未启用时与杂波为零
+    pub labels: Vec<Option<usize>>,          // 每条测量瞄准的目标在 true_targets 中的索引，杂波为 None
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub detections: Vec<DetectionOpportunity>, // 所有可能的测量站-目标观测，含漏检的
+    // 生成参数；`seed` 为实际使用的种子，由外部随机数发生器生成（`*_with_rng`、多帧数据的单帧）时为 None
+    pub config: GeneratorConfig,
+}
+
+/// 一对可能的测量站-目标观测
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DetectionOpportunity {
+    pub target: usize,                  // 目标在 true_targets 中的索引
+    pub station_position: Point3<f64>, // 测量站的真实、无噪声位置
+    pub range_m: f64,                   // 测量站到目标的距离（米）
+    pub probability: f64,               // 检测概率
+    pub measurement: Option<usize>,     // 生成的测量在 measurements 中的索引，漏检为 None
+}
+
+impl Scenario {
+    /// 实际检测到的比例（检测数 / 可能的观测数），没有可能的观测时为 None
+    pub fn detection_rate(&self) -> Option<f64> {
+        if self.detections.is_empty() {
+            return None;
+        }
+        let detected = self.detections.iter().filter(|d| d.measurement.is_some()).count();
+        Some(detected as f64 / self.detections.len() as f64)
+    }
+
+    /// 瞄准第 `target` 个目标的测量索引
+    pub fn measurements_of(&self, target: usize) -> Vec<usize> {
+        self.labels
+            .iter()
+            .enumerate()
+            .filter(|(_, label)| **label == Some(target))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// 杂波测量的索引
+    pub fn clutter_indices(&self) -> Vec<usize> {
+        self.labels
+            .iter()
+            .enumerate()
+            .filter(|(_, label)| label.is_none())
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// 按各测量的真实测角噪声设置权重后的测量
+    ///
+    /// 权重为 (σ̄/σ)²，σ̄ 为各目标测量 σ 的均方根，使权重在 1 附近；杂波、σ 为 0 或未记录 σ 的测量权重不变。
+    pub fn noise_weighted_measurements(&self) -> Vec<Measurement> {
+        let sigmas: Vec<f64> = self.angle_noise_stds.iter().flatten().copied().filter(|&s| s > 0.0).collect();
+        let reference = (sigmas.iter().map(|s| s * s).sum::<f64>() / sigmas.len().max(1) as f64).sqrt();
+        self.measurements
+            .iter()
+            .enumerate()
+            .map(|(k, m)| match self.angle_noise_stds.get(k).copied().flatten() {
+                Some(sigma) if sigma > 0.0 => m.clone().with_weight((reference / sigma).powi(2)),
+                _ => m.clone(),
+            })
+            .collect()
+    }
+
+    /// 各测量站实际叠加的相关测角误差序列，见 `correlated_error_sequences`
+    pub fn correlated_error_sequences(&self) -> Vec<(Point3<f64>, Vec<StationBias>)> {
+        correlated_error_sequences([self])
+    }
+
+    /// 对场景中的测量运行 `find_targets_with_config`，可用 `evaluation::match_targets` 与 `true_targets` 比较
+    pub fn locate(&self, config: &FindTargetsConfig) -> Vec<LocatedTarget> {
+        find_targets_with_config(&self.measurements, config)
+    }
+
+    /// 将场景写为 JSON 文件
+    #[cfg(feature = "serde")]
+    pub fn save_json(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(&mut writer, self)?;
+        writer.flush()
+    }
+
+    /// 读取 `save_json` 写出的场景
+    #[cfg(feature = "serde")]
+    pub fn load_json(path: impl AsRef<Path>) -> io::Result<Scenario> {
+        Ok(serde_json::from_reader(BufReader::new(File::open(path)?))?)
+    }
+}
+
+/// 按测量站（以真实站位区分）汇总一组场景中实际叠加的相关测角误差
+///
+/// 测量站按首次出现的顺序排列，每个序列按测量次序排列；多帧数据依次传入各帧的场景时即为跨帧的误差序列。
+/// 不含杂波；未记录相关误差的旧场景按零计。
+pub fn correlated_error_sequences<'a>(
+    scenarios: impl IntoIterator<Item = &'a Scenario>,
+) -> Vec<(Point3<f64>, Vec<StationBias>)> {
+    let mut sequences: Vec<(Point3<f64>, Vec<StationBias>)> = Vec::new();
+    for scenario in scenarios {
+        for (k, station) in scenario.station_positions.iter().enumerate() {
+            if scenario.labels.get(k).copied().flatten().is_none() {
+                continue;
+            }
+            let error = scenario.correlated_errors.get(k).copied().unwrap_or_default();
+            match sequences.iter_mut().find(|(position, _)| position == station) {
+                Some((_, sequence)) => sequence.push(error),
+                None => sequences.push((*station, vec![error])),
+            }
+        }
+    }
+    sequences
+}
 
 /// 生成模拟雷达测量数据和真实目标位置。
 ///
 /// 此函数为多个目标创建一组测量数据，其中包括
-/// 测量站位置和测量角度中逼真的噪声。
-///
-/// # 参数
-/// * `num_targets` - 要生成的目标数量。
-/// * `target_x_range`, `target_y_range`, `target_z_range` - 目标位置的最小/最大范围。
-/// * `num_stations_per_target_range` - 每个目标对应的测量站数量的最小/最大范围元组。
-/// * `station_dist_range` - 测量站与其各自目标之间距离的最小/最大范围。
-/// * `station_z_range` - 测量站海拔高度的最小/最大范围。
-/// * `pos_noise_std` - 测量站位置噪声的标准差。
-/// * `alt_noise_std` - 测量站海拔高度噪声的标准差。
-/// * `angle_noise_std` - 测量角度噪声的标准差。
+/// 测量站位置和测量角度中逼真的噪声。噪声服从 `config.noise_model` 指定的分布
+/// （默认正态分布；早期版本为宽度等于参数的均匀分布）。
+/// 设置 `config.seed` 时目标位置、测量站布局和噪声全部由种子决定，结果可复现。
+/// `config.clutter_fraction` 大于 0 时，测量末尾附加不对应任何目标的杂波测量。
+///
+/// `config` 应已通过校验（见 `GeneratorConfig::validate`），否则可能 panic。
 ///
 /// # 返回值
 /// 一个元组，包含：
 /// * `Vec<Point3<f64>>` - 目标的真实、无噪声位置的向量。
 /// * `Vec<Measurement>` - 生成的带噪声的测量数据的向量。
+pub fn generate_data_from_config(config: &GeneratorConfig) -> (Vec<Point3<f64>>, Vec<Measurement>) {
+    let scenario = generate_scenario(config);
+    (scenario.true_targets, scenario.measurements)
+}
+
+/// 使用给定随机数发生器的 `generate_data_from_config`，忽略 `config.seed`
+pub fn generate_data_from_config_with_rng<R: Rng + ?Sized>(
+    rng: &mut R,
+    config: &GeneratorConfig,
+) -> (Vec<Point3<f64>>, Vec<Measurement>) {
+    let scenario = generate_scenario_with_rng(rng, config);
+    (scenario.true_targets, scenario.measurements)
+}
+
+/// 同 `generate_data_from_config`，并返回每条测量的真值关联
+///
+/// 未设置 `config.seed` 时随机取种，场景的 `config.seed` 记录实际使用的种子，可据此重新生成。
+pub fn generate_scenario(config: &GeneratorConfig) -> Scenario {
+    let seed = config.seed.unwrap_or_else(|| thread_rng().gen());
+    let mut scenario = generate_scenario_with_rng(&mut StdRng::seed_from_u64(seed), config);
+    scenario.config.seed = Some(seed);
+    scenario
+}
+
+/// 为给定的目标位置生成测量，目标不再随机抽取
+///
+/// 忽略 `config.num_targets` 和目标范围（杂波和共享测量站仍按目标范围分布），
+/// 测量站布设与噪声同 `generate_data_from_config`。返回的测量与 `generate_scenario_for_targets`
+/// 相同，可用于精确摆放目标的对照实验（例如分辨率测试）。
+pub fn generate_data_for_targets(true_targets: &[Point3<f64>], config: &GeneratorConfig) -> Vec<Measurement> {
+    generate_scenario_for_targets(true_targets, config).measurements
+}
+
+/// 同 `generate_data_for_targets`，并返回每条测量的真值关联；种子的处理同 `generate_scenario`
+pub fn generate_scenario_for_targets(true_targets: &[Point3<f64>], config: &GeneratorConfig) -> Scenario {
+    let seed = config.seed.unwrap_or_else(|| thread_rng().gen());
+    let mut scenario = generate_scenario_for_targets_with_rng(&mut StdRng::seed_from_u64(seed), true_targets, config);
+    scenario.config.seed = Some(seed);
+    scenario
+}
+
+/// 使用给定随机数发生器的 `generate_scenario_for_targets`，忽略 `config.seed`
+pub fn generate_scenario_for_targets_with_rng<R: Rng + ?Sized>(
+    rng: &mut R,
+    true_targets: &[Point3<f64>],
+    config: &GeneratorConfig,
+) -> Scenario {
+    simulate(rng, config, true_targets.len(), |_, target_index| true_targets[target_index])
+}
+
+/// 在目标范围内随机生成一个目标位置
+fn random_target<R: Rng + ?Sized>(rng: &mut R, config: &GeneratorConfig) -> Point3<f64> {
+    Point3::new(
+        rng.gen_range(config.target_x_range.0..config.target_x_range.1),
+        rng.gen_range(config.target_y_range.0..config.target_y_range.1),
+        rng.gen_range(config.target_z_range.0..config.target_z_range.1),
+    )
+}
+
+/// 给测量站真实位置加上位置/海拔噪声
+fn noisy_station<R: Rng + ?Sized>(
+    rng: &mut R,
+    config: &GeneratorConfig,
+    true_station_pos: &Point3<f64>,
+) -> Point3<f64> {
+    let noise = config.noise_model;
+    Point3::new(
+        true_station_pos.x + noise.sample(rng, config.pos_noise_std),
+        true_station_pos.y + noise.sample(rng, config.pos_noise_std),
+        true_station_pos.z + noise.sample(rng, config.alt_noise_std),
+    )
+}
+
+/// 与单位向量 `direction` 垂直、方位均匀分布的随机单位向量
+fn random_perpendicular<R: Rng + ?Sized>(rng: &mut R, direction: &Vector3<f64>) -> Unit<Vector3<f64>> {
+    // 取与 direction 夹角最大的坐标轴构造垂直平面的正交基
+    let helper = if direction.x.abs() < 0.9 { Vector3::x() } else { Vector3::y() };
+    let u = direction.cross(&helper).normalize();
+    let v = direction.cross(&u);
+    let phi = rng.gen_range(0.0..2.0 * PI);
+    Unit::new_normalize(u * phi.cos() + v * phi.sin())
+}
+
+/// 抽取一个测量站的测向偏差；范围两端相等时不消耗随机数
+fn random_bias<R: Rng + ?Sized>(rng: &mut R, config: &GeneratorConfig) -> StationBias {
+    let mut draw = |(min, max): (f64, f64)| if min < max { rng.gen_range(min..max) } else { min };
+    StationBias {
+        azimuth_rad: draw(config.azimuth_bias_range),
+        elevation_rad: draw(config.elevation_bias_range),
+    }
+}
+
+/// 抽取一个测量站的测角噪声标准差
+///
+/// 固定站址布局给出 `angle_noise_stds` 时取第 `station_index` 个；否则设置了 `angle_noise_std_range`
+/// 时均匀抽取（范围两端相等时不消耗随机数），未设置时为 `angle_noise_std`，不消耗随机数。
+fn station_angle_noise_std<R: Rng + ?Sized>(rng: &mut R, config: &GeneratorConfig, station_index: Option<usize>) -> f64 {
+    if let (StationLayout::Fixed { angle_noise_stds, .. }, Some(k)) = (&config.station_layout, station_index) {
+        if !angle_noise_stds.is_empty() {
+            return angle_noise_stds[k];
+        }
+    }
+    match config.angle_noise_std_range {
+        Some((min, max)) if min < max => rng.gen_range(min..max),
+        Some((min, _)) => min,
+        None => config.angle_noise_std,
+    }
+}
+
+/// 一个测量站的相关测角误差状态，见 `GeneratorConfigBuilder::correlated_angle_noise`
+///
+/// 状态为垂直于视线的水平横向与俯仰两个角分量（弧度），每个分量的平稳标准差为 σ/√2，
+/// 偏离角的均方根因此为 σ。首条测量从平稳分布抽取，之后按 AR(1) 递推。
+#[derive(Debug, Clone, Copy, Default)]
+struct CorrelatedError {
+    state: Option<(f64, f64)>,
+}
+
+impl CorrelatedError {
+    /// 推进到该测量站的下一条测量，返回对指向 `direction` 的测量叠加的方位角/俯仰角误差；
+    /// 未启用（标准差为 0）时返回零且不消耗随机数
+    fn next<R: Rng + ?Sized>(&mut self, rng: &mut R, config: &GeneratorConfig, direction: &Vector3<f64>) -> StationBias {
+        let std = config.correlated_angle_noise_std;
+        if std <= 0.0 {
+            return StationBias::default();
+        }
+        let normal = Normal::new(0.0, std / 2f64.sqrt()).unwrap();
+        let (cross, elevation) = match self.state {
+            None => (normal.sample(rng), normal.sample(rng)),
+            Some((cross, elevation)) => {
+                let rho = if config.correlated_noise_length > 0.0 {
+                    (-1.0 / config.correlated_noise_length).exp()
+                } else {
+                    0.0
+                };
+                let innovation = (1.0 - rho * rho).sqrt();
+                (
+                    rho * cross + innovation * normal.sample(rng),
+                    rho * elevation + innovation * normal.sample(rng),
+                )
+            }
+        };
+        self.state = Some((cross, elevation));
+        // 水平横向偏离 cross 对应方位角变化 cross / cos(俯仰角)
+        let cos_elevation = (1.0 - direction.z * direction.z).max(0.0).sqrt().max(1e-9);
+        StationBias {
+            azimuth_rad: cross / cos_elevation,
+            elevation_rad: elevation,
+        }
+    }
+}
+
+/// 由（带噪声的）测量站位置和指向目标的真实方向生成测量，方向加标准差为 `angle_noise_std` 的噪声后
+/// 依次叠加相关测角误差与测量站偏差，最后按 `azimuth_step_rad`/`elevation_step_rad` 量化
+fn noisy_measurement<R: Rng + ?Sized>(
+    rng: &mut R,
+    config: &GeneratorConfig,
+    measured_station_pos: &Point3<f64>,
+    bias: &StationBias,
+    correlated_error: &StationBias,
+    angle_noise_std: f64,
+    true_direction: &Vector3<f64>,
+) -> Measurement {
+    let noise = config.noise_model;
+    let measured_direction = match noise {
+        NoiseModel::Componentwise => Vector3::new(
+            true_direction.x + noise.sample(rng, angle_noise_std),
+            true_direction.y + noise.sample(rng, angle_noise_std),
+            true_direction.z + noise.sample(rng, angle_noise_std),
+        )
+        .normalize(),
+        NoiseModel::Gaussian | NoiseModel::Uniform => {
+            let axis = random_perpendicular(rng, true_direction);
+            let angle = noise.sample(rng, angle_noise_std);
+            UnitQuaternion::from_axis_angle(&axis, angle) * true_direction
+        }
+    };
+    let measured_direction = bias.apply(&correlated_error.apply(&measured_direction));
+
+    let m = Measurement::new(
+        measured_station_pos.x,
+        measured_station_pos.y,
+        measured_station_pos.z,
+        measured_direction.x,
+        measured_direction.y,
+        measured_direction.z,
+    );
+    quantize_direction(config, m)
+}
+
+/// 将测量方向的方位角/俯仰角各自舍入到最近的量化步长；两个步长均为 0 时原样返回
+///
+/// 俯仰角舍入后限制在 [-π/2, π/2]。
+fn quantize_direction(config: &GeneratorConfig, m: Measurement) -> Measurement {
+    if config.azimuth_step_rad == 0.0 && config.elevation_step_rad == 0.0 {
+        return m;
+    }
+    let round = |angle: f64, step: f64| if step > 0.0 { (angle / step).round() * step } else { angle };
+    let (azimuth, elevation) = m.azimuth_elevation();
+    let elevation = round(elevation, config.elevation_step_rad).clamp(-FRAC_PI_2, FRAC_PI_2);
+    Measurement::from_az_el(m.x, m.y, m.z, round(azimuth, config.azimuth_step_rad), elevation)
+}
+
+/// 按检测模型决定测量站能否观测到目标，返回这次观测机会
+///
+/// 先以共享布局的 `layout_probability` 抽取，再以 `GeneratorConfig::detection_probability_at` 抽取；
+/// 概率为 1 时不消耗随机数，因此默认参数下随机数的消耗与不建模漏检时相同。
+/// 检测到时 `measurement` 为 `next_measurement`。
+fn detect<R: Rng + ?Sized>(
+    rng: &mut R,
+    config: &GeneratorConfig,
+    layout_probability: f64,
+    target: usize,
+    true_target_pos: &Point3<f64>,
+    true_station_pos: &Point3<f64>,
+    next_measurement: usize,
+) -> DetectionOpportunity {
+    let range_m = (true_target_pos - true_station_pos).norm();
+    let probability = config.detection_probability_at(range_m);
+    let detected = rng.gen_bool(layout_probability) && (probability >= 1.0 || rng.gen_bool(probability));
+    DetectionOpportunity {
+        target,
+        station_position: *true_station_pos,
+        range_m,
+        probability: layout_probability * probability,
+        measurement: detected.then_some(next_measurement),
+    }
+}
+
+/// 使用给定随机数发生器的 `generate_scenario`，忽略 `config.seed`
+pub fn generate_scenario_with_rng<R: Rng + ?Sized>(rng: &mut R, config: &GeneratorConfig) -> Scenario {
+    simulate(rng, config, config.num_targets, |rng, _| random_target(rng, config))
+}
+
+/// 模拟 `num_targets` 个目标的测量
+///
+/// 目标位置由 `target_at(rng, 目标序号)` 按序号依次取得；逐目标布局下与该目标的测量站交替生成，
+/// 因此随机目标与指定目标共用同一流程且随机数的消耗顺序不变。
+fn simulate<R, F>(rng: &mut R, config: &GeneratorConfig, num_targets: usize, mut target_at: F) -> Scenario
+where
+    R: Rng + ?Sized,
+    F: FnMut(&mut R, usize) -> Point3<f64>,
+{
+    let mut all_data = Vec::new();
+    let mut station_positions = Vec::new();
+    let mut station_biases = Vec::new();
+    let mut angle_noise_stds = Vec::new();
+    let mut correlated_errors = Vec::new();
+    let mut labels = Vec::new();
+    let mut detections = Vec::new();
+    let mut true_targets = Vec::new();
+
+    match &config.station_layout {
+        StationLayout::PerTarget => {
+            for target_index in 0..num_targets {
+                let true_target_pos = target_at(rng, target_index);
+                true_targets.push(true_target_pos);
+
+                let (min_stations, max_stations) = config.num_stations_per_target_range;
+                let num_stations = rng.gen_range(min_stations..=max_stations);
+                for _ in 0..num_stations {
+                    // 直接在笛卡尔坐标系中生成测量站位置
+                    let angle = rng.gen_range(0.0..2.0 * PI);
+                    let dist = rng.gen_range(config.station_dist_range.0..config.station_dist_range.1);
+                    let true_station_pos = Point3::new(
+                        true_target_pos.x + dist * angle.cos(),
+                        true_target_pos.y + dist * angle.sin(),
+                        rng.gen_range(config.station_z_range.0..config.station_z_range.1),
+                    );
+
+                    let detection =
+                        detect(rng, config, 1.0, target_index, &true_target_pos, &true_station_pos, all_data.len());
+                    detections.push(detection);
+                    if detection.measurement.is_none() {
+                        continue;
+                    }
+                    let true_direction = (true_target_pos - true_station_pos).normalize();
+
+                    // 添加噪声
+                    let measured_station_pos = noisy_station(rng, config, &true_station_pos);
+                    let bias = random_bias(rng, config);
+                    let std = station_angle_noise_std(rng, config, None);
+                    let correlated = CorrelatedError::default().next(rng, config, &true_direction);
+                    all_data.push(noisy_measurement(
+                        rng,
+                        config,
+                        &measured_station_pos,
+                        &bias,
+                        &correlated,
+                        std,
+                        &true_direction,
+                    ));
+                    station_positions.push(true_station_pos);
+                    station_biases.push(bias);
+                    angle_noise_stds.push(Some(std));
+                    correlated_errors.push(correlated);
+                    labels.push(Some(target_index));
+                }
+            }
+        }
+        layout @ (StationLayout::Shared { .. } | StationLayout::Fixed { .. } | StationLayout::Polyline { .. }) => {
+            true_targets = (0..num_targets).map(|i| target_at(rng, i)).collect();
+
+            let (stations, detection_probability) = shared_stations(rng, config, layout);
+
+            // 每个测量站的位置误差、测向偏差和测角噪声水平固定，对其观测到的所有目标相同；
+            // 相关测角误差按该站的测量次序推进
+            for (station_index, true_station_pos) in stations.iter().enumerate() {
+                let measured_station_pos = noisy_station(rng, config, true_station_pos);
+                let bias = random_bias(rng, config);
+                let std = station_angle_noise_std(rng, config, Some(station_index));
+                let mut correlated_error = CorrelatedError::default();
+                for (target_index, true_target_pos) in true_targets.iter().enumerate() {
+                    let detection = detect(
+                        rng,
+                        config,
+                        detection_probability,
+                        target_index,
+                        true_target_pos,
+                        true_station_pos,
+                        all_data.len(),
+                    );
+                    detections.push(detection);
+                    if detection.measurement.is_none() {
+                        continue;
+                    }
+                    let true_direction = (true_target_pos - true_station_pos).normalize();
+                    let correlated = correlated_error.next(rng, config, &true_direction);
+                    all_data.push(
+                        noisy_measurement(rng, config, &measured_station_pos, &bias, &correlated, std, &true_direction)
+                            .with_station_id(format!("S{}", station_index)),
+                    );
+                    station_positions.push(*true_station_pos);
+                    station_biases.push(bias);
+                    angle_noise_stds.push(Some(std));
+                    correlated_errors.push(correlated);
+                    labels.push(Some(target_index));
+                }
+            }
+        }
+    }
+
+    let mut scenario = Scenario {
+        true_targets,
+        station_positions,
+        station_biases,
+        angle_noise_stds,
+        correlated_errors,
+        measurements: all_data,
+        labels,
+        detections,
+        config: GeneratorConfig {
+            seed: None,
+            ..config.clone()
+        },
+    };
+    append_clutter(rng, config, &mut scenario);
+    scenario
+}
+
+/// 在目标区域向外扩展最大测站距离的范围内随机生成一个测量站位置
+fn random_site<R: Rng + ?Sized>(rng: &mut R, config: &GeneratorConfig) -> Point3<f64> {
+    let max_dist = config.station_dist_range.1;
+    Point3::new(
+        rng.gen_range(config.target_x_range.0 - max_dist..config.target_x_range.1 + max_dist),
+        rng.gen_range(config.target_y_range.0 - max_dist..config.target_y_range.1 + max_dist),
+        rng.gen_range(config.station_z_range.0..config.station_z_range.1),
+    )
+}
+
+/// 共享测量站布局的真实站位和检测概率
+fn shared_stations<R: Rng + ?Sized>(
+    rng: &mut R,
+    config: &GeneratorConfig,
+    layout: &StationLayout,
+) -> (Vec<Point3<f64>>, f64) {
+    match layout {
+        StationLayout::Shared {
+            num_stations,
+            detection_probability,
+        } => ((0..*num_stations).map(|_| random_site(rng, config)).collect(), *detection_probability),
+        StationLayout::Fixed {
+            positions,
+            detection_probability,
+            ..
+        } => (positions.clone(), *detection_probability),
+        StationLayout::Polyline {
+            vertices,
+            num_stations,
+            lateral_jitter_m,
+            detection_probability,
+        } => (
+            (0..*num_stations).map(|_| polyline_site(rng, vertices, *lateral_jitter_m)).collect(),
+            *detection_probability,
+        ),
+        StationLayout::PerTarget => unreachable!("逐目标布局没有共享测量站"),
+    }
+}
+
+/// 折线总长度
+fn polyline_length(vertices: &[Point3<f64>]) -> f64 {
+    vertices.windows(2).map(|w| (w[1] - w[0]).norm()).sum()
+}
+
+/// 沿折线按长度均匀抽取一个测量站位置，再在水平面内垂直于所在线段偏移 ±`lateral_jitter_m` 内的均匀随机量
+/// （偏移为 0 时不消耗随机数；竖直线段没有水平法向，不偏移）
+fn polyline_site<R: Rng + ?Sized>(rng: &mut R, vertices: &[Point3<f64>], lateral_jitter_m: f64) -> Point3<f64> {
+    let mut remaining = rng.gen_range(0.0..polyline_length(vertices));
+    let mut k = 0;
+    while k + 2 < vertices.len() && remaining >= (vertices[k + 1] - vertices[k]).norm() {
+        remaining -= (vertices[k + 1] - vertices[k]).norm();
+        k += 1;
+    }
+    let along = vertices[k + 1] - vertices[k];
+    let length = along.norm();
+    let t = if length > 0.0 { (remaining / length).min(1.0) } else { 0.0 };
+    let mut site = vertices[k] + along * t;
+    let normal = Vector3::new(-along.y, along.x, 0.0);
+    if lateral_jitter_m > 0.0 && normal.norm() > 0.0 {
+        site += normal.normalize() * rng.gen_range(-lateral_jitter_m..lateral_jitter_m);
+    }
+    site
+}
+
+/// 在场景末尾附加杂波测量
+fn append_clutter<R: Rng + ?Sized>(rng: &mut R, config: &GeneratorConfig, scenario: &mut Scenario) {
+    // 杂波：随机位置的测量站朝上半球随机方向的测量，使其占全部测量的 clutter_fraction
+    let num_clutter = (scenario.measurements.len() as f64 * config.clutter_fraction
+        / (1.0 - config.clutter_fraction))
+        .round() as usize;
+    for _ in 0..num_clutter {
+        let station = random_site(rng, config);
+        // 上半球均匀分布：z 分量在 [0, 1) 均匀
+        let azimuth = rng.gen_range(0.0..2.0 * PI);
+        let z: f64 = rng.gen_range(0.0..1.0);
+        let horizontal = (1.0 - z * z).sqrt();
+        scenario.measurements.push(Measurement::new(
+            station.x,
+            station.y,
+            station.z,
+            horizontal * azimuth.cos(),
+            horizontal * azimuth.sin(),
+            z,
+        ));
+        scenario.station_positions.push(station);
+        scenario.station_biases.push(StationBias::default());
+        scenario.angle_noise_stds.push(None);
+        scenario.correlated_errors.push(StationBias::default());
+        scenario.labels.push(None);
+    }
+}
+
+/// 匀速直线运动目标的轨迹
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TargetTrajectory {
+    pub initial_position: Point3<f64>, // 时刻 0 的位置
+    pub velocity: Vector3<f64>,        // 速度（米/秒）
+}
+
+impl TargetTrajectory {
+    pub fn new(initial_position: Point3<f64>, velocity: Vector3<f64>) -> Self {
+        TargetTrajectory {
+            initial_position,
+            velocity,
+        }
+    }
+
+    /// 时刻 `t`（秒）的位置
+    pub fn position_at(&self, t: f64) -> Point3<f64> {
+        self.initial_position + self.velocity * t
+    }
+}
+
+/// 多帧模拟中的一帧
+///
+/// `scenario.true_targets` 与输入轨迹一一对应，`scenario.labels` 中的目标索引在各帧间保持一致，
+/// 可直接作为跟踪的真值。
+#[derive(Debug, Clone)]
+pub struct TrajectoryFrame {
+    pub index: usize,       // 帧序号，从 0 开始
+    pub timestamp: f64,     // 帧时刻（秒），等于 index * frame_interval
+    pub scenario: Scenario, // 本帧的真实目标位置与测量，测量的 timestamp 均为本帧时刻
+}
+
+/// 生成匀速运动目标的多帧模拟数据
+///
+/// 目标由 `trajectories` 给出，忽略 `config.num_targets` 和目标范围（杂波和共享测量站仍按目标范围分布）。
+/// 测量站在第一帧前按 `config.station_layout` 生成一次并在各帧保持不变：逐目标布局下测量站围绕
+/// 目标的初始位置布设，只观测该目标；共享布局下每帧每个测量站按检测概率观测每个目标。
+/// 测量站位置误差和测向偏差同样只抽取一次，各帧的方向噪声和杂波独立抽取。所有测量带有测量站标识 `S{k}`
+/// 和所在帧的时刻。
+///
+/// `config` 应已通过校验（见 `GeneratorConfig::validate`），否则可能 panic。
+pub fn generate_trajectory_data(
+    trajectories: &[TargetTrajectory],
+    num_frames: usize,
+    frame_interval: f64,
+    config: &GeneratorConfig,
+) -> Vec<TrajectoryFrame> {
+    let mut seeded;
+    let mut unseeded;
+    let rng: &mut dyn RngCore = match config.seed {
+        Some(seed) => {
+            seeded = StdRng::seed_from_u64(seed);
+            &mut seeded
+        }
+        None => {
+            unseeded = thread_rng();
+            &mut unseeded
+        }
+    };
+    generate_trajectory_data_with_rng(rng, trajectories, num_frames, frame_interval, config)
+}
+
+/// 多帧模拟中在各帧间保持不变的测量站
+struct SimulatedStation {
+    true_position: Point3<f64>,
+    measured_position: Point3<f64>, // 带位置误差
+    bias: StationBias,
+    angle_noise_std: f64,
+    correlated_error: CorrelatedError, // 跨帧持续推进
+    observes: Option<usize>, // 观测的目标，None 为全部目标
+}
+
+/// 使用给定随机数发生器的 `generate_trajectory_data`，忽略 `config.seed`
+pub fn generate_trajectory_data_with_rng<R: Rng + ?Sized>(
+    rng: &mut R,
+    trajectories: &[TargetTrajectory],
+    num_frames: usize,
+    frame_interval: f64,
+    config: &GeneratorConfig,
+) -> Vec<TrajectoryFrame> {
+    let mut stations = Vec::new();
+    let detection_probability = match &config.station_layout {
+        StationLayout::PerTarget => {
+            for (target_index, trajectory) in trajectories.iter().enumerate() {
+                let (min_stations, max_stations) = config.num_stations_per_target_range;
+                let num_stations = rng.gen_range(min_stations..=max_stations);
+                for _ in 0..num_stations {
+                    let angle = rng.gen_range(0.0..2.0 * PI);
+                    let dist = rng.gen_range(config.station_dist_range.0..config.station_dist_range.1);
+                    let true_station_pos = Point3::new(
+                        trajectory.initial_position.x + dist * angle.cos(),
+                        trajectory.initial_position.y + dist * angle.sin(),
+                        rng.gen_range(config.station_z_range.0..config.station_z_range.1),
+                    );
+                    let measured_station_pos = noisy_station(rng, config, &true_station_pos);
+                    let bias = random_bias(rng, config);
+                    stations.push(SimulatedStation {
+                        true_position: true_station_pos,
+                        measured_position: measured_station_pos,
+                        bias,
+                        angle_noise_std: station_angle_noise_std(rng, config, None),
+                        correlated_error: CorrelatedError::default(),
+                        observes: Some(target_index),
+                    });
+                }
+            }
+            1.0
+        }
+        layout => {
+            let (positions, detection_probability) = shared_stations(rng, config, layout);
+            for (station_index, true_station_pos) in positions.into_iter().enumerate() {
+                let measured_station_pos = noisy_station(rng, config, &true_station_pos);
+                let bias = random_bias(rng, config);
+                stations.push(SimulatedStation {
+                    true_position: true_station_pos,
+                    measured_position: measured_station_pos,
+                    bias,
+                    angle_noise_std: station_angle_noise_std(rng, config, Some(station_index)),
+                    correlated_error: CorrelatedError::default(),
+                    observes: None,
+                });
+            }
+            detection_probability
+        }
+    };
+
+    (0..num_frames)
+        .map(|index| {
+            let timestamp = index as f64 * frame_interval;
+            let true_targets: Vec<Point3<f64>> = trajectories.iter().map(|t| t.position_at(timestamp)).collect();
+            let mut scenario = Scenario {
+                true_targets,
+                station_positions: Vec::new(),
+                station_biases: Vec::new(),
+                angle_noise_stds: Vec::new(),
+                correlated_errors: Vec::new(),
+                measurements: Vec::new(),
+                labels: Vec::new(),
+                detections: Vec::new(),
+                config: GeneratorConfig {
+                    seed: None,
+                    ..config.clone()
+                },
+            };
+            for (station_index, station) in stations.iter_mut().enumerate() {
+                for (target_index, true_target_pos) in scenario.true_targets.iter().enumerate() {
+                    if station.observes.is_some_and(|t| t != target_index) {
+                        continue;
+                    }
+                    let detection = detect(
+                        rng,
+                        config,
+                        detection_probability,
+                        target_index,
+                        true_target_pos,
+                        &station.true_position,
+                        scenario.measurements.len(),
+                    );
+                    scenario.detections.push(detection);
+                    if detection.measurement.is_none() {
+                        continue;
+                    }
+                    let true_direction = (true_target_pos - station.true_position).normalize();
+                    let correlated = station.correlated_error.next(rng, config, &true_direction);
+                    scenario.measurements.push(
+                        noisy_measurement(
+                            rng,
+                            config,
+                            &station.measured_position,
+                            &station.bias,
+                            &correlated,
+                            station.angle_noise_std,
+                            &true_direction,
+                        )
+                        .with_station_id(format!("S{}", station_index)),
+                    );
+                    scenario.station_positions.push(station.true_position);
+                    scenario.station_biases.push(station.bias);
+                    scenario.angle_noise_stds.push(Some(station.angle_noise_std));
+                    scenario.correlated_errors.push(correlated);
+                    scenario.labels.push(Some(target_index));
+                }
+            }
+            append_clutter(rng, config, &mut scenario);
+            for m in &mut scenario.measurements {
+                m.timestamp = Some(timestamp);
+            }
+            TrajectoryFrame {
+                index,
+                timestamp,
+                scenario,
+            }
+        })
+        .collect()
+}
+
+/// 由旧版位置参数构造配置
+#[allow(clippy::too_many_arguments)]
+fn positional_config(
+    num_targets: usize,
+    target_x_range: (f64, f64),
+    target_y_range: (f64, f64),
+    target_z_range: (f64, f64),
+    num_stations_per_target_range: (usize, usize),
+    station_dist_range: (f64, f64),
+    station_z_range: (f64, f64),
+    pos_noise_std: f64,
+    alt_noise_std: f64,
+    angle_noise_std: f64,
+) -> GeneratorConfig {
+    GeneratorConfig {
+        num_targets,
+        target_x_range,
+        target_y_range,
+        target_z_range,
+        num_stations_per_target_range,
+        station_dist_range,
+        station_z_range,
+        pos_noise_std,
+        alt_noise_std,
+        angle_noise_std,
+        angle_noise_std_range: None,
+        noise_model: NoiseModel::Gaussian,
+        azimuth_bias_range: (0.0, 0.0),
+        elevation_bias_range: (0.0, 0.0),
+        clutter_fraction: 0.0,
+        station_layout: StationLayout::PerTarget,
+        detection_probability: 1.0,
+        detection_falloff: DetectionFalloff::None,
+        azimuth_step_rad: 0.0,
+        elevation_step_rad: 0.0,
+        correlated_angle_noise_std: 0.0,
+        correlated_noise_length: 0.0,
+        seed: None,
+    }
+}
+
+/// 按位置参数生成数据，参数含义见 `GeneratorConfig` 的同名字段
+#[deprecated(note = "请使用 GeneratorConfig::builder() 与 generate_data_from_config")]
+#[allow(clippy::too_many_arguments)]
 pub fn generate_data(
     num_targets: usize,
     target_x_range: (f64, f64),
@@ -36,56 +1427,770 @@ pub fn generate_data(
     alt_noise_std: f64,
     angle_noise_std: f64,
 ) -> (Vec<Point3<f64>>, Vec<Measurement>) {
-    let mut rng = thread_rng();
-    let mut all_data = Vec::new();
-    let mut true_targets = Vec::new();
+    generate_data_from_config(&positional_config(
+        num_targets,
+        target_x_range,
+        target_y_range,
+        target_z_range,
+        num_stations_per_target_range,
+        station_dist_range,
+        station_z_range,
+        pos_noise_std,
+        alt_noise_std,
+        angle_noise_std,
+    ))
+}
 
-    for _ in 0..num_targets {
-        // 直接在笛卡尔坐标系中生成目标位置
-        let true_target_pos = Point3::new(
-            rng.gen_range(target_x_range.0..target_x_range.1),
-            rng.gen_range(target_y_range.0..target_y_range.1),
-            rng.gen_range(target_z_range.0..target_z_range.1),
-        );
-        true_targets.push(true_target_pos);
-
-        let num_stations =
-            rng.gen_range(num_stations_per_target_range.0..=num_stations_per_target_range.1);
-        for _ in 0..num_stations {
-            // 直接在笛卡尔坐标系中生成测量站位置
-            let angle = rng.gen_range(0.0..2.0 * PI);
-            let dist = rng.gen_range(station_dist_range.0..station_dist_range.1);
-            let true_station_pos = Point3::new(
-                true_target_pos.x + dist * angle.cos(),
-                true_target_pos.y + dist * angle.sin(),
-                rng.gen_range(station_z_range.0..station_z_range.1),
-            );
-
-            let true_direction = (true_target_pos - true_station_pos).normalize();
-
-            // 添加噪声
-            let measured_station_pos = Point3::new(
-                true_station_pos.x + rng.gen_range(-pos_noise_std..pos_noise_std),
-                true_station_pos.y + rng.gen_range(-pos_noise_std..pos_noise_std),
-                true_station_pos.z + rng.gen_range(-alt_noise_std..alt_noise_std),
-            );
-
-            let measured_direction = Vector3::new(
-                true_direction.x + rng.gen_range(-angle_noise_std..angle_noise_std),
-                true_direction.y + rng.gen_range(-angle_noise_std..angle_noise_std),
-                true_direction.z + rng.gen_range(-angle_noise_std..angle_noise_std),
-            )
-            .normalize();
-
-            all_data.push(Measurement {
-                x: measured_station_pos.x,
-                y: measured_station_pos.y,
-                z: measured_station_pos.z,
-                direction_x: measured_direction.x,
-                direction_y: measured_direction.y,
-                direction_z: measured_direction.z,
+/// 以固定种子按位置参数生成数据
+#[deprecated(note = "请使用 GeneratorConfig::builder().seed(..) 与 generate_data_from_config")]
+#[allow(clippy::too_many_arguments)]
+pub fn generate_data_seeded(
+    seed: u64,
+    num_targets: usize,
+    target_x_range: (f64, f64),
+    target_y_range: (f64, f64),
+    target_z_range: (f64, f64),
+    num_stations_per_target_range: (usize, usize),
+    station_dist_range: (f64, f64),
+    station_z_range: (f64, f64),
+    pos_noise_std: f64,
+    alt_noise_std: f64,
+    angle_noise_std: f64,
+) -> (Vec<Point3<f64>>, Vec<Measurement>) {
+    let mut config = positional_config(
+        num_targets,
+        target_x_range,
+        target_y_range,
+        target_z_range,
+        num_stations_per_target_range,
+        station_dist_range,
+        station_z_range,
+        pos_noise_std,
+        alt_noise_std,
+        angle_noise_std,
+    );
+    config.seed = Some(seed);
+    generate_data_from_config(&config)
+}
+
+/// 使用给定随机数发生器和噪声分布按位置参数生成数据
+#[deprecated(note = "请使用 generate_data_from_config_with_rng")]
+#[allow(clippy::too_many_arguments)]
+pub fn generate_data_with_rng<R: Rng + ?Sized>(
+    rng: &mut R,
+    noise_model: NoiseModel,
+    num_targets: usize,
+    target_x_range: (f64, f64),
+    target_y_range: (f64, f64),
+    target_z_range: (f64, f64),
+    num_stations_per_target_range: (usize, usize),
+    station_dist_range: (f64, f64),
+    station_z_range: (f64, f64),
+    pos_noise_std: f64,
+    alt_noise_std: f64,
+    angle_noise_std: f64,
+) -> (Vec<Point3<f64>>, Vec<Measurement>) {
+    let mut config = positional_config(
+        num_targets,
+        target_x_range,
+        target_y_range,
+        target_z_range,
+        num_stations_per_target_range,
+        station_dist_range,
+        station_z_range,
+        pos_noise_std,
+        alt_noise_std,
+        angle_noise_std,
+    );
+    config.noise_model = noise_model;
+    generate_data_from_config_with_rng(rng, &config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_noise_model_std() {
+        let mut rng = StdRng::seed_from_u64(11);
+        for model in [NoiseModel::Gaussian, NoiseModel::Uniform] {
+            let samples: Vec<f64> = (0..20000).map(|_| model.sample(&mut rng, 2.0)).collect();
+            let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+            let var = samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / samples.len() as f64;
+            assert!(mean.abs() < 0.05, "{:?} 均值 {}", model, mean);
+            assert!((var.sqrt() - 2.0).abs() < 0.05, "{:?} 标准差 {}", model, var.sqrt());
+        }
+    }
+
+    #[test]
+    fn test_presets_are_valid_and_distinct() {
+        let configs: Vec<GeneratorConfig> = ScenarioPreset::ALL.iter().map(|&p| GeneratorConfig::preset(p)).collect();
+        for (preset, config) in ScenarioPreset::ALL.iter().zip(&configs) {
+            assert!(config.validate().is_ok(), "{:?}", preset);
+            assert_eq!(config.seed, None);
+            let (targets, measurements) = generate_data_from_config(&GeneratorConfig {
+                seed: Some(1),
+                ..config.clone()
             });
+            assert_eq!(targets.len(), config.num_targets, "{:?}", preset);
+            assert!(!measurements.is_empty());
+        }
+        for i in 0..configs.len() {
+            for j in i + 1..configs.len() {
+                assert_ne!(configs[i], configs[j]);
+            }
+        }
+        let sparse = GeneratorConfig::preset(ScenarioPreset::SparseStations);
+        assert_eq!(sparse.num_stations_per_target_range, (2, 3));
+    }
+
+    #[test]
+    fn test_generate_data_seeded_reproducible() {
+        let generate = |seed| {
+            let config = GeneratorConfig::builder().num_targets(3).seed(seed).build().unwrap();
+            generate_data_from_config(&config)
+        };
+        let bits = |(targets, measurements): (Vec<Point3<f64>>, Vec<Measurement>)| {
+            let mut bits: Vec<u64> = targets.iter().flat_map(|p| p.iter().map(|v| v.to_bits())).collect();
+            for m in &measurements {
+                bits.extend([m.x, m.y, m.z, m.direction_x, m.direction_y, m.direction_z, m.weight].map(f64::to_bits));
+                assert_eq!(m.station_id, None);
+            }
+            bits
+        };
+        assert_eq!(bits(generate(42)), bits(generate(42)));
+        assert_ne!(bits(generate(42)), bits(generate(43)));
+    }
+
+    #[test]
+    fn test_zero_noise_std() {
+        let mut rng = StdRng::seed_from_u64(1);
+        assert_eq!(NoiseModel::Gaussian.sample(&mut rng, 0.0), 0.0);
+        assert_eq!(NoiseModel::Uniform.sample(&mut rng, 0.0), 0.0);
+
+        // 无噪声时每条光线都精确经过目标
+        let config = GeneratorConfig::builder()
+            .num_targets(2)
+            .num_stations_per_target_range(3, 3)
+            .pos_noise_std(0.0)
+            .alt_noise_std(0.0)
+            .angle_noise_std(0.0)
+            .build()
+            .unwrap();
+        let (targets, measurements) = generate_data_from_config(&config);
+        assert_eq!(measurements.len(), 6);
+        for (i, m) in measurements.iter().enumerate() {
+            let line = m.try_into_line().unwrap();
+            assert!(line.distance_to_point(&targets[i / 3]) < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_builder_rejects_invalid_ranges() {
+        let error = GeneratorConfig::builder().target_x_range(10.0, -10.0).build().unwrap_err();
+        assert_eq!(
+            error,
+            OptiRadarError::InvalidRange { name: "target_x_range", min: 10.0, max: -10.0 }
+        );
+        assert!(error.to_string().contains("target_x_range"));
+        assert!(GeneratorConfig::builder().station_z_range(5.0, 5.0).build().is_err());
+        assert!(GeneratorConfig::builder().station_dist_range(f64::NAN, 5.0).build().is_err());
+        assert!(GeneratorConfig::builder().num_stations_per_target_range(4, 3).build().is_err());
+        assert!(GeneratorConfig::builder().num_stations_per_target_range(3, 3).build().is_ok());
+        assert_eq!(
+            GeneratorConfig::builder().angle_noise_std(-0.1).build().unwrap_err(),
+            OptiRadarError::InvalidParameter { name: "angle_noise_std", value: -0.1 }
+        );
+        assert_eq!(GeneratorConfig::builder().build().unwrap(), GeneratorConfig::default());
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_positional_shim_matches_config() {
+        let config = GeneratorConfig::builder()
+            .num_targets(2)
+            .target_x_range(0.0, 10.0)
+            .angle_noise_std(0.01)
+            .seed(9)
+            .build()
+            .unwrap();
+        let (targets, measurements) = generate_data_from_config(&config);
+        let (shim_targets, shim_measurements) = generate_data_seeded(
+            9,
+            2,
+            (0.0, 10.0),
+            config.target_y_range,
+            config.target_z_range,
+            config.num_stations_per_target_range,
+            config.station_dist_range,
+            config.station_z_range,
+            config.pos_noise_std,
+            config.alt_noise_std,
+            0.01,
+        );
+        assert_eq!(targets, shim_targets);
+        assert_eq!(measurements.len(), shim_measurements.len());
+        for (a, b) in measurements.iter().zip(&shim_measurements) {
+            assert_eq!((a.x, a.y, a.z), (b.x, b.y, b.z));
+            assert_eq!((a.direction_x, a.direction_y, a.direction_z), (b.direction_x, b.direction_y, b.direction_z));
+        }
+    }
+
+    #[test]
+    fn test_clutter_labels() {
+        let config = GeneratorConfig::builder()
+            .num_targets(4)
+            .clutter_fraction(0.3)
+            .seed(3)
+            .build()
+            .unwrap();
+        let scenario = generate_scenario(&config);
+        assert_eq!(scenario.labels.len(), scenario.measurements.len());
+        assert_eq!(scenario.station_positions.len(), scenario.measurements.len());
+        let total: usize = (0..4).map(|t| scenario.measurements_of(t).len()).sum();
+        assert_eq!(total + scenario.clutter_indices().len(), scenario.measurements.len());
+        let clutter = scenario.clutter_indices();
+        let fraction = clutter.len() as f64 / scenario.measurements.len() as f64;
+        assert!((fraction - 0.3).abs() < 0.05);
+
+        // 非杂波测量精确瞄准（噪声较小）其标注的目标
+        for (m, label) in scenario.measurements.iter().zip(&scenario.labels) {
+            let line = m.try_into_line().unwrap();
+            if let Some(target) = label {
+                let to_target = scenario.true_targets[*target] - line.start;
+                assert!(to_target.normalize().dot(&line.direction) > 0.99);
+            } else {
+                assert!(line.direction.z >= 0.0);
+            }
+        }
+
+        // clutter_fraction 须小于 1；为 0 时没有杂波测量
+        assert!(GeneratorConfig::builder().clutter_fraction(1.0).build().is_err());
+        let clean = GeneratorConfig { clutter_fraction: 0.0, ..config };
+        assert!(generate_scenario(&clean).clutter_indices().is_empty());
+    }
+
+    #[test]
+    fn test_shared_stations() {
+        let config = GeneratorConfig::builder()
+            .num_targets(3)
+            .station_layout(StationLayout::Shared {
+                num_stations: 5,
+                detection_probability: 1.0,
+            })
+            .seed(11)
+            .build()
+            .unwrap();
+        let scenario = generate_scenario(&config);
+        // 检测概率为 1 时每个测量站对每个目标各有一条测量
+        assert_eq!(scenario.measurements.len(), 15);
+        for target in 0..3 {
+            assert_eq!(scenario.measurements_of(target).len(), 5);
+        }
+
+        // 同一测量站的测量共享站位（含噪声）与标识
+        for (i, a) in scenario.measurements.iter().enumerate() {
+            for (j, b) in scenario.measurements.iter().enumerate() {
+                if a.station_id == b.station_id {
+                    assert_eq!((a.x, a.y, a.z), (b.x, b.y, b.z));
+                    assert_eq!(scenario.station_positions[i], scenario.station_positions[j]);
+                }
+            }
+        }
+        let mut ids: Vec<_> = scenario.measurements.iter().map(|m| m.station_id.clone()).collect();
+        ids.dedup();
+        assert_eq!(ids.len(), 5);
+
+        // 检测概率为 0 时没有测量；固定站位按给定位置生成
+        let none = GeneratorConfig {
+            station_layout: StationLayout::Shared { num_stations: 5, detection_probability: 0.0 },
+            ..config.clone()
+        };
+        assert!(generate_scenario(&none).measurements.is_empty());
+        let positions = vec![Point3::new(0.0, 0.0, 10.0), Point3::new(100.0, 0.0, 20.0)];
+        let fixed = GeneratorConfig {
+            station_layout: StationLayout::Fixed { positions: positions.clone(), detection_probability: 1.0, angle_noise_stds: Vec::new() },
+            ..config.clone()
+        };
+        let scenario = generate_scenario(&fixed);
+        assert_eq!(scenario.measurements.len(), 6);
+        assert!(scenario.station_positions.iter().all(|p| positions.contains(p)));
+
+        assert!(GeneratorConfig::builder()
+            .station_layout(StationLayout::Shared { num_stations: 5, detection_probability: 1.5 })
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_correlated_angle_noise() {
+        // 单个固定测量站观测 400 个目标，只有相关测角误差
+        let config = GeneratorConfig::builder()
+            .num_targets(400)
+            .station_layout(StationLayout::Fixed {
+                positions: vec![Point3::new(0.0, -2000.0, 10.0)],
+                detection_probability: 1.0,
+                angle_noise_stds: Vec::new(),
+            })
+            .pos_noise_std(0.0)
+            .alt_noise_std(0.0)
+            .angle_noise_std(0.0)
+            .correlated_angle_noise(0.01, 20.0)
+            .seed(41)
+            .build()
+            .unwrap();
+        let scenario = generate_scenario(&config);
+        let sequences = scenario.correlated_error_sequences();
+        assert_eq!(sequences.len(), 1);
+        let (station, sequence) = &sequences[0];
+        assert_eq!(sequence.len(), 400);
+
+        // 实际测量方向与真实方向的夹角即记录的误差
+        let direction = |m: &Measurement| Vector3::new(m.direction_x, m.direction_y, m.direction_z);
+        let mut angles = Vec::new();
+        for (k, m) in scenario.measurements.iter().enumerate() {
+            let truth = (scenario.true_targets[scenario.labels[k].unwrap()] - station).normalize();
+            let expected = scenario.correlated_errors[k].apply(&truth);
+            assert!((direction(m) - expected).norm() < 1e-12);
+            angles.push(direction(m).angle(&truth));
+        }
+        let rms = (angles.iter().map(|a| a * a).sum::<f64>() / angles.len() as f64).sqrt();
+        assert!((0.006..0.014).contains(&rms), "{}", rms);
+
+        let lag_one = |sequence: &[StationBias]| {
+            let values: Vec<f64> = sequence.iter().map(|e| e.elevation_rad).collect();
+            let mean = values.iter().sum::<f64>() / values.len() as f64;
+            let var: f64 = values.iter().map(|v| (v - mean).powi(2)).sum();
+            values.windows(2).map(|w| (w[0] - mean) * (w[1] - mean)).sum::<f64>() / var
+        };
+        assert!(lag_one(sequence) > 0.85, "{}", lag_one(sequence));
+        let white = generate_scenario(&GeneratorConfig {
+            correlated_noise_length: 0.0,
+            ..config.clone()
+        });
+        assert!(lag_one(&white.correlated_error_sequences()[0].1).abs() < 0.15);
+
+        // 未启用时误差为零，且不改变其余随机数的消耗
+        let off = GeneratorConfig::builder().num_targets(3).seed(41).build().unwrap();
+        let plain = generate_scenario(&off);
+        assert!(plain.correlated_errors.iter().all(|e| *e == StationBias::default()));
+        let length_only = generate_scenario(&GeneratorConfig {
+            correlated_noise_length: 20.0,
+            ..off.clone()
+        });
+        let bits = |s: &Scenario| -> Vec<u64> {
+            s.measurements.iter().flat_map(|m| [m.direction_x, m.direction_y, m.direction_z].map(f64::to_bits)).collect()
+        };
+        assert_eq!(bits(&plain), bits(&length_only));
+        assert!(GeneratorConfig::builder().correlated_angle_noise(-0.01, 5.0).build().is_err());
+        assert!(GeneratorConfig::builder().correlated_angle_noise(0.01, f64::NAN).build().is_err());
+    }
+
+    #[test]
+    fn test_polyline_stations() {
+        // L 形道路：先沿 +x 1000 米，再沿 +y 500 米，高度从 10 米升到 40 米
+        let vertices = vec![
+            Point3::new(0.0, 0.0, 10.0),
+            Point3::new(1000.0, 0.0, 20.0),
+            Point3::new(1000.0, 500.0, 40.0),
+        ];
+        let config = GeneratorConfig::builder()
+            .num_targets(2)
+            .station_layout(StationLayout::Polyline {
+                vertices: vertices.clone(),
+                num_stations: 200,
+                lateral_jitter_m: 5.0,
+                detection_probability: 1.0,
+            })
+            .seed(31)
+            .build()
+            .unwrap();
+        let scenario = generate_scenario(&config);
+        assert_eq!(scenario.measurements.len(), 400);
+        let mut on_second_leg = 0;
+        for p in &scenario.station_positions {
+            let first_leg = p.x < 995.0;
+            if first_leg {
+                assert!(p.y.abs() <= 5.0 && (-5.0..=1000.0).contains(&p.x), "{:?}", p);
+                assert!((p.z - (10.0 + 10.0 * p.x / 1000.0)).abs() < 0.1, "{:?}", p);
+            } else {
+                on_second_leg += 1;
+                assert!((p.x - 1000.0).abs() <= 5.0 && (0.0..=500.0).contains(&p.y), "{:?}", p);
+            }
+        }
+        // 按长度均匀：约 1/3 的测量站（每站 2 个目标）在第二段
+        assert!((100..=170).contains(&on_second_leg), "{}", on_second_leg);
+
+        // 无横向偏移时测量站严格位于折线上
+        let exact = GeneratorConfig {
+            station_layout: StationLayout::Polyline {
+                vertices: vertices[..2].to_vec(),
+                num_stations: 10,
+                lateral_jitter_m: 0.0,
+                detection_probability: 1.0,
+            },
+            ..config.clone()
+        };
+        assert!(generate_scenario(&exact).station_positions.iter().all(|p| p.y == 0.0));
+
+        let polyline = |vertices: Vec<Point3<f64>>, lateral_jitter_m| GeneratorConfig {
+            station_layout: StationLayout::Polyline {
+                vertices,
+                num_stations: 4,
+                lateral_jitter_m,
+                detection_probability: 1.0,
+            },
+            ..config.clone()
+        };
+        assert!(polyline(vertices[..1].to_vec(), 0.0).validate().is_err());
+        assert!(polyline(vec![vertices[0], vertices[0]], 0.0).validate().is_err());
+        assert!(polyline(vertices.clone(), -1.0).validate().is_err());
+        assert!(polyline(vec![vertices[0], Point3::new(f64::NAN, 0.0, 0.0)], 0.0).validate().is_err());
+    }
+
+    #[test]
+    fn test_trajectory_frames() {
+        let trajectories = [
+            TargetTrajectory::new(Point3::new(0.0, 0.0, 100.0), Vector3::new(10.0, 0.0, 0.0)),
+            TargetTrajectory::new(Point3::new(200.0, 100.0, 80.0), Vector3::new(0.0, -5.0, 1.0)),
+        ];
+        let config = GeneratorConfig::builder().seed(21).build().unwrap();
+        let frames = generate_trajectory_data(&trajectories, 4, 0.5, &config);
+        assert_eq!(frames.len(), 4);
+
+        let station_ids = |frame: &TrajectoryFrame| -> Vec<Option<String>> {
+            frame.scenario.measurements.iter().map(|m| m.station_id.clone()).collect()
+        };
+        for frame in &frames {
+            assert_eq!(frame.timestamp, frame.index as f64 * 0.5);
+            for (k, trajectory) in trajectories.iter().enumerate() {
+                assert_eq!(frame.scenario.true_targets[k], trajectory.position_at(frame.timestamp));
+            }
+            assert!(frame.scenario.measurements.iter().all(|m| m.timestamp == Some(frame.timestamp)));
+            // 测量站在各帧保持不变
+            assert_eq!(frame.scenario.station_positions, frames[0].scenario.station_positions);
+            assert_eq!(station_ids(frame), station_ids(&frames[0]));
+
+            for (m, label) in frame.scenario.measurements.iter().zip(&frame.scenario.labels) {
+                let line = m.try_into_line().unwrap();
+                let to_target = frame.scenario.true_targets[label.unwrap()] - line.start;
+                assert!(to_target.normalize().dot(&line.direction) > 0.99);
+            }
+        }
+
+        // 同一种子结果可复现
+        let again = generate_trajectory_data(&trajectories, 4, 0.5, &config);
+        assert_eq!(again[3].scenario.measurements[0].direction_x, frames[3].scenario.measurements[0].direction_x);
+    }
+
+    #[test]
+    fn test_angular_noise_std() {
+        // 旋转模型下夹角均方根等于 angle_noise_std，且与方向朝向无关
+        let std = 0.01;
+        let mut rng = StdRng::seed_from_u64(31);
+        let station = Point3::origin();
+        for model in [NoiseModel::Gaussian, NoiseModel::Uniform] {
+            let config = GeneratorConfig::builder().angle_noise_std(std).noise_model(model).build().unwrap();
+            for direction in [Vector3::z(), Vector3::x(), Vector3::new(1.0, -2.0, 0.5).normalize()] {
+                let n = 20000;
+                let sum_sq: f64 = (0..n)
+                    .map(|_| {
+                        let m = noisy_measurement(&mut rng, &config, &station, &StationBias::default(), &StationBias::default(), std, &direction);
+                        let measured = Vector3::new(m.direction_x, m.direction_y, m.direction_z);
+                        measured.angle(&direction).powi(2)
+                    })
+                    .sum();
+                let rms = (sum_sq / n as f64).sqrt();
+                assert!((rms / std - 1.0).abs() < 0.05, "{:?} {:?} 均方根 {}", model, direction, rms);
+            }
+        }
+
+        // 旧的逐分量模型角度误差约为 √2σ
+        let config = GeneratorConfig::builder()
+            .angle_noise_std(std)
+            .noise_model(NoiseModel::Componentwise)
+            .build()
+            .unwrap();
+        let n = 20000;
+        let sum_sq: f64 = (0..n)
+            .map(|_| {
+                let m = noisy_measurement(&mut rng, &config, &station, &StationBias::default(), &StationBias::default(), std, &Vector3::z());
+                Vector3::new(m.direction_x, m.direction_y, m.direction_z).angle(&Vector3::z()).powi(2)
+            })
+            .sum();
+        assert!(((sum_sq / n as f64).sqrt() / std - 2f64.sqrt()).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_station_bias() {
+        // 偏差按方位角/俯仰角叠加
+        let bias = StationBias { azimuth_rad: 0.1, elevation_rad: -0.05 };
+        let m = Measurement::from_az_el(0.0, 0.0, 0.0, 1.0, 0.3);
+        let biased = bias.apply(&Vector3::new(m.direction_x, m.direction_y, m.direction_z));
+        let expected = Measurement::from_az_el(0.0, 0.0, 0.0, 1.1, 0.25);
+        assert!((biased - Vector3::new(expected.direction_x, expected.direction_y, expected.direction_z)).norm() < 1e-12);
+
+        let config = GeneratorConfig::builder()
+            .num_targets(3)
+            .station_layout(StationLayout::Shared { num_stations: 4, detection_probability: 1.0 })
+            .angle_noise_std(0.0)
+            .azimuth_bias_range(-0.01, 0.01)
+            .elevation_bias_range(0.002, 0.002)
+            .clutter_fraction(0.2)
+            .seed(41)
+            .build()
+            .unwrap();
+        let scenario = generate_scenario(&config);
+        assert_eq!(scenario.station_biases.len(), scenario.measurements.len());
+        for (i, m) in scenario.measurements.iter().enumerate() {
+            let b = scenario.station_biases[i];
+            match scenario.labels[i] {
+                Some(target) => {
+                    // 同一测量站的偏差一致，且测量方向等于真实方向叠加偏差
+                    assert!((-0.01..0.01).contains(&b.azimuth_rad));
+                    assert_eq!(b.elevation_rad, 0.002);
+                    let same_station = scenario.measurements.iter().zip(&scenario.station_biases);
+                    for (other, other_bias) in same_station {
+                        if other.station_id == m.station_id {
+                            assert_eq!(*other_bias, b);
+                        }
+                    }
+                    let true_direction = (scenario.true_targets[target] - scenario.station_positions[i]).normalize();
+                    let direction = Vector3::new(m.direction_x, m.direction_y, m.direction_z);
+                    assert!((direction - b.apply(&true_direction)).norm() < 1e-12);
+                }
+                None => assert_eq!(b, StationBias::default()),
+            }
+        }
+
+        assert!(GeneratorConfig::builder().azimuth_bias_range(0.1, -0.1).build().is_err());
+    }
+
+    #[test]
+    fn test_generate_for_given_targets() {
+        let targets = [Point3::new(0.0, 0.0, 100.0), Point3::new(5.0, 0.0, 100.0)];
+        let config = GeneratorConfig::builder()
+            .num_targets(7) // 指定目标时忽略
+            .angle_noise_std(0.0)
+            .pos_noise_std(0.0)
+            .alt_noise_std(0.0)
+            .seed(51)
+            .build()
+            .unwrap();
+        let scenario = generate_scenario_for_targets(&targets, &config);
+        assert_eq!(scenario.true_targets, targets);
+        for (m, label) in scenario.measurements.iter().zip(&scenario.labels) {
+            let line = m.try_into_line().unwrap();
+            assert!(line.distance_to_point(&targets[label.unwrap()]) < 1e-9);
+        }
+        let measurements = generate_data_for_targets(&targets, &config);
+        assert_eq!(measurements.len(), scenario.measurements.len());
+    }
+
+    #[test]
+    fn test_scenario_records_seed() {
+        // 未设置种子时记录随机取得的种子，按它重新生成得到同样的场景
+        let config = GeneratorConfig::builder().num_targets(2).clutter_fraction(0.1).build().unwrap();
+        let scenario = generate_scenario(&config);
+        let seed = scenario.config.seed.expect("应记录种子");
+        let again = generate_scenario(&GeneratorConfig { seed: Some(seed), ..config.clone() });
+        assert_eq!(again.true_targets, scenario.true_targets);
+        assert_eq!(again.labels, scenario.labels);
+        assert_eq!(again.config, scenario.config);
+        // 外部随机数发生器生成的场景不记录种子
+        assert_eq!(generate_scenario_with_rng(&mut StdRng::seed_from_u64(seed), &config).config.seed, None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_scenario_json_round_trip() {
+        let config = GeneratorConfig::builder()
+            .num_targets(3)
+            .clutter_fraction(0.2)
+            .azimuth_bias_range(-0.01, 0.01)
+            .station_layout(StationLayout::Shared {
+                num_stations: 6,
+                detection_probability: 0.8,
+            })
+            .seed(17)
+            .build()
+            .unwrap();
+        let scenario = generate_scenario(&config);
+        let path = std::env::temp_dir().join(format!("opti_radar_scenario_{}.json", std::process::id()));
+        scenario.save_json(&path).unwrap();
+        let loaded = Scenario::load_json(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.config, config);
+        assert_eq!(loaded.true_targets, scenario.true_targets);
+        assert_eq!(loaded.station_positions, scenario.station_positions);
+        assert_eq!(loaded.station_biases, scenario.station_biases);
+        assert_eq!(loaded.labels, scenario.labels);
+        assert_eq!(serde_json::to_string(&loaded).unwrap(), serde_json::to_string(&scenario).unwrap());
+        assert!(Scenario::load_json(&path).is_err());
+    }
+
+    #[test]
+    fn test_detection_model() {
+        // 默认参数：每个可能的观测都被检测到，且与测量一一对应
+        let config = GeneratorConfig::builder().num_targets(3).clutter_fraction(0.2).seed(5).build().unwrap();
+        let scenario = generate_scenario(&config);
+        let target_measurements = scenario.labels.iter().filter(|l| l.is_some()).count();
+        assert_eq!(scenario.detections.len(), target_measurements);
+        assert_eq!(scenario.detection_rate(), Some(1.0));
+        for d in &scenario.detections {
+            let k = d.measurement.unwrap();
+            assert_eq!(scenario.labels[k], Some(d.target));
+            assert_eq!(scenario.station_positions[k], d.station_position);
+        }
+
+        // 最大距离：超出的观测漏检，测量只来自范围内的测量站
+        let config = GeneratorConfig {
+            num_targets: 20,
+            detection_falloff: DetectionFalloff::MaxRange { range_m: 300.0 },
+            ..config
+        };
+        let scenario = generate_scenario(&config);
+        let (near, far): (Vec<&DetectionOpportunity>, Vec<_>) = scenario.detections.iter().partition(|d| d.range_m <= 300.0);
+        assert!(!near.is_empty() && !far.is_empty());
+        assert!(near.iter().all(|d| d.measurement.is_some() && d.probability == 1.0));
+        assert!(far.iter().all(|d| d.measurement.is_none() && d.probability == 0.0));
+        assert_eq!(scenario.labels.iter().filter(|l| l.is_some()).count(), near.len());
+
+        // 指数衰减：检测比例与平均检测概率相符；共享布局下每对测量站-目标都是一次观测机会
+        let config = GeneratorConfig::builder()
+            .num_targets(10)
+            .station_layout(StationLayout::Shared {
+                num_stations: 40,
+                detection_probability: 0.9,
+            })
+            .detection_probability(0.8)
+            .detection_falloff(DetectionFalloff::Exponential { range_m: 800.0 })
+            .seed(6)
+            .build()
+            .unwrap();
+        let scenario = generate_scenario(&config);
+        assert_eq!(scenario.detections.len(), 400);
+        let expected = scenario.detections.iter().map(|d| d.probability).sum::<f64>() / 400.0;
+        for d in &scenario.detections {
+            assert!((d.probability - 0.72 * (-d.range_m / 800.0).exp()).abs() < 1e-12);
+        }
+        let rate = scenario.detection_rate().unwrap();
+        assert!((rate - expected).abs() < 0.06, "检测比例 {:.3}，期望 {:.3}", rate, expected);
+        assert_eq!(scenario.measurements.len(), scenario.detections.iter().filter(|d| d.measurement.is_some()).count());
+
+        // 多帧数据同样记录每帧的观测机会
+        let frames = generate_trajectory_data(
+            &[TargetTrajectory::new(Point3::new(0.0, 0.0, 100.0), Vector3::new(10.0, 0.0, 0.0))],
+            3,
+            1.0,
+            &GeneratorConfig {
+                detection_probability: 0.5,
+                ..GeneratorConfig::builder().seed(7).build().unwrap()
+            },
+        );
+        for frame in &frames {
+            let detected = frame.scenario.detections.iter().filter(|d| d.measurement.is_some()).count();
+            assert_eq!(detected, frame.scenario.measurements.len());
+            assert!(frame.scenario.detections.len() >= detected);
+        }
+
+        assert!(GeneratorConfig::builder().detection_probability(1.5).build().is_err());
+        assert!(GeneratorConfig::builder()
+            .detection_falloff(DetectionFalloff::Exponential { range_m: 0.0 })
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_angle_quantization() {
+        let (azimuth_step, elevation_step) = (0.5f64.to_radians(), 1f64.to_radians());
+        let config = GeneratorConfig::builder()
+            .num_targets(5)
+            .angle_quantization(azimuth_step, elevation_step)
+            .seed(8)
+            .build()
+            .unwrap();
+        let quantized = generate_scenario(&config);
+        // 步长为 0 时不量化；量化不消耗随机数，两者的测量一一对应
+        let plain = generate_scenario(&GeneratorConfig {
+            azimuth_step_rad: 0.0,
+            elevation_step_rad: 0.0,
+            ..config.clone()
+        });
+        assert_eq!(quantized.measurements.len(), plain.measurements.len());
+
+        let on_grid = |angle: f64, step: f64| ((angle / step) - (angle / step).round()).abs() < 1e-9;
+        let direction = |m: &Measurement| Vector3::new(m.direction_x, m.direction_y, m.direction_z);
+        for (q, p) in quantized.measurements.iter().zip(&plain.measurements) {
+            let (azimuth, elevation) = q.azimuth_elevation();
+            assert!(on_grid(azimuth, azimuth_step) && on_grid(elevation, elevation_step));
+            assert!((direction(q).norm() - 1.0).abs() < 1e-12);
+            assert_eq!((q.x, q.y, q.z), (p.x, p.y, p.z));
+            // 方位角、俯仰角各自的舍入误差不超过半个步长
+            let (plain_azimuth, plain_elevation) = p.azimuth_elevation();
+            let azimuth_error = (azimuth - plain_azimuth + PI).rem_euclid(2.0 * PI) - PI;
+            assert!(azimuth_error.abs() <= 0.5 * azimuth_step + 1e-12);
+            assert!((elevation - plain_elevation).abs() <= 0.5 * elevation_step + 1e-12);
+        }
+        assert!(GeneratorConfig::builder().angle_quantization(-0.1, 0.0).build().is_err());
+    }
+
+    #[test]
+    fn test_per_station_angle_noise() {
+        // 逐目标布局：每个测量站的标准差取自给定范围，并记录在场景中
+        let config = GeneratorConfig::builder()
+            .num_targets(4)
+            .angle_noise_std_range(0.001, 0.01)
+            .clutter_fraction(0.2)
+            .seed(9)
+            .build()
+            .unwrap();
+        let scenario = generate_scenario(&config);
+        assert_eq!(scenario.angle_noise_stds.len(), scenario.measurements.len());
+        for (sigma, label) in scenario.angle_noise_stds.iter().zip(&scenario.labels) {
+            assert_eq!(sigma.is_some(), label.is_some());
+            assert!(sigma.is_none_or(|s| (0.001..0.01).contains(&s)));
+        }
+        let distinct: Vec<f64> = scenario.angle_noise_stds.iter().flatten().copied().collect();
+        assert!(distinct.windows(2).any(|w| w[0] != w[1]));
+
+        // 加权测量：权重与 σ² 成反比，杂波权重不变
+        let weighted = scenario.noise_weighted_measurements();
+        for (k, m) in weighted.iter().enumerate() {
+            match scenario.angle_noise_stds[k] {
+                Some(sigma) => assert!((m.weight * sigma * sigma - weighted[0].weight * distinct[0].powi(2)).abs() < 1e-12),
+                None => assert_eq!(m.weight, 1.0),
+            }
+        }
+
+        // 固定站址：逐站给定的标准差用于该站的全部测量
+        let positions = vec![Point3::new(0.0, 0.0, 0.0), Point3::new(800.0, 0.0, 0.0), Point3::new(0.0, 800.0, 0.0)];
+        let fixed = GeneratorConfig {
+            num_targets: 2,
+            station_layout: StationLayout::Fixed {
+                positions: positions.clone(),
+                detection_probability: 1.0,
+                angle_noise_stds: vec![0.001, 0.002, 0.003],
+            },
+            ..config.clone()
+        };
+        let scenario = generate_scenario(&fixed);
+        for (m, sigma) in scenario.measurements.iter().zip(&scenario.angle_noise_stds) {
+            if let Some(id) = &m.station_id {
+                let k: usize = id[1..].parse().unwrap();
+                assert_eq!(*sigma, Some([0.001, 0.002, 0.003][k]));
+            }
         }
+        let mismatched = GeneratorConfig {
+            station_layout: StationLayout::Fixed {
+                positions,
+                detection_probability: 1.0,
+                angle_noise_stds: vec![0.001],
+            },
+            ..config
+        };
+        assert!(mismatched.validate().is_err());
+        assert!(GeneratorConfig::builder().angle_noise_std_range(0.01, 0.001).build().is_err());
     }
-    (true_targets, all_data)
-}
\ No newline at end of file
+}
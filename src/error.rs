@@ -0,0 +1,43 @@
+// src/error.rs
+
+use std::fmt;
+
+/// opti_radar 的错误类型
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OptiRadarError {
+    /// 方向向量为零（或长度过小无法单位化）
+    ZeroDirection,
+    /// 方向向量含 NaN 或无穷大
+    NonFiniteDirection,
+    /// 测量站坐标含 NaN 或无穷大
+    NonFiniteStation,
+    /// 参数范围无效（最小值不小于最大值或含非有限值）
+    InvalidRange {
+        name: &'static str,
+        min: f64,
+        max: f64,
+    },
+    /// 参数取值无效（例如负的噪声标准差）
+    InvalidParameter { name: &'static str, value: f64 },
+    /// 协方差矩阵不对称、非正定或含非有限值
+    InvalidCovariance,
+}
+
+impl fmt::Display for OptiRadarError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OptiRadarError::ZeroDirection => write!(f, "测量方向向量为零"),
+            OptiRadarError::NonFiniteDirection => write!(f, "测量方向向量含 NaN 或无穷大"),
+            OptiRadarError::NonFiniteStation => write!(f, "测量站坐标含 NaN 或无穷大"),
+            OptiRadarError::InvalidRange { name, min, max } => {
+                write!(f, "参数 {} 的范围 ({}, {}) 无效：须为有限值且最小值小于最大值", name, min, max)
+            }
+            OptiRadarError::InvalidParameter { name, value } => {
+                write!(f, "参数 {} 的取值 {} 无效", name, value)
+            }
+            OptiRadarError::InvalidCovariance => write!(f, "协方差矩阵须为有限值的对称正定矩阵"),
+        }
+    }
+}
+
+impl std::error::Error for OptiRadarError {}
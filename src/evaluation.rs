@@ -0,0 +1,327 @@
+// src/evaluation.rs
+
+use crate::target_processor::LocatedTarget;
+use nalgebra::Point3;
+
+// --- 定位结果评估 ---
+//
+// 真实目标与定位结果之间按最优分配（匈牙利算法）一一匹配，
+// 结果与输入顺序无关，并给出误差、准确率（precision）与召回率（recall）等指标。
+
+/// 一对匹配：真实目标与定位结果的索引及两者距离
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TargetMatch {
+    pub truth: usize,    // 在真实目标列表中的索引
+    pub estimate: usize, // 在定位结果列表中的索引
+    pub distance: f64,   // 位置误差（米）
+}
+
+/// 匹配结果
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct MatchResult {
+    pub matches: Vec<TargetMatch>, // 按真实目标索引升序
+    pub missed: Vec<usize>,        // 未匹配的真实目标（漏检），升序
+    pub false_tracks: Vec<usize>,  // 未匹配的定位结果（虚警），升序
+}
+
+impl MatchResult {
+    /// 匹配对的误差，顺序同 `matches`
+    pub fn errors(&self) -> Vec<f64> {
+        self.matches.iter().map(|m| m.distance).collect()
+    }
+
+    /// 平均误差；没有匹配时为 None
+    pub fn mean_error(&self) -> Option<f64> {
+        if self.matches.is_empty() {
+            return None;
+        }
+        Some(self.errors().iter().sum::<f64>() / self.matches.len() as f64)
+    }
+
+    /// 误差中位数（偶数个时取中间两个的平均）；没有匹配时为 None
+    pub fn median_error(&self) -> Option<f64> {
+        let mut errors = self.errors();
+        if errors.is_empty() {
+            return None;
+        }
+        errors.sort_by(f64::total_cmp);
+        let mid = errors.len() / 2;
+        Some(if errors.len().is_multiple_of(2) {
+            (errors[mid - 1] + errors[mid]) / 2.0
+        } else {
+            errors[mid]
+        })
+    }
+
+    /// 均方根误差；没有匹配时为 None
+    pub fn rms_error(&self) -> Option<f64> {
+        if self.matches.is_empty() {
+            return None;
+        }
+        let sum_sq: f64 = self.matches.iter().map(|m| m.distance * m.distance).sum();
+        Some((sum_sq / self.matches.len() as f64).sqrt())
+    }
+
+    /// 准确率：匹配数 / 定位结果数；没有定位结果时为 1
+    pub fn precision(&self) -> f64 {
+        let num_estimates = self.matches.len() + self.false_tracks.len();
+        if num_estimates == 0 {
+            1.0
+        } else {
+            self.matches.len() as f64 / num_estimates as f64
+        }
+    }
+
+    /// 统计定位结果中关联正确的内点光线数，返回 (正确数, 内点总数)
+    ///
+    /// `labels` 为每条测量的真值目标索引（见 `data_generator::Scenario::labels`），
+    /// `located` 须为参与匹配的定位结果。内点的真值标注等于所匹配的真实目标时计为正确，
+    /// 未匹配的定位结果的内点全部计为错误。
+    pub fn correct_assignments(&self, labels: &[Option<usize>], located: &[LocatedTarget]) -> (usize, usize) {
+        let mut correct = 0;
+        let mut total = 0;
+        for (located_index, target) in located.iter().enumerate() {
+            let true_index = self.matches.iter().find(|m| m.estimate == located_index).map(|m| m.truth);
+            total += target.inlier_indices.len();
+            correct += target
+                .inlier_indices
+                .iter()
+                .filter(|&&i| true_index.is_some() && labels[i] == true_index)
+                .count();
+        }
+        (correct, total)
+    }
+
+    /// 召回率：匹配数 / 真实目标数；没有真实目标时为 1
+    pub fn recall(&self) -> f64 {
+        let num_truths = self.matches.len() + self.missed.len();
+        if num_truths == 0 {
+            1.0
+        } else {
+            self.matches.len() as f64 / num_truths as f64
+        }
+    }
+}
+
+/// 将定位结果与真实目标按最优分配匹配
+///
+/// 使匹配对的距离之和最小；距离超过 `max_dist` 的配对不算匹配，
+/// 对应的真实目标计为漏检、定位结果计为虚警。`max_dist` 可取 `f64::INFINITY` 表示不设门限。
+pub fn match_targets(true_targets: &[Point3<f64>], located: &[LocatedTarget], max_dist: f64) -> MatchResult {
+    let positions: Vec<Point3<f64>> = located.iter().map(|t| t.position).collect();
+    match_points(true_targets, &positions, max_dist)
+}
+
+/// `match_targets` 的点坐标版本
+pub fn match_points(true_targets: &[Point3<f64>], estimates: &[Point3<f64>], max_dist: f64) -> MatchResult {
+    // 超过门限的配对代价按门限计，等价于两者都不匹配
+    let cost: Vec<Vec<f64>> = true_targets
+        .iter()
+        .map(|t| estimates.iter().map(|e| (e - t).norm().min(max_dist)).collect())
+        .collect();
+    let assignment = min_cost_assignment(&cost, true_targets.len(), estimates.len());
+
+    let mut result = MatchResult::default();
+    let mut estimate_used = vec![false; estimates.len()];
+    for (truth, estimate) in assignment.into_iter().enumerate() {
+        match estimate {
+            Some(estimate) if (estimates[estimate] - true_targets[truth]).norm() <= max_dist => {
+                estimate_used[estimate] = true;
+                result.matches.push(TargetMatch {
+                    truth,
+                    estimate,
+                    distance: (estimates[estimate] - true_targets[truth]).norm(),
+                });
+            }
+            _ => result.missed.push(truth),
+        }
+    }
+    result.false_tracks = (0..estimates.len()).filter(|&i| !estimate_used[i]).collect();
+    result
+}
+
+/// 匈牙利算法（Kuhn-Munkres）求 rows×cols 代价矩阵的最小代价分配
+///
+/// 返回每行分配到的列；行数多于列数时部分行为 None。复杂度 O(n²m)。
+pub(crate) fn min_cost_assignment(cost: &[Vec<f64>], rows: usize, cols: usize) -> Vec<Option<usize>> {
+    if rows == 0 || cols == 0 {
+        return vec![None; rows];
+    }
+    // 算法要求行数不多于列数，否则转置求解
+    if rows > cols {
+        let transposed: Vec<Vec<f64>> = (0..cols).map(|j| (0..rows).map(|i| cost[i][j]).collect()).collect();
+        let mut result = vec![None; rows];
+        for (j, i) in min_cost_assignment(&transposed, cols, rows).into_iter().enumerate() {
+            if let Some(i) = i {
+                result[i] = Some(j);
+            }
+        }
+        return result;
+    }
+
+    // 势函数版本，下标从 1 开始，第 0 列为虚拟列
+    let (n, m) = (rows, cols);
+    let mut u = vec![0.0; n + 1];
+    let mut v = vec![0.0; m + 1];
+    let mut row_of_col = vec![0usize; m + 1];
+    let mut way = vec![0usize; m + 1];
+    for i in 1..=n {
+        row_of_col[0] = i;
+        let mut j0 = 0;
+        let mut min_v = vec![f64::INFINITY; m + 1];
+        let mut used = vec![false; m + 1];
+        loop {
+            used[j0] = true;
+            let i0 = row_of_col[j0];
+            let mut delta = f64::INFINITY;
+            let mut j1 = 0;
+            for j in 1..=m {
+                if used[j] {
+                    continue;
+                }
+                let reduced = cost[i0 - 1][j - 1] - u[i0] - v[j];
+                if reduced < min_v[j] {
+                    min_v[j] = reduced;
+                    way[j] = j0;
+                }
+                if min_v[j] < delta {
+                    delta = min_v[j];
+                    j1 = j;
+                }
+            }
+            for j in 0..=m {
+                if used[j] {
+                    u[row_of_col[j]] += delta;
+                    v[j] -= delta;
+                } else {
+                    min_v[j] -= delta;
+                }
+            }
+            j0 = j1;
+            if row_of_col[j0] == 0 {
+                break;
+            }
+        }
+        // 沿增广路径更新匹配
+        loop {
+            let j1 = way[j0];
+            row_of_col[j0] = row_of_col[j1];
+            j0 = j1;
+            if j0 == 0 {
+                break;
+            }
+        }
+    }
+
+    let mut result = vec![None; n];
+    for j in 1..=m {
+        if row_of_col[j] != 0 {
+            result[row_of_col[j] - 1] = Some(j - 1);
+        }
+    }
+    result
+}
+
+/// 升序样本的第 `percent` 百分位数（0~100，相邻次序统计量间线性插值），`sorted` 须非空
+pub(crate) fn interpolated_percentile(sorted: &[f64], percent: f64) -> f64 {
+    let rank = percent / 100.0 * (sorted.len() - 1) as f64;
+    let (lower, upper) = (sorted[rank.floor() as usize], sorted[rank.ceil() as usize]);
+    let fraction = rank - rank.floor();
+    // 两端相等（含同为无穷大）时直接返回，避免 ∞ - ∞
+    if fraction == 0.0 || lower == upper {
+        lower
+    } else {
+        lower + (upper - lower) * fraction
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::prelude::*;
+
+    #[test]
+    fn test_optimal_assignment_beats_greedy() {
+        // 贪心按真实目标顺序匹配会让第一个目标抢走第二个目标的最近结果
+        let truths = [Point3::new(0.0, 0.0, 0.0), Point3::new(2.0, 0.0, 0.0)];
+        let estimates = [Point3::new(1.1, 0.0, 0.0), Point3::new(-1.0, 0.0, 0.0)];
+        let result = match_points(&truths, &estimates, f64::INFINITY);
+        assert_eq!(
+            result.matches.iter().map(|m| (m.truth, m.estimate)).collect::<Vec<_>>(),
+            vec![(0, 1), (1, 0)]
+        );
+        assert!((result.mean_error().unwrap() - 0.95).abs() < 1e-12);
+
+        // 与输入顺序无关
+        let reversed = [estimates[1], estimates[0]];
+        let total: f64 = match_points(&truths, &reversed, f64::INFINITY).errors().iter().sum();
+        assert!((total - 1.9).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_gating_and_metrics() {
+        let truths = [
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(100.0, 0.0, 0.0),
+            Point3::new(200.0, 0.0, 0.0),
+        ];
+        let estimates = [
+            Point3::new(1.0, 0.0, 0.0),
+            Point3::new(203.0, 0.0, 0.0),
+            Point3::new(500.0, 0.0, 0.0),
+            Point3::new(0.0, 2.0, 0.0),
+        ];
+        let result = match_points(&truths, &estimates, 10.0);
+        assert_eq!(result.missed, vec![1]);
+        assert_eq!(result.false_tracks, vec![2, 3]);
+        assert_eq!(result.errors(), vec![1.0, 3.0]);
+        assert_eq!(result.median_error(), Some(2.0));
+        assert!((result.rms_error().unwrap() - 5f64.sqrt()).abs() < 1e-12);
+        assert_eq!(result.precision(), 0.5);
+        assert!((result.recall() - 2.0 / 3.0).abs() < 1e-12);
+
+        // 行数多于列数、空输入
+        let result = match_points(&truths, &estimates[..1], f64::INFINITY);
+        assert_eq!(result.matches.len(), 1);
+        assert_eq!(result.missed, vec![1, 2]);
+        let empty = match_points(&[], &estimates, 10.0);
+        assert_eq!(empty.false_tracks.len(), 4);
+        assert_eq!((empty.precision(), empty.recall(), empty.mean_error()), (0.0, 1.0, None));
+    }
+
+    #[test]
+    fn test_matches_brute_force() {
+        // 与穷举所有排列的最优解比较
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..50 {
+            let random_points = |rng: &mut StdRng, n: usize| -> Vec<Point3<f64>> {
+                (0..n)
+                    .map(|_| Point3::new(rng.gen_range(0.0..10.0), rng.gen_range(0.0..10.0), 0.0))
+                    .collect()
+            };
+            let truths = random_points(&mut rng, 5);
+            let estimates = random_points(&mut rng, 5);
+            let total: f64 = match_points(&truths, &estimates, f64::INFINITY).errors().iter().sum();
+
+            let mut best = f64::INFINITY;
+            let mut perm: Vec<usize> = (0..5).collect();
+            permutations(&mut perm, 0, &mut |p| {
+                let cost: f64 = p.iter().enumerate().map(|(i, &j)| (estimates[j] - truths[i]).norm()).sum();
+                best = best.min(cost);
+            });
+            assert!((total - best).abs() < 1e-9, "{} vs {}", total, best);
+        }
+    }
+
+    fn permutations(p: &mut Vec<usize>, k: usize, visit: &mut dyn FnMut(&[usize])) {
+        if k == p.len() {
+            visit(p);
+            return;
+        }
+        for i in k..p.len() {
+            p.swap(k, i);
+            permutations(p, k + 1, visit);
+            p.swap(k, i);
+        }
+    }
+}
@@ -0,0 +1,304 @@
+// src/io.rs
+
+use crate::target_processor::{LocatedTarget, Measurement};
+use nalgebra::Point3;
+use serde_json::{json, Value};
+use std::io::{self, BufRead, Read, Write};
+
+/// 测量 CSV 的表头；`station_id` 可为空，`weight` 为空时取 1.0
+pub const MEASUREMENT_CSV_HEADER: &str = "x,y,z,direction_x,direction_y,direction_z,station_id,weight";
+
+/// 带时间戳的测量 CSV 表头：在 `MEASUREMENT_CSV_HEADER` 后多一列 `timestamp`（秒），可为空
+pub const TIMESTAMPED_MEASUREMENT_CSV_HEADER: &str =
+    "x,y,z,direction_x,direction_y,direction_z,station_id,weight,timestamp";
+
+/// 定位结果 CSV 的表头
+pub const TARGET_CSV_HEADER: &str = "TargetID,EstX,EstY,EstZ,NumLines,AvgError";
+
+/// 点 CSV（例如真实目标位置）的表头
+pub const POINT_CSV_HEADER: &str = "x,y,z";
+
+/// 构造数据格式错误
+fn invalid_data(line_number: usize, message: impl std::fmt::Display) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("第 {} 行: {}", line_number, message))
+}
+
+/// 解析 CSV 数值字段
+fn parse_field(field: &str, line_number: usize, name: &str) -> io::Result<f64> {
+    field
+        .trim()
+        .parse()
+        .map_err(|_| invalid_data(line_number, format!("字段 {} 不是数值: {:?}", name, field)))
+}
+
+/// 逐行读取带表头的 CSV，跳过空行，返回 (行号, 字段) 列表
+fn read_csv_rows<R: BufRead>(reader: R, header: &str) -> io::Result<Vec<(usize, Vec<String>)>> {
+    let mut rows = Vec::new();
+    let mut lines = reader.lines().enumerate();
+    let first = lines.next().map(|(_, line)| line).transpose()?;
+    if first.as_deref().map(str::trim) != Some(header) {
+        return Err(invalid_data(1, format!("缺少表头 {}", header)));
+    }
+    let num_fields = header.split(',').count();
+    for (i, line) in lines {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields: Vec<String> = line.split(',').map(|f| f.trim().to_string()).collect();
+        if fields.len() != num_fields {
+            return Err(invalid_data(i + 1, format!("应有 {} 个字段，实际 {} 个", num_fields, fields.len())));
+        }
+        rows.push((i + 1, fields));
+    }
+    Ok(rows)
+}
+
+/// 从 JSON 数组读取测量，字段见 `Measurement` 的序列化格式
+pub fn read_measurements_json<R: Read>(reader: R) -> io::Result<Vec<Measurement>> {
+    Ok(serde_json::from_reader(reader)?)
+}
+
+/// 按表头判断测量 CSV 是否带 `timestamp` 列；表头既不是 `MEASUREMENT_CSV_HEADER`
+/// 也不是 `TIMESTAMPED_MEASUREMENT_CSV_HEADER` 时返回错误
+pub fn measurement_csv_has_timestamp(header: &str) -> io::Result<bool> {
+    match header.trim() {
+        MEASUREMENT_CSV_HEADER => Ok(false),
+        TIMESTAMPED_MEASUREMENT_CSV_HEADER => Ok(true),
+        _ => Err(invalid_data(1, format!("缺少表头 {}", MEASUREMENT_CSV_HEADER))),
+    }
+}
+
+/// 解析测量 CSV 的一行数据，`with_timestamp` 由 `measurement_csv_has_timestamp` 得到
+pub fn parse_measurement_csv_line(line: &str, line_number: usize, with_timestamp: bool) -> io::Result<Measurement> {
+    let header = if with_timestamp { TIMESTAMPED_MEASUREMENT_CSV_HEADER } else { MEASUREMENT_CSV_HEADER };
+    let names: Vec<&str> = header.split(',').collect();
+    let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+    if fields.len() != names.len() {
+        return Err(invalid_data(line_number, format!("应有 {} 个字段，实际 {} 个", names.len(), fields.len())));
+    }
+    // 测量站位置与方向
+    let mut values = [[0.0; 3]; 2];
+    for (k, value) in values.iter_mut().flatten().enumerate() {
+        *value = parse_field(fields[k], line_number, names[k])?;
+    }
+    let mut m = Measurement::from_arrays(values[0], values[1]);
+    if !fields[6].is_empty() {
+        m.station_id = Some(fields[6].to_string());
+    }
+    if !fields[7].is_empty() {
+        m.weight = parse_field(fields[7], line_number, names[7])?;
+    }
+    if with_timestamp && !fields[8].is_empty() {
+        m.timestamp = Some(parse_field(fields[8], line_number, names[8])?);
+    }
+    Ok(m)
+}
+
+/// 从 CSV 读取测量，表头须为 `MEASUREMENT_CSV_HEADER` 或 `TIMESTAMPED_MEASUREMENT_CSV_HEADER`
+pub fn read_measurements_csv<R: BufRead>(reader: R) -> io::Result<Vec<Measurement>> {
+    iter_measurements_csv(reader)?.collect()
+}
+
+/// 逐行读取测量 CSV，不先收集为 `Vec`（可直接交给 `try_find_targets_from_iter`）
+///
+/// 表头无效或无法读取时立即返回错误；之后每个非空行产生一项，
+/// 读取失败或无法解析的行产生 `Err`（错误信息含行号），迭代继续。
+pub fn iter_measurements_csv<R: BufRead>(reader: R) -> io::Result<impl Iterator<Item = io::Result<Measurement>>> {
+    let mut lines = reader.lines().enumerate();
+    let header = lines.next().map(|(_, line)| line).transpose()?;
+    let with_timestamp = measurement_csv_has_timestamp(header.as_deref().unwrap_or(""))?;
+    Ok(lines.filter_map(move |(i, line)| match line {
+        Ok(line) if line.trim().is_empty() => None,
+        Ok(line) => Some(parse_measurement_csv_line(&line, i + 1, with_timestamp)),
+        Err(error) => Some(Err(error)),
+    }))
+}
+
+/// 逐批读取测量 CSV 流（例如标准输入），每凑齐一批调用一次 `on_batch`
+///
+/// 空行结束当前批；指定 `window_s` 时，时间戳进入下一个时间窗（从流中第一个时间戳起算、
+/// 宽度 `window_s` 秒）也结束当前批。没有时间戳或时间戳早于当前时间窗的测量归入当前批。
+/// 无法解析的行交给 `on_error`（错误信息含行号）后跳过，不中断读取；流结束时输出最后一批。
+/// 表头无效、读取失败或 `on_batch` 返回错误时停止并返回该错误。
+pub fn read_measurement_batches<R: BufRead>(
+    reader: R,
+    window_s: Option<f64>,
+    mut on_batch: impl FnMut(Vec<Measurement>) -> io::Result<()>,
+    mut on_error: impl FnMut(io::Error),
+) -> io::Result<()> {
+    let mut lines = reader.lines().enumerate();
+    let header = lines.next().map(|(_, line)| line).transpose()?;
+    let with_timestamp = measurement_csv_has_timestamp(header.as_deref().unwrap_or(""))?;
+
+    let mut batch = Vec::new();
+    let mut start = None;
+    let mut current_window = None;
+    for (i, line) in lines {
+        let line = line?;
+        if line.trim().is_empty() {
+            if !batch.is_empty() {
+                on_batch(std::mem::take(&mut batch))?;
+            }
+            continue;
+        }
+        let m = match parse_measurement_csv_line(&line, i + 1, with_timestamp) {
+            Ok(m) => m,
+            Err(error) => {
+                on_error(error);
+                continue;
+            }
+        };
+        if let (Some(window_s), Some(t)) = (window_s, m.timestamp.filter(|t| t.is_finite())) {
+            let start = *start.get_or_insert(t);
+            let window = ((t - start) / window_s).floor() as i64;
+            if current_window.is_some_and(|current| window > current) && !batch.is_empty() {
+                on_batch(std::mem::take(&mut batch))?;
+            }
+            current_window = Some(current_window.map_or(window, |current: i64| current.max(window)));
+        }
+        batch.push(m);
+    }
+    if !batch.is_empty() {
+        on_batch(batch)?;
+    }
+    Ok(())
+}
+
+/// 将测量写为 CSV，格式同 `read_measurements_csv`；有测量带时间戳时输出 `timestamp` 列
+///
+/// 字段不加引号，读取时按 `,` 拆分并去掉首尾空白。`station_id` 为空串、含 `,` 或换行符、
+/// 或有首尾空白时读回的值会不同，此时不写出任何内容并返回 `InvalidInput`。
+pub fn write_measurements_csv<W: Write>(mut writer: W, measurements: &[Measurement]) -> io::Result<()> {
+    for (index, m) in measurements.iter().enumerate() {
+        if let Some(id) = m.station_id.as_deref() {
+            if id.is_empty() || id.contains([',', '\n', '\r']) || id.trim() != id {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("第 {} 条测量的测量站标识 {:?} 无法写入 CSV", index, id),
+                ));
+            }
+        }
+    }
+    let with_timestamp = measurements.iter().any(|m| m.timestamp.is_some());
+    let header = if with_timestamp { TIMESTAMPED_MEASUREMENT_CSV_HEADER } else { MEASUREMENT_CSV_HEADER };
+    writeln!(writer, "{}", header)?;
+    for m in measurements {
+        write!(
+            writer,
+            "{},{},{},{},{},{},{},{}",
+            m.x,
+            m.y,
+            m.z,
+            m.direction_x,
+            m.direction_y,
+            m.direction_z,
+            m.station_id.as_deref().unwrap_or(""),
+            m.weight
+        )?;
+        if with_timestamp {
+            write!(writer, ",{}", m.timestamp.map(|t| t.to_string()).unwrap_or_default())?;
+        }
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+/// 从 CSV 读取点坐标，表头须为 `POINT_CSV_HEADER`
+pub fn read_points_csv<R: BufRead>(reader: R) -> io::Result<Vec<Point3<f64>>> {
+    read_csv_rows(reader, POINT_CSV_HEADER)?
+        .into_iter()
+        .map(|(line_number, fields)| {
+            Ok(Point3::new(
+                parse_field(&fields[0], line_number, "x")?,
+                parse_field(&fields[1], line_number, "y")?,
+                parse_field(&fields[2], line_number, "z")?,
+            ))
+        })
+        .collect()
+}
+
+/// 将点坐标写为 CSV，格式同 `read_points_csv`
+pub fn write_points_csv<W: Write>(mut writer: W, points: &[Point3<f64>]) -> io::Result<()> {
+    writeln!(writer, "{}", POINT_CSV_HEADER)?;
+    for p in points {
+        writeln!(writer, "{},{},{}", p.x, p.y, p.z)?;
+    }
+    Ok(())
+}
+
+/// 将定位结果写为 CSV：TargetID, EstX, EstY, EstZ, NumLines, AvgError
+pub fn write_targets_csv<W: Write>(mut writer: W, targets: &[LocatedTarget]) -> io::Result<()> {
+    writeln!(writer, "{}", TARGET_CSV_HEADER)?;
+    for t in targets {
+        write_target_csv_row(&mut writer, t)?;
+    }
+    Ok(())
+}
+
+/// 写出一行定位结果 CSV（不含表头），列同 `TARGET_CSV_HEADER`
+pub fn write_target_csv_row<W: Write>(mut writer: W, t: &LocatedTarget) -> io::Result<()> {
+    writeln!(
+        writer,
+        "{},{},{},{},{},{}",
+        t.id, t.position.x, t.position.y, t.position.z, t.num_lines, t.avg_error_dist_m
+    )
+}
+
+/// 从 JSON 数组读取定位结果，格式同 `write_targets_json`
+pub fn read_targets_json<R: Read>(reader: R) -> io::Result<Vec<LocatedTarget>> {
+    Ok(serde_json::from_reader(reader)?)
+}
+
+/// 本地 ENU 坐标 → [经度, 纬度] 的转换函数
+pub type LonLatFn<'a> = &'a dyn Fn(&Point3<f64>) -> [f64; 2];
+
+/// 将定位结果以 JSON 数组写出，字段见 `LocatedTarget` 的序列化格式
+pub fn write_targets_json<W: Write>(writer: W, targets: &[LocatedTarget]) -> io::Result<()> {
+    serde_json::to_writer_pretty(writer, targets)?;
+    Ok(())
+}
+
+/// 将定位结果写为 GeoJSON FeatureCollection，每个目标一个 Point 要素
+///
+/// 要素属性包含 `id`、`num_lines` 和 `avg_error_dist_m`。
+/// 提供 `to_lon_lat` 时用它把本地 ENU 坐标转换为 [经度, 纬度]，高度取 z；
+/// 否则直接输出本地坐标 [x, y, z]。
+pub fn write_targets_geojson<W: Write>(
+    writer: W,
+    targets: &[LocatedTarget],
+    to_lon_lat: Option<LonLatFn>,
+) -> io::Result<()> {
+    let features: Vec<Value> = targets
+        .iter()
+        .map(|target| {
+            let p = target.position;
+            let coordinates = match to_lon_lat {
+                Some(convert) => {
+                    let [lon, lat] = convert(&p);
+                    [lon, lat, p.z]
+                }
+                None => target.position_array(),
+            };
+            json!({
+                "type": "Feature",
+                "geometry": {
+                    "type": "Point",
+                    "coordinates": coordinates,
+                },
+                "properties": {
+                    "id": target.id,
+                    "num_lines": target.num_lines,
+                    "avg_error_dist_m": target.avg_error_dist_m,
+                },
+            })
+        })
+        .collect();
+
+    let collection = json!({
+        "type": "FeatureCollection",
+        "features": features,
+    });
+    serde_json::to_writer_pretty(writer, &collection)?;
+    Ok(())
+}
@@ -3,4 +3,21 @@
 #![allow(dead_code)]
 
 pub mod target_processor;
+#[cfg(feature = "simulation")]
 pub mod data_generator;
+pub mod bundle_adjust;
+pub mod calibration;
+pub mod coords;
+pub mod coverage;
+pub mod crlb;
+pub mod error;
+pub mod evaluation;
+#[cfg(feature = "simulation")]
+pub mod simulation;
+pub mod line_soa;
+pub mod locator;
+pub mod motion;
+pub mod prelude;
+pub mod tracking;
+#[cfg(feature = "serde")]
+pub mod io;
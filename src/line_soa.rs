@@ -0,0 +1,274 @@
+// src/line_soa.rs
+
+use crate::target_processor::{Line, ResidualModel};
+use nalgebra::Point3;
+use std::ops::{Add, Div, Mul, Sub};
+
+// --- 列式（SoA）光线存储与残差距离核 ---
+//
+// 内点统计对每个候选点计算大量光线的残差距离。`Line` 按结构体数组存放，起点与方向之间夹着测量站编号与权重，
+// 逐条读取不利于向量化；这里把起点与方向的 6 个分量各存为一列连续的 f64，
+// 启用 `simd` 特性时按 4 条光线一组用 wide 的 f64x4 计算，否则逐条计算。连续索引段上的标量循环编译器
+// 本身已能自动向量化，显式 SIMD 的收益主要在零散索引（贪心提取移除部分光线后的子集、网格索引的候选）上。
+// 两条路径与 `ResidualModel::residual(..).norm()` 使用同一公式和相同的运算顺序（不使用融合乘加），
+// 因此结果逐位相同。
+
+/// 光线起点与方向的列式存储，由 `find_targets` 的 RANSAC 每次调用建立一次
+#[derive(Debug, Clone, Default)]
+pub struct LineSoa {
+    start: [Vec<f64>; 3],     // 起点的 x、y、z 列
+    direction: [Vec<f64>; 3], // 单位方向的 x、y、z 列
+}
+
+impl LineSoa {
+    pub fn new(lines: &[Line]) -> Self {
+        LineSoa {
+            start: [0, 1, 2].map(|k| lines.iter().map(|line| line.start[k]).collect()),
+            direction: [0, 1, 2].map(|k| lines.iter().map(|line| line.direction[k]).collect()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.start[0].len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 依次计算 `indices` 中各条光线到 `point` 的残差距离，写入 `out`（先清空）
+    ///
+    /// 与对相应 `Line` 计算 `residual_model.residual(line, point, ray_mode).norm()` 的结果相同。
+    /// 连续的索引段按列切片整块计算，零散的索引逐个读取后按组计算。
+    pub fn residuals(
+        &self,
+        indices: &[usize],
+        point: &Point3<f64>,
+        residual_model: ResidualModel,
+        ray_mode: bool,
+        out: &mut Vec<f64>,
+    ) {
+        out.clear();
+        out.resize(indices.len(), 0.0);
+        // 残差模型与射线模式在循环外分派，核内没有与数据无关的分支
+        match (ray_mode, residual_model) {
+            (false, ResidualModel::Metric) => self.residuals_into::<false, false>(indices, point, out),
+            (false, ResidualModel::Angular) => self.residuals_into::<false, true>(indices, point, out),
+            (true, ResidualModel::Metric) => self.residuals_into::<true, false>(indices, point, out),
+            (true, ResidualModel::Angular) => self.residuals_into::<true, true>(indices, point, out),
+        }
+    }
+
+    fn residuals_into<const RAY: bool, const ANGULAR: bool>(
+        &self,
+        indices: &[usize],
+        point: &Point3<f64>,
+        out: &mut [f64],
+    ) {
+        let mut k = 0;
+        while k < indices.len() {
+            let first = indices[k];
+            let run = indices[k..].iter().zip(first..).take_while(|&(&i, j)| i == j).count();
+            if run >= LANES {
+                self.residuals_range::<RAY, ANGULAR>(first, point, &mut out[k..k + run]);
+                k += run;
+            } else {
+                let end = (k + LANES).min(indices.len());
+                self.residuals_gathered::<RAY, ANGULAR>(&indices[k..end], point, &mut out[k..end]);
+                k = end;
+            }
+        }
+    }
+
+    /// 光线 first..first + out.len() 的残差距离
+    fn residuals_range<const RAY: bool, const ANGULAR: bool>(&self, first: usize, point: &Point3<f64>, out: &mut [f64]) {
+        let range = first..first + out.len();
+        let s = [0, 1, 2].map(|k| &self.start[k][range.clone()]);
+        let d = [0, 1, 2].map(|k| &self.direction[k][range.clone()]);
+        #[cfg(feature = "simd")]
+        let whole = {
+            use wide::f64x4;
+            let p = [f64x4::splat(point.x), f64x4::splat(point.y), f64x4::splat(point.z)];
+            // 各列按 4 个一组整块读取
+            let s = s.map(|column| column.as_chunks::<LANES>().0);
+            let d = d.map(|column| column.as_chunks::<LANES>().0);
+            let (chunks, _) = out.as_chunks_mut::<LANES>();
+            for (c, chunk) in chunks.iter_mut().enumerate() {
+                let load = |column: &[[f64; LANES]]| f64x4::new(column[c]);
+                let lanes = residual_lanes::<_, RAY, ANGULAR>(
+                    [load(s[0]), load(s[1]), load(s[2])],
+                    [load(d[0]), load(d[1]), load(d[2])],
+                    p,
+                );
+                *chunk = lanes.to_array();
+            }
+            chunks.len() * LANES
+        };
+        #[cfg(not(feature = "simd"))]
+        let whole = 0;
+        let p = [point.x, point.y, point.z];
+        for (j, value) in out.iter_mut().enumerate().skip(whole) {
+            *value = residual_lanes::<_, RAY, ANGULAR>([s[0][j], s[1][j], s[2][j]], [d[0][j], d[1][j], d[2][j]], p);
+        }
+    }
+
+    /// `indices`（不超过 `LANES` 个）中各条光线的残差距离
+    fn residuals_gathered<const RAY: bool, const ANGULAR: bool>(
+        &self,
+        indices: &[usize],
+        point: &Point3<f64>,
+        out: &mut [f64],
+    ) {
+        #[cfg(feature = "simd")]
+        if let &[a, b, c, e] = indices {
+            use wide::f64x4;
+            let gather = |column: &[f64]| f64x4::new([column[a], column[b], column[c], column[e]]);
+            let (s, d) = (&self.start, &self.direction);
+            let lanes = residual_lanes::<_, RAY, ANGULAR>(
+                [gather(&s[0]), gather(&s[1]), gather(&s[2])],
+                [gather(&d[0]), gather(&d[1]), gather(&d[2])],
+                [f64x4::splat(point.x), f64x4::splat(point.y), f64x4::splat(point.z)],
+            );
+            out.copy_from_slice(lanes.as_array_ref());
+            return;
+        }
+        let p = [point.x, point.y, point.z];
+        let (s, d) = (&self.start, &self.direction);
+        for (&i, value) in indices.iter().zip(out) {
+            *value = residual_lanes::<_, RAY, ANGULAR>([s[0][i], s[1][i], s[2][i]], [d[0][i], d[1][i], d[2][i]], p);
+        }
+    }
+}
+
+/// 每组计算的光线数（f64x4 的分量数）
+const LANES: usize = 4;
+
+/// 残差核的数值类型：单个 f64 或 f64x4 的各分量
+trait Lanes: Copy + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> + Div<Output = Self> {
+    fn splat(value: f64) -> Self;
+    fn sqrt(self) -> Self;
+    /// 各分量中 self < 0 的取 `negative` 的分量，其余取 `other` 的分量
+    fn select_negative(self, negative: Self, other: Self) -> Self;
+    /// 各分量中 self > 0 的取 `positive` 的分量，其余取 `other` 的分量（NaN 取 `other`）
+    fn select_positive(self, positive: Self, other: Self) -> Self;
+}
+
+impl Lanes for f64 {
+    fn splat(value: f64) -> Self {
+        value
+    }
+
+    fn sqrt(self) -> Self {
+        f64::sqrt(self)
+    }
+
+    fn select_negative(self, negative: Self, other: Self) -> Self {
+        if self < 0.0 {
+            negative
+        } else {
+            other
+        }
+    }
+
+    fn select_positive(self, positive: Self, other: Self) -> Self {
+        if self > 0.0 {
+            positive
+        } else {
+            other
+        }
+    }
+}
+
+#[cfg(feature = "simd")]
+impl Lanes for wide::f64x4 {
+    fn splat(value: f64) -> Self {
+        wide::f64x4::splat(value)
+    }
+
+    fn sqrt(self) -> Self {
+        wide::f64x4::sqrt(self)
+    }
+
+    fn select_negative(self, negative: Self, other: Self) -> Self {
+        use wide::CmpLt;
+        self.cmp_lt(wide::f64x4::ZERO).blend(negative, other)
+    }
+
+    fn select_positive(self, positive: Self, other: Self) -> Self {
+        use wide::CmpGt;
+        self.cmp_gt(wide::f64x4::ZERO).blend(positive, other)
+    }
+}
+
+/// 残差距离公式，逐步对应 `residual_vector`、`ResidualModel::residual` 与 `norm`
+///
+/// 分量逐个写出而不用数组的 `map`，保证热循环中完全内联。
+#[inline(always)]
+fn residual_lanes<L: Lanes, const RAY: bool, const ANGULAR: bool>(s: [L; 3], d: [L; 3], p: [L; 3]) -> L {
+    // closest_parameter：(p - start)·direction
+    let (vx, vy, vz) = (p[0] - s[0], p[1] - s[1], p[2] - s[2]);
+    let t = vx * d[0] + vy * d[1] + vz * d[2];
+    // perpendicular_vector_to：p - (start + direction t)；射线模式下最近点在起点之后时取 p - start
+    let (mut rx, mut ry, mut rz) = (p[0] - (s[0] + d[0] * t), p[1] - (s[1] + d[1] * t), p[2] - (s[2] + d[2] * t));
+    if RAY {
+        (rx, ry, rz) = (t.select_negative(vx, rx), t.select_negative(vy, ry), t.select_negative(vz, rz));
+    }
+    if ANGULAR {
+        let range = (vx * vx + vy * vy + vz * vz).sqrt();
+        let divisor = range.select_positive(range, L::splat(1.0));
+        (rx, ry, rz) = (rx / divisor, ry / divisor, rz / divisor);
+    }
+    (rx * rx + ry * ry + rz * rz).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra::Vector3;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn test_residuals_match_scalar() {
+        // 随机光线与随机点（含位于起点、位于起点之后和远离原点的情形），连续与离散索引、不足 4 条的余数
+        let mut rng = StdRng::seed_from_u64(601);
+        let mut lines: Vec<Line> = (0..1003)
+            .map(|_| {
+                let scale = [1.0, 1e3, 1e6][rng.gen_range(0..3)];
+                let start = Point3::new(
+                    rng.gen_range(-scale..scale),
+                    rng.gen_range(-scale..scale),
+                    rng.gen_range(-scale..scale),
+                );
+                let direction = Vector3::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0));
+                Line::new(start, direction)
+            })
+            .collect();
+        lines.push(Line::new(Point3::new(5.0, 0.0, 0.0), Vector3::x()));
+        let soa = LineSoa::new(&lines);
+        assert_eq!(soa.len(), lines.len());
+        let contiguous: Vec<usize> = (0..lines.len()).collect();
+        let scattered: Vec<usize> = (0..lines.len()).filter(|_| rng.gen_bool(0.6)).collect();
+        let mut points = vec![Point3::new(5.0, 0.0, 0.0), Point3::new(-3.0, 0.0, 0.0)];
+        points.extend((0..20).map(|_| Point3::new(rng.gen_range(-2e3..2e3), rng.gen_range(-2e3..2e3), rng.gen_range(-2e3..2e3))));
+        points.extend(lines.iter().take(10).map(|line| line.start));
+
+        let mut out = Vec::new();
+        for point in &points {
+            for residual_model in [ResidualModel::Metric, ResidualModel::Angular] {
+                for ray_mode in [false, true] {
+                    for indices in [&contiguous[..], &scattered[..], &contiguous[7..10]] {
+                        soa.residuals(indices, point, residual_model, ray_mode, &mut out);
+                        assert_eq!(out.len(), indices.len());
+                        for (&i, &distance) in indices.iter().zip(&out) {
+                            let expected = residual_model.residual(&lines[i], point, ray_mode).norm();
+                            assert!((distance - expected).abs() <= 1e-12 * expected.max(1.0), "{} {}", distance, expected);
+                            assert_eq!(distance.to_bits(), expected.to_bits());
+                        }
+                    }
+                }
+            }
+        }
+        assert!(LineSoa::new(&[]).is_empty());
+    }
+}
@@ -0,0 +1,223 @@
+// src/locator.rs
+
+use crate::error::OptiRadarError;
+use crate::target_processor::{
+    extract_targets, fit_target, keep_closest_per_station, prepare_lines, refine_targets, seeded_rng,
+    within_threshold, FindTargetsConfig, FindTargetsDiagnostics, LocatedTarget, Measurement, Pipeline,
+};
+use std::collections::HashSet;
+
+// --- 增量定位 ---
+//
+// 测量逐条到达时，`TargetLocator` 保存当前的测量窗口和上一次的定位结果。
+// 每次求解先以已有目标的位置为中心按阈值筛选附近光线并重新运行 LM（热启动），
+// 只有未被已有目标解释的剩余光线才进入完整的 RANSAC 贪心提取。
+
+/// 增量目标定位器
+#[derive(Debug, Clone)]
+pub struct TargetLocator {
+    config: FindTargetsConfig,
+    measurements: Vec<Measurement>,
+    targets: Vec<LocatedTarget>,
+    diagnostics: FindTargetsDiagnostics,
+    next_id: usize,
+}
+
+impl TargetLocator {
+    /// 以给定定位参数创建空的定位器
+    pub fn new(config: FindTargetsConfig) -> Self {
+        TargetLocator {
+            config,
+            measurements: Vec::new(),
+            targets: Vec::new(),
+            diagnostics: FindTargetsDiagnostics::default(),
+            next_id: 1,
+        }
+    }
+
+    /// 定位参数
+    pub fn config(&self) -> &FindTargetsConfig {
+        &self.config
+    }
+
+    /// 当前窗口内的测量，按加入顺序排列；`inlier_indices` 为其中的索引
+    pub fn measurements(&self) -> &[Measurement] {
+        &self.measurements
+    }
+
+    /// 上一次 `solve` 的定位结果
+    pub fn targets(&self) -> &[LocatedTarget] {
+        &self.targets
+    }
+
+    /// 上一次 `solve` 的诊断信息（耗时与 LM 统计包括已有目标的重新拟合）
+    pub fn diagnostics(&self) -> &FindTargetsDiagnostics {
+        &self.diagnostics
+    }
+
+    /// 加入一条测量，无法通过 `Measurement::try_into_line` 校验时返回错误且不加入
+    pub fn add_measurement(&mut self, measurement: Measurement) -> Result<(), OptiRadarError> {
+        measurement.try_into_line()?;
+        self.measurements.push(measurement);
+        Ok(())
+    }
+
+    /// 移除时间戳早于 `timestamp` 的测量，返回移除的条数
+    ///
+    /// 没有时间戳的测量不会被移除。移除后 `targets()` 中的 `inlier_indices`
+    /// 在下一次 `solve` 之前不再有效。
+    pub fn remove_older_than(&mut self, timestamp: f64) -> usize {
+        let before = self.measurements.len();
+        self.measurements.retain(|m| m.timestamp.is_none_or(|t| t >= timestamp));
+        before - self.measurements.len()
+    }
+
+    /// 按当前测量重新定位并返回结果
+    ///
+    /// 已有目标依次以其上次的位置为中心，收集阈值内尚未使用的光线
+    /// （每个测量站只保留最近的一条），不少于 `min_lines` 条时以上次的位置为初值重新运行 LM
+    /// 并沿用原标识，否则丢弃。剩余光线按 `find_targets` 的流程贪心提取新目标，
+    /// 新目标的编号在整个定位器生命周期内递增；最后按配置合并过近的目标并重新分配光线。
+    pub fn solve(&mut self) -> &[LocatedTarget] {
+        let (all_lines, station_names, _) = prepare_lines(self.measurements.iter().enumerate(), &mut Vec::new());
+        let pipeline = Pipeline::new(&self.config);
+        let mut used_line_indices = HashSet::new();
+        let mut located_targets = Vec::new();
+        self.diagnostics = FindTargetsDiagnostics::default();
+
+        for previous in &self.targets {
+            let candidates = within_threshold(&all_lines, &previous.position, &self.config.ransac)
+                .into_iter()
+                .filter(|(i, _)| !used_line_indices.contains(i))
+                .collect();
+            let inliers = keep_closest_per_station(&all_lines, candidates);
+            if inliers.len() < self.config.ransac.min_lines {
+                continue;
+            }
+            let target = fit_target(
+                previous.index,
+                &all_lines,
+                inliers,
+                previous.position,
+                &pipeline,
+                &station_names,
+            );
+            used_line_indices.extend(target.inlier_indices.iter().copied());
+            located_targets.push(target);
+        }
+
+        extract_targets(
+            &all_lines,
+            &station_names,
+            &pipeline,
+            &mut used_line_indices,
+            &mut located_targets,
+            &mut self.next_id,
+            &mut self.diagnostics,
+            &mut seeded_rng(self.config.ransac.seed),
+        );
+        self.targets = refine_targets(&all_lines, &station_names, &pipeline, located_targets);
+        self.diagnostics.record_results(all_lines.len(), &self.targets, &pipeline);
+        &self.targets
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> FindTargetsConfig {
+        let mut config = FindTargetsConfig::new(20.0, 3);
+        config.ransac.seed = Some(5);
+        config
+    }
+
+    #[test]
+    #[cfg(feature = "simulation")]
+    fn test_incremental_matches_batch() {
+        use crate::data_generator::{generate_scenario, GeneratorConfig};
+        use crate::evaluation::match_targets;
+        use crate::target_processor::find_targets_with_config;
+
+        let generator = GeneratorConfig::builder()
+            .num_targets(4)
+            .target_x_range(-2000.0, 2000.0)
+            .target_y_range(-2000.0, 2000.0)
+            .num_stations_per_target_range(5, 8)
+            .angle_noise_std(0.001)
+            .seed(21)
+            .build()
+            .unwrap();
+        let scenario = generate_scenario(&generator);
+        // 前三个目标的测量先到，第四个目标的测量后到
+        let (early, late): (Vec<_>, Vec<_>) = scenario
+            .measurements
+            .iter()
+            .cloned()
+            .zip(&scenario.labels)
+            .partition(|(_, label)| **label != Some(3));
+
+        let mut locator = TargetLocator::new(config());
+        for (m, _) in early {
+            locator.add_measurement(m).unwrap();
+        }
+        assert_eq!(locator.solve().len(), 3);
+        let first_ids: Vec<_> = locator.targets().iter().map(|t| t.id.clone()).collect();
+
+        for (m, _) in late {
+            locator.add_measurement(m).unwrap();
+        }
+        let incremental = locator.solve().to_vec();
+        let batch = find_targets_with_config(locator.measurements(), &config());
+        assert_eq!(incremental.len(), 4);
+        assert_eq!(batch.len(), 4);
+
+        // 已有目标沿用标识，新目标继续编号
+        let ids: Vec<_> = incremental.iter().map(|t| t.id.as_str()).collect();
+        assert_eq!(ids[..3], first_ids.iter().map(String::as_str).collect::<Vec<_>>()[..]);
+        assert_eq!(ids[3], "Target_4");
+
+        // 与批处理结果一致
+        let batch_positions: Vec<_> = batch.iter().map(|t| t.position).collect();
+        let result = match_targets(&batch_positions, &incremental, 1.0);
+        assert_eq!(result.matches.len(), 4, "{:?}", result);
+        for m in &result.matches {
+            assert_eq!(batch[m.truth].num_lines, incremental[m.estimate].num_lines);
+        }
+        let truth = match_targets(&scenario.true_targets, &incremental, f64::INFINITY);
+        assert!(truth.mean_error().unwrap() < 20.0);
+    }
+
+    #[test]
+    fn test_remove_older_than() {
+        let mut locator = TargetLocator::new(config());
+        let targets = [
+            nalgebra::Point3::new(0.0, 0.0, 100.0),
+            nalgebra::Point3::new(800.0, 0.0, 100.0),
+        ];
+        for (t, target) in targets.iter().enumerate() {
+            for k in 0..4 {
+                let angle = k as f64 * 1.5;
+                let start = target + nalgebra::Vector3::new(300.0 * angle.cos(), 300.0 * angle.sin(), -90.0);
+                let d = target - start;
+                let m = Measurement::new(start.x, start.y, start.z, d.x, d.y, d.z).with_timestamp(t as f64 * 10.0);
+                locator.add_measurement(m).unwrap();
+            }
+        }
+        // 没有时间戳的测量不会过期
+        locator.add_measurement(Measurement::new(0.0, 0.0, 0.0, 1.0, 0.0, 0.0)).unwrap();
+        assert_eq!(locator.solve().len(), 2);
+
+        assert_eq!(locator.remove_older_than(5.0), 4);
+        assert_eq!(locator.measurements().len(), 5);
+        let located = locator.solve();
+        assert_eq!(located.len(), 1);
+        assert!((located[0].position - targets[1]).norm() < 1e-6);
+        assert_eq!(located[0].inlier_indices, vec![0, 1, 2, 3]);
+
+        assert_eq!(
+            locator.add_measurement(Measurement::new(0.0, 0.0, 0.0, 0.0, 0.0, 0.0)),
+            Err(OptiRadarError::ZeroDirection)
+        );
+    }
+}
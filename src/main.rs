@@ -1,36 +1,782 @@
 // src/main.rs
-use opti_radar::{target_processor::{find_targets, LocatedTarget}, data_generator::generate_data};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use nalgebra::Point3;
+use opti_radar::{
+    data_generator::{generate_data_from_config, GeneratorConfig, NoiseModel, StationLayout},
+    evaluation::match_targets,
+    io,
+    target_processor::{
+        find_targets_with_diagnostics, BoundingBox, FindTargetsConfig, GeometryCriterion, LocatedTarget, Measurement, OutOfBounds,
+        Optimizer, OutputOrdering, RansacScoring, ResidualModel, RobustEstimator, StopReason,
+    },
+};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process;
 
-fn main() {
-    // 数据生成参数
-    let (true_targets, measurements) = generate_data(
-        5,                     // num_targets
-        (0.0, 50.0),           // target_x_range
-        (0.0, 50.0),           // target_y_range
-        (0.0, 20.0),           // target_z_range
-        (3, 6),                // num_stations_per_target_range
-        (5.0, 15.0),           // station_dist_range
-        (1.0, 5.0),            // station_z_range
-        0.1,                   // pos_noise_std
-        0.2,                   // alt_noise_std
-        0.01,                  // angle_noise_std
+/// 多站光学测向目标定位
+///
+/// 不带子命令时生成模拟数据并定位，输出真实位置与估计位置的对照。
+#[derive(Parser)]
+#[command(name = "opti_radar_main", version, args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    generator: GeneratorArgs,
+
+    #[command(flatten)]
+    solver: SolverArgs,
+
+    /// 输出格式
+    #[arg(long, value_enum, default_value_t = OutputFormat::Csv)]
+    output_format: OutputFormat,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// 生成模拟测量数据
+    Simulate(Box<SimulateArgs>),
+    /// 从文件或标准输入读取测量数据并定位目标
+    Locate(Box<LocateArgs>),
+    /// 将定位结果与真实目标位置比较，输出每个目标的误差
+    Evaluate(EvaluateArgs),
+}
+
+/// 输出格式
+#[derive(Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    Csv,
+    Json,
+    Geojson,
+}
+
+/// 定位结果的排列顺序
+#[derive(Clone, Copy, ValueEnum)]
+enum OrderingArg {
+    /// 按发现顺序
+    Discovery,
+    /// 按光线数从多到少
+    NumLines,
+    /// 按可信度从高到低
+    Confidence,
+    /// 按位置 (x, y, z) 字典序
+    Position,
+}
+
+/// 精化目标位置的优化方法
+#[derive(Clone, Copy, ValueEnum)]
+enum OptimizerArg {
+    /// Levenberg-Marquardt
+    Lm,
+    /// 信赖域 dogleg
+    Dogleg,
+}
+
+/// 噪声分布
+#[derive(Clone, Copy, ValueEnum)]
+enum NoiseArg {
+    Gaussian,
+    Uniform,
+    /// 方向逐分量加噪声（旧模型，仅用于对比）
+    Componentwise,
+}
+
+/// `GeneratorConfig` 的全部参数
+#[derive(Args)]
+struct GeneratorArgs {
+    /// 目标数量
+    #[arg(long, default_value_t = 5)]
+    num_targets: usize,
+    /// 目标 x 坐标范围 MIN,MAX
+    #[arg(long, value_parser = parse_range, default_value = "0,50")]
+    target_x_range: (f64, f64),
+    /// 目标 y 坐标范围 MIN,MAX
+    #[arg(long, value_parser = parse_range, default_value = "0,50")]
+    target_y_range: (f64, f64),
+    /// 目标 z 坐标范围 MIN,MAX
+    #[arg(long, value_parser = parse_range, default_value = "0,20")]
+    target_z_range: (f64, f64),
+    /// 每个目标的测量站数量范围 MIN,MAX（含两端）
+    #[arg(long, value_parser = parse_count_range, default_value = "3,6")]
+    stations_per_target: (usize, usize),
+    /// 测量站到目标的水平距离范围 MIN,MAX
+    #[arg(long, value_parser = parse_range, default_value = "5,15")]
+    station_dist_range: (f64, f64),
+    /// 测量站海拔范围 MIN,MAX
+    #[arg(long, value_parser = parse_range, default_value = "1,5")]
+    station_z_range: (f64, f64),
+    /// 测量站水平位置噪声标准差
+    #[arg(long, value_parser = parse_non_negative, default_value_t = 0.1)]
+    pos_noise_std: f64,
+    /// 测量站海拔噪声标准差
+    #[arg(long, value_parser = parse_non_negative, default_value_t = 0.2)]
+    alt_noise_std: f64,
+    /// 测量方向噪声标准差
+    #[arg(long, value_parser = parse_non_negative, default_value_t = 0.01)]
+    angle_noise_std: f64,
+    /// 噪声分布（参数均为标准差）
+    #[arg(long, value_enum, default_value_t = NoiseArg::Gaussian)]
+    noise_model: NoiseArg,
+    /// 每个测量站方位角偏差的均匀分布范围（弧度），MIN 可等于 MAX
+    #[arg(long, value_parser = parse_bias_range, default_value = "0,0")]
+    azimuth_bias_range: (f64, f64),
+    /// 每个测量站俯仰角偏差的均匀分布范围（弧度），MIN 可等于 MAX
+    #[arg(long, value_parser = parse_bias_range, default_value = "0,0")]
+    elevation_bias_range: (f64, f64),
+    /// 杂波（虚警）测量占全部测量的比例，[0, 1)
+    #[arg(long, value_parser = parse_fraction, default_value_t = 0.0)]
+    clutter_fraction: f64,
+    /// 共享测量站数量；指定时所有目标由同一组测量站观测（忽略 --stations-per-target）
+    #[arg(long, group = "shared_layout")]
+    shared_stations: Option<usize>,
+    /// 测量站位置文件（点 CSV，.json 为 JSON）；指定时所有目标由这些测量站观测
+    /// （忽略 --stations-per-target 与 --station-dist-range）
+    #[arg(long, value_name = "FILE", group = "shared_layout")]
+    stations: Option<PathBuf>,
+    /// 共享或指定测量站模式下每个测量站观测到每个目标的概率，[0, 1]
+    #[arg(long, value_parser = parse_probability, default_value_t = 1.0, requires = "shared_layout")]
+    detection_probability: f64,
+    /// 随机种子，指定时场景可复现
+    #[arg(long)]
+    seed: Option<u64>,
+}
+
+/// 定位参数
+#[derive(Args)]
+struct SolverArgs {
+    /// RANSAC 内点阈值（米；指定 --angular 时为弧度）
+    #[arg(long, value_parser = parse_positive, default_value_t = 1.0)]
+    ransac_threshold: f64,
+    /// 使用角度残差（垂直距离除以测量站到目标的距离）代替垂直距离
+    #[arg(long)]
+    angular: bool,
+    /// 使用最小中位数平方（LMedS）代替 RANSAC，内点阈值由数据导出
+    /// （--ransac-threshold 仍用于合并等后续步骤）
+    #[arg(long)]
+    lmeds: bool,
+    /// RANSAC 使用 MSAC 评分（内点按残差平方计代价）代替内点计数
+    #[arg(long)]
+    msac: bool,
+    /// 目标高度先验 Z,STD（米），作为软约束加入 LM
+    #[arg(long, value_name = "Z,STD", value_parser = parse_altitude_prior)]
+    altitude_prior: Option<(f64, f64)>,
+    /// 已知的目标高度 Z（米），只求解水平位置；可为负数，如 --fixed-altitude=-12
+    #[arg(long, value_name = "Z", value_parser = parse_finite, conflicts_with = "altitude_prior")]
+    fixed_altitude: Option<f64>,
+    /// 距离小于该值（米）的定位结果合并为一个目标，0 表示不合并
+    #[arg(long, value_parser = parse_non_negative, default_value_t = 0.0)]
+    min_separation: f64,
+    /// 定位目标数上限
+    #[arg(long)]
+    max_targets: Option<usize>,
+    /// RANSAC 提取轮数上限
+    #[arg(long)]
+    max_rounds: Option<usize>,
+    /// 一致集质量（1 - RMS 残差 / 阈值）下限，低于该值时停止提取
+    #[arg(long, value_parser = parse_fraction)]
+    min_inlier_quality: Option<f64>,
+    /// 关注区域 XMIN,YMIN,ZMIN,XMAX,YMAX,ZMAX（米），区域外的候选与定位结果被丢弃
+    #[arg(long, value_name = "XMIN,YMIN,ZMIN,XMAX,YMAX,ZMAX", value_parser = parse_bounds, allow_hyphen_values = true)]
+    bounds: Option<BoundingBox>,
+    /// 精化结果超出 --bounds 时截断到区域边界而不是丢弃
+    #[arg(long, requires = "bounds")]
+    clamp_to_bounds: bool,
+    /// 一致集内光线两两交会角的最大值下限（弧度），交会角更小的一致集不构成目标
+    #[arg(long, value_name = "RAD", value_parser = parse_positive)]
+    min_crossing_angle: Option<f64>,
+    /// 测向噪声每个角度分量的 1σ（弧度），设置时对每个目标做 χ² 一致性检验并标记离群光线
+    #[arg(long, value_name = "RAD", value_parser = parse_positive)]
+    measurement_sigma: Option<f64>,
+    /// 剔除被标记的离群光线后重新拟合一次
+    #[arg(long, requires = "measurement_sigma")]
+    refit_outliers: bool,
+    /// 剩余光线数不超过该值时 RANSAC 穷举全部三元组（结果确定），0 表示总是随机抽样
+    #[arg(long, default_value_t = 12)]
+    exhaustive_max_lines: usize,
+    /// 构成目标所需的最少光线数；取 2 时剩余光线两两交会，结果标记为低可信度
+    #[arg(long, value_parser = clap::value_parser!(u64).range(2..), default_value_t = 3)]
+    min_lines: u64,
+    /// LM 最大迭代次数
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..), default_value_t = 200)]
+    lm_iterations: u64,
+    /// LM 的起点个数；大于 1 时各起点先运行短程 LM，再从代价最低者继续
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..), default_value_t = 1)]
+    lm_starts: u64,
+    /// 精化目标位置的优化方法
+    #[arg(long, value_enum, default_value_t = OptimizerArg::Lm)]
+    optimizer: OptimizerArg,
+    /// 定位结果的排列顺序；发现顺序以外的顺序按排序后的位置重新编号
+    #[arg(long, value_enum, default_value_t = OrderingArg::Discovery)]
+    ordering: OrderingArg,
+    /// 目标标识的前缀，标识为前缀加编号
+    #[arg(long, default_value = "Target_")]
+    id_prefix: String,
+}
+
+#[derive(Args)]
+struct SimulateArgs {
+    #[command(flatten)]
+    generator: GeneratorArgs,
+    /// 测量数据输出文件（.json 为 JSON，否则为 CSV）；缺省写到标准输出（CSV）
+    #[arg(long)]
+    measurements: Option<PathBuf>,
+    /// 真实目标位置输出文件（.json 为 JSON，否则为 CSV）
+    #[arg(long)]
+    truth: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct LocateArgs {
+    /// 测量数据文件（.json 为 JSON，否则为 CSV）
+    #[arg(required_unless_present = "stdin")]
+    input: Option<PathBuf>,
+    /// 从标准输入逐批读取测量 CSV，每批定位后立即输出，直到输入结束；
+    /// 空行结束一批，CSV 输出只在开头写一次表头，JSON 输出每行一个目标
+    #[arg(long, conflicts_with = "input")]
+    stdin: bool,
+    /// 标准输入模式下按测量时间戳（`timestamp` 列）每 SECONDS 秒结束一批
+    #[arg(long, value_name = "SECONDS", value_parser = parse_positive, conflicts_with = "input")]
+    window_seconds: Option<f64>,
+    #[command(flatten)]
+    solver: SolverArgs,
+    /// 输出格式
+    #[arg(long, value_enum, default_value_t = OutputFormat::Csv)]
+    output_format: OutputFormat,
+}
+
+#[derive(Args)]
+struct EvaluateArgs {
+    /// 定位结果文件（`locate --output-format json` 的输出）
+    #[arg(long)]
+    targets: PathBuf,
+    /// 真实目标位置文件（.json 为 JSON，否则为 CSV）
+    #[arg(long)]
+    truth: PathBuf,
+    /// 匹配门限（米），误差超过门限的配对计为漏检和虚警；缺省不设门限
+    #[arg(long, value_parser = parse_positive)]
+    max_dist: Option<f64>,
+}
+
+/// 解析 MIN,MAX 形式的取值范围，要求 MIN < MAX
+fn parse_range(s: &str) -> Result<(f64, f64), String> {
+    let (min, max) = s.split_once(',').ok_or("应为 MIN,MAX 形式")?;
+    let min: f64 = min.trim().parse().map_err(|_| format!("无效的最小值: {}", min))?;
+    let max: f64 = max.trim().parse().map_err(|_| format!("无效的最大值: {}", max))?;
+    if !(min.is_finite() && max.is_finite() && min < max) {
+        return Err(format!("范围须满足 MIN < MAX，实际为 {},{}", min, max));
+    }
+    Ok((min, max))
+}
+
+/// 解析 MIN,MAX 形式的偏差范围，要求 MIN ≤ MAX
+fn parse_bias_range(s: &str) -> Result<(f64, f64), String> {
+    let (min, max) = s.split_once(',').ok_or("应为 MIN,MAX 形式")?;
+    let min: f64 = min.trim().parse().map_err(|_| format!("无效的最小值: {}", min))?;
+    let max: f64 = max.trim().parse().map_err(|_| format!("无效的最大值: {}", max))?;
+    if !(min.is_finite() && max.is_finite() && min <= max) {
+        return Err(format!("范围须满足 MIN ≤ MAX，实际为 {},{}", min, max));
+    }
+    Ok((min, max))
+}
+
+/// 解析 MIN,MAX 形式的数量范围，要求 MIN ≤ MAX
+fn parse_count_range(s: &str) -> Result<(usize, usize), String> {
+    let (min, max) = s.split_once(',').ok_or("应为 MIN,MAX 形式")?;
+    let min: usize = min.trim().parse().map_err(|_| format!("无效的最小值: {}", min))?;
+    let max: usize = max.trim().parse().map_err(|_| format!("无效的最大值: {}", max))?;
+    if min > max {
+        return Err(format!("范围须满足 MIN ≤ MAX，实际为 {},{}", min, max));
+    }
+    Ok((min, max))
+}
+
+/// 解析 Z,STD 形式的高度先验，要求 STD > 0
+fn parse_altitude_prior(s: &str) -> Result<(f64, f64), String> {
+    let (z, std) = s.split_once(',').ok_or("应为 Z,STD 形式")?;
+    let z: f64 = z.trim().parse().map_err(|_| format!("无效的高度: {}", z))?;
+    let std: f64 = std.trim().parse().map_err(|_| format!("无效的标准差: {}", std))?;
+    if !(z.is_finite() && std.is_finite() && std > 0.0) {
+        return Err(format!("高度须为有限值且标准差须为正数，实际为 {},{}", z, std));
+    }
+    Ok((z, std))
+}
+
+/// 解析 XMIN,YMIN,ZMIN,XMAX,YMAX,ZMAX 形式的关注区域
+fn parse_bounds(s: &str) -> Result<BoundingBox, String> {
+    let values = s
+        .split(',')
+        .map(|v| v.trim().parse::<f64>().map_err(|_| format!("无效的数值: {}", v)))
+        .collect::<Result<Vec<_>, _>>()?;
+    let [x_min, y_min, z_min, x_max, y_max, z_max] = values[..] else {
+        return Err("应为 XMIN,YMIN,ZMIN,XMAX,YMAX,ZMAX 形式".to_string());
+    };
+    BoundingBox::new(Point3::new(x_min, y_min, z_min), Point3::new(x_max, y_max, z_max)).map_err(|e| e.to_string())
+}
+
+/// 解析有限实数
+fn parse_finite(s: &str) -> Result<f64, String> {
+    let value: f64 = s.trim().parse().map_err(|_| format!("无效的数值: {}", s))?;
+    if !value.is_finite() {
+        return Err(format!("须为有限值，实际为 {}", value));
+    }
+    Ok(value)
+}
+
+/// 解析非负的有限实数
+fn parse_non_negative(s: &str) -> Result<f64, String> {
+    let value: f64 = s.trim().parse().map_err(|_| format!("无效的数值: {}", s))?;
+    if !(value.is_finite() && value >= 0.0) {
+        return Err(format!("须为非负数，实际为 {}", value));
+    }
+    Ok(value)
+}
+
+/// 解析 [0, 1) 内的比例
+fn parse_fraction(s: &str) -> Result<f64, String> {
+    let value: f64 = s.trim().parse().map_err(|_| format!("无效的数值: {}", s))?;
+    if !(0.0..1.0).contains(&value) {
+        return Err(format!("须在 [0, 1) 内，实际为 {}", value));
+    }
+    Ok(value)
+}
+
+/// 解析 [0, 1] 内的概率
+fn parse_probability(s: &str) -> Result<f64, String> {
+    let value: f64 = s.trim().parse().map_err(|_| format!("无效的数值: {}", s))?;
+    if !(0.0..=1.0).contains(&value) {
+        return Err(format!("须在 [0, 1] 内，实际为 {}", value));
+    }
+    Ok(value)
+}
+
+/// 解析正的有限实数
+fn parse_positive(s: &str) -> Result<f64, String> {
+    let value: f64 = s.trim().parse().map_err(|_| format!("无效的数值: {}", s))?;
+    if !(value.is_finite() && value > 0.0) {
+        return Err(format!("须为正数，实际为 {}", value));
+    }
+    Ok(value)
+}
+
+impl GeneratorArgs {
+    fn config(&self) -> Result<GeneratorConfig, String> {
+        let noise_model = match self.noise_model {
+            NoiseArg::Gaussian => NoiseModel::Gaussian,
+            NoiseArg::Uniform => NoiseModel::Uniform,
+            NoiseArg::Componentwise => NoiseModel::Componentwise,
+        };
+        let mut builder = GeneratorConfig::builder()
+            .num_targets(self.num_targets)
+            .target_x_range(self.target_x_range.0, self.target_x_range.1)
+            .target_y_range(self.target_y_range.0, self.target_y_range.1)
+            .target_z_range(self.target_z_range.0, self.target_z_range.1)
+            .num_stations_per_target_range(self.stations_per_target.0, self.stations_per_target.1)
+            .station_dist_range(self.station_dist_range.0, self.station_dist_range.1)
+            .station_z_range(self.station_z_range.0, self.station_z_range.1)
+            .pos_noise_std(self.pos_noise_std)
+            .alt_noise_std(self.alt_noise_std)
+            .angle_noise_std(self.angle_noise_std)
+            .noise_model(noise_model)
+            .azimuth_bias_range(self.azimuth_bias_range.0, self.azimuth_bias_range.1)
+            .elevation_bias_range(self.elevation_bias_range.0, self.elevation_bias_range.1)
+            .clutter_fraction(self.clutter_fraction);
+        if let Some(num_stations) = self.shared_stations {
+            builder = builder.station_layout(StationLayout::Shared {
+                num_stations,
+                detection_probability: self.detection_probability,
+            });
+        }
+        if let Some(path) = &self.stations {
+            builder = builder.station_layout(StationLayout::Fixed {
+                positions: read_points(path)?,
+                detection_probability: self.detection_probability,
+                angle_noise_stds: Vec::new(),
+            });
+        }
+        if let Some(seed) = self.seed {
+            builder = builder.seed(seed);
+        }
+        builder.build().map_err(|e| e.to_string())
+    }
+
+    fn generate(&self) -> Result<(Vec<Point3<f64>>, Vec<Measurement>), String> {
+        Ok(generate_data_from_config(&self.config()?))
+    }
+}
+
+impl SolverArgs {
+    fn config(&self) -> FindTargetsConfig {
+        let mut config = FindTargetsConfig::new(self.ransac_threshold, self.min_lines as usize);
+        config.lm.iterations = self.lm_iterations as usize;
+        config.lm.multi_start = self.lm_starts as usize;
+        config.optimizer = match self.optimizer {
+            OptimizerArg::Lm => Optimizer::LevenbergMarquardt,
+            OptimizerArg::Dogleg => Optimizer::Dogleg,
+        };
+        config.ransac.exhaustive_max_lines = self.exhaustive_max_lines;
+        if self.angular {
+            config = config.with_residual_model(ResidualModel::Angular);
+        }
+        if self.lmeds {
+            config = config.with_estimator(RobustEstimator::Lmeds);
+        }
+        if self.msac {
+            config = config.with_scoring(RansacScoring::Msac);
+        }
+        config.altitude_prior = self.altitude_prior;
+        config.fixed_altitude = self.fixed_altitude;
+        config.min_separation_m = self.min_separation;
+        config.max_targets = self.max_targets;
+        config.max_rounds = self.max_rounds;
+        config.min_inlier_quality = self.min_inlier_quality;
+        config.min_geometry = self.min_crossing_angle.map(GeometryCriterion::MinCrossingAngle);
+        config.measurement_sigma = self.measurement_sigma;
+        config.refit_without_outliers = self.refit_outliers;
+        if let Some(bounds) = self.bounds {
+            let out_of_bounds = if self.clamp_to_bounds { OutOfBounds::Clamp } else { OutOfBounds::Discard };
+            config = config.with_bounds(bounds, out_of_bounds);
+        }
+        config.output_ordering = match self.ordering {
+            OrderingArg::Discovery => OutputOrdering::Discovery,
+            OrderingArg::NumLines => OutputOrdering::ByNumLinesDesc,
+            OrderingArg::Confidence => OutputOrdering::ByConfidenceDesc,
+            OrderingArg::Position => OutputOrdering::ByPosition,
+        };
+        config.id_prefix = self.id_prefix.clone();
+        config
+    }
+}
+
+fn is_json(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+}
+
+fn open(path: &Path) -> Result<BufReader<File>, String> {
+    File::open(path)
+        .map(BufReader::new)
+        .map_err(|e| format!("无法打开 {}: {}", path.display(), e))
+}
+
+fn create(path: &Path) -> Result<BufWriter<File>, String> {
+    File::create(path)
+        .map(BufWriter::new)
+        .map_err(|e| format!("无法创建 {}: {}", path.display(), e))
+}
+
+fn read_measurements(path: &Path) -> Result<Vec<Measurement>, String> {
+    let reader = open(path)?;
+    let result = if is_json(path) {
+        io::read_measurements_json(reader)
+    } else {
+        io::read_measurements_csv(reader)
+    };
+    result.map_err(|e| format!("读取 {} 失败: {}", path.display(), e))
+}
+
+fn read_points(path: &Path) -> Result<Vec<Point3<f64>>, String> {
+    let reader = open(path)?;
+    let result = if is_json(path) {
+        serde_json::from_reader(reader).map_err(std::io::Error::from)
+    } else {
+        io::read_points_csv(reader)
+    };
+    result.map_err(|e| format!("读取 {} 失败: {}", path.display(), e))
+}
+
+fn write_targets<W: Write>(writer: W, targets: &[LocatedTarget], format: OutputFormat) -> std::io::Result<()> {
+    match format {
+        OutputFormat::Csv => io::write_targets_csv(writer, targets),
+        OutputFormat::Json => io::write_targets_json(writer, targets),
+        OutputFormat::Geojson => io::write_targets_geojson(writer, targets, None),
+    }
+}
+
+/// 定位并在标准错误上报告被跳过的无效测量及提前停止的原因
+fn locate(measurements: &[Measurement], solver: &SolverArgs) -> Vec<LocatedTarget> {
+    let (located_targets, diagnostics) = find_targets_with_diagnostics(measurements, &solver.config());
+    for (index, error) in &diagnostics.skipped {
+        eprintln!("跳过第 {} 条测量: {}", index, error);
+    }
+    match diagnostics.stop_reason {
+        StopReason::MaxTargets => eprintln!("已达到目标数上限，停止提取"),
+        StopReason::MaxRounds => eprintln!("已达到提取轮数上限，停止提取"),
+        StopReason::LowQuality => eprintln!("剩余一致集质量低于下限，停止提取"),
+        StopReason::PoorGeometry => eprintln!("连续多个一致集的交会角过小，停止提取"),
+        StopReason::Cancelled => eprintln!("定位已取消"),
+        StopReason::InsufficientLines | StopReason::NoConsensus => {}
+    }
+    located_targets
+}
+
+/// 默认运行：模拟 + 定位，输出真实位置与估计位置的对照（按最优分配匹配）
+fn run_demo(cli: &Cli) -> Result<(), String> {
+    let (true_targets, measurements) = cli.generator.generate()?;
+    let located_targets = locate(&measurements, &cli.solver);
+
+    let stdout = std::io::stdout();
+    let result = match cli.output_format {
+        OutputFormat::Csv => write_demo_csv(stdout.lock(), &true_targets, &located_targets),
+        format => write_targets(stdout.lock(), &located_targets, format),
+    };
+    result.map_err(|e| format!("输出失败: {}", e))
+}
+
+/// 写出真实位置与估计位置的对照 CSV
+///
+/// 列为 TargetID, TrueX, TrueY, TrueZ, EstX, EstY, EstZ, AvgError, MatchedDistance。
+/// 定位结果按发现顺序排列，先与真实目标最优匹配，再按真实目标的顺序逐行对照；
+/// 未匹配的真实目标估计列为空，未匹配的定位结果（排在最后）真实列为空。
+fn write_demo_csv<W: Write>(
+    mut writer: W,
+    true_targets: &[Point3<f64>],
+    located_targets: &[LocatedTarget],
+) -> std::io::Result<()> {
+    let result = match_targets(true_targets, located_targets, f64::INFINITY);
+    writeln!(writer, "TargetID,TrueX,TrueY,TrueZ,EstX,EstY,EstZ,AvgError,MatchedDistance")?;
+    for (truth, true_pos) in true_targets.iter().enumerate() {
+        match result.matches.iter().find(|m| m.truth == truth) {
+            Some(m) => {
+                let est = &located_targets[m.estimate];
+                writeln!(writer, "{},{},{},{},{},{},{},{},{}",
+                    est.id,
+                    true_pos.x,
+                    true_pos.y,
+                    true_pos.z,
+                    est.position.x,
+                    est.position.y,
+                    est.position.z,
+                    est.avg_error_dist_m,
+                    m.distance
+                )?;
+            }
+            None => writeln!(writer, ",{},{},{},,,,,", true_pos.x, true_pos.y, true_pos.z)?,
+        }
+    }
+    for &estimate in &result.false_tracks {
+        let est = &located_targets[estimate];
+        writeln!(writer, "{},,,,{},{},{},{},",
+            est.id, est.position.x, est.position.y, est.position.z, est.avg_error_dist_m
+        )?;
+    }
+    Ok(())
+}
+
+fn run_simulate(args: &SimulateArgs) -> Result<(), String> {
+    let (true_targets, measurements) = args.generator.generate()?;
+
+    let result = match &args.measurements {
+        Some(path) if is_json(path) => {
+            serde_json::to_writer_pretty(create(path)?, &measurements).map_err(std::io::Error::from)
+        }
+        Some(path) => io::write_measurements_csv(create(path)?, &measurements),
+        None => io::write_measurements_csv(std::io::stdout().lock(), &measurements),
+    };
+    result.map_err(|e| format!("写出测量数据失败: {}", e))?;
+
+    if let Some(path) = &args.truth {
+        let result = if is_json(path) {
+            serde_json::to_writer_pretty(create(path)?, &true_targets).map_err(std::io::Error::from)
+        } else {
+            io::write_points_csv(create(path)?, &true_targets)
+        };
+        result.map_err(|e| format!("写出真实位置失败: {}", e))?;
+    }
+    Ok(())
+}
+
+fn run_locate(args: &LocateArgs) -> Result<(), String> {
+    let Some(input) = &args.input else {
+        return run_locate_stream(args);
+    };
+    let measurements = read_measurements(input)?;
+    let located_targets = locate(&measurements, &args.solver);
+    write_targets(std::io::stdout().lock(), &located_targets, args.output_format)
+        .map_err(|e| format!("输出失败: {}", e))
+}
+
+/// 标准输入模式：逐批定位并立即输出
+///
+/// 目标标识在整个流中连续编号；按时间窗分批时 `timestamp` 为该批时间戳范围的中点。
+/// 解析失败的行在标准错误上报告后跳过。
+fn run_locate_stream(args: &LocateArgs) -> Result<(), String> {
+    if matches!(args.output_format, OutputFormat::Geojson) {
+        return Err("标准输入模式不支持 GeoJSON 输出".to_string());
+    }
+    let mut stdout = std::io::stdout().lock();
+    if matches!(args.output_format, OutputFormat::Csv) {
+        writeln!(stdout, "{}", io::TARGET_CSV_HEADER).map_err(|e| format!("输出失败: {}", e))?;
+    }
+    let config = args.solver.config();
+    let mut num_located = 0;
+    let on_batch = |batch: Vec<Measurement>| -> std::io::Result<()> {
+        let timestamps = batch.iter().filter_map(|m| m.timestamp.filter(|t| t.is_finite()));
+        let (min, max) = timestamps.fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), t| (lo.min(t), hi.max(t)));
+        for mut target in locate(&batch, &args.solver) {
+            num_located += 1;
+            target.index = num_located;
+            target.id = config.target_id(num_located);
+            if args.window_seconds.is_some() && min <= max {
+                target.timestamp = Some((min + max) / 2.0);
+            }
+            match args.output_format {
+                OutputFormat::Json => {
+                    serde_json::to_writer(&mut stdout, &target)?;
+                    writeln!(stdout)?;
+                }
+                _ => io::write_target_csv_row(&mut stdout, &target)?,
+            }
+        }
+        stdout.flush()
+    };
+    io::read_measurement_batches(
+        std::io::stdin().lock(),
+        args.window_seconds,
+        on_batch,
+        |error| eprintln!("跳过无效测量: {}", error),
+    )
+    .map_err(|e| format!("读取标准输入失败: {}", e))
+}
+
+fn run_evaluate(args: &EvaluateArgs) -> Result<(), String> {
+    let located_targets = io::read_targets_json(open(&args.targets)?)
+        .map_err(|e| format!("读取 {} 失败: {}", args.targets.display(), e))?;
+    let true_targets = read_points(&args.truth)?;
+
+    // 最优分配匹配真实目标与定位结果
+    let result = match_targets(&true_targets, &located_targets, args.max_dist.unwrap_or(f64::INFINITY));
+    println!("TargetID,TrueX,TrueY,TrueZ,EstX,EstY,EstZ,Error");
+    for (truth, true_pos) in true_targets.iter().enumerate() {
+        match result.matches.iter().find(|m| m.truth == truth) {
+            Some(m) => {
+                let est = &located_targets[m.estimate];
+                println!("{},{},{},{},{},{},{},{}",
+                    est.id, true_pos.x, true_pos.y, true_pos.z,
+                    est.position.x, est.position.y, est.position.z, m.distance
+                );
+            }
+            None => println!(",{},{},{},,,,", true_pos.x, true_pos.y, true_pos.z),
+        }
+    }
+
+    eprintln!(
+        "匹配 {} / {} 个真实目标，多余定位结果 {} 个，准确率 {:.3}，召回率 {:.3}",
+        result.matches.len(),
+        true_targets.len(),
+        result.false_tracks.len(),
+        result.precision(),
+        result.recall()
     );
+    if let (Some(mean), Some(median), Some(rms)) = (result.mean_error(), result.median_error(), result.rms_error()) {
+        eprintln!("平均误差 {:.3} 米，中位数 {:.3} 米，均方根 {:.3} 米", mean, median, rms);
+    }
+    Ok(())
+}
 
-    let located_targets: Vec<LocatedTarget> = find_targets(&measurements, 1.0, 3);
+fn main() {
+    let cli = Cli::parse();
+    let result = match &cli.command {
+        None => run_demo(&cli),
+        Some(Command::Simulate(args)) => run_simulate(args),
+        Some(Command::Locate(args)) => run_locate(args),
+        Some(Command::Evaluate(args)) => run_evaluate(args),
+    };
+    if let Err(message) = result {
+        eprintln!("错误: {}", message);
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opti_radar::target_processor::find_targets_with_config;
+
+    /// 在 `targets` 处各有四条精确光线，定位结果按到最后一个目标的距离排列，与真实目标的顺序相反
+    fn located_out_of_order(targets: &[Point3<f64>]) -> Vec<LocatedTarget> {
+        let mut measurements = Vec::new();
+        for target in targets {
+            for (dx, dy) in [(-30.0, 0.0), (0.0, 30.0), (30.0, 0.0), (0.0, -30.0)] {
+                let start = Point3::new(target.x + dx, target.y + dy, 0.0);
+                let d = target - start;
+                measurements.push(Measurement::new(start.x, start.y, start.z, d.x, d.y, d.z));
+            }
+        }
+        let mut config = FindTargetsConfig::new(1.0, 3);
+        config.ransac.seed = Some(566);
+        let mut located = find_targets_with_config(&measurements, &config);
+        assert_eq!(located.len(), targets.len());
+        let last = targets[targets.len() - 1];
+        located.sort_by(|a, b| (a.position - last).norm().total_cmp(&(b.position - last).norm()));
+        located
+    }
+
+    fn demo_csv(true_targets: &[Point3<f64>], located: &[LocatedTarget]) -> Vec<String> {
+        let mut buffer = Vec::new();
+        write_demo_csv(&mut buffer, true_targets, located).unwrap();
+        String::from_utf8(buffer).unwrap().lines().map(str::to_string).collect()
+    }
 
-    // 输出 CSV：TargetID, TrueX, TrueY, TrueZ, EstX, EstY, EstZ, AvgError
-    println!("TargetID,TrueX,TrueY,TrueZ,EstX,EstY,EstZ,AvgError");
-    for (i, est) in located_targets.iter().enumerate() {
-        let true_pos = &true_targets[i];
-        println!("{},{},{},{},{},{},{},{}",
+    fn matched_row(truth: &Point3<f64>, est: &LocatedTarget) -> String {
+        format!(
+            "{},{},{},{},{},{},{},{},{}",
             est.id,
-            true_pos.x,
-            true_pos.y,
-            true_pos.z,
+            truth.x,
+            truth.y,
+            truth.z,
             est.position.x,
             est.position.y,
             est.position.z,
-            est.avg_error_dist_m
+            est.avg_error_dist_m,
+            (est.position - truth).norm()
+        )
+    }
+
+    fn closest<'a>(located: &'a [LocatedTarget], truth: &Point3<f64>) -> &'a LocatedTarget {
+        located.iter().min_by(|a, b| (a.position - truth).norm().total_cmp(&(b.position - truth).norm())).unwrap()
+    }
+
+    #[test]
+    fn test_demo_csv_unmatched_truth() {
+        let a = Point3::new(10.0, 20.0, 5.0);
+        let b = Point3::new(-50.0, -30.0, 8.0);
+        let c = Point3::new(200.0, 200.0, 50.0);
+        let located = located_out_of_order(&[a, b]);
+        assert!((located[0].position - b).norm() < 1e-3);
+        // 估计按真实目标的顺序逐行对照，与定位结果的排列顺序无关
+        let rows = demo_csv(&[a, b, c], &located);
+        assert_eq!(
+            rows,
+            vec![
+                "TargetID,TrueX,TrueY,TrueZ,EstX,EstY,EstZ,AvgError,MatchedDistance".to_string(),
+                matched_row(&a, closest(&located, &a)),
+                matched_row(&b, closest(&located, &b)),
+                ",200,200,50,,,,,".to_string(),
+            ]
+        );
+        assert!(rows[1..].iter().all(|row| row.split(',').count() == 9));
+    }
+
+    #[test]
+    fn test_demo_csv_unmatched_estimate() {
+        let a = Point3::new(10.0, 20.0, 5.0);
+        let b = Point3::new(-50.0, -30.0, 8.0);
+        let located = located_out_of_order(&[a, b]);
+        let extra = closest(&located, &b);
+        let rows = demo_csv(&[a], &located);
+        assert_eq!(
+            rows,
+            vec![
+                "TargetID,TrueX,TrueY,TrueZ,EstX,EstY,EstZ,AvgError,MatchedDistance".to_string(),
+                matched_row(&a, closest(&located, &a)),
+                format!(
+                    "{},,,,{},{},{},{},",
+                    extra.id, extra.position.x, extra.position.y, extra.position.z, extra.avg_error_dist_m
+                ),
+            ]
         );
+        assert!(rows[1..].iter().all(|row| row.split(',').count() == 9));
     }
 }
@@ -0,0 +1,508 @@
+// src/motion.rs
+
+use crate::target_processor::{
+    draw_three_distinct, range_consistent, required_iterations, within_bounds, ConsensusEstimator, Line, LmConfig,
+    LmReport, ProgressTracker, RandomSource, RansacConfig, RansacModel, RansacScoring, RansacStats, ResidualModel,
+    MAX_DEGENERATE_RESAMPLES,
+};
+use nalgebra::{Matrix3, Matrix6, Point3, Vector3, Vector6};
+use std::rc::Rc;
+
+// --- 匀速运动模型 ---
+//
+// 测量的时间戳跨越数秒时，运动目标的光线不再交会于一点：静止模型会把它们拆成多个目标，或给出偏离的位置。
+// 匀速模型求解参考时刻 t₀ 的位置 p 与速度 v（6 个参数），光线 i 的残差在外推位置 qᵢ = p + v (tᵢ − t₀) 处
+// 按静止模型计算（残差模型、射线模式与鲁棒损失不变）。设 Jᵢ 为静止残差在 qᵢ 处的雅可比，则对 (p, v) 的雅可比为
+// [Jᵢ, τᵢ Jᵢ]（τᵢ = tᵢ − t₀），法方程由 3×3 块 [JᵀJ, τJᵀJ; τJᵀJ, τ²JᵀJ] 累加为 6×6。
+// 求解时时间按 τ 的均方根缩放，使位置块与速度块量级相当；t₀ 取测量时刻的平均值，位置与速度的估计近似不相关。
+// 由 `FindTargetsConfig::motion_model` 启用，见 `MotionModel::ConstantVelocity`。
+
+/// 光线测量时刻的平均值，作为匀速模型的参考时刻；没有光线时为 0
+pub fn mean_time(lines: &[Line]) -> f64 {
+    if lines.is_empty() {
+        return 0.0;
+    }
+    lines.iter().map(|line| line.time).sum::<f64>() / lines.len() as f64
+}
+
+/// 光线测量时刻的目标位置 p + v (t − t₀)
+fn extrapolate(line: &Line, position: &Point3<f64>, velocity: &Vector3<f64>, reference_time: f64) -> Point3<f64> {
+    position + velocity * (line.time - reference_time)
+}
+
+/// τ = t − t₀ 的均方根，作为求解时的时间尺度；为零（测量时刻全部相同）或非有限值时为 None
+fn time_scale(lines: &[Line], reference_time: f64) -> Option<f64> {
+    if lines.is_empty() {
+        return None;
+    }
+    let mean_square = lines.iter().map(|line| (line.time - reference_time).powi(2)).sum::<f64>() / lines.len() as f64;
+    let scale = mean_square.sqrt();
+    (scale > 0.0 && scale.is_finite()).then_some(scale)
+}
+
+/// 将 3×3 块 `m` 按 [m, τm; τm, τ²m] 累加到 6×6 矩阵
+fn add_blocks(a: &mut Matrix6<f64>, m: &Matrix3<f64>, tau: f64) {
+    let mut block = a.fixed_view_mut::<3, 3>(0, 0);
+    block += m;
+    let mut block = a.fixed_view_mut::<3, 3>(0, 3);
+    block += m * tau;
+    let mut block = a.fixed_view_mut::<3, 3>(3, 0);
+    block += m * tau;
+    let mut block = a.fixed_view_mut::<3, 3>(3, 3);
+    block += m * (tau * tau);
+}
+
+/// 将 3 维向量 `v` 按 (v; τv) 累加到 6 维向量
+fn add_rows(b: &mut Vector6<f64>, v: &Vector3<f64>, tau: f64) {
+    let mut rows = b.fixed_rows_mut::<3>(0);
+    rows += v;
+    let mut rows = b.fixed_rows_mut::<3>(3);
+    rows += v * tau;
+}
+
+/// 6×6 对称半正定矩阵的条件数（最大与最小特征值之比）是否不超过 `max_condition_number`
+///
+/// 最小特征值不为正或含 NaN 时为 false。
+fn is_well_conditioned(a: &Matrix6<f64>, max_condition_number: f64) -> bool {
+    let eigenvalues = a.symmetric_eigenvalues();
+    eigenvalues.min() > 0.0 && eigenvalues.max() <= eigenvalues.min() * max_condition_number
+}
+
+/// 匀速模型的闭式线性最小二乘解：参考时刻 `reference_time` 的位置与速度
+///
+/// 外推位置到各条直线的加权距离平方和对 (p, v) 是二次的，最小值满足 6×6 线性方程
+/// Σ w [P, τP; τP, τ²P] (p; v) = Σ w (P s; τ P s)，其中 P = I − d dᵀ，τ = t − t₀。
+/// 光线少于 3 条、测量时刻全部相同或方向退化导致方程奇异时返回 None。
+/// 该解将光线视为无限长直线，可直接使用，也可作为 `constant_velocity_optimize` 的初值。
+pub fn linear_triangulate_constant_velocity(
+    lines: &[Line],
+    reference_time: f64,
+) -> Option<(Point3<f64>, Vector3<f64>)> {
+    solve_linear(lines, reference_time, 1e9)
+}
+
+/// `linear_triangulate_constant_velocity`，按时间缩放后方程的条件数超过 `max_condition_number` 时返回 None
+fn solve_linear(lines: &[Line], reference_time: f64, max_condition_number: f64) -> Option<(Point3<f64>, Vector3<f64>)> {
+    let scale = time_scale(lines, reference_time)?;
+    let mut a = Matrix6::zeros();
+    let mut b = Vector6::zeros();
+    for line in lines {
+        let tau = (line.time - reference_time) / scale;
+        let projector = (Matrix3::identity() - line.direction * line.direction.transpose()) * line.weight;
+        add_blocks(&mut a, &projector, tau);
+        add_rows(&mut b, &(projector * line.start.coords), tau);
+    }
+    if !is_well_conditioned(&a, max_condition_number) {
+        return None;
+    }
+    let x = a.cholesky()?.solve(&b);
+    let position = Point3::new(x[0], x[1], x[2]);
+    let velocity = Vector3::new(x[3], x[4], x[5]) / scale;
+    (position.iter().chain(velocity.iter()).all(|v| v.is_finite())).then_some((position, velocity))
+}
+
+/// 缩放后的参数 x = (p; v s) 对应的位置与速度
+fn unpack(x: &Vector6<f64>, scale: f64) -> (Point3<f64>, Vector3<f64>) {
+    (Point3::new(x[0], x[1], x[2]), Vector3::new(x[3], x[4], x[5]) / scale)
+}
+
+/// 匀速模型在 `x`（缩放后的参数）处的 LM 代价 Σ w ρ(‖r‖)
+fn cost(lines: &[Line], x: &Vector6<f64>, reference_time: f64, scale: f64, config: &LmConfig) -> f64 {
+    let (position, velocity) = unpack(x, scale);
+    lines.iter().fold(0.0, |sum, line| {
+        let q = extrapolate(line, &position, &velocity, reference_time);
+        let r = config.residual_model.residual(line, &q, config.ray_mode).norm();
+        sum + line.weight * config.robust_loss.cost(r)
+    })
+}
+
+/// `x` 处的 6×6 法方程 H = Σ JᵢᵀJᵢ 与 b = Σ Jᵢᵀeᵢ（不含阻尼），权重含鲁棒损失的 IRLS 降权
+fn normal_equations(
+    lines: &[Line],
+    x: &Vector6<f64>,
+    reference_time: f64,
+    scale: f64,
+    config: &LmConfig,
+) -> (Matrix6<f64>, Vector6<f64>) {
+    let (position, velocity) = unpack(x, scale);
+    let mut h = Matrix6::zeros();
+    let mut b = Vector6::zeros();
+    for line in lines {
+        let tau = (line.time - reference_time) / scale;
+        let q = extrapolate(line, &position, &velocity, reference_time);
+        let residual = config.residual_model.residual(line, &q, config.ray_mode);
+        let w = line.weight * config.robust_loss.weight(residual.norm());
+        let jacobian = config.residual_model.jacobian(line, &q, config.ray_mode);
+        add_blocks(&mut h, &(jacobian.transpose() * jacobian * w), tau);
+        add_rows(&mut b, &(jacobian.transpose() * residual * w), tau);
+    }
+    (h, b)
+}
+
+/// 匀速模型的 LM 优化：参考时刻 `reference_time` 的位置与速度
+///
+/// 残差、权重与鲁棒损失同 `levenberg_marquardt_optimize_detailed`，只是每条光线的残差在外推位置
+/// p + v (t − t₀) 处计算。使用 `config` 的 `iterations`、`initial_lambda`、`max_lambda`、`xtol`、`ftol`、
+/// `ray_mode`、`robust_loss`、`residual_model` 与 `min_rcond`，多起点参数不适用。
+/// 步长与阻尼作用于缩放后的参数 (p, v s)，s 为测量时刻相对参考时刻的均方根（时刻全部相同时取 1，此时速度不可观测，
+/// 报告为退化）。返回的 `LmReport::position` 为参考时刻的位置，另返回速度。
+pub fn constant_velocity_optimize(
+    lines: &[Line],
+    initial_position: Point3<f64>,
+    initial_velocity: Vector3<f64>,
+    reference_time: f64,
+    config: &LmConfig,
+) -> (LmReport, Vector3<f64>) {
+    let scale = time_scale(lines, reference_time).unwrap_or(1.0);
+    let mut x = Vector6::new(
+        initial_position.x,
+        initial_position.y,
+        initial_position.z,
+        initial_velocity.x * scale,
+        initial_velocity.y * scale,
+        initial_velocity.z * scale,
+    );
+    let mut lambda = config.initial_lambda;
+    let initial_cost = cost(lines, &x, reference_time, scale, config);
+    let mut current_cost = initial_cost;
+    let mut iterations_used = 0;
+    let mut converged = false;
+    let mut non_finite_steps = 0;
+
+    // 输入含非有限值或鲁棒损失参数无效（见 `RobustLoss::validate`）时不迭代
+    let valid = config.validate().is_ok()
+        && x.iter().all(|v| v.is_finite())
+        && reference_time.is_finite()
+        && lines.iter().all(|line| {
+            line.start.iter().chain(line.direction.iter()).all(|v| v.is_finite())
+                && line.weight.is_finite()
+                && line.time.is_finite()
+        });
+    while valid && !lines.is_empty() && iterations_used < config.iterations {
+        // 已精确通过所有光线，无法继续下降
+        if current_cost == 0.0 {
+            converged = true;
+            break;
+        }
+        iterations_used += 1;
+
+        let (h, b) = normal_equations(lines, &x, reference_time, scale, config);
+        let Some(cholesky) = (h + Matrix6::identity() * lambda).cholesky() else {
+            lambda *= 10.0;
+            continue;
+        };
+        let delta = cholesky.solve(&-b);
+        let new_x = x + delta;
+        let new_cost = cost(lines, &new_x, reference_time, scale, config);
+        // 步长容差不低于当前参数的 10 个 ulp（xtol 为 0 时仍关闭）
+        let xtol = if config.xtol > 0.0 { config.xtol.max(10.0 * f64::EPSILON * x.norm()) } else { 0.0 };
+
+        // 数值溢出等产生非有限值时拒绝该步，连续多次则放弃
+        if !(new_x.iter().all(|v| v.is_finite()) && new_cost.is_finite()) {
+            non_finite_steps += 1;
+            lambda *= 10.0;
+            if non_finite_steps >= 5 || lambda > config.max_lambda {
+                break;
+            }
+            continue;
+        }
+        non_finite_steps = 0;
+
+        if new_cost < current_cost {
+            let relative_decrease = (current_cost - new_cost) / current_cost;
+            x = new_x;
+            current_cost = new_cost;
+            lambda *= 0.1;
+            if delta.norm() < xtol || relative_decrease < config.ftol {
+                converged = true;
+                break;
+            }
+        } else {
+            if delta.norm() < xtol {
+                converged = true;
+                break;
+            }
+            lambda *= 10.0;
+            if lambda > config.max_lambda {
+                break;
+            }
+        }
+    }
+
+    let (h, _) = normal_equations(lines, &x, reference_time, scale, config);
+    let eigenvalues = h.symmetric_eigenvalues();
+    let degenerate = time_scale(lines, reference_time).is_none()
+        || (h.iter().all(|v| v.is_finite()) && eigenvalues.min() <= eigenvalues.max() * config.min_rcond);
+    let (position, velocity) = unpack(&x, scale);
+    let report = LmReport {
+        position,
+        iterations_used,
+        initial_cost,
+        final_cost: current_cost,
+        converged,
+        final_lambda: lambda,
+        degenerate,
+    };
+    (report, velocity)
+}
+
+/// 匀速模型下 (位置; 速度) 的 6×6 协方差 σ² (JᵀJ)⁻¹，约定同 `estimate_covariance`
+///
+/// 每条光线的残差（外推位置到直线的垂直向量）有 2 个自由度，σ² = Σ w‖r‖² / (2n − 6)。
+/// JᵀJ 奇异（测量时刻全部相同、光线近乎平行等）或自由度不足时返回 None。
+pub fn estimate_constant_velocity_covariance(
+    lines: &[Line],
+    position: Point3<f64>,
+    velocity: Vector3<f64>,
+    reference_time: f64,
+) -> Option<Matrix6<f64>> {
+    let dof = 2 * lines.len() as i64 - 6;
+    if dof <= 0 {
+        return None;
+    }
+    let scale = time_scale(lines, reference_time)?;
+
+    let mut jtj = Matrix6::zeros();
+    let mut error_sq = 0.0;
+    for line in lines {
+        let q = extrapolate(line, &position, &velocity, reference_time);
+        error_sq += line.weight * line.perpendicular_vector_to(&q).norm_squared();
+        let projector = Matrix3::identity() - line.direction * line.direction.transpose();
+        add_blocks(&mut jtj, &(projector * line.weight), (line.time - reference_time) / scale);
+    }
+    if !is_well_conditioned(&jtj, 1e9) {
+        return None;
+    }
+
+    // 缩放参数 (p; v s) 的协方差换算回 (p; v)
+    let sigma_sq = error_sq / dof as f64;
+    let unscale = Matrix6::from_diagonal(&Vector6::new(1.0, 1.0, 1.0, 1.0 / scale, 1.0 / scale, 1.0 / scale));
+    jtj.try_inverse()
+        .map(|inv| unscale * inv * unscale * sigma_sq)
+        .filter(|cov| cov.iter().all(|v| v.is_finite()))
+}
+
+/// 匀速模型下各条光线到外推位置的垂直距离（米），射线模式同 `ResidualModel::Metric`
+pub(crate) fn line_distances(
+    lines: &[Line],
+    position: &Point3<f64>,
+    velocity: &Vector3<f64>,
+    reference_time: f64,
+    ray_mode: bool,
+) -> Vec<f64> {
+    lines
+        .iter()
+        .map(|line| {
+            let q = extrapolate(line, position, velocity, reference_time);
+            ResidualModel::Metric.residual(line, &q, ray_mode).norm()
+        })
+        .collect()
+}
+
+/// 按匀速模型抽样的 RANSAC，只在 `active`（升序、不重复）列出的光线上估计，内点为 `all_lines` 中的索引
+///
+/// 每次随机抽取 3 条光线（6 个方程）求闭式解，以样本测量时刻的平均为参考时刻；样本的测量时刻全部相同、
+/// 方向退化或按时间缩放后方程的条件数超过 `max_condition_number` 时重新抽样。外推位置到光线的残差
+/// （按 `residual_model`）小于阈值且在外推位置处测距一致（见 `RansacConfig::range_gate`）的光线为内点，
+/// 同一测量站不同时刻的光线都计入，不按测量站去重。
+/// 评分方式、自适应终止（样本大小 3）与区域过滤（样本参考时刻的位置）同 `ransac_fit_lines`，不穷举三元组。
+/// 返回的候选位置为样本参考时刻的位置。随机数取自 `rng`（忽略 `config.seed`）。
+pub fn ransac_fit_constant_velocity(
+    all_lines: &[Line],
+    active: &[usize],
+    config: &RansacConfig,
+    rng: &mut dyn RandomSource,
+) -> (Option<RansacModel>, RansacStats) {
+    constant_velocity_ransac(all_lines, active, config, rng, None)
+}
+
+/// `ransac_fit_constant_velocity`，设置 `progress` 时每 `progress.interval` 次迭代报告一次进度，取消时停止抽样
+fn constant_velocity_ransac(
+    all_lines: &[Line],
+    active: &[usize],
+    config: &RansacConfig,
+    rng: &mut dyn RandomSource,
+    progress: Option<&ProgressTracker>,
+) -> (Option<RansacModel>, RansacStats) {
+    let mut stats = RansacStats {
+        iterations_run: 0,
+        required_iterations: config.max_iterations,
+        degenerate_samples_rejected: 0,
+    };
+    if active.len() < 3 {
+        return (None, stats);
+    }
+
+    let mut best: Option<RansacModel> = None;
+    let mut best_score = f64::INFINITY;
+    let mut next_report = progress.map_or(usize::MAX, |progress| progress.interval);
+    while stats.iterations_run < stats.required_iterations {
+        stats.iterations_run += 1;
+        let (hypothesis, rejected) = random_hypothesis(all_lines, active, config, rng);
+        stats.degenerate_samples_rejected += rejected;
+        if let Some((position, velocity, reference_time)) = hypothesis.filter(|(p, _, _)| within_bounds(config, p)) {
+            let mut inliers = Vec::new();
+            let mut inlier_cost = 0.0;
+            for &i in active {
+                let line = &all_lines[i];
+                let q = extrapolate(line, &position, &velocity, reference_time);
+                let distance = config.residual_model.residual(line, &q, config.ray_mode).norm();
+                if distance < config.threshold && range_consistent(line, &q, config) {
+                    inliers.push(i);
+                    inlier_cost += distance * distance;
+                }
+            }
+            let score = match config.scoring {
+                RansacScoring::InlierCount => -(inliers.len() as f64),
+                RansacScoring::Msac => {
+                    inlier_cost + (active.len() - inliers.len()) as f64 * config.threshold * config.threshold
+                }
+            };
+            if inliers.len() >= config.min_lines && score < best_score {
+                best_score = score;
+                let inlier_ratio = inliers.len() as f64 / active.len() as f64;
+                stats.required_iterations =
+                    required_iterations(inlier_ratio, config.confidence, 3, config.max_iterations);
+                best = Some((position, inliers));
+            }
+        }
+        if let Some(progress) = progress.filter(|_| stats.iterations_run >= next_report) {
+            next_report = stats.iterations_run + progress.interval;
+            if progress.ransac_progress(stats.iterations_run).is_break() {
+                break;
+            }
+        }
+    }
+    (best, stats)
+}
+
+/// 匀速假设：(参考时刻的位置, 速度, 参考时刻)
+type Hypothesis = (Point3<f64>, Vector3<f64>, f64);
+
+/// 从 `active` 中随机选取 3 条光线求 (位置, 速度, 参考时刻)，退化时重新抽样
+///
+/// 返回假设（多次重抽仍退化时为 None）及被拒绝的样本数。
+fn random_hypothesis(
+    all_lines: &[Line],
+    active: &[usize],
+    config: &RansacConfig,
+    rng: &mut dyn RandomSource,
+) -> (Option<Hypothesis>, usize) {
+    let mut rejected = 0;
+    while rejected <= MAX_DEGENERATE_RESAMPLES {
+        let sample = draw_three_distinct(rng, active.len()).map(|k| all_lines[active[k]]);
+        let reference_time = mean_time(&sample);
+        match solve_linear(&sample, reference_time, config.max_condition_number) {
+            Some((position, velocity)) => return (Some((position, velocity, reference_time)), rejected),
+            None => rejected += 1,
+        }
+    }
+    (None, rejected)
+}
+
+/// `Pipeline::new` 在匀速运动模型下使用的一致集估计（`ransac_fit_constant_velocity`）
+pub(crate) struct ConstantVelocityEstimator {
+    pub config: RansacConfig,
+    pub progress: Rc<ProgressTracker>, // 与 `Pipeline::progress` 共享
+}
+
+impl ConsensusEstimator for ConstantVelocityEstimator {
+    fn estimate(&self, lines: &[Line], rng: &mut dyn RandomSource) -> Option<RansacModel> {
+        self.estimate_with_stats(lines, rng).0
+    }
+
+    fn estimate_with_stats(&self, lines: &[Line], rng: &mut dyn RandomSource) -> (Option<RansacModel>, RansacStats) {
+        let active: Vec<usize> = (0..lines.len()).collect();
+        self.estimate_subset(lines, &active, rng)
+    }
+
+    fn estimate_subset(
+        &self,
+        lines: &[Line],
+        active: &[usize],
+        rng: &mut dyn RandomSource,
+    ) -> (Option<RansacModel>, RansacStats) {
+        constant_velocity_ransac(lines, active, &self.config, rng, self.progress.active())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::target_processor::SplitMix64;
+
+    /// 匀速目标在各测量站、各时刻的无噪声光线
+    fn moving_target_lines(position: Point3<f64>, velocity: Vector3<f64>) -> Vec<Line> {
+        let stations = [
+            Point3::new(800.0, 0.0, 0.0),
+            Point3::new(-600.0, 500.0, 10.0),
+            Point3::new(0.0, -900.0, 5.0),
+            Point3::new(-300.0, -700.0, 0.0),
+        ];
+        let mut lines = Vec::new();
+        for (k, station) in stations.iter().enumerate() {
+            for step in 0..5 {
+                let time = 100.0 + step as f64 + 0.25 * k as f64;
+                let mut line = Line::new(*station, position + velocity * (time - 100.0) - station);
+                line.time = time;
+                line.station = Some(k);
+                lines.push(line);
+            }
+        }
+        lines
+    }
+
+    #[test]
+    fn test_constant_velocity_solvers_recover_motion() {
+        let (position, velocity) = (Point3::new(50.0, 20.0, 300.0), Vector3::new(30.0, -10.0, 2.0));
+        let lines = moving_target_lines(position, velocity);
+        let t0 = mean_time(&lines);
+        let expected = position + velocity * (t0 - 100.0);
+
+        let (linear, linear_velocity) = linear_triangulate_constant_velocity(&lines, t0).unwrap();
+        assert!((linear - expected).norm() < 1e-6, "{}", linear);
+        assert!((linear_velocity - velocity).norm() < 1e-6);
+
+        // 从静止初值出发的 LM 收敛到真值，残差为零
+        let config = LmConfig::default();
+        let (report, fitted_velocity) =
+            constant_velocity_optimize(&lines, expected + Vector3::new(40.0, -30.0, 20.0), Vector3::zeros(), t0, &config);
+        assert!(report.converged && !report.degenerate);
+        assert!((report.position - expected).norm() < 1e-6, "{}", report.position);
+        assert!((fitted_velocity - velocity).norm() < 1e-6, "{}", fitted_velocity);
+        assert!(line_distances(&lines, &report.position, &fitted_velocity, t0, true).iter().all(|&d| d < 1e-6));
+
+        // 测量时刻全部相同时速度不可观测
+        let mut simultaneous = lines.clone();
+        simultaneous.iter_mut().for_each(|line| line.time = 0.0);
+        assert!(linear_triangulate_constant_velocity(&simultaneous, 0.0).is_none());
+        assert!(constant_velocity_optimize(&simultaneous, expected, Vector3::zeros(), 0.0, &config).0.degenerate);
+        assert!(estimate_constant_velocity_covariance(&simultaneous, expected, velocity, 0.0).is_none());
+    }
+
+    #[test]
+    fn test_constant_velocity_covariance_and_ransac() {
+        let (position, velocity) = (Point3::new(-40.0, 60.0, 250.0), Vector3::new(-20.0, 25.0, 0.0));
+        let mut lines = moving_target_lines(position, velocity);
+        let t0 = mean_time(&lines);
+        // 偏移一条光线使残差非零
+        lines[3].start.x += 2.0;
+        let (fit, fitted_velocity) = linear_triangulate_constant_velocity(&lines, t0).unwrap();
+        let covariance = estimate_constant_velocity_covariance(&lines, fit, fitted_velocity, t0).unwrap();
+        assert!(covariance.symmetric_eigenvalues().min() > 0.0);
+        assert!((covariance - covariance.transpose()).norm() < 1e-9 * covariance.norm());
+
+        // 杂波光线不被计为内点，运动目标的 20 条光线全部为内点
+        let num_target_lines = lines.len();
+        lines.push(Line::new(Point3::new(2000.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.2)));
+        lines.push(Line::new(Point3::new(0.0, 2000.0, 0.0), Vector3::new(1.0, 0.0, 0.5)));
+        let mut config = RansacConfig::new(100, 5.0, 3);
+        config.confidence = 0.999;
+        let active: Vec<usize> = (0..lines.len()).collect();
+        let (model, stats) = ransac_fit_constant_velocity(&lines, &active, &config, &mut SplitMix64::new(4));
+        let (_, inliers) = model.unwrap();
+        assert_eq!(inliers, (0..num_target_lines).collect::<Vec<_>>());
+        assert!(stats.iterations_run >= 1 && stats.iterations_run <= config.max_iterations);
+        assert_eq!(ransac_fit_constant_velocity(&lines, &active[..2], &config, &mut SplitMix64::new(4)).0, None);
+    }
+}
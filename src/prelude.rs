@@ -0,0 +1,15 @@
+// src/prelude.rs
+
+//! 常用类型与函数的稳定导入路径
+//!
+//! `use opti_radar::prelude::*;` 即可构造测量、按需配置 `FindTargetsConfig` 的各项参数、定位目标并读取结果，
+//! 包括所需的 nalgebra 类型，无需关心各项定义在哪个模块中。
+
+pub use crate::error::OptiRadarError;
+pub use crate::target_processor::{
+    find_targets, find_targets_with_config, find_targets_with_diagnostics, BootstrapConfig, BoundingBox, DbscanConfig,
+    EmConfig, FindTargetsConfig, FindTargetsDiagnostics, GeometryCriterion, Line, LmConfig, LocatedTarget, Measurement,
+    MotionModel, Optimizer, OutOfBounds, OutputOrdering, RansacConfig, RansacScoring, ResidualModel, RobustEstimator,
+    RobustLoss,
+};
+pub use nalgebra::{Point3, Vector3};
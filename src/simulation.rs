@@ -0,0 +1,162 @@
+// src/simulation.rs
+
+use crate::data_generator::{generate_scenario, GeneratorConfig};
+use crate::evaluation::match_targets;
+use crate::target_processor::{find_targets_with_config, FindTargetsConfig};
+use std::time::{Duration, Instant};
+
+// --- 蒙特卡洛仿真 ---
+//
+// 按给定场景参数重复“生成数据 → 定位 → 评估”，汇总误差、检出率和耗时，
+// 便于扫描噪声水平等参数。第 k 次运行（k 从 1 开始）的数据种子和 RANSAC 种子均为 seed + k，
+// 因此报告完全由参数决定（耗时除外）。
+
+/// 单次运行的统计
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunStats {
+    pub run: usize,                 // 运行序号，从 1 开始
+    pub seed: u64,                  // 本次运行的数据与 RANSAC 种子
+    pub num_true_targets: usize,    // 真实目标数
+    pub num_located: usize,         // 定位结果数
+    pub num_matched: usize,         // 匹配上的目标数（最优分配，不设门限）
+    pub mean_error: Option<f64>,    // 匹配对的平均误差（米），没有匹配时为 None
+    pub correct_assignments: usize, // 关联正确的内点光线数
+    pub total_assignments: usize,   // 内点光线总数
+    pub elapsed: Duration,          // 定位耗时（不含数据生成和评估）
+}
+
+/// 蒙特卡洛仿真报告
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct MonteCarloReport {
+    pub runs: Vec<RunStats>, // 按运行序号排列
+}
+
+impl MonteCarloReport {
+    /// 至少匹配上一个目标的运行数
+    pub fn successful_runs(&self) -> usize {
+        self.runs.iter().filter(|r| r.mean_error.is_some()).count()
+    }
+
+    /// 成功运行的平均误差的均值（米）；没有成功运行时为无穷大
+    pub fn mean_error(&self) -> f64 {
+        let errors: Vec<f64> = self.runs.iter().filter_map(|r| r.mean_error).collect();
+        if errors.is_empty() {
+            f64::INFINITY
+        } else {
+            errors.iter().sum::<f64>() / errors.len() as f64
+        }
+    }
+
+    /// 所有运行匹配上的目标总数
+    pub fn total_matched(&self) -> usize {
+        self.runs.iter().map(|r| r.num_matched).sum()
+    }
+
+    /// 所有运行的定位结果总数
+    pub fn total_located(&self) -> usize {
+        self.runs.iter().map(|r| r.num_located).sum()
+    }
+
+    /// 所有运行的真实目标总数
+    pub fn total_true_targets(&self) -> usize {
+        self.runs.iter().map(|r| r.num_true_targets).sum()
+    }
+
+    /// 检出率：匹配目标总数 / 真实目标总数；没有真实目标时为 1
+    pub fn success_rate(&self) -> f64 {
+        let total = self.total_true_targets();
+        if total == 0 {
+            1.0
+        } else {
+            self.total_matched() as f64 / total as f64
+        }
+    }
+
+    /// 光线关联正确率；没有内点时为 1
+    pub fn association_rate(&self) -> f64 {
+        let total: usize = self.runs.iter().map(|r| r.total_assignments).sum();
+        let correct: usize = self.runs.iter().map(|r| r.correct_assignments).sum();
+        if total == 0 {
+            1.0
+        } else {
+            correct as f64 / total as f64
+        }
+    }
+
+    /// 定位总耗时
+    pub fn total_time(&self) -> Duration {
+        self.runs.iter().map(|r| r.elapsed).sum()
+    }
+}
+
+/// 运行 `num_runs` 次蒙特卡洛仿真
+///
+/// 每次运行按 `generator` 生成场景（忽略其中的 `seed`），用 `solver` 定位（忽略其中的 RANSAC 种子），
+/// 再与真实目标最优匹配。启用 `parallel` 特性时各次运行并行执行，结果与串行相同。
+pub fn run_monte_carlo(
+    generator: &GeneratorConfig,
+    solver: &FindTargetsConfig,
+    num_runs: usize,
+    seed: u64,
+) -> MonteCarloReport {
+    #[cfg(feature = "parallel")]
+    let runs = {
+        use rayon::prelude::*;
+        (1..=num_runs)
+            .into_par_iter()
+            .map(|run| run_once(generator, solver, run, seed))
+            .collect()
+    };
+    #[cfg(not(feature = "parallel"))]
+    let runs = (1..=num_runs).map(|run| run_once(generator, solver, run, seed)).collect();
+    MonteCarloReport { runs }
+}
+
+/// 第 `run` 次运行
+fn run_once(generator: &GeneratorConfig, solver: &FindTargetsConfig, run: usize, base_seed: u64) -> RunStats {
+    let seed = base_seed.wrapping_add(run as u64);
+    let scenario = generate_scenario(&GeneratorConfig {
+        seed: Some(seed),
+        ..generator.clone()
+    });
+    let mut solver = solver.clone();
+    solver.ransac.seed = Some(seed);
+
+    let start = Instant::now();
+    let located_targets = find_targets_with_config(&scenario.measurements, &solver);
+    let elapsed = start.elapsed();
+
+    let result = match_targets(&scenario.true_targets, &located_targets, f64::INFINITY);
+    let (correct_assignments, total_assignments) = result.correct_assignments(&scenario.labels, &located_targets);
+    RunStats {
+        run,
+        seed,
+        num_true_targets: scenario.true_targets.len(),
+        num_located: located_targets.len(),
+        num_matched: result.matches.len(),
+        mean_error: result.mean_error(),
+        correct_assignments,
+        total_assignments,
+        elapsed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_monte_carlo_reproducible() {
+        let generator = GeneratorConfig::builder().num_targets(3).build().unwrap();
+        let solver = FindTargetsConfig::new(5.0, 3);
+        let a = run_monte_carlo(&generator, &solver, 4, 100);
+        let b = run_monte_carlo(&generator, &solver, 4, 100);
+        assert_eq!(a.runs.len(), 4);
+        for (x, y) in a.runs.iter().zip(&b.runs) {
+            assert_eq!((x.run, x.seed, x.num_located, x.mean_error), (y.run, y.seed, y.num_located, y.mean_error));
+        }
+        assert_eq!(a.runs[0].seed, 101);
+        assert_eq!(a.total_true_targets(), 12);
+        assert!(a.success_rate() > 0.9 && a.mean_error() < 5.0);
+    }
+}
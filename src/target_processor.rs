@@ -1,12 +1,33 @@
 // src/target_processor.rs
 
+use crate::coords::{self, Geodetic};
+use crate::error::OptiRadarError;
+use crate::evaluation::interpolated_percentile;
+use crate::line_soa::LineSoa;
+use crate::motion::{self, ConstantVelocityEstimator};
 use nalgebra as na;
-use na::{DMatrix, DVector, Matrix3, Point3, Vector3};
-use rand::prelude::*;
-use std::collections::HashSet;
+use na::{Matrix2, Matrix3, Point3, RealField, UnitQuaternion, Vector3};
+use std::borrow::Borrow;
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::f64::consts::TAU;
+use std::fmt;
+use std::ops::ControlFlow;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 // --- 数据结构 ---
-// Measurement 表示原始传感器数据
+//
+// 启用 `serde` 特性后，Measurement、Line、LocatedTarget 可序列化。
+// JSON 字段名与结构体字段名一致（snake_case），视为稳定接口；
+// Point3/Vector3 序列化为 [x, y, z]，Matrix3 序列化为按列排列的 9 个数。
+
+/// Measurement 表示原始传感器数据
+///
+/// 反序列化时 `station_id`、`timestamp`、`range` 缺省为 null，`weight` 缺省为 1.0。
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Measurement {
     pub x: f64,
     pub y: f64,
@@ -14,339 +35,7844 @@ pub struct Measurement {
     pub direction_x: f64,
     pub direction_y: f64,
     pub direction_z: f64,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub station_id: Option<String>, // 测量站标识，同一目标至多接受同一测量站的一条光线
+    #[cfg_attr(feature = "serde", serde(default = "default_weight"))]
+    pub weight: f64,                // LM 拟合权重，默认 1.0
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub timestamp: Option<f64>,     // 测量时刻（秒），`find_targets_windowed` 按其分组；单帧定位只在匀速运动模型下使用
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub range: Option<(f64, f64)>,  // 沿方向的测距 (值, 标准差)（米），只在 `LmConfig::use_range` 与 `RansacConfig::range_gate` 下使用
+}
+
+#[cfg(feature = "serde")]
+fn default_weight<T: Real>() -> T {
+    T::one()
+}
+
+#[cfg(feature = "serde")]
+fn default_time<T: Real>() -> T {
+    T::zero()
+}
+
+impl Default for Measurement {
+    fn default() -> Self {
+        Measurement::new(0.0, 0.0, 0.0, 0.0, 0.0, 0.0)
+    }
+}
+
+impl Measurement {
+    /// 由测量站位置和方向分量创建不带测量站标识的测量
+    pub fn new(
+        x: f64,
+        y: f64,
+        z: f64,
+        direction_x: f64,
+        direction_y: f64,
+        direction_z: f64,
+    ) -> Self {
+        Measurement {
+            x,
+            y,
+            z,
+            direction_x,
+            direction_y,
+            direction_z,
+            station_id: None,
+            weight: 1.0,
+            timestamp: None,
+            range: None,
+        }
+    }
+
+    /// 由测量站位置 `[x, y, z]` 和方向 `[dx, dy, dz]` 创建测量
+    pub fn from_arrays(position: [f64; 3], direction: [f64; 3]) -> Self {
+        let [x, y, z] = position;
+        let [direction_x, direction_y, direction_z] = direction;
+        Measurement::new(x, y, z, direction_x, direction_y, direction_z)
+    }
+
+    /// 由方位角/俯仰角（弧度）创建测量
+    ///
+    /// 坐标约定为 ENU（x 东、y 北、z 天）：方位角从 +Y（北）顺时针转向 +X（东），
+    /// 俯仰角从水平面起算，向上为正。方位角可超出 [0, 2π)，按周期处理。
+    pub fn from_az_el(x: f64, y: f64, z: f64, azimuth_rad: f64, elevation_rad: f64) -> Self {
+        let horizontal = elevation_rad.cos();
+        Measurement::new(
+            x,
+            y,
+            z,
+            horizontal * azimuth_rad.sin(),
+            horizontal * azimuth_rad.cos(),
+            elevation_rad.sin(),
+        )
+    }
+
+    /// `from_az_el` 的角度制版本
+    pub fn from_az_el_deg(x: f64, y: f64, z: f64, azimuth_deg: f64, elevation_deg: f64) -> Self {
+        Measurement::from_az_el(x, y, z, azimuth_deg.to_radians(), elevation_deg.to_radians())
+    }
+
+    /// 读取方向的方位角/俯仰角（弧度），约定同 `from_az_el`，见 `Line::azimuth_elevation`
+    pub fn azimuth_elevation(&self) -> (f64, f64) {
+        Line::from_measurement(self).azimuth_elevation()
+    }
+
+    /// 由测量站的 WGS84 经纬高（弧度、米）及其当地的方位角/俯仰角（弧度）创建测量
+    ///
+    /// 方位角/俯仰角相对测量站自身的当地水平面与真北，约定同 `from_az_el`；
+    /// 测量站位置与方向均转换到以 `origin` 为原点的 ENU 坐标系，
+    /// 同一批测量须使用同一个 `origin`，定位结果可用 `LocatedTarget::to_geodetic` 转回经纬高。
+    pub fn from_geodetic(
+        latitude: f64,
+        longitude: f64,
+        altitude: f64,
+        azimuth_rad: f64,
+        elevation_rad: f64,
+        origin: &Geodetic,
+    ) -> Self {
+        let station = Geodetic::new(latitude, longitude, altitude);
+        let position = coords::geodetic_to_enu(&station, origin);
+        let local = Measurement::from_az_el(0.0, 0.0, 0.0, azimuth_rad, elevation_rad);
+        let local_direction = Vector3::new(local.direction_x, local.direction_y, local.direction_z);
+        let direction =
+            coords::ecef_vector_to_enu(&coords::enu_vector_to_ecef(&local_direction, &station), origin);
+        Measurement::new(position.x, position.y, position.z, direction.x, direction.y, direction.z)
+    }
+
+    /// `from_geodetic` 的角度制版本（纬度、经度、方位角、俯仰角均为度）
+    pub fn from_geodetic_deg(
+        latitude_deg: f64,
+        longitude_deg: f64,
+        altitude: f64,
+        azimuth_deg: f64,
+        elevation_deg: f64,
+        origin: &Geodetic,
+    ) -> Self {
+        Measurement::from_geodetic(
+            latitude_deg.to_radians(),
+            longitude_deg.to_radians(),
+            altitude,
+            azimuth_deg.to_radians(),
+            elevation_deg.to_radians(),
+            origin,
+        )
+    }
+
+    /// 由传感器本体坐标系中的方向创建测量
+    ///
+    /// `attitude` 为本体系到 ENU 的旋转，方向先旋转到 ENU 再构造测量；
+    /// 传感器相对测量站参考点有安装偏移时用 `from_mounted`。
+    pub fn from_body_frame(
+        station_pos: Point3<f64>,
+        body_direction: Vector3<f64>,
+        attitude: UnitQuaternion<f64>,
+    ) -> Self {
+        Measurement::from_mounted(station_pos, body_direction, &StationMount::new(attitude))
+    }
+
+    /// 由安装在 `mount` 上的传感器的本体系方向创建测量
+    ///
+    /// 光线起点为测量站参考点加上旋转到 ENU 的杆臂，方向为旋转到 ENU 的本体系方向。
+    pub fn from_mounted(station_pos: Point3<f64>, body_direction: Vector3<f64>, mount: &StationMount) -> Self {
+        let start = station_pos + mount.attitude * mount.lever_arm;
+        let direction = mount.attitude * body_direction;
+        Measurement::new(start.x, start.y, start.z, direction.x, direction.y, direction.z)
+    }
+
+    /// 设置测量站标识
+    pub fn with_station_id(mut self, station_id: impl Into<String>) -> Self {
+        self.station_id = Some(station_id.into());
+        self
+    }
+
+    /// 设置拟合权重（例如角度方差的倒数）
+    pub fn with_weight(mut self, weight: f64) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    /// 设置测量时刻（秒）
+    pub fn with_timestamp(mut self, timestamp: f64) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    /// 设置测距值与其标准差（米）
+    pub fn with_range(mut self, range: f64, sigma: f64) -> Self {
+        self.range = Some((range, sigma));
+        self
+    }
+
+    /// 校验测量并转换为光线
+    ///
+    /// 方向向量为零或含非有限值、测量站坐标含非有限值时返回错误，
+    /// 避免单位化产生的 NaN 污染后续计算；权重为负或非有限值、测距为负或非有限值、
+    /// 测距标准差不为正有限值时返回 `InvalidParameter`。
+    pub fn try_into_line(&self) -> Result<Line, OptiRadarError> {
+        if ![self.x, self.y, self.z].iter().all(|v| v.is_finite()) {
+            return Err(OptiRadarError::NonFiniteStation);
+        }
+        if !(self.weight.is_finite() && self.weight >= 0.0) {
+            return Err(OptiRadarError::InvalidParameter {
+                name: "weight",
+                value: self.weight,
+            });
+        }
+        if let Some((range, sigma)) = self.range {
+            if !(range.is_finite() && range >= 0.0) {
+                return Err(OptiRadarError::InvalidParameter { name: "range", value: range });
+            }
+            if !(sigma.is_finite() && sigma > 0.0) {
+                return Err(OptiRadarError::InvalidParameter { name: "range.sigma", value: sigma });
+            }
+        }
+        let direction = Vector3::new(self.direction_x, self.direction_y, self.direction_z);
+        if !direction.iter().all(|v| v.is_finite()) {
+            return Err(OptiRadarError::NonFiniteDirection);
+        }
+        if direction.norm() < 1e-12 {
+            return Err(OptiRadarError::ZeroDirection);
+        }
+        Ok(Line::from_measurement(self))
+    }
+
+    /// 同 `try_into_line`，转换为指定标量类型的光线（在 f64 下单位化后再转换）
+    pub fn try_into_line_as<T: Real>(&self) -> Result<Line<T>, OptiRadarError> {
+        self.try_into_line().map(|line| line.cast())
+    }
+}
+
+impl From<([f64; 3], [f64; 3])> for Measurement {
+    /// (测量站位置, 方向)，同 `Measurement::from_arrays`
+    fn from((position, direction): ([f64; 3], [f64; 3])) -> Self {
+        Measurement::from_arrays(position, direction)
+    }
+}
+
+impl TryFrom<&[f64]> for Measurement {
+    type Error = OptiRadarError;
+
+    /// `[x, y, z, dx, dy, dz]`；长度不是 6 时返回 `InvalidParameter`（`value` 为实际长度）
+    fn try_from(values: &[f64]) -> Result<Self, Self::Error> {
+        match *values {
+            [x, y, z, direction_x, direction_y, direction_z] => {
+                Ok(Measurement::new(x, y, z, direction_x, direction_y, direction_z))
+            }
+            _ => Err(OptiRadarError::InvalidParameter {
+                name: "values.len",
+                value: values.len() as f64,
+            }),
+        }
+    }
+}
+
+impl From<&Measurement> for [f64; 6] {
+    /// `[x, y, z, dx, dy, dz]`，与 `TryFrom<&[f64]>` 互逆（测量站标识、权重、时间戳和测距不保留）
+    fn from(m: &Measurement) -> Self {
+        [m.x, m.y, m.z, m.direction_x, m.direction_y, m.direction_z]
+    }
+}
+
+/// 合并重复或近乎重复的测量
+///
+/// 测量站标识相同、起点相距不超过 `pos_eps`（米）且方向夹角不超过 `angle_eps`（弧度）的测量
+/// 视为同一次测量（重传、同一测量站毫秒级内的连续测向），按输入顺序贪心归入第一条与之匹配的组，
+/// 每组只保留组内第一条测量，避免该测量站在 RANSAC 计票和 LM 拟合中被重复计入。
+/// 方向为零或含非有限值的测量原样保留，由后续定位报告为跳过。
+pub fn dedupe_measurements(data: &[Measurement], pos_eps: f64, angle_eps: f64) -> Vec<Measurement> {
+    duplicate_groups(data, pos_eps, angle_eps).into_iter().map(|group| data[group[0]].clone()).collect()
+}
+
+/// 同 `dedupe_measurements`，但合并后的测量累加组内权重
+///
+/// 起点和方向取组内按权重的平均（组内权重之和为零时取不加权的平均），权重为组内权重之和，
+/// 时刻取组内第一条测量的时刻。适用于近乎重复的测量确为独立测向的情形：LM 拟合中的总权重不变，RANSAC 计票只计一次。
+pub fn dedupe_measurements_weighted(data: &[Measurement], pos_eps: f64, angle_eps: f64) -> Vec<Measurement> {
+    duplicate_groups(data, pos_eps, angle_eps)
+        .into_iter()
+        .map(|group| {
+            let mut merged = data[group[0]].clone();
+            if group.len() > 1 {
+                let weight: f64 = group.iter().map(|&k| data[k].weight).sum();
+                let factor = |k: usize| if weight > 0.0 { data[k].weight } else { 1.0 };
+                let mut start = Vector3::zeros();
+                let mut direction = Vector3::zeros();
+                for &k in &group {
+                    let line = Line::from_measurement(&data[k]);
+                    start += line.start.coords * factor(k);
+                    direction += line.direction * factor(k);
+                }
+                let total: f64 = group.iter().map(|&k| factor(k)).sum();
+                let (start, direction) = (start / total, direction.normalize());
+                merged.x = start.x;
+                merged.y = start.y;
+                merged.z = start.z;
+                merged.direction_x = direction.x;
+                merged.direction_y = direction.y;
+                merged.direction_z = direction.z;
+                merged.weight = weight;
+            }
+            merged
+        })
+        .collect()
+}
+
+/// 将测量分为重复组，每组为输入中的索引（升序），各组按第一条测量的索引排列
+fn duplicate_groups(data: &[Measurement], pos_eps: f64, angle_eps: f64) -> Vec<Vec<usize>> {
+    let min_cos = angle_eps.cos();
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    let mut representatives: Vec<Option<Line>> = Vec::new();
+    for (index, m) in data.iter().enumerate() {
+        let line = m.try_into_line().ok();
+        let matching = line.as_ref().and_then(|line| {
+            groups.iter().zip(&representatives).position(|(group, representative)| {
+                representative.as_ref().is_some_and(|representative| {
+                    data[group[0]].station_id == m.station_id
+                        && (representative.start - line.start).norm() <= pos_eps
+                        && representative.direction.dot(&line.direction) >= min_cos
+                })
+            })
+        });
+        match matching {
+            Some(k) => groups[k].push(index),
+            None => {
+                groups.push(vec![index]);
+                representatives.push(line);
+            }
+        }
+    }
+    groups
+}
+
+/// 传感器在测量站上的安装方式
+///
+/// 本体系约定：x 为传感器前向、y 向左、z 向上；姿态为零时与 ENU 的东、北、天重合。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StationMount {
+    pub attitude: UnitQuaternion<f64>, // 本体系到 ENU 的旋转
+    pub lever_arm: Vector3<f64>,       // 传感器相对测量站参考点的偏移（本体系，米）
+}
+
+impl StationMount {
+    /// 无杆臂的安装
+    pub fn new(attitude: UnitQuaternion<f64>) -> Self {
+        StationMount { attitude, lever_arm: Vector3::zeros() }
+    }
+
+    /// 由偏航/俯仰/横滚角（弧度）创建，依次绕本体 z、y、x 轴旋转
+    ///
+    /// 偏航绕天向逆时针为正（与方位角的方向相反），偏航 90° 时前向指向正北。
+    pub fn from_yaw_pitch_roll(yaw: f64, pitch: f64, roll: f64) -> Self {
+        StationMount::new(UnitQuaternion::from_euler_angles(roll, pitch, yaw))
+    }
+
+    /// 设置杆臂（本体系，米）
+    pub fn with_lever_arm(mut self, lever_arm: Vector3<f64>) -> Self {
+        self.lever_arm = lever_arm;
+        self
+    }
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LocatedTarget {
-    pub id: String,
+    pub id: String,            // 标识：`FindTargetsConfig::id_prefix` 加编号
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub index: usize,          // 数字编号，与 id 中的序号相同（从 1 开始）
     pub position: Point3<f64>, // 目标位置
     pub num_lines: usize,      // 用于拟合的光线数量
     pub avg_error_dist_m: f64, // 平均残差（米）
+    pub covariance: Option<Matrix3<f64>>, // 位置协方差，几何退化时为 None
+    pub inlier_indices: Vec<usize>, // 内点在输入测量中的索引（升序）
+    pub residuals: Vec<f64>,   // 每条内点光线的垂直距离（米），与 inlier_indices 顺序一致
+    pub max_residual_m: f64,   // 最大单线残差（米）
+    pub stations: Vec<String>, // 贡献光线的测量站标识（按内点顺序，去重）
+    pub converged: bool,       // LM 是否收敛
+    pub final_cost: f64,       // LM 结束时的（鲁棒）代价
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub lm_iterations: usize,  // LM 实际运行的迭代次数
+    pub geometry_dop: f64,     // 内点光线的几何精度因子，见 `geometry_dop`
+    pub horizontal_dop: f64,   // 水平分量
+    pub vertical_dop: f64,     // 垂直分量
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub timestamp: Option<f64>, // 所在时间窗的中心时刻（秒），见 `find_targets_windowed`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub low_confidence: bool,   // 少于 3 条光线定位，没有多余观测校验，可信度较低
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub confidence: f64,        // [0, 1] 的综合可信度，见 `target_confidence`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub chi_square: Option<f64>, // 归一化 χ² 统计量（χ² / 自由度），仅设置 `measurement_sigma` 且有多余观测时计算
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub inconsistent: bool,     // `chi_square` 超过 `chi_square_quantile` 分位数：残差与测角噪声不符，内点可能混入错误光线
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub outlier_indices: Vec<usize>, // 标准化残差超过 `outlier_sigma` 的光线在输入测量中的索引（升序），重新拟合时为被剔除的光线
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub degenerate: bool,       // LM 报告几何退化（见 `LmReport::degenerate`），例如光线近乎平行
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub bootstrap: Option<BootstrapResult>, // 内点光线的 bootstrap 不确定度，仅设置 `FindTargetsConfig::bootstrap` 时计算
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub velocity: Option<Vector3<f64>>, // 速度（米/秒），仅匀速运动模型下估计；此时 `position` 为 `timestamp` 时刻的位置
+}
+
+impl fmt::Display for LocatedTarget {
+    /// 单行摘要，例如 `Target_1 position=(1.000, 2.000, 3.000) lines=5 avg_residual=0.120 confidence=0.85`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} position=({:.3}, {:.3}, {:.3}) lines={} avg_residual={:.3} confidence={:.2}",
+            self.id,
+            self.position.x,
+            self.position.y,
+            self.position.z,
+            self.num_lines,
+            self.avg_error_dist_m,
+            self.confidence,
+        )?;
+        if self.low_confidence {
+            write!(f, " low_confidence")?;
+        }
+        if self.inconsistent {
+            write!(f, " inconsistent")?;
+        }
+        if self.degenerate {
+            write!(f, " degenerate")?;
+        }
+        Ok(())
+    }
+}
+
+impl LocatedTarget {
+    /// 时刻 `time` 的位置：有速度估计时按匀速模型从 `timestamp` 外推，否则为 `position`
+    pub fn position_at(&self, time: f64) -> Point3<f64> {
+        match (self.velocity, self.timestamp) {
+            (Some(velocity), Some(reference)) => self.position + velocity * (time - reference),
+            _ => self.position,
+        }
+    }
+
+    /// 位置的 x 坐标（米）
+    pub fn x(&self) -> f64 {
+        self.position.x
+    }
+
+    /// 位置的 y 坐标（米）
+    pub fn y(&self) -> f64 {
+        self.position.y
+    }
+
+    /// 位置的 z 坐标（米）
+    pub fn z(&self) -> f64 {
+        self.position.z
+    }
+
+    /// 位置坐标 `[x, y, z]`，同 `position_array`
+    pub fn as_array(&self) -> [f64; 3] {
+        self.position_array()
+    }
+
+    /// 位置坐标 `[x, y, z]`
+    pub fn position_array(&self) -> [f64; 3] {
+        self.position.into()
+    }
+
+    /// 位置转换为 WGS84 经纬高，`origin` 须与构造测量时（`Measurement::from_geodetic`）所用的相同
+    pub fn to_geodetic(&self, origin: &Geodetic) -> Geodetic {
+        coords::enu_to_geodetic(&self.position, origin)
+    }
+
+    /// 以指定标量类型读取位置，例如与 f32 光线一起计算残差
+    pub fn position_as<T: Real>(&self) -> Point3<T> {
+        self.position.map(real)
+    }
+
+    /// x/y/z 方向的 1σ 标准差
+    pub fn std_devs(&self) -> Option<Vector3<f64>> {
+        self.covariance
+            .map(|cov| Vector3::new(cov[(0, 0)].sqrt(), cov[(1, 1)].sqrt(), cov[(2, 2)].sqrt()))
+    }
+
+    /// 不确定性椭球的主轴
+    ///
+    /// 返回 (各主轴 1σ 半轴长度, 对应的单位主轴方向（按列）)。
+    pub fn principal_axes(&self) -> Option<(Vector3<f64>, Matrix3<f64>)> {
+        self.covariance.map(|cov| {
+            let eigen = cov.symmetric_eigen();
+            let sigmas = eigen.eigenvalues.map(|v| v.max(0.0).sqrt());
+            (sigmas, eigen.eigenvectors)
+        })
+    }
+}
+
+/// 光线几何、LM 与 RANSAC 的浮点标量类型
+///
+/// `Line`、`ClosestApproach`、`LmReport`、`RansacModel` 的标量参数缺省为 f64，
+/// 不写类型参数的代码均为 f64 版本；嵌入式平台可改用 f32（约 7 位有效数字）。
+/// 各配置中的阈值与容差仍为 f64，使用时转换为对应的标量类型。
+pub trait Real: RealField + Copy {}
+
+impl<T: RealField + Copy> Real for T {}
+
+/// f64 常量转换为标量类型
+fn real<T: Real>(value: f64) -> T {
+    na::convert(value)
+}
+
+/// 标量转换为 f64（f32、f64 均无损）
+fn to_f64<T: Real>(value: T) -> f64 {
+    value.to_subset_unchecked()
+}
+
+/// 数值容差：f64 下的取值与标量类型机器精度 ε 的 `eps_multiple` 倍中的较大者
+///
+/// f64 下各容差保持原值；f32 的 ε 约为 1.2e-7，按 f64 设定的极小容差会相应放宽。
+/// 取值不为正时返回 0，保留“设为 0 即关闭该判据”的约定。
+fn tolerance<T: Real>(f64_value: f64, eps_multiple: f64) -> T {
+    if f64_value <= 0.0 {
+        return T::zero();
+    }
+    real::<T>(f64_value).max(T::default_epsilon() * real(eps_multiple))
+}
+
+/// f32 光线
+pub type Line32 = Line<f32>;
+
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Line<T: Real = f64> {
+    pub start: Point3<T>,     // 光线起点
+    pub direction: Vector3<T>, // 单位化方向
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub station: Option<usize>, // 测量站编号（由 find_targets 根据 station_id 分配）
+    #[cfg_attr(feature = "serde", serde(default = "default_weight"))]
+    pub weight: T,              // LM 拟合权重
+    #[cfg_attr(feature = "serde", serde(default = "default_time"))]
+    pub time: T,                // 测量时刻（秒），取自 `Measurement::timestamp`，没有时为 0；只有匀速运动模型使用
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub range: Option<(T, T)>,  // 测距 (值, 标准差)（米），取自 `Measurement::range`
+}
+
+impl Line {
+    /// 创建不属于任何测量站的光线，方向会被单位化
+    ///
+    /// 只接受 f64，使浮点字面量无需标注类型；其他标量类型用 `from_parts` 或 `cast`。
+    pub fn new(start: Point3<f64>, direction: Vector3<f64>) -> Self {
+        Line::from_parts(start, direction)
+    }
+
+    /// 创建从 `a` 指向 `b` 的光线
+    ///
+    /// 校验同 `Measurement::try_into_line`：`a` 含非有限值、`b` 含非有限值或两点重合时返回错误。
+    pub fn from_points(a: Point3<f64>, b: Point3<f64>) -> Result<Self, OptiRadarError> {
+        Measurement::from_arrays(a.into(), (b - a).into()).try_into_line()
+    }
+
+    /// 由测量直接构造光线（方向单位化，保留权重、时间戳与测距），不做校验
+    ///
+    /// 方向为零或含非有限值时结果含 NaN，需要校验时用 `Measurement::try_into_line`；
+    /// `station_id` 是字符串，不转换为测量站编号，`station` 为 None。没有时间戳或时间戳非有限值时 `time` 为 0。
+    pub fn from_measurement(m: &Measurement) -> Self {
+        let start_point = Point3::new(m.x, m.y, m.z);
+        let direction = Vector3::new(m.direction_x, m.direction_y, m.direction_z).normalize();
+        Line {
+            start: start_point,
+            direction,
+            station: None,
+            weight: m.weight,
+            time: m.timestamp.filter(|t| t.is_finite()).unwrap_or(0.0),
+            range: m.range,
+        }
+    }
+
+    /// 读取方向的方位角/俯仰角（弧度），约定同 `Measurement::from_az_el`
+    ///
+    /// 方位角范围 [0, 2π)；方向接近竖直（水平分量为零）时方位角无定义，返回 0。
+    pub fn azimuth_elevation(&self) -> (f64, f64) {
+        let d = self.direction;
+        let horizontal = (d.x * d.x + d.y * d.y).sqrt();
+        let elevation = d.z.atan2(horizontal);
+        if horizontal < 1e-12 {
+            return (0.0, elevation);
+        }
+        let azimuth = d.x.atan2(d.y).rem_euclid(TAU);
+        // rem_euclid 可能因舍入返回 TAU 本身
+        (if azimuth >= TAU { 0.0 } else { azimuth }, elevation)
+    }
+
+    /// `azimuth_elevation` 的角度制版本，方位角范围 [0, 360)
+    pub fn azimuth_elevation_deg(&self) -> (f64, f64) {
+        let (azimuth, elevation) = self.azimuth_elevation();
+        (azimuth.to_degrees(), elevation.to_degrees())
+    }
+}
+
+impl<T: Real> Line<T> {
+    /// 任意标量类型下创建光线，约定同 `Line::new`
+    pub fn from_parts(start: Point3<T>, direction: Vector3<T>) -> Self {
+        Line {
+            start,
+            direction: direction.normalize(),
+            station: None,
+            weight: T::one(),
+            time: T::zero(),
+            range: None,
+        }
+    }
+
+    /// 转换标量类型，例如 `line.cast::<f32>()`；测量站、权重、时刻与测距一并保留
+    pub fn cast<U: Real>(&self) -> Line<U> {
+        let convert = |v: T| real::<U>(to_f64(v));
+        Line {
+            start: self.start.map(convert),
+            direction: self.direction.map(convert),
+            station: self.station,
+            weight: convert(self.weight),
+            time: convert(self.time),
+            range: self.range.map(|(range, sigma)| (convert(range), convert(sigma))),
+        }
+    }
+
+    /// 点在直线上的投影参数 t，使 `point_at(t)` 为直线上距该点最近的点
+    pub fn closest_parameter(&self, point: &Point3<T>) -> T {
+        (point - self.start).dot(&self.direction)
+    }
+
+    /// 直线上参数为 t 的点 `start + direction * t`
+    pub fn point_at(&self, t: T) -> Point3<T> {
+        self.start + self.direction * t
+    }
+
+    /// 从直线上最近点指向该点的垂直向量（视为无限长直线）
+    pub fn perpendicular_vector_to(&self, point: &Point3<T>) -> Vector3<T> {
+        point - self.point_at(self.closest_parameter(point))
+    }
+
+    /// 点到直线的垂直距离（视为无限长直线）
+    pub fn distance_to_point(&self, point: &Point3<T>) -> T {
+        self.perpendicular_vector_to(point).norm()
+    }
+
+    /// 求与另一条直线的最近点（将两者视为无限长直线）
+    ///
+    /// 方向近乎平行时返回 `ClosestApproach::Parallel`。
+    pub fn closest_point_between(&self, other: &Line<T>) -> ClosestApproach<T> {
+        let w0 = self.start - other.start;
+        let a = self.direction.dot(&self.direction);
+        let b = self.direction.dot(&other.direction);
+        let c = other.direction.dot(&other.direction);
+        let d = self.direction.dot(&w0);
+        let e = other.direction.dot(&w0);
+        let denom = a * c - b * b;
+        if denom.abs() < tolerance(1e-6, 100.0) {
+            // 平行线间距：起点差去掉沿方向的分量
+            let gap = (w0 - self.direction * (d / a)).norm();
+            return ClosestApproach::Parallel { gap };
+        }
+        let s = (b * e - c * d) / denom;
+        let t = (a * e - b * d) / denom;
+        let point_on_self = self.point_at(s);
+        let point_on_other = other.point_at(t);
+        ClosestApproach::Points {
+            midpoint: Point3::from((point_on_self.coords + point_on_other.coords) * real::<T>(0.5)),
+            point_on_self,
+            point_on_other,
+            s,
+            t,
+            gap: (point_on_self - point_on_other).norm(),
+        }
+    }
+}
+
+/// 两条直线的最近接近结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ClosestApproach<T: Real = f64> {
+    /// 两线不平行：各自线上的最近点及其参数（start + direction * s/t）
+    Points {
+        midpoint: Point3<T>,       // 两最近点的中点
+        point_on_self: Point3<T>,  // 调用者线上的最近点
+        point_on_other: Point3<T>, // 另一条线上的最近点
+        s: T,                      // 调用者线上最近点的参数
+        t: T,                      // 另一条线上最近点的参数
+        gap: T,                    // 两线间最短距离
+    },
+    /// 两线平行或接近平行，最近点不唯一
+    Parallel {
+        gap: T, // 两平行线间距
+    },
+}
+
+/// 两条光线的交会定位结果，见 `triangulate_pair`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PairFix {
+    pub midpoint: Point3<f64>,        // 两最近点的中点，作为目标位置估计
+    pub miss_distance: f64,           // 两线间最短距离（米），越小交会越一致
+    pub crossing_angle: f64,          // 两线夹角（弧度，0..=π/2），越接近 π/2 几何越好
+    pub point_on_first: Point3<f64>,  // 第一条线上的最近点
+    pub point_on_second: Point3<f64>, // 第二条线上的最近点
+}
+
+/// 两条光线交会定位（视为无限长直线）
+///
+/// 方向接近平行（见 `Line::closest_point_between`）时没有唯一交会点，返回 None。
+/// 只有两条光线时没有多余观测，`miss_distance` 是唯一的一致性检验。
+pub fn triangulate_pair(first: &Line, second: &Line) -> Option<PairFix> {
+    let ClosestApproach::Points { midpoint, point_on_self, point_on_other, gap, .. } =
+        first.closest_point_between(second)
+    else {
+        return None;
+    };
+    let cos = first.direction.normalize().dot(&second.direction.normalize()).abs();
+    Some(PairFix {
+        midpoint,
+        miss_distance: gap,
+        crossing_angle: cos.min(1.0).acos(),
+        point_on_first: point_on_self,
+        point_on_second: point_on_other,
+    })
+}
+
+/// 点到光线的残差向量（从光线上最近点指向该点）
+///
+/// 射线模式下光线只向前延伸：若点位于测量站背后（投影 `pa·d` < 0），
+/// 残差为点到射线起点的向量。
+fn residual_vector<T: Real>(line: &Line<T>, point: &Point3<T>, ray_mode: bool) -> Vector3<T> {
+    if ray_mode && line.closest_parameter(point) < T::zero() {
+        point - line.start
+    } else {
+        line.perpendicular_vector_to(point)
+    }
+}
+
+/// `residual_vector` 对点坐标的雅可比
+fn residual_jacobian<T: Real>(line: &Line<T>, point: &Point3<T>, ray_mode: bool) -> Matrix3<T> {
+    if ray_mode && line.closest_parameter(point) < T::zero() {
+        Matrix3::identity()
+    } else {
+        // 残差 = (p - start) - d ( (p - start)·d )，对 p 的导数为 I - d dᵀ
+        perpendicular_projector(line)
+    }
+}
+
+/// 内点判定与 LM 优化使用的残差模型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResidualModel {
+    /// 点到光线的垂直距离（米），阈值单位为米
+    #[default]
+    Metric,
+    /// 垂直距离除以测量站到点的距离，即测量方向与指向点方向夹角的正弦（弧度），
+    /// 阈值单位为弧度。远距离目标不会因微小的角度噪声超出阈值。
+    Angular,
+}
+
+impl ResidualModel {
+    /// 按残差模型缩放后的残差向量
+    pub(crate) fn residual<T: Real>(&self, line: &Line<T>, point: &Point3<T>, ray_mode: bool) -> Vector3<T> {
+        let raw = residual_vector(line, point, ray_mode);
+        match self {
+            ResidualModel::Metric => raw,
+            ResidualModel::Angular => {
+                let range = (point - line.start).norm();
+                if range > T::zero() {
+                    raw / range
+                } else {
+                    raw
+                }
+            }
+        }
+    }
+
+    /// `residual` 对点坐标的雅可比
+    pub(crate) fn jacobian<T: Real>(&self, line: &Line<T>, point: &Point3<T>, ray_mode: bool) -> Matrix3<T> {
+        let raw_jacobian = residual_jacobian(line, point, ray_mode);
+        match self {
+            ResidualModel::Metric => raw_jacobian,
+            ResidualModel::Angular => {
+                let pa = point - line.start;
+                let range = pa.norm();
+                if range == T::zero() {
+                    return raw_jacobian;
+                }
+                // e = r / ρ，ρ = ‖p - start‖：de/dp = (J_r - e uᵀ) / ρ，u = (p - start) / ρ
+                let e = residual_vector(line, point, ray_mode) / range;
+                (raw_jacobian - e * (pa / range).transpose()) / range
+            }
+        }
+    }
+}
+
+/// 每条光线到点的残差距离
+///
+/// 启用 `parallel` 特性且光线数超过 `parallel_cutoff` 时并行计算，结果顺序与输入一致。
+fn line_distances<T: Real>(
+    lines: &[Line<T>],
+    point: &Point3<T>,
+    ray_mode: bool,
+    parallel_cutoff: usize,
+) -> Vec<T> {
+    #[cfg(feature = "parallel")]
+    if lines.len() > parallel_cutoff {
+        use rayon::prelude::*;
+        return lines
+            .par_iter()
+            .map(|line| residual_vector(line, point, ray_mode).norm())
+            .collect();
+    }
+    #[cfg(not(feature = "parallel"))]
+    let _ = parallel_cutoff;
+    lines
+        .iter()
+        .map(|line| residual_vector(line, point, ray_mode).norm())
+        .collect()
 }
 
-#[derive(Clone, Copy)]
-pub struct Line {
-    pub start: Point3<f64>,     // 光线起点
-    pub direction: Vector3<f64>, // 单位化方向
-}
+/// 光线的测距是否与候选点一致：设置 `range_gate` 且光线带测距时，|‖p − start‖ − 测距| 不超过 `range_gate` 倍 σ
+pub(crate) fn range_consistent<T: Real>(line: &Line<T>, point: &Point3<T>, config: &RansacConfig) -> bool {
+    match (config.range_gate, line.range) {
+        (Some(gate), Some((range, sigma))) => ((point - line.start).norm() - range).abs() <= real::<T>(gate) * sigma,
+        _ => true,
+    }
+}
+
+/// 找出残差（按 `residual_model`）小于阈值且测距一致（见 `range_consistent`）的光线，返回升序的 (索引, 残差)
+pub(crate) fn within_threshold<T: Real>(
+    lines: &[Line<T>],
+    point: &Point3<T>,
+    config: &RansacConfig,
+) -> Vec<(usize, T)> {
+    let threshold: T = real(config.threshold);
+    let candidate = |(i, line): (usize, &Line<T>)| {
+        let distance = config.residual_model.residual(line, point, config.ray_mode).norm();
+        (distance < threshold && range_consistent(line, point, config)).then_some((i, distance))
+    };
+    #[cfg(feature = "parallel")]
+    if lines.len() > config.parallel_cutoff {
+        use rayon::prelude::*;
+        // filter_map + collect 保持索引顺序
+        return lines.par_iter().enumerate().filter_map(candidate).collect();
+    }
+    lines.iter().enumerate().filter_map(candidate).collect()
+}
+
+/// `within_threshold` 的子集版本：只检查 `active` 列出的光线，返回 `lines` 中的索引
+///
+/// `active` 须为升序，结果同样按索引升序。
+/// 给定 `index` 时：候选点在其网格区域内则只精确检验网格中与阈值球相交的光线；
+/// 有列式存储则用 `LineSoa::residuals` 计算距离。结果都与逐条检验相同。
+fn within_threshold_in<T: Real>(
+    lines: &[Line<T>],
+    active: &[usize],
+    point: &Point3<T>,
+    config: &RansacConfig,
+    index: Option<&SubsetIndex>,
+) -> Vec<(usize, T)> {
+    let threshold: T = real(config.threshold);
+    let candidate = |&i: &usize| {
+        let distance = config.residual_model.residual(&lines[i], point, config.ray_mode).norm();
+        (distance < threshold && range_consistent(&lines[i], point, config)).then_some((i, distance))
+    };
+    let consistent = |i: usize| range_consistent(&lines[i], point, config);
+    if let Some(index) = index {
+        let point = point.map(to_f64);
+        let mut nearby = Vec::new();
+        let near = index.grid.is_some_and(|grid| grid.candidates(&point, &mut nearby));
+        if near {
+            nearby.retain(|&i| index.active[i]);
+        }
+        let tested = if near { &nearby[..] } else { active };
+        match index.soa {
+            Some(soa) => {
+                let mut distances = Vec::new();
+                soa.residuals(tested, &point, config.residual_model, config.ray_mode, &mut distances);
+                return tested
+                    .iter()
+                    .zip(distances)
+                    .filter(|&(&i, distance)| distance < config.threshold && consistent(i))
+                    .map(|(&i, distance)| (i, real(distance)))
+                    .collect();
+            }
+            None if near => return nearby.iter().filter_map(candidate).collect(),
+            None => {}
+        }
+    }
+    #[cfg(feature = "parallel")]
+    if active.len() > config.parallel_cutoff {
+        use rayon::prelude::*;
+        return active.par_iter().filter_map(candidate).collect();
+    }
+    active.iter().filter_map(candidate).collect()
+}
+
+/// 统计到候选点距离小于阈值的内点
+///
+/// 同一测量站的多条光线都满足阈值时只保留距离最近的一条，
+/// 避免单个异常测量站主导一致集。返回的索引为升序。
+fn collect_inliers<T: Real>(lines: &[Line<T>], point: &Point3<T>, config: &RansacConfig) -> Vec<usize> {
+    keep_closest_per_station(lines, within_threshold(lines, point, config))
+}
+
+/// `collect_inliers` 的子集版本，只统计 `active`（升序）列出的光线
+fn collect_inliers_in<T: Real>(
+    lines: &[Line<T>],
+    active: &[usize],
+    point: &Point3<T>,
+    config: &RansacConfig,
+    index: Option<&SubsetIndex>,
+) -> Vec<usize> {
+    keep_closest_per_station(lines, within_threshold_in(lines, active, point, config, index))
+}
+
+// --- 内点统计的空间网格索引 ---
+//
+// 逐条检验全部光线时，每个候选点的内点统计为 O(光线数)。光线数很多时，先把每条光线在关注区域内的
+// 部分登记到粗网格的各个格子中：候选点 p 的内点 q（p 到光线的最近点，射线模式下可能是起点）距 p
+// 小于阈值，必然落在与以 p 为中心、阈值为半径的球的包围盒相交的格子里，因此只需精确检验这些格子中的光线。
+// 登记是保守的（格子可能多登记光线，不会漏登），结果与逐条检验完全相同。
+
+/// 关注区域内光线的网格索引，只适用于米制残差
+pub(crate) struct LineGrid {
+    region: BoundingBox, // 可查询的区域，区域外的候选点退回逐条检验
+    origin: Point3<f64>, // 网格原点：区域向外扩展 `radius`
+    cell_size: f64,
+    dims: [usize; 3],
+    radius: f64,         // 查询半径：阈值加上舍入余量
+    offsets: Vec<usize>, // 第 c 个格子的光线为 ids[offsets[c]..offsets[c + 1]]
+    ids: Vec<u32>,
+}
+
+/// 网格格子数上限相对光线数的倍数
+const LINE_GRID_CELLS_PER_LINE: f64 = 8.0;
+
+impl LineGrid {
+    /// 为 `lines` 建立索引；残差模型不是米制、阈值不是正的有限值或区域退化时返回 None
+    ///
+    /// 区域取 `config.bounds`，未设置时取全部光线起点的包围盒向各方向扩展其最大边长。
+    /// 格子边长不小于阈值，且格子总数不超过光线数的 8 倍。
+    pub(crate) fn build(lines: &[Line], config: &RansacConfig) -> Option<Self> {
+        let threshold = config.threshold;
+        if config.residual_model != ResidualModel::Metric || !(threshold.is_finite() && threshold > 0.0) {
+            return None;
+        }
+        let region = match config.bounds {
+            Some(bounds) => bounds,
+            None => {
+                let mut min = lines.first()?.start;
+                let mut max = min;
+                for line in lines {
+                    min = min.inf(&line.start);
+                    max = max.sup(&line.start);
+                }
+                let margin = (max - min).max();
+                BoundingBox {
+                    min: min - Vector3::repeat(margin),
+                    max: max + Vector3::repeat(margin),
+                }
+            }
+        };
+        let scale = region.min.coords.abs().max().max(region.max.coords.abs().max());
+        let radius = threshold + 1e-9 * (threshold + scale);
+        let origin = region.min - Vector3::repeat(radius);
+        let extent = region.max - region.min + Vector3::repeat(2.0 * radius);
+        if !extent.iter().all(|v| v.is_finite()) {
+            return None;
+        }
+        let volume_cell = (extent.product() / (LINE_GRID_CELLS_PER_LINE * lines.len() as f64)).cbrt();
+        let cell_size = threshold.max(volume_cell);
+        let dims = [0, 1, 2].map(|k| ((extent[k] / cell_size).ceil() as usize).max(1));
+
+        // 每条光线在扩展区域内的线段按 3D DDA 依次经过的格子登记（起止格子间每步沿一个轴前进一格）。
+        // 线段贴近格子棱角时舍入可能走到相邻的格子，偏差远小于查询半径中的余量，不影响结果
+        let cell_of = |v: f64, k: usize| {
+            (((v - origin[k]) / cell_size).floor().max(0.0) as usize).min(dims[k] - 1)
+        };
+        let flat = |c: [usize; 3]| (c[2] * dims[1] + c[1]) * dims[0] + c[0];
+        let mut entries: Vec<(u32, u32)> = Vec::new();
+        for (i, line) in lines.iter().enumerate() {
+            let Some((t0, t1)) = clip_to_box(line, &origin, &(origin + extent)) else {
+                continue;
+            };
+            let a = line.start + line.direction * t0;
+            let b = line.start + line.direction * t1;
+            let mut cell = [0, 1, 2].map(|k| cell_of(a[k], k));
+            let end = [0, 1, 2].map(|k| cell_of(b[k], k));
+            // 沿各轴到下一条格线的参数 t_max 及跨过一格的参数增量 t_delta（t ∈ [0, 1] 对应 a → b）
+            let mut t_max = [0.0; 3];
+            let mut t_delta = [0.0; 3];
+            for k in 0..3 {
+                let d = b[k] - a[k];
+                if d == 0.0 {
+                    t_max[k] = f64::INFINITY;
+                    continue;
+                }
+                let boundary = origin[k] + (cell[k] + usize::from(d > 0.0)) as f64 * cell_size;
+                t_max[k] = (boundary - a[k]) / d;
+                t_delta[k] = cell_size / d.abs();
+            }
+            entries.push((flat(cell) as u32, i as u32));
+            while cell != end {
+                let k = (0..3)
+                    .filter(|&k| cell[k] != end[k])
+                    .min_by(|&x, &y| t_max[x].total_cmp(&t_max[y]))
+                    .expect("未到达终点格子时至少一个轴未对齐");
+                if end[k] > cell[k] {
+                    cell[k] += 1;
+                } else {
+                    cell[k] -= 1;
+                }
+                t_max[k] += t_delta[k];
+                entries.push((flat(cell) as u32, i as u32));
+            }
+        }
+        // 按格子计数排序，同一格子中的光线保持升序
+        let num_cells = dims[0] * dims[1] * dims[2];
+        let mut offsets = vec![0; num_cells + 1];
+        for &(c, _) in &entries {
+            offsets[c as usize + 1] += 1;
+        }
+        for c in 0..num_cells {
+            offsets[c + 1] += offsets[c];
+        }
+        let mut ids = vec![0; entries.len()];
+        let mut next = offsets.clone();
+        for (c, i) in entries {
+            ids[next[c as usize]] = i;
+            next[c as usize] += 1;
+        }
+        Some(LineGrid {
+            region,
+            origin,
+            cell_size,
+            dims,
+            radius,
+            offsets,
+            ids,
+        })
+    }
+
+    /// 把可能在 `point` 阈值内的光线索引（升序、不重复）写入 `out`；`point` 不在区域内时返回 false
+    pub(crate) fn candidates(&self, point: &Point3<f64>, out: &mut Vec<usize>) -> bool {
+        out.clear();
+        if !self.region.contains(point) {
+            return false;
+        }
+        let cell_of = |v: f64, k: usize| {
+            (((v - self.origin[k]) / self.cell_size).floor().max(0.0) as usize).min(self.dims[k] - 1)
+        };
+        let lo = [0, 1, 2].map(|k| cell_of(point[k] - self.radius, k));
+        let hi = [0, 1, 2].map(|k| cell_of(point[k] + self.radius, k));
+        for z in lo[2]..=hi[2] {
+            for y in lo[1]..=hi[1] {
+                for x in lo[0]..=hi[0] {
+                    let c = (z * self.dims[1] + y) * self.dims[0] + x;
+                    out.extend(self.ids[self.offsets[c]..self.offsets[c + 1]].iter().map(|&i| i as usize));
+                }
+            }
+        }
+        out.sort_unstable();
+        out.dedup();
+        true
+    }
+}
+
+/// 直线 start + t·direction 在轴对齐盒内的参数区间 [t0, t1]（slab 法），不相交时返回 None
+fn clip_to_box(line: &Line, min: &Point3<f64>, max: &Point3<f64>) -> Option<(f64, f64)> {
+    let (mut t0, mut t1) = (f64::NEG_INFINITY, f64::INFINITY);
+    for k in 0..3 {
+        let (s, d) = (line.start[k], line.direction[k]);
+        if d == 0.0 {
+            if s < min[k] || s > max[k] {
+                return None;
+            }
+            continue;
+        }
+        let (a, b) = ((min[k] - s) / d, (max[k] - s) / d);
+        t0 = t0.max(a.min(b));
+        t1 = t1.min(a.max(b));
+    }
+    (t0 <= t1 && t0.is_finite() && t1.is_finite()).then_some((t0, t1))
+}
+
+/// 一次 RANSAC 调用中的网格索引（附 `active` 的成员标记，无网格时为空）与列式存储
+pub(crate) struct SubsetIndex<'a> {
+    grid: Option<&'a LineGrid>,
+    soa: Option<&'a LineSoa>,
+    active: Vec<bool>,
+}
+
+/// 从升序的 (索引, 距离) 候选中，为每个测量站只保留距离最近的一条光线
+pub(crate) fn keep_closest_per_station<T: Real>(lines: &[Line<T>], candidates: Vec<(usize, T)>) -> Vec<usize> {
+    let mut inliers = Vec::new();
+    let mut distances = Vec::new();
+    let mut station_slots: HashMap<usize, usize> = HashMap::new();
+    let mut replaced = false;
+
+    for (i, distance) in candidates {
+        let line = &lines[i];
+        match line.station.map(|station| (station, station_slots.get(&station).copied())) {
+            Some((_, Some(slot))) => {
+                if distance < distances[slot] {
+                    inliers[slot] = i;
+                    distances[slot] = distance;
+                    replaced = true;
+                }
+            }
+            Some((station, None)) => {
+                station_slots.insert(station, inliers.len());
+                inliers.push(i);
+                distances.push(distance);
+            }
+            None => {
+                inliers.push(i);
+                distances.push(distance);
+            }
+        }
+    }
+
+    if replaced {
+        inliers.sort_unstable();
+    }
+    inliers
+}
+
+/// 求两条光线之间的最近点中点
+fn find_closest_midpoint<T: Real>(line1: &Line<T>, line2: &Line<T>) -> Point3<T> {
+    match line1.closest_point_between(line2) {
+        ClosestApproach::Points { midpoint, .. } => midpoint,
+        // 平行或接近平行，直接返回起点平均
+        ClosestApproach::Parallel { .. } => {
+            Point3::from((line1.start.coords + line2.start.coords) * real::<T>(0.5))
+        }
+    }
+}
+
+/// 作用于单线残差范数 ‖r‖ 的鲁棒损失
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum RobustLoss {
+    /// 普通最小二乘 ρ(r) = r²
+    #[default]
+    None,
+    /// Huber 损失，参数为 δ（米）：超过 δ 的残差按 δ/‖r‖ 降权
+    Huber(f64),
+    /// Cauchy 损失，参数为尺度 δ（米）：权重 1 / (1 + (‖r‖/δ)²)
+    Cauchy(f64),
+}
+
+impl RobustLoss {
+    /// 检查 Huber、Cauchy 的 δ 为正的有限值，否则返回 `InvalidParameter`（`robust_loss.delta`）
+    ///
+    /// δ 为零时 Cauchy 损失为 0·ln(∞) = NaN，δ 为负时 Huber 的 IRLS 权重为负，法方程不再半正定。
+    pub fn validate(&self) -> Result<(), OptiRadarError> {
+        match *self {
+            RobustLoss::None => Ok(()),
+            RobustLoss::Huber(delta) | RobustLoss::Cauchy(delta) if delta.is_finite() && delta > 0.0 => Ok(()),
+            RobustLoss::Huber(delta) | RobustLoss::Cauchy(delta) => Err(OptiRadarError::InvalidParameter {
+                name: "robust_loss.delta",
+                value: delta,
+            }),
+        }
+    }
+
+    /// 组装法方程时对残差块施加的 IRLS 权重
+    pub fn weight<T: Real>(&self, r: T) -> T {
+        match *self {
+            RobustLoss::None => T::one(),
+            RobustLoss::Huber(delta) => {
+                let delta = real::<T>(delta);
+                if r <= delta {
+                    T::one()
+                } else {
+                    delta / r
+                }
+            }
+            RobustLoss::Cauchy(delta) => T::one() / (T::one() + (r / real(delta)).powi(2)),
+        }
+    }
+
+    /// 损失值 ρ(r)，在 r 较小时均近似 r²
+    pub fn cost<T: Real>(&self, r: T) -> T {
+        match *self {
+            RobustLoss::None => r * r,
+            RobustLoss::Huber(delta) => {
+                let delta = real::<T>(delta);
+                if r <= delta {
+                    r * r
+                } else {
+                    real::<T>(2.0) * delta * r - delta * delta
+                }
+            }
+            RobustLoss::Cauchy(delta) => {
+                let delta = real::<T>(delta);
+                delta * delta * (T::one() + (r / delta).powi(2)).ln()
+            }
+        }
+    }
+}
+
+/// LM 优化参数
+#[derive(Debug, Clone)]
+pub struct LmConfig {
+    pub iterations: usize,        // 最大迭代次数
+    pub initial_lambda: f64,      // 初始阻尼系数
+    pub ray_mode: bool,           // 将测量视为射线而非无限长直线
+    pub robust_loss: RobustLoss,  // 鲁棒损失，默认为普通最小二乘；参数单位与残差模型一致
+    pub residual_model: ResidualModel, // 残差模型，默认为垂直距离
+    pub xtol: f64,                // 接受的步长 ‖Δp‖（米）低于该值时终止
+    pub ftol: f64,                // 接受的步的相对代价下降低于该值时终止
+    pub max_lambda: f64,          // 阻尼系数超过该值时终止（无法再下降）
+    pub multi_start: usize,       // 多起点个数 K，默认 1 只从给定初值出发，见 `multi_start_guesses`
+    pub multi_start_iterations: usize, // 多起点时各起点短程 LM 的迭代次数
+    pub min_rcond: f64,           // JᵀJ 最小与最大特征值之比不超过该值时报告为几何退化，默认 1e-6
+    pub use_range: bool,          // 对带测距的光线加入测距残差（见 `range_residual`），默认 false；匀速运动模型不使用
+}
+
+impl Default for LmConfig {
+    fn default() -> Self {
+        LmConfig {
+            iterations: 200,
+            initial_lambda: 0.001,
+            ray_mode: true,
+            robust_loss: RobustLoss::None,
+            residual_model: ResidualModel::Metric,
+            xtol: 1e-10,
+            ftol: 1e-12,
+            max_lambda: 1e12,
+            multi_start: 1,
+            multi_start_iterations: 10,
+            min_rcond: 1e-6,
+            use_range: false,
+        }
+    }
+}
+
+impl LmConfig {
+    /// 检查鲁棒损失参数，见 `RobustLoss::validate`
+    pub fn validate(&self) -> Result<(), OptiRadarError> {
+        self.robust_loss.validate()
+    }
+}
+
+/// LM 优化结果
+#[derive(Debug, Clone)]
+pub struct LmReport<T: Real = f64> {
+    pub position: Point3<T>,    // 优化后的位置
+    pub iterations_used: usize, // 实际运行的迭代次数（含被拒绝的步）
+    pub initial_cost: T,        // 初值处的（鲁棒）代价
+    pub final_cost: T,          // 结果处的（鲁棒）代价
+    pub converged: bool,        // 是否满足收敛条件（而非耗尽迭代或阻尼发散）
+    pub final_lambda: T,        // 结束时的阻尼系数
+    pub degenerate: bool,       // 结果处不含阻尼的 JᵀJ 病态（见 `LmConfig::min_rcond`），位置沿弱方向几乎不可观测；输入含非有限值时不检查
+}
+
+/// LM 中附加的高斯先验项，残差为 `sqrt_information (p - mean)`
+///
+/// 先验残差不经过鲁棒损失，直接与按权重缩放的光线残差平方相加；
+/// 光线权重取 1/σ² 时两者尺度一致。
+#[derive(Debug, Clone, Copy)]
+struct PriorTerm<T: Real = f64> {
+    mean: Point3<T>,
+    sqrt_information: Matrix3<T>, // 每行为一个残差行，全零行表示该方向无约束
+}
+
+impl PriorTerm {
+    /// 高度先验 (z - z_prior) / σ，水平方向无约束
+    fn altitude(z_prior: f64, std: f64) -> Self {
+        PriorTerm {
+            mean: Point3::new(0.0, 0.0, z_prior),
+            sqrt_information: Matrix3::from_diagonal(&Vector3::new(0.0, 0.0, 1.0 / std)),
+        }
+    }
+
+    /// 由均值与协方差构造先验 L⁻¹(p - mean)，L 为协方差的 Cholesky 因子
+    fn gaussian(mean: Point3<f64>, covariance: &Matrix3<f64>) -> Result<Self, OptiRadarError> {
+        let symmetric = (covariance - covariance.transpose()).abs().max() <= 1e-9 * covariance.abs().max();
+        if !mean.iter().chain(covariance.iter()).all(|v| v.is_finite()) || !symmetric {
+            return Err(OptiRadarError::InvalidCovariance);
+        }
+        let l = covariance.cholesky().ok_or(OptiRadarError::InvalidCovariance)?.l();
+        let sqrt_information = l.try_inverse().ok_or(OptiRadarError::InvalidCovariance)?;
+        Ok(PriorTerm { mean, sqrt_information })
+    }
+}
+
+impl<T: Real> PriorTerm<T> {
+    fn residual(&self, p: &Point3<T>) -> Vector3<T> {
+        self.sqrt_information * (p - self.mean)
+    }
+}
+
+/// 测距残差 e = (‖p − start‖ − 测距) / σ 及其对位置的梯度 u / σ，u 为从起点指向 p 的单位向量
+///
+/// 与先验项一样不经过鲁棒损失、不乘光线权重，σ 已给出该项的尺度。
+/// 光线不带测距或 p 与起点重合（梯度无定义）时为 None。
+fn range_residual<T: Real>(line: &Line<T>, p: &Point3<T>) -> Option<(T, Vector3<T>)> {
+    let (range, sigma) = line.range?;
+    let offset = p - line.start;
+    let distance = offset.norm();
+    (distance > T::zero()).then(|| ((distance - range) / sigma, offset / (distance * sigma)))
+}
+
+/// 位置 `p` 处的 LM 代价 Σ w ρ(‖r‖)，含先验项 ‖e_prior‖² 与启用 `use_range` 时的测距项 Σ e²
+fn lm_cost<T: Real>(lines: &[Line<T>], p: &Point3<T>, config: &LmConfig, prior: Option<&PriorTerm<T>>) -> T {
+    let line_cost = lines.iter().fold(T::zero(), |sum, line| {
+        let r = config.residual_model.residual(line, p, config.ray_mode).norm();
+        let range_cost = match range_residual(line, p).filter(|_| config.use_range) {
+            Some((e, _)) => e * e,
+            None => T::zero(),
+        };
+        sum + line.weight * config.robust_loss.cost(r) + range_cost
+    });
+    line_cost + prior.map_or(T::zero(), |prior| prior.residual(p).norm_squared())
+}
+
+/// 使用 Levenberg-Marquardt 优化点到多条光线的残差
+///
+/// 残差定义为：点到每条光线的垂直向量 `distance_vec`
+/// 维度为 `3n`，LM 会最小化所有残差向量的加权平方和；
+/// 每条光线的残差块和雅可比块按 sqrt(weight) 缩放。
+/// 使用 `LmConfig` 的默认设置（射线模式）。f32 光线同样适用，收敛容差按 f32 的精度放宽。
+pub fn levenberg_marquardt_optimize<T: Real>(
+    lines: &[Line<T>],
+    initial_guess: Point3<T>,
+    iterations: usize,
+    initial_lambda: f64,
+) -> Point3<T> {
+    let config = LmConfig {
+        iterations,
+        initial_lambda,
+        ..LmConfig::default()
+    };
+    levenberg_marquardt_optimize_with_config(lines, initial_guess, &config)
+}
+
+/// 按 `LmConfig` 运行 Levenberg-Marquardt 优化
+///
+/// 鲁棒损失参数无效（见 `RobustLoss::validate`）时不迭代，原样返回初值。
+pub fn levenberg_marquardt_optimize_with_config<T: Real>(
+    lines: &[Line<T>],
+    initial_guess: Point3<T>,
+    config: &LmConfig,
+) -> Point3<T> {
+    levenberg_marquardt_optimize_detailed(lines, initial_guess, config).position
+}
+
+/// 按 `LmConfig` 运行 Levenberg-Marquardt 优化并返回运行报告
+///
+/// 满足以下任一条件时视为收敛并提前终止：接受的步长低于 `xtol`、
+/// 相对代价下降低于 `ftol`、被拒绝的步长已低于 `xtol`（位于极小点），或代价已为零。
+/// 阻尼系数超过 `max_lambda` 或耗尽迭代次数时终止并标记为未收敛，
+/// 此时仍返回当前最优位置。
+/// 鲁棒损失参数无效（见 `RobustLoss::validate`）时不迭代，原样返回初值并标记为未收敛。
+///
+/// `xtol` 不低于当前位置的 10 个 ulp、`ftol` 不低于标量类型的 ε，
+/// 否则 f32 下无法满足收敛条件；f64 下一般不受影响。两者设为 0 时仍关闭对应判据。
+pub fn levenberg_marquardt_optimize_detailed<T: Real>(
+    lines: &[Line<T>],
+    initial_guess: Point3<T>,
+    config: &LmConfig,
+) -> LmReport<T> {
+    lm_solve(lines, initial_guess, config, None, None)
+}
+
+/// 融合位置先验的 Levenberg-Marquardt 优化（最大后验估计）
+///
+/// 在光线残差之外加入 3 行先验残差 L⁻¹(p - prior_mean)，L 为 `prior_cov` 的 Cholesky 因子，
+/// 并以 `prior_mean` 为初值。适用于跟踪中用上一帧的预测位置与协方差热启动；
+/// 只有一两条光线时先验保证法方程仍然良态。
+/// `prior_cov` 不是有限值的对称正定矩阵时返回 `InvalidCovariance`，鲁棒损失参数无效时返回 `InvalidParameter`。
+pub fn levenberg_marquardt_optimize_with_prior(
+    lines: &[Line],
+    prior_mean: Point3<f64>,
+    prior_cov: &Matrix3<f64>,
+    config: &LmConfig,
+) -> Result<LmReport, OptiRadarError> {
+    config.validate()?;
+    let prior = PriorTerm::gaussian(prior_mean, prior_cov)?;
+    Ok(lm_solve(lines, prior_mean, config, Some(&prior), None))
+}
+
+/// 已知目标高度的 Levenberg-Marquardt 优化：z 固定为 `altitude`，只优化 x、y
+///
+/// `initial_guess` 的 z 分量被忽略。残差与 `levenberg_marquardt_optimize_detailed` 相同，
+/// 法方程只保留 x、y 的 2×2 部分。地面目标的高度已知（例如取自地形）而测量站近乎共面时，
+/// 竖直方向的不确定性会经由交会几何传到水平位置，固定高度可明显提高水平精度。
+pub fn levenberg_marquardt_optimize_fixed_altitude<T: Real>(
+    lines: &[Line<T>],
+    initial_guess: Point3<T>,
+    altitude: T,
+    config: &LmConfig,
+) -> LmReport<T> {
+    lm_solve(lines, initial_guess, config, None, Some(altitude))
+}
+
+/// 信赖域 dogleg（Powell）优化点到多条光线的残差
+///
+/// 残差、雅可比、权重与鲁棒损失与 LM 相同（见 `levenberg_marquardt_optimize_detailed`），
+/// 使用 `config` 的 `iterations`、`xtol`、`ftol`、`ray_mode`、`robust_loss`、`residual_model`
+/// 与 `min_rcond`；`initial_lambda`、`max_lambda` 与多起点参数不适用。
+/// 每步在信赖域内取 Cauchy 点与高斯牛顿步之间的折线步，按实际与模型预测的代价下降之比调整信赖域半径。
+/// 报告的 `final_lambda` 为结束时的信赖域半径（位置步长，米）。
+pub fn dogleg_optimize<T: Real>(lines: &[Line<T>], initial_guess: Point3<T>, config: &LmConfig) -> LmReport<T> {
+    dogleg_solve(lines, initial_guess, config, None, None)
+}
+
+/// LM 求解，`prior` 为可选的附加先验残差，`fixed_altitude` 为 Some 时 z 固定不参与优化
+///
+/// `config.multi_start` 大于 1 时先从各起点（见 `multi_start_guesses`）各运行
+/// `multi_start_iterations` 次短程 LM，再从代价最低的结果继续完整的 LM（代价相同时取靠前的起点）。
+/// 报告的 `initial_cost` 为给定初值处的代价，`iterations_used` 含短程运行的迭代次数。
+fn lm_solve<T: Real>(
+    lines: &[Line<T>],
+    initial_guess: Point3<T>,
+    config: &LmConfig,
+    prior: Option<&PriorTerm<T>>,
+    fixed_altitude: Option<T>,
+) -> LmReport<T> {
+    if config.multi_start <= 1 || lines.is_empty() {
+        return lm_run(lines, initial_guess, config, prior, fixed_altitude);
+    }
+    let short = LmConfig {
+        iterations: config.multi_start_iterations,
+        ..config.clone()
+    };
+    let mut short_iterations = 0;
+    let mut best: Option<LmReport<T>> = None;
+    for guess in multi_start_guesses(lines, initial_guess, config.multi_start) {
+        let report = lm_run(lines, guess, &short, prior, fixed_altitude);
+        short_iterations += report.iterations_used;
+        // 代价非有限值的起点不参与比较
+        if report.final_cost.is_finite() && best.as_ref().is_none_or(|best| report.final_cost < best.final_cost) {
+            best = Some(report);
+        }
+    }
+    let mut initial = initial_guess;
+    if let Some(z) = fixed_altitude {
+        initial.z = z;
+    }
+    let start = best.map_or(initial, |best| best.position);
+    let mut report = lm_run(lines, start, config, prior, fixed_altitude);
+    report.initial_cost = lm_cost(lines, &initial, config, prior);
+    report.iterations_used += short_iterations;
+    report
+}
+
+/// 多起点 LM 的 K 个起点：给定初值、闭式线性解、光线两两最近点中点的平均，
+/// 其余为给定初值沿 Σ w(I - d dᵀ) 的特征方向（从约束最弱的方向起）交替正负偏移，
+/// 偏移量为初值到各光线起点平均距离的 0.1 倍，每轮三个方向后加倍。
+/// 线性解奇异时跳过，起点总数仍为 K。
+fn multi_start_guesses<T: Real>(lines: &[Line<T>], initial_guess: Point3<T>, k: usize) -> Vec<Point3<T>> {
+    let mut guesses = vec![initial_guess];
+    guesses.extend(linear_triangulate(lines));
+    let mut midpoints = Vector3::zeros();
+    let mut pairs = 0;
+    for (i, a) in lines.iter().enumerate() {
+        for b in &lines[i + 1..] {
+            midpoints += find_closest_midpoint(a, b).coords;
+            pairs += 1;
+        }
+    }
+    if pairs > 0 {
+        guesses.push(Point3::from(midpoints / real::<T>(pairs as f64)));
+    }
+
+    let normal: Matrix3<T> = lines.iter().map(|line| perpendicular_projector(line) * line.weight).sum();
+    let eigen = normal.symmetric_eigen();
+    let mut axes: Vec<(T, Vector3<T>)> =
+        eigen.eigenvalues.iter().copied().zip(eigen.eigenvectors.column_iter().map(|c| c.into_owned())).collect();
+    axes.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+    let mean_range = lines.iter().fold(T::zero(), |sum, line| sum + (initial_guess - line.start).norm())
+        / real::<T>(lines.len() as f64);
+    let mut scale = mean_range * real::<T>(0.1);
+    'perturb: loop {
+        for (_, axis) in &axes {
+            for sign in [T::one(), -T::one()] {
+                if guesses.len() >= k {
+                    break 'perturb;
+                }
+                guesses.push(initial_guess + axis * (scale * sign));
+            }
+        }
+        scale *= real::<T>(2.0);
+    }
+    guesses.truncate(k);
+    guesses
+}
+
+/// 连续多少次产生非有限值的步后放弃，返回最后的有限位置
+const MAX_NON_FINITE_STEPS: usize = 5;
+
+/// LM 主循环（单一起点）
+///
+/// 初值、光线（起点、方向、权重）或先验含非有限值，或鲁棒损失参数无效（见 `RobustLoss::validate`）时
+/// 不迭代，原样返回初值并标记为未收敛。
+/// 某一步的增量、新位置或代价不是有限值时拒绝该步并增大阻尼系数，
+/// 连续 `MAX_NON_FINITE_STEPS` 次后终止并标记为未收敛，返回最后的有限位置。
+#[cfg_attr(
+    feature = "trace",
+    tracing::instrument(name = "levenberg_marquardt_optimize", level = "debug", skip_all, fields(lines = lines.len()))
+)]
+fn lm_run<T: Real>(
+    lines: &[Line<T>],
+    initial_guess: Point3<T>,
+    config: &LmConfig,
+    prior: Option<&PriorTerm<T>>,
+    fixed_altitude: Option<T>,
+) -> LmReport<T> {
+    let mut current_pos = initial_guess;
+    if let Some(z) = fixed_altitude {
+        current_pos.z = z;
+    }
+    let mut lambda: T = real(config.initial_lambda);
+    let lambda_factor_up: T = real(10.0);
+    let lambda_factor_down: T = real(0.1);
+    let ftol: T = tolerance(config.ftol, 1.0);
+    let max_lambda: T = real(config.max_lambda);
+    let mut iterations_used = 0;
+    let initial_cost = lm_cost(lines, &current_pos, config, prior);
+    let mut current_error_sq = initial_cost;
+    let mut converged = false;
+    let mut non_finite_steps = 0;
+
+    if !inputs_finite(lines, &current_pos, prior) || config.validate().is_err() {
+        return LmReport {
+            position: current_pos,
+            iterations_used,
+            initial_cost,
+            final_cost: current_error_sq,
+            converged,
+            final_lambda: lambda,
+            degenerate: false,
+        };
+    }
+
+    while (!lines.is_empty() || prior.is_some()) && iterations_used < config.iterations {
+        // 已精确通过所有光线，无法继续下降
+        if current_error_sq == T::zero() {
+            converged = true;
+            break;
+        }
+        iterations_used += 1;
+
+        let (h_approx, b) = lm_normal_equations(lines, &current_pos, config, prior, fixed_altitude.is_some());
+
+        // LM 更新： (H + λI) Δp = -b，Cholesky 分解求解（λ 很大时显式求逆的行列式会溢出）
+        let h_lm = h_approx + Matrix3::identity() * lambda;
+        let delta_vec = match h_lm.cholesky() {
+            Some(cholesky) => cholesky.solve(&-b),
+            None => {
+                lambda *= lambda_factor_up;
+                continue;
+            }
+        };
+
+        let new_pos = current_pos + delta_vec;
+        // 步长容差不低于当前位置的 10 个 ulp（xtol 为 0 时仍关闭）
+        let xtol = tolerance::<T>(config.xtol, 10.0 * to_f64(current_pos.coords.norm()));
+
+        // 计算（鲁棒）误差和
+        let new_error_sq = lm_cost(lines, &new_pos, config, prior);
+
+        // 数值溢出等产生非有限值时拒绝该步，连续多次则放弃
+        if !(new_pos.iter().all(|v| v.is_finite()) && new_error_sq.is_finite()) {
+            non_finite_steps += 1;
+            lambda *= lambda_factor_up;
+            if non_finite_steps >= MAX_NON_FINITE_STEPS || lambda > max_lambda {
+                break;
+            }
+            continue;
+        }
+        non_finite_steps = 0;
+
+        // 接受或拒绝更新
+        #[cfg(feature = "trace")]
+        tracing::trace!(
+            iteration = iterations_used,
+            lambda = to_f64(lambda),
+            cost = to_f64(current_error_sq),
+            new_cost = to_f64(new_error_sq),
+            accepted = new_error_sq < current_error_sq,
+            "LM 步"
+        );
+        if new_error_sq < current_error_sq {
+            let relative_decrease = (current_error_sq - new_error_sq) / current_error_sq;
+            current_pos = new_pos;
+            current_error_sq = new_error_sq;
+            lambda *= lambda_factor_down; // 更接近高斯牛顿
+
+            if delta_vec.norm() < xtol || relative_decrease < ftol {
+                converged = true;
+                break;
+            }
+        } else {
+            if delta_vec.norm() < xtol {
+                converged = true;
+                break;
+            }
+            lambda *= lambda_factor_up; // 更接近梯度下降
+            if lambda > max_lambda {
+                break;
+            }
+        }
+    }
+
+    #[cfg(feature = "trace")]
+    tracing::debug!(
+        iterations = iterations_used,
+        initial_cost = to_f64(initial_cost),
+        final_cost = to_f64(current_error_sq),
+        final_lambda = to_f64(lambda),
+        converged,
+        "LM 结束"
+    );
+    let (h_final, _) = lm_normal_equations(lines, &current_pos, config, prior, false);
+    LmReport {
+        position: current_pos,
+        iterations_used,
+        initial_cost,
+        final_cost: current_error_sq,
+        converged,
+        final_lambda: lambda,
+        degenerate: is_degenerate(&h_final, fixed_altitude.is_some(), config.min_rcond),
+    }
+}
+
+/// 信赖域 dogleg 主循环，`prior`、`fixed_altitude` 同 `lm_solve`
+///
+/// 初始信赖域半径为初值的模（为零时取 1）。输入含非有限值或鲁棒损失参数无效时不迭代；
+/// 某一步产生非有限值时拒绝并缩小信赖域，连续 `MAX_NON_FINITE_STEPS` 次后终止，均标记为未收敛。
+fn dogleg_solve<T: Real>(
+    lines: &[Line<T>],
+    initial_guess: Point3<T>,
+    config: &LmConfig,
+    prior: Option<&PriorTerm<T>>,
+    fixed_altitude: Option<T>,
+) -> LmReport<T> {
+    let mut current_pos = initial_guess;
+    if let Some(z) = fixed_altitude {
+        current_pos.z = z;
+    }
+    let ftol: T = tolerance(config.ftol, 1.0);
+    let initial_cost = lm_cost(lines, &current_pos, config, prior);
+    let mut current_cost = initial_cost;
+    let mut radius = current_pos.coords.norm();
+    if radius == T::zero() {
+        radius = T::one();
+    }
+    let mut iterations_used = 0;
+    let mut converged = false;
+    let mut non_finite_steps = 0;
+    let report = |position, iterations_used, final_cost, converged, radius, degenerate| LmReport {
+        position,
+        iterations_used,
+        initial_cost,
+        final_cost,
+        converged,
+        final_lambda: radius,
+        degenerate,
+    };
+    if !inputs_finite(lines, &current_pos, prior) || config.validate().is_err() {
+        return report(current_pos, 0, current_cost, false, radius, false);
+    }
+
+    let quarter: T = real(0.25);
+    let three_quarters: T = real(0.75);
+    while (!lines.is_empty() || prior.is_some()) && iterations_used < config.iterations {
+        if current_cost == T::zero() {
+            converged = true;
+            break;
+        }
+        iterations_used += 1;
+
+        // 代价 ≈ cost + 2 gᵀp + pᵀHp，g = Jᵀe
+        let (h, g) = lm_normal_equations(lines, &current_pos, config, prior, fixed_altitude.is_some());
+        let g_norm = g.norm();
+        if g_norm == T::zero() {
+            converged = true;
+            break;
+        }
+        // 最速下降方向上模型的极小点（Cauchy 点）
+        let g_h_g = g.dot(&(h * g));
+        let cauchy = if g_h_g > T::zero() { -g * (g.norm_squared() / g_h_g) } else { -g * (radius / g_norm) };
+        let gauss_newton = h.cholesky().map(|cholesky| cholesky.solve(&-g));
+        let step = match gauss_newton {
+            Some(gn) if gn.norm() <= radius => gn,
+            _ if cauchy.norm() >= radius => -g * (radius / g_norm),
+            Some(gn) => {
+                // 在 cauchy + τ (gn - cauchy) 上取 ‖p‖ = radius 的 τ ∈ [0, 1]
+                let d = gn - cauchy;
+                let a = d.norm_squared();
+                let b = real::<T>(2.0) * cauchy.dot(&d);
+                let c = cauchy.norm_squared() - radius * radius;
+                let tau = (-b + (b * b - real::<T>(4.0) * a * c).max(T::zero()).sqrt()) / (real::<T>(2.0) * a);
+                cauchy + d * tau
+            }
+            None => cauchy,
+        };
+
+        let new_pos = current_pos + step;
+        let new_cost = lm_cost(lines, &new_pos, config, prior);
+        let step_norm = step.norm();
+        let xtol = tolerance::<T>(config.xtol, 10.0 * to_f64(current_pos.coords.norm()));
+        if !(new_pos.iter().all(|v| v.is_finite()) && new_cost.is_finite()) {
+            non_finite_steps += 1;
+            radius *= quarter;
+            if non_finite_steps >= MAX_NON_FINITE_STEPS {
+                break;
+            }
+            continue;
+        }
+        non_finite_steps = 0;
+
+        let predicted = -(real::<T>(2.0) * g.dot(&step) + step.dot(&(h * step)));
+        let actual = current_cost - new_cost;
+        let ratio = if predicted > T::zero() { actual / predicted } else { -T::one() };
+        if ratio < quarter {
+            radius = step_norm * quarter;
+        } else if ratio > three_quarters && step_norm >= radius * real::<T>(0.99) {
+            radius *= real::<T>(2.0);
+        }
+
+        if actual > T::zero() {
+            let relative_decrease = actual / current_cost;
+            current_pos = new_pos;
+            current_cost = new_cost;
+            if step_norm < xtol || relative_decrease < ftol {
+                converged = true;
+                break;
+            }
+        } else if step_norm < xtol || radius < xtol {
+            converged = true;
+            break;
+        }
+    }
+
+    let (h_final, _) = lm_normal_equations(lines, &current_pos, config, prior, false);
+    let degenerate = is_degenerate(&h_final, fixed_altitude.is_some(), config.min_rcond);
+    report(current_pos, iterations_used, current_cost, converged, radius, degenerate)
+}
+
+/// 初值、光线（起点、方向、权重）与先验是否全为有限值
+fn inputs_finite<T: Real>(lines: &[Line<T>], p: &Point3<T>, prior: Option<&PriorTerm<T>>) -> bool {
+    p.iter().all(|v| v.is_finite())
+        && lines.iter().all(|line| {
+            line.start.iter().chain(line.direction.iter()).all(|v| v.is_finite()) && line.weight.is_finite()
+        })
+        && prior.is_none_or(|prior| prior.mean.iter().chain(prior.sqrt_information.iter()).all(|v| v.is_finite()))
+}
+
+/// 位置 `p` 处的 3×3 法方程 H = Σ JᵢᵀJᵢ 与 b = Σ Jᵢᵀeᵢ（不含阻尼），直接累加以避免构造 3n×3 的 J
+///
+/// `fix_z` 为 true 时去掉 z 的行列（H 的 z 对角元置 1、b 的 z 分量置 0），求解得到的 Δz 恒为 0。
+fn lm_normal_equations<T: Real>(
+    lines: &[Line<T>],
+    p: &Point3<T>,
+    config: &LmConfig,
+    prior: Option<&PriorTerm<T>>,
+    fix_z: bool,
+) -> (Matrix3<T>, Vector3<T>) {
+    let mut h = Matrix3::zeros();
+    let mut b = Vector3::zeros();
+    for line in lines.iter() {
+        let raw_vec = config.residual_model.residual(line, p, config.ray_mode);
+        let r = raw_vec.norm();
+
+        // 权重包含鲁棒损失的 IRLS 降权
+        let w = line.weight * config.robust_loss.weight(r);
+        let jac_block = config.residual_model.jacobian(line, p, config.ray_mode);
+        let jac_t = jac_block.transpose();
+        h += jac_t * jac_block * w;
+        b += jac_t * raw_vec * w;
+
+        // 测距残差为标量，雅可比为 1×3 的行 gᵀ
+        if let Some((e, g)) = range_residual(line, p).filter(|_| config.use_range) {
+            h += g * g.transpose();
+            b += g * e;
+        }
+    }
+    // 先验残差的雅可比即 sqrt_information
+    if let Some(prior) = prior {
+        let jac_t = prior.sqrt_information.transpose();
+        h += jac_t * prior.sqrt_information;
+        b += jac_t * prior.residual(p);
+    }
+    // 固定高度：只剩 x、y 的 2×2 方程
+    if fix_z {
+        h.row_mut(2).fill(T::zero());
+        h.column_mut(2).fill(T::zero());
+        h[(2, 2)] = T::one();
+        b[2] = T::zero();
+    }
+    (h, b)
+}
+
+/// 不含阻尼的法方程 H 是否病态：最小特征值不超过最大特征值的 `min_rcond` 倍
+///
+/// 固定高度时只检查 x、y 的 2×2 部分。H 为零（没有光线）时视为病态，含非有限值时不检查。
+fn is_degenerate<T: Real>(h: &Matrix3<T>, fixed_altitude: bool, min_rcond: f64) -> bool {
+    if !h.iter().all(|v| v.is_finite()) {
+        return false;
+    }
+    let (min, max) = if fixed_altitude {
+        let eigenvalues = h.fixed_view::<2, 2>(0, 0).into_owned().symmetric_eigenvalues();
+        (eigenvalues.min(), eigenvalues.max())
+    } else {
+        let eigenvalues = h.symmetric_eigenvalues();
+        (eigenvalues.min(), eigenvalues.max())
+    };
+    min <= max * real::<T>(min_rcond)
+}
+
+/// 单条光线的投影矩阵 I - d dᵀ（到垂直于光线平面的投影）
+fn perpendicular_projector<T: Real>(line: &Line<T>) -> Matrix3<T> {
+    Matrix3::identity() - line.direction * line.direction.transpose()
+}
+
+/// 3×3 对称半正定矩阵是否足够非奇异（最小特征值不低于最大特征值的 1e-9 倍，f32 下为 100ε 倍）
+pub(crate) fn is_well_conditioned<T: Real>(m: &Matrix3<T>) -> bool {
+    let eigenvalues = m.symmetric_eigenvalues();
+    eigenvalues.min() > eigenvalues.max() * tolerance(1e-9, 100.0)
+}
+
+/// 点到各条直线（视为无限长直线）的加权距离平方和 Σ w d²
+///
+/// 即 `linear_triangulate` 最小化的目标函数，可用于比较候选位置而无需运行优化。
+pub fn sum_squared_distance<T: Real>(lines: &[Line<T>], point: &Point3<T>) -> T {
+    lines.iter().fold(T::zero(), |sum, line| {
+        sum + line.weight * line.perpendicular_vector_to(point).norm_squared()
+    })
+}
+
+/// 闭式线性最小二乘三角定位
+///
+/// 点到多条直线的加权距离平方和的最小值满足线性方程
+/// Σ w (I - d dᵀ) p = Σ w (I - d dᵀ) s，用 Cholesky 分解求解。
+/// 光线平行或数量不足导致方程奇异时返回 None。
+/// 该解将光线视为无限长直线，可直接使用，也可作为 LM 的初值。
+pub fn linear_triangulate<T: Real>(lines: &[Line<T>]) -> Option<Point3<T>> {
+    let mut a = Matrix3::zeros();
+    let mut b = Vector3::zeros();
+    for line in lines {
+        let projector = perpendicular_projector(line) * line.weight;
+        a += projector;
+        b += projector * line.start.coords;
+    }
+
+    if !is_well_conditioned(&a) {
+        return None;
+    }
+    a.cholesky()
+        .map(|chol| Point3::from(chol.solve(&b)))
+        .filter(|p| p.iter().all(|v| v.is_finite()))
+}
+
+/// 几何精度因子（DOP），见 `geometry_dop`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeometryDop {
+    pub total: f64,      // √tr(A⁻¹)
+    pub horizontal: f64, // √(A⁻¹ₓₓ + A⁻¹ᵧᵧ)
+    pub vertical: f64,   // √A⁻¹_zz
+}
+
+/// 由光线几何计算精度因子，与残差无关
+///
+/// 设每条光线在垂直于自身的两个方向上各有标准差为 1 米的独立误差，则位置误差协方差为 A⁻¹，
+/// A = Σ(I - d dᵀ)（不计权重）。DOP 无量纲，定位误差约为 DOP × 单线垂直误差：
+/// 3 条两两正交的光线 DOP = √1.5 ≈ 1.22，n 条方向均匀分布的光线约为 3/√(2n)；
+/// 光线交会角越小 DOP 越大，近乎平行时沿光线方向的误差急剧增大。
+/// A 奇异时各分量为无穷大。
+pub fn geometry_dop(lines: &[Line]) -> GeometryDop {
+    let a: Matrix3<f64> = lines.iter().map(perpendicular_projector).sum();
+    match a.try_inverse().filter(|_| is_well_conditioned(&a)) {
+        Some(inv) => GeometryDop {
+            total: inv.trace().max(0.0).sqrt(),
+            horizontal: (inv[(0, 0)] + inv[(1, 1)]).max(0.0).sqrt(),
+            vertical: inv[(2, 2)].max(0.0).sqrt(),
+        },
+        None => GeometryDop {
+            total: f64::INFINITY,
+            horizontal: f64::INFINITY,
+            vertical: f64::INFINITY,
+        },
+    }
+}
+
+/// 估计 LM 收敛点的位置协方差：σ² · (JᵀJ)⁻¹
+///
+/// 每条光线的残差只在垂直于光线的平面内有 2 个自由度，
+/// 因此残差方差 σ² = Σ w‖r‖² / (2n - 3)，JᵀJ 同样按权重累加。
+/// 当 JᵀJ 奇异（例如光线近乎平行）或自由度不足时返回 None。
+pub fn estimate_covariance(lines: &[Line], position: Point3<f64>) -> Option<Matrix3<f64>> {
+    let dof = 2 * lines.len() as i64 - 3;
+    if dof <= 0 {
+        return None;
+    }
+
+    let mut jtj = Matrix3::zeros();
+    let mut error_sq = 0.0;
+    for line in lines {
+        error_sq += line.weight * line.perpendicular_vector_to(&position).norm_squared();
+        jtj += perpendicular_projector(line) * line.weight;
+    }
+
+    // 最小特征值相对最大特征值过小即视为奇异
+    if !is_well_conditioned(&jtj) {
+        return None;
+    }
+
+    let sigma_sq = error_sq / dof as f64;
+    jtj.try_inverse()
+        .map(|inv| inv * sigma_sq)
+        .filter(|cov| cov.iter().all(|v| v.is_finite()))
+}
+
+/// 高度固定时的位置协方差：只估计 x、y，自由度为 2n - 2，z 的行列为 0
+fn estimate_horizontal_covariance(lines: &[Line], position: Point3<f64>) -> Option<Matrix3<f64>> {
+    let dof = 2 * lines.len() as i64 - 2;
+    if dof <= 0 {
+        return None;
+    }
+
+    let mut jtj = Matrix2::zeros();
+    let mut error_sq = 0.0;
+    for line in lines {
+        error_sq += line.weight * line.perpendicular_vector_to(&position).norm_squared();
+        jtj += perpendicular_projector(line).fixed_view::<2, 2>(0, 0) * line.weight;
+    }
+
+    let eigenvalues = jtj.symmetric_eigenvalues();
+    if eigenvalues.min() <= eigenvalues.max() * 1e-9 {
+        return None;
+    }
+
+    let sigma_sq = error_sq / dof as f64;
+    let inv = jtj.try_inverse()? * sigma_sq;
+    let mut cov = Matrix3::zeros();
+    cov.fixed_view_mut::<2, 2>(0, 0).copy_from(&inv);
+    Some(cov).filter(|cov| cov.iter().all(|v| v.is_finite()))
+}
+
+/// 自助法（bootstrap）估计的位置不确定度，见 `bootstrap_uncertainty`
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BootstrapResult {
+    pub positions: Vec<Point3<f64>>, // 各次有效重采样的 LM 结果（位置云）
+    pub mean: Point3<f64>,           // 位置云的均值
+    pub covariance: Matrix3<f64>,    // 位置云的样本协方差（除以 n - 1）
+    pub lower: Point3<f64>,          // 各轴的 2.5% 分位数
+    pub median: Point3<f64>,         // 各轴的中位数
+    pub upper: Point3<f64>,          // 各轴的 97.5% 分位数
+    pub num_degenerate: usize,       // 因几何退化被丢弃的重采样数
+}
+
+impl BootstrapResult {
+    /// 位置云各轴的第 `percent` 百分位数（0~100，线性插值），`percent` 超出 [0, 100] 时返回 `InvalidParameter`
+    pub fn percentile(&self, percent: f64) -> Result<Point3<f64>, OptiRadarError> {
+        if !(0.0..=100.0).contains(&percent) {
+            return Err(OptiRadarError::InvalidParameter {
+                name: "percent",
+                value: percent,
+            });
+        }
+        Ok(self.axis_percentiles(percent))
+    }
+
+    /// 同 `percentile`，不检查 `percent`
+    fn axis_percentiles(&self, percent: f64) -> Point3<f64> {
+        let axis = |k: usize| {
+            let mut values: Vec<f64> = self.positions.iter().map(|p| p[k]).collect();
+            values.sort_by(f64::total_cmp);
+            interpolated_percentile(&values, percent)
+        };
+        Point3::new(axis(0), axis(1), axis(2))
+    }
+
+    /// 各轴的标准差（米）
+    pub fn std_devs(&self) -> Vector3<f64> {
+        self.covariance.diagonal().map(|v| v.max(0.0).sqrt())
+    }
+}
+
+/// `find_targets` 为每个目标附加 bootstrap 统计量的参数，见 `FindTargetsConfig::bootstrap`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BootstrapConfig {
+    pub num_resamples: usize, // 重采样次数，默认 200
+    pub lm_iterations: usize, // 每次重采样的 LM 迭代上限，默认 20（从原位置出发，通常几步即收敛）
+    pub seed: Option<u64>,    // 随机种子，第 k 个目标使用 seed + k；None 时随机取种
+}
+
+impl Default for BootstrapConfig {
+    fn default() -> Self {
+        BootstrapConfig {
+            num_resamples: 200,
+            lm_iterations: 20,
+            seed: None,
+        }
+    }
+}
+
+/// 以默认的短 LM（20 次迭代，其余同 `LmConfig::default()`）运行 `bootstrap_uncertainty_with_config`
+pub fn bootstrap_uncertainty(
+    lines: &[Line],
+    position: Point3<f64>,
+    n_resamples: usize,
+    rng: &mut dyn RandomSource,
+) -> Option<BootstrapResult> {
+    let lm = LmConfig {
+        iterations: BootstrapConfig::default().lm_iterations,
+        ..LmConfig::default()
+    };
+    bootstrap_uncertainty_with_config(lines, position, n_resamples, &lm, rng)
+}
+
+/// 自助法估计定位结果的不确定度
+///
+/// 从 `lines`（目标的内点光线）中有放回地抽取同样数量的光线，以 `position` 为初值按 `lm` 重新优化，
+/// 重复 `n_resamples` 次，得到位置云的经验协方差与各轴分位数。光线较少时线性化的
+/// `estimate_covariance` 偏乐观，bootstrap 能反映个别光线对结果的影响。
+/// 抽到的不同光线少于 2 条或 LM 报告几何退化的重采样被丢弃；有效重采样少于 2 次时返回 None。
+pub fn bootstrap_uncertainty_with_config(
+    lines: &[Line],
+    position: Point3<f64>,
+    n_resamples: usize,
+    lm: &LmConfig,
+    rng: &mut dyn RandomSource,
+) -> Option<BootstrapResult> {
+    if lines.len() < 2 {
+        return None;
+    }
+    let mut positions = Vec::with_capacity(n_resamples);
+    let mut num_degenerate = 0;
+    let mut picks = vec![0; lines.len()];
+    let mut resampled = Vec::with_capacity(lines.len());
+    for _ in 0..n_resamples {
+        for pick in picks.iter_mut() {
+            *pick = random_index(rng, lines.len());
+        }
+        if picks.iter().all(|&i| i == picks[0]) {
+            num_degenerate += 1;
+            continue;
+        }
+        resampled.clear();
+        resampled.extend(picks.iter().map(|&i| lines[i]));
+        let report = levenberg_marquardt_optimize_detailed(&resampled, position, lm);
+        if report.degenerate || !report.position.iter().all(|v| v.is_finite()) {
+            num_degenerate += 1;
+            continue;
+        }
+        positions.push(report.position);
+    }
+    if positions.len() < 2 {
+        return None;
+    }
+
+    let n = positions.len() as f64;
+    let mean = Point3::from(positions.iter().map(|p| p.coords).sum::<Vector3<f64>>() / n);
+    let covariance = positions
+        .iter()
+        .map(|p| (p - mean) * (p - mean).transpose())
+        .sum::<Matrix3<f64>>()
+        / (n - 1.0);
+    let mut result = BootstrapResult {
+        positions,
+        mean,
+        covariance,
+        lower: mean,
+        median: mean,
+        upper: mean,
+        num_degenerate,
+    };
+    result.lower = result.axis_percentiles(2.5);
+    result.median = result.axis_percentiles(50.0);
+    result.upper = result.axis_percentiles(97.5);
+    Some(result)
+}
+
+/// RANSAC 候选模型的评分方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RansacScoring {
+    /// 内点数越多越好，内点数相同时保留先找到的模型
+    #[default]
+    InlierCount,
+    /// MSAC：每条光线的代价为 min(d², 阈值²)，总代价越小越好。
+    /// 内点按残差大小区分优劣，内点数相同时偏向残差更小的模型。
+    Msac,
+}
+
+/// RANSAC 参数
+#[derive(Debug, Clone)]
+pub struct RansacConfig {
+    pub max_iterations: usize, // 迭代次数硬上限
+    pub threshold: f64,        // 内点阈值（米；角度残差模型下为弧度）
+    pub min_lines: usize,      // 构成目标所需的最少内点数
+    pub confidence: f64,       // 自适应终止的置信度，例如 0.99
+    pub ray_mode: bool,        // 将测量视为射线，测量站背后的点不计为内点
+    pub seed: Option<u64>,     // 随机种子，None 时每次调用随机取种
+    pub parallel_cutoff: usize, // 光线数超过该值时并行统计内点（需启用 `parallel` 特性）
+    pub max_parallel_cos: f64,  // 样本中光线两两方向余弦绝对值均超过该值时视为退化
+    pub max_condition_number: f64, // 样本三角定位矩阵条件数上限，超过视为退化
+    pub residual_model: ResidualModel, // 内点判定使用的残差模型
+    pub exhaustive_max_lines: usize, // 光线数不超过该值时穷举全部三元组而不随机抽样；0 为从不穷举
+    pub scoring: RansacScoring, // 候选模型的评分方式
+    pub bounds: Option<BoundingBox>, // 关注区域，候选点在区域外的模型不参与评分
+    pub spatial_index_cutoff: usize, // 光线数超过该值时 `find_targets` 为内点统计建立网格索引（见 `LineGrid`），usize::MAX 为不使用
+    pub range_gate: Option<f64>, // 带测距的光线到候选点（匀速模型下为外推位置）的距离与测距之差超过该值倍 σ 时不计为内点，None 不检验
+}
+
+/// 轴对齐的关注区域（米），边界包含在内
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    pub min: Point3<f64>,
+    pub max: Point3<f64>,
+}
+
+impl BoundingBox {
+    /// 由两个角点创建，各坐标须为有限值且 min ≤ max，否则返回 `InvalidParameter`
+    pub fn new(min: Point3<f64>, max: Point3<f64>) -> Result<Self, OptiRadarError> {
+        for (name, lo, hi) in [("bounds.x", min.x, max.x), ("bounds.y", min.y, max.y), ("bounds.z", min.z, max.z)] {
+            if !(lo.is_finite() && hi.is_finite()) {
+                return Err(OptiRadarError::InvalidParameter { name, value: if lo.is_finite() { hi } else { lo } });
+            }
+            if lo > hi {
+                return Err(OptiRadarError::InvalidParameter { name, value: lo });
+            }
+        }
+        Ok(BoundingBox { min, max })
+    }
+
+    /// 点是否在区域内（含边界）
+    pub fn contains<T: Real>(&self, p: &Point3<T>) -> bool {
+        (0..3).all(|k| {
+            let v = to_f64(p[k]);
+            self.min[k] <= v && v <= self.max[k]
+        })
+    }
+
+    /// 将点截断到区域内
+    pub fn clamp(&self, p: &Point3<f64>) -> Point3<f64> {
+        Point3::from(Vector3::from_fn(|k, _| p[k].clamp(self.min[k], self.max[k])))
+    }
+}
+
+impl RansacConfig {
+    /// 使用默认置信度 0.99、射线模式创建参数
+    pub fn new(max_iterations: usize, threshold: f64, min_lines: usize) -> Self {
+        RansacConfig {
+            max_iterations,
+            threshold,
+            min_lines,
+            confidence: 0.99,
+            ray_mode: true,
+            seed: None,
+            parallel_cutoff: 4096,
+            max_parallel_cos: 0.9999,
+            max_condition_number: 1e5,
+            residual_model: ResidualModel::Metric,
+            exhaustive_max_lines: 12,
+            scoring: RansacScoring::InlierCount,
+            bounds: None,
+            spatial_index_cutoff: 5000,
+            range_gate: None,
+        }
+    }
+}
+
+/// RANSAC 运行统计
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RansacStats {
+    pub iterations_run: usize,      // 实际运行的迭代次数
+    pub required_iterations: usize, // 按当前内点率估计所需的迭代次数（不超过上限）
+    pub degenerate_samples_rejected: usize, // 因退化被拒绝并重新抽样的样本数
+}
+
+/// RANSAC 模型：候选目标位置及其内点索引
+pub type RansacModel<T = f64> = (Point3<T>, Vec<usize>);
+
+/// 按内点率估计达到给定置信度所需的迭代次数
+///
+/// N = ln(1 - p) / ln(1 - wˢ)，其中 w 为内点率，s 为样本大小。
+pub(crate) fn required_iterations(
+    inlier_ratio: f64,
+    confidence: f64,
+    sample_size: i32,
+    max_iterations: usize,
+) -> usize {
+    let good_sample_prob = inlier_ratio.powi(sample_size);
+    if good_sample_prob >= 1.0 {
+        return 1.min(max_iterations);
+    }
+    if good_sample_prob <= 0.0 || confidence >= 1.0 {
+        return max_iterations;
+    }
+    let n = (1.0 - confidence).ln() / (1.0 - good_sample_prob).ln();
+    if !n.is_finite() || n >= max_iterations as f64 {
+        max_iterations
+    } else {
+        (n.ceil() as usize).max(1)
+    }
+}
+
+// --- 随机数 ---
+//
+// 核心库不依赖 rand：抽样使用内置的 `SplitMix64`，调用方可通过 `RandomSource` 注入自己的发生器。
+// 启用 `simulation` 特性时任何 `rand::RngCore` 都可直接作为 `RandomSource` 使用，
+// 未设置种子时也改由 `thread_rng` 取种。
+
+/// 64 位随机数来源
+pub trait RandomSource {
+    fn next_u64(&mut self) -> u64;
+}
+
+#[cfg(feature = "simulation")]
+impl<R: rand::RngCore + ?Sized> RandomSource for R {
+    fn next_u64(&mut self) -> u64 {
+        rand::RngCore::next_u64(self)
+    }
+}
+
+/// SplitMix64 伪随机数发生器：状态只有一个 u64，种子相同时序列相同
+#[derive(Debug, Clone)]
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        SplitMix64 { state: seed }
+    }
+}
+
+impl RandomSource for SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(SPLITMIX_GAMMA);
+        splitmix_finalize(self.state)
+    }
+}
+
+const SPLITMIX_GAMMA: u64 = 0x9E37_79B9_7F4A_7C15;
+
+fn splitmix_finalize(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// 未设置种子时使用的随机种子
+#[cfg(feature = "simulation")]
+fn fresh_seed() -> u64 {
+    rand::random()
+}
+
+/// 未设置种子时使用的随机种子（取自标准库 `RandomState` 的随机键）
+#[cfg(not(feature = "simulation"))]
+fn fresh_seed() -> u64 {
+    use std::hash::{BuildHasher, Hasher};
+    std::collections::hash_map::RandomState::new().build_hasher().finish()
+}
+
+/// 以 `seed` 初始化的发生器，`seed` 为 None 时随机取种
+pub(crate) fn seeded_rng(seed: Option<u64>) -> SplitMix64 {
+    SplitMix64::new(seed.unwrap_or_else(fresh_seed))
+}
+
+/// [0, bound) 内的随机整数（乘法映射，偏差不超过 bound / 2^64）
+fn random_index<R: RandomSource + ?Sized>(rng: &mut R, bound: usize) -> usize {
+    ((rng.next_u64() as u128 * bound as u128) >> 64) as usize
+}
+
+/// 单次迭代中因退化重新抽样的次数上限
+pub(crate) const MAX_DEGENERATE_RESAMPLES: usize = 100;
+
+/// 3 条样本光线是否退化
+///
+/// 光线两两近乎平行，或 Σ (I - d dᵀ) 的条件数过大（方向近乎共线）时，
+/// 两两最近点的平均没有意义。
+fn is_degenerate_sample<T: Real>(sample_lines: &[Line<T>], config: &RansacConfig) -> bool {
+    let max_parallel_cos: T = real(config.max_parallel_cos);
+    let all_parallel = sample_lines.iter().enumerate().all(|(i, a)| {
+        sample_lines[i + 1..]
+            .iter()
+            .all(|b| a.direction.dot(&b.direction).abs() > max_parallel_cos)
+    });
+    if all_parallel {
+        return true;
+    }
+
+    let a: Matrix3<T> = sample_lines.iter().map(perpendicular_projector).sum();
+    let eigenvalues = a.symmetric_eigenvalues();
+    let min_eigenvalue = eigenvalues.min();
+    let condition_number = if min_eigenvalue > T::zero() {
+        to_f64(eigenvalues.max() / min_eigenvalue)
+    } else {
+        f64::INFINITY
+    };
+    condition_number > config.max_condition_number
+}
+
+/// 从 `active` 中随机选取 3 条光线求候选点，退化时重新抽样
+///
+/// 返回候选点（多次重抽仍退化时为 None）及被拒绝的样本数。
+fn random_sample_guess<T: Real>(
+    all_lines: &[Line<T>],
+    active: &[usize],
+    config: &RansacConfig,
+    rng: &mut dyn RandomSource,
+) -> (Option<Point3<T>>, usize) {
+    let mut rejected = 0;
+    while rejected <= MAX_DEGENERATE_RESAMPLES {
+        let sample = draw_three_distinct(rng, active.len()).map(|k| active[k]);
+        match sample_guess(all_lines, config, sample) {
+            Some(guess) => return (Some(guess), rejected),
+            None => rejected += 1,
+        }
+    }
+    (None, rejected)
+}
+
+/// 从 0..n（n >= 3）中不放回地抽取 3 个不同的下标，按升序返回
+///
+/// Floyd 算法：每个下标只取一次随机数，不需要拒绝重抽，也不分配内存。
+pub(crate) fn draw_three_distinct<R: RandomSource + ?Sized>(rng: &mut R, n: usize) -> [usize; 3] {
+    let mut sample = [0; 3];
+    for (k, j) in (n - 3..n).enumerate() {
+        let t = random_index(rng, j + 1);
+        sample[k] = if sample[..k].contains(&t) { j } else { t };
+    }
+    sample.sort_unstable();
+    sample
+}
+
+/// 由给定的 3 条光线求候选点，样本退化时返回 None
+fn sample_guess<T: Real>(all_lines: &[Line<T>], config: &RansacConfig, sample: [usize; 3]) -> Option<Point3<T>> {
+    let sample_lines = sample.map(|i| all_lines[i]);
+    if is_degenerate_sample(&sample_lines, config) {
+        return None;
+    }
+
+    // 初始猜测：3 条光线两两最近点的平均
+    let initial_guess = (find_closest_midpoint(&sample_lines[0], &sample_lines[1]).coords
+        + find_closest_midpoint(&sample_lines[0], &sample_lines[2]).coords
+        + find_closest_midpoint(&sample_lines[1], &sample_lines[2]).coords)
+        / real::<T>(3.0);
+    Some(Point3::from(initial_guess))
+}
+
+/// 按字典序列出 n 条光线的全部 C(n,3) 个三元组
+fn exhaustive_samples(n: usize) -> Vec<[usize; 3]> {
+    (0..n)
+        .flat_map(|i| (i + 1..n).flat_map(move |j| (j + 1..n).map(move |k| [i, j, k])))
+        .collect()
+}
+
+/// 对每一项求值并保持输入顺序；启用 `parallel` 特性时并行求值
+fn map_ordered<X: Send, T: Send>(items: Vec<X>, f: impl Fn(X) -> T + Sync + Send) -> Vec<T> {
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+        items.into_par_iter().map(f).collect()
+    }
+    #[cfg(not(feature = "parallel"))]
+    {
+        items.into_iter().map(f).collect()
+    }
+}
+
+/// 穷举 `active` 的全部三元组，按字典序返回各自的候选模型（退化样本为 None）
+fn exhaustive_candidates<T: Real>(
+    all_lines: &[Line<T>],
+    active: &[usize],
+    config: &RansacConfig,
+) -> Vec<Option<RansacModel<T>>> {
+    map_ordered(exhaustive_samples(active.len()), |sample| {
+        sample_guess(all_lines, config, sample.map(|k| active[k]))
+            .map(|guess| (guess, collect_inliers_in(all_lines, active, &guess, config, None)))
+    })
+}
+
+/// 并行评估时每批的迭代数；批大小只影响多余计算量，不影响结果
+#[cfg(feature = "parallel")]
+const RANSAC_BATCH_SIZE: usize = 32;
+
+/// 依次计算接下来 `count` 次迭代的候选模型
+///
+/// 抽样在调用线程上按迭代顺序进行，共用同一个随机数发生器；只有内点统计并行，
+/// 因此结果与线程数、批大小以及是否启用 `parallel` 特性无关。
+fn ransac_candidates<T: Real>(
+    all_lines: &[Line<T>],
+    active: &[usize],
+    config: &RansacConfig,
+    rng: &mut dyn RandomSource,
+    count: usize,
+    index: Option<&SubsetIndex>,
+) -> Vec<(Option<RansacModel<T>>, usize)> {
+    let guesses: Vec<_> = (0..count).map(|_| random_sample_guess(all_lines, active, config, rng)).collect();
+    map_ordered(guesses, |(guess, rejected)| {
+        // 统计内点
+        let model = guess.map(|guess| (guess, collect_inliers_in(all_lines, active, &guess, config, index)));
+        (model, rejected)
+    })
+}
+
+/// 候选点是否在 `config.bounds` 内（未设置区域时总为 true）
+pub(crate) fn within_bounds<T: Real>(config: &RansacConfig, p: &Point3<T>) -> bool {
+    config.bounds.as_ref().is_none_or(|bounds| bounds.contains(p))
+}
+
+/// 候选模型的得分，越小越好
+///
+/// 内点计数取内点数的相反数；MSAC 中内点的代价为残差平方，其余光线
+/// （包括同一测量站中未被保留的光线）的代价为阈值平方。
+fn model_score<T: Real>(
+    all_lines: &[Line<T>],
+    num_lines: usize,
+    (position, inliers): &RansacModel<T>,
+    config: &RansacConfig,
+) -> f64 {
+    match config.scoring {
+        RansacScoring::InlierCount => -(inliers.len() as f64),
+        RansacScoring::Msac => {
+            let inlier_cost: f64 = inliers
+                .iter()
+                .map(|&i| {
+                    to_f64(config.residual_model.residual(&all_lines[i], position, config.ray_mode).norm_squared())
+                })
+                .sum();
+            let outliers = num_lines - inliers.len();
+            inlier_cost + outliers as f64 * config.threshold * config.threshold
+        }
+    }
+}
+
+/// RANSAC 拟合光线集合，寻找最大内点集
+///
+/// 每当找到更大的一致集时，按观测到的内点率重新估计所需迭代次数，
+/// 达到后提前终止；`max_iterations` 为硬上限。候选模型按 `scoring` 评分，
+/// 默认取内点最多者，MSAC 下取总代价最小者。设置 `bounds` 时候选点在区域外的模型直接跳过。
+///
+/// 每次调用以 `seed` 初始化一个 `SplitMix64` 依次抽样，设置 `seed` 时结果可复现；
+/// 使用自己的随机数发生器时见 `ransac_fit_lines_with_rng`。
+/// 启用 `parallel` 特性后各批候选点的内点统计并行进行，但仍按迭代序号依次归约，
+/// 因此结果与串行版本及线程数无关。
+///
+/// 光线数不超过 `exhaustive_max_lines` 时不再随机抽样，而是按字典序穷举全部三元组
+/// （同样跳过退化样本），取得分最好的一致集，结果与种子无关。此时 `iterations_run`
+/// 与 `required_iterations` 均为 C(n,3)。
+///
+/// 对 `Line<f32>` 同样适用，`config` 中的阈值与退化判据按 f32 转换。
+pub fn ransac_fit_lines<T: Real>(
+    all_lines: &[Line<T>],
+    config: &RansacConfig,
+) -> (Option<RansacModel<T>>, RansacStats) {
+    ransac_fit_lines_with_rng(all_lines, config, &mut seeded_rng(config.seed))
+}
+
+/// 同 `ransac_fit_lines`，随机数取自 `rng`（忽略 `config.seed`）
+pub fn ransac_fit_lines_with_rng<T: Real>(
+    all_lines: &[Line<T>],
+    config: &RansacConfig,
+    rng: &mut dyn RandomSource,
+) -> (Option<RansacModel<T>>, RansacStats) {
+    let active: Vec<usize> = (0..all_lines.len()).collect();
+    ransac_fit_subset(all_lines, &active, config, rng)
+}
+
+/// 只在 `active` 列出的光线上运行 `ransac_fit_lines_with_rng`
+///
+/// `active` 须为升序且不重复；返回的内点为 `all_lines` 中的索引（升序）。
+/// 抽样按 `active` 中的位置进行，因此结果与先复制出这些光线再调用 `ransac_fit_lines_with_rng`
+/// 并映射回原索引相同，但不需要逐轮复制光线。
+pub fn ransac_fit_subset<T: Real>(
+    all_lines: &[Line<T>],
+    active: &[usize],
+    config: &RansacConfig,
+    rng: &mut dyn RandomSource,
+) -> (Option<RansacModel<T>>, RansacStats) {
+    ransac_fit_subset_indexed(all_lines, active, config, rng, None, None, None)
+}
+
+/// `ransac_fit_subset`，随机抽样时的内点统计可使用为 `all_lines` 建立的网格索引与列式存储，结果不变
+///
+/// 给定 `progress` 时每 `interval` 次迭代报告一次进度（批量并行时在批末尾），回调取消后停止抽样，
+/// 返回到此为止的最优模型。
+#[cfg_attr(
+    feature = "trace",
+    tracing::instrument(name = "ransac_fit_lines", level = "debug", skip_all, fields(lines = active.len()))
+)]
+pub(crate) fn ransac_fit_subset_indexed<T: Real>(
+    all_lines: &[Line<T>],
+    active: &[usize],
+    config: &RansacConfig,
+    rng: &mut dyn RandomSource,
+    grid: Option<&LineGrid>,
+    soa: Option<&LineSoa>,
+    progress: Option<&ProgressTracker>,
+) -> (Option<RansacModel<T>>, RansacStats) {
+    let mut best_inliers_indices = Vec::new();
+    let mut best_model_pos = Point3::origin();
+    let mut best_score = f64::INFINITY;
+    let mut stats = RansacStats {
+        iterations_run: 0,
+        required_iterations: config.max_iterations,
+        degenerate_samples_rejected: 0,
+    };
+
+    if active.len() < 3 {
+        return (None, stats);
+    }
+
+    if active.len() <= config.exhaustive_max_lines {
+        let candidates = exhaustive_candidates(all_lines, active, config);
+        stats.iterations_run = candidates.len();
+        stats.required_iterations = candidates.len();
+        for candidate in candidates {
+            let Some(model) = candidate else {
+                stats.degenerate_samples_rejected += 1;
+                continue;
+            };
+            if !within_bounds(config, &model.0) {
+                continue;
+            }
+            let score = model_score(all_lines, active.len(), &model, config);
+            if model.1.len() >= config.min_lines && score < best_score {
+                best_score = score;
+                (best_model_pos, best_inliers_indices) = model;
+            }
+        }
+        #[cfg(feature = "trace")]
+        tracing::debug!(
+            exhaustive = true,
+            iterations = stats.iterations_run,
+            degenerate = stats.degenerate_samples_rejected,
+            inliers = best_inliers_indices.len(),
+            "RANSAC 结束"
+        );
+        return if best_inliers_indices.len() >= config.min_lines {
+            (Some((best_model_pos, best_inliers_indices)), stats)
+        } else {
+            (None, stats)
+        };
+    }
+
+    #[cfg(feature = "parallel")]
+    let batch_size = RANSAC_BATCH_SIZE;
+    #[cfg(not(feature = "parallel"))]
+    let batch_size = 1;
+
+    let index = (grid.is_some() || soa.is_some()).then(|| {
+        let mut members = Vec::new();
+        if grid.is_some() {
+            members.resize(all_lines.len(), false);
+            for &i in active {
+                members[i] = true;
+            }
+        }
+        SubsetIndex {
+            grid,
+            soa,
+            active: members,
+        }
+    });
+    let mut next_report = progress.map_or(usize::MAX, |progress| progress.interval);
+    while stats.iterations_run < stats.required_iterations {
+        let batch_end = (stats.iterations_run + batch_size).min(stats.required_iterations);
+        let candidates =
+            ransac_candidates(all_lines, active, config, rng, batch_end - stats.iterations_run, index.as_ref());
+
+        for (candidate, rejected) in candidates {
+            if stats.iterations_run >= stats.required_iterations {
+                break;
+            }
+            stats.iterations_run += 1;
+            stats.degenerate_samples_rejected += rejected;
+            let Some(model) = candidate.filter(|(guess, _)| within_bounds(config, guess)) else {
+                continue;
+            };
+
+            let score = model_score(all_lines, active.len(), &model, config);
+            if model.1.len() >= config.min_lines && score < best_score {
+                best_score = score;
+                (best_model_pos, best_inliers_indices) = model;
+
+                // 更新自适应迭代次数
+                let inlier_ratio = best_inliers_indices.len() as f64 / active.len() as f64;
+                stats.required_iterations = required_iterations(
+                    inlier_ratio,
+                    config.confidence,
+                    3,
+                    config.max_iterations,
+                );
+            }
+        }
+        if let Some(progress) = progress.filter(|_| stats.iterations_run >= next_report) {
+            next_report = stats.iterations_run + progress.interval;
+            if progress.ransac_progress(stats.iterations_run).is_break() {
+                break;
+            }
+        }
+    }
+
+    #[cfg(feature = "trace")]
+    tracing::debug!(
+        exhaustive = false,
+        iterations = stats.iterations_run,
+        required_iterations = stats.required_iterations,
+        degenerate = stats.degenerate_samples_rejected,
+        inliers = best_inliers_indices.len(),
+        "RANSAC 结束"
+    );
+    if best_inliers_indices.len() >= config.min_lines {
+        (Some((best_model_pos, best_inliers_indices)), stats)
+    } else {
+        (None, stats)
+    }
+}
+
+/// LMedS 稳健尺度的一致性系数：高斯噪声下 1.4826 × 残差中位数为 σ 的估计
+const LMEDS_SCALE_FACTOR: f64 = 1.4826;
+/// LMedS 由稳健尺度导出内点阈值时取的 σ 倍数
+const LMEDS_INLIER_SIGMAS: f64 = 2.5;
+/// LMedS 导出阈值的下限，避免无噪声数据下阈值退化为 0
+const LMEDS_MIN_THRESHOLD: f64 = 1e-6;
+
+/// 最小中位数平方（LMedS）拟合光线集合，不需要预先给定内点阈值
+///
+/// 与 RANSAC 相同地抽样（或穷举）三元组并跳过退化样本，但以全部光线残差平方的中位数
+/// 为候选点评分，取中位数最小者；再由稳健尺度 σ = 1.4826 × √中位数 导出阈值 2.5σ，
+/// 按该阈值统计内点（每个测量站保留最近的一条）。`config.threshold` 不参与计算。
+///
+/// 固定运行 `max_iterations` 次（不自适应终止）。中位数要求目标的光线占输入的一半以上，
+/// 多目标场景下每轮剩余光线中最大的目标须过半，否则得到的是几个目标之间的折中点。
+pub fn lmeds_fit_lines(all_lines: &[Line], config: &RansacConfig) -> (Option<RansacModel>, RansacStats) {
+    let active: Vec<usize> = (0..all_lines.len()).collect();
+    lmeds_fit_subset(all_lines, &active, config, &mut seeded_rng(config.seed))
+}
+
+/// 只在 `active`（升序、不重复）列出的光线上运行 `lmeds_fit_lines`，内点为 `all_lines` 中的索引
+///
+/// 随机数取自 `rng`（忽略 `config.seed`）。
+pub fn lmeds_fit_subset(
+    all_lines: &[Line],
+    active: &[usize],
+    config: &RansacConfig,
+    rng: &mut dyn RandomSource,
+) -> (Option<RansacModel>, RansacStats) {
+    let mut stats = RansacStats {
+        iterations_run: 0,
+        required_iterations: config.max_iterations,
+        degenerate_samples_rejected: 0,
+    };
+    if active.len() < 3 {
+        return (None, stats);
+    }
+
+    let candidates = if active.len() <= config.exhaustive_max_lines {
+        map_ordered(exhaustive_samples(active.len()), |sample| {
+            let guess = sample_guess(all_lines, config, sample.map(|k| active[k]));
+            (guess, usize::from(guess.is_none()))
+        })
+    } else {
+        (0..config.max_iterations)
+            .map(|_| random_sample_guess(all_lines, active, config, rng))
+            .collect()
+    };
+    stats.iterations_run = candidates.len();
+    stats.required_iterations = candidates.len();
+
+    let mut best: Option<(f64, Point3<f64>)> = None;
+    for (guess, rejected) in candidates {
+        stats.degenerate_samples_rejected += rejected;
+        let Some(guess) = guess.filter(|guess| within_bounds(config, guess)) else {
+            continue;
+        };
+        let median = median_squared_residual(all_lines, active, &guess, config);
+        if best.is_none_or(|(best_median, _)| median < best_median) {
+            best = Some((median, guess));
+        }
+    }
+    let Some((median, position)) = best else {
+        return (None, stats);
+    };
+
+    let threshold = (LMEDS_INLIER_SIGMAS * LMEDS_SCALE_FACTOR * median.sqrt()).max(LMEDS_MIN_THRESHOLD);
+    let inlier_config = RansacConfig { threshold, ..config.clone() };
+    let inliers = collect_inliers_in(all_lines, active, &position, &inlier_config, None);
+    if inliers.len() >= config.min_lines {
+        (Some((position, inliers)), stats)
+    } else {
+        (None, stats)
+    }
+}
+
+/// `active` 中全部光线到候选点残差平方的中位数（偶数个时取较大的中间值）
+fn median_squared_residual(all_lines: &[Line], active: &[usize], point: &Point3<f64>, config: &RansacConfig) -> f64 {
+    let mut squared: Vec<f64> = active
+        .iter()
+        .map(|&i| config.residual_model.residual(&all_lines[i], point, config.ray_mode).norm_squared())
+        .collect();
+    let mid = squared.len() / 2;
+    *squared.select_nth_unstable_by(mid, f64::total_cmp).1
+}
+
+/// 贪心提取每轮使用的稳健估计方法
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RobustEstimator {
+    /// RANSAC，按 `ransac.threshold` 判定内点，见 `ransac_fit_lines`
+    #[default]
+    Ransac,
+    /// 最小中位数平方，内点阈值由数据导出，见 `lmeds_fit_lines`。
+    /// 合并、重新分配等后续步骤仍使用 `ransac.threshold`
+    Lmeds,
+}
+
+/// EM 软分配精化的参数，见 `refine_targets_em`
+///
+/// `temperature` 与 `clutter_distance` 以 `ransac.threshold` 为单位，因此同样适用于角度残差模型。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EmConfig {
+    pub max_iterations: usize, // E/M 交替的最大轮数，默认 20
+    pub temperature: f64,      // softmax 温度：光线对目标的对数得分为 -(d / T)² / 2，默认 0.3；过大时相近的目标会合并
+    pub clutter_distance: f64, // 背景（杂波）类的等效距离：最终分配时与所有目标都远于该值的光线不属于任何目标，默认 1.0
+    pub tolerance: f64,        // 相邻两轮责任（responsibility）的最大变化小于该值时停止，默认 1e-3
+}
+
+impl Default for EmConfig {
+    fn default() -> Self {
+        EmConfig {
+            max_iterations: 20,
+            temperature: 0.3,
+            clutter_distance: 1.0,
+            tolerance: 1e-3,
+        }
+    }
+}
+
+/// `find_targets_clustered` 预分组阶段的 DBSCAN 参数
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DbscanConfig {
+    pub eps_m: f64,       // 邻域半径（米），`FindTargetsConfig::new` 中取 RANSAC 阈值的一半；角度残差模型下须另行设置
+    pub min_pts: usize,   // 核心点的邻域内（含自身）至少的交会点数，默认 5（4 条光线两两交会得到 6 个点）
+    pub max_pairs: usize, // 光线对数超过该值时随机抽取这么多对，而不是穷举全部光线对，默认 100 万
+}
+
+/// find_targets 的完整参数
+#[derive(Debug, Clone)]
+pub struct FindTargetsConfig {
+    pub estimator: RobustEstimator, // 贪心提取使用的稳健估计方法
+    pub ransac: RansacConfig, // RANSAC 参数（阈值、最少内点数等）
+    pub lm: LmConfig,         // LM 优化参数
+    pub optimizer: Optimizer, // 精化使用的优化方法，默认 LM；两者共用 `lm` 中的参数
+    pub motion_model: MotionModel, // 目标的运动模型，默认静止
+    pub reassignment_passes: usize, // 贪心提取后全局重新分配光线的最大轮数，0 表示不启用
+    pub altitude_prior: Option<(f64, f64)>, // 目标高度先验 (z, σ)（米，σ > 0），作为额外残差行 (z - z_prior)/σ 加入 LM
+    pub fixed_altitude: Option<f64>, // 已知的目标高度 z（米），LM 只优化 x、y；按区域取不同高度时可自定义 `Refiner`
+    pub min_separation_m: f64, // 距离小于该值的定位结果合并为一个目标，0 表示不合并
+    pub max_targets: Option<usize>, // 贪心提取的目标数上限
+    pub max_rounds: Option<usize>,  // 贪心提取的 RANSAC 轮数上限（含被拒绝的轮）
+    pub min_inlier_quality: Option<f64>, // 一致集质量 1 - RMS 残差 / 阈值的下限，低于该值时停止提取
+    pub output_ordering: OutputOrdering, // 定位结果的排列顺序
+    pub id_prefix: String, // 目标标识的前缀，标识为前缀加编号，默认 "Target_"
+    pub out_of_bounds: OutOfBounds, // 精化后的位置超出 `ransac.bounds` 时的处理方式
+    pub min_geometry: Option<GeometryCriterion>, // 一致集的交会几何下限，不满足的一致集不构成目标
+    pub bearing_only_max_angle_rad: f64, // `find_targets_extended` 中仅方位聚类内光线两两夹角的上限（弧度）
+    pub measurement_sigma: Option<f64>, // 测向噪声每个角度分量的 1σ（弧度，σ > 0），设置时对每个目标做 χ² 一致性检验
+    pub chi_square_quantile: f64, // χ² 检验的分位数，默认 0.99
+    pub outlier_sigma: f64,       // 标准化残差超过该值的内点光线被标记为离群，默认 3.0
+    pub refit_without_outliers: bool, // 剔除被标记的光线后重新运行一次 LM，默认 false
+    pub bootstrap: Option<BootstrapConfig>, // 设置时为每个目标计算 `LocatedTarget::bootstrap`（每个目标多运行 num_resamples 次短 LM）
+    pub em: Option<EmConfig>, // 设置时在合并、重新分配之后运行 EM 软分配精化，见 `refine_targets_em`
+    pub jlinkage_hypotheses: usize, // `find_targets_jlinkage` 生成的假设数，默认 500
+    pub dbscan: DbscanConfig, // `find_targets_clustered` 的预分组参数
+    pub progress: Option<ProgressCallback>, // 进度回调，返回 `ControlFlow::Break` 时取消定位，见 `with_progress`
+    pub progress_interval: usize, // 内置 RANSAC 每运行这么多次迭代调用一次进度回调，默认 100
+}
+
+/// 目标的运动模型，见 `motion` 模块
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MotionModel {
+    /// 静止（默认）：所有光线交会于同一点，忽略测量时刻
+    #[default]
+    Static,
+    /// 匀速：求解参考时刻的位置与速度（6 个参数），每条光线的残差在目标外推到其测量时刻（`Line::time`）的位置处计算。
+    /// 参考时刻为内点测量时刻的平均值，记入 `LocatedTarget::timestamp`，速度记入 `LocatedTarget::velocity`。
+    /// 一致集估计总是使用按该模型抽样的 RANSAC（`estimator` 不适用），精化使用 `lm` 的参数，
+    /// 不支持高度先验、固定高度、dogleg 与多起点；合并、重新分配、EM 精化与 χ² 一致性检验基于静止模型，此模式下不运行。
+    /// 同一测量站在不同时刻的多条光线都可以是同一目标的内点
+    ConstantVelocity,
+}
+
+/// 精化目标位置的优化方法
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Optimizer {
+    /// Levenberg-Marquardt（默认），见 `levenberg_marquardt_optimize_detailed`
+    #[default]
+    LevenbergMarquardt,
+    /// 信赖域 dogleg，见 `dogleg_optimize`
+    Dogleg,
+}
+
+/// 一致集交会几何的接受条件
+///
+/// 所有光线以掠射角交会（测量站大致与目标共线）时沿视线方向几乎不可观测，
+/// 这类一致集往往是虚假的。不满足条件的一致集被拒绝，其光线暂不参与下一轮，
+/// 下一个目标被接受后重新放回，供之后更好的假设使用。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GeometryCriterion {
+    /// 内点光线两两交会角（0 ~ π/2，弧度）的最大值须不小于该值
+    MinCrossingAngle(f64),
+    /// Σ(I - d dᵀ) 的最小特征值须不小于该值；n 条方向均匀分布的光线约为 2n/3，光线全部平行时为 0
+    MinEigenvalue(f64),
+}
+
+impl GeometryCriterion {
+    /// 光线集合是否满足条件
+    pub fn accepts(&self, lines: &[Line]) -> bool {
+        match *self {
+            GeometryCriterion::MinCrossingAngle(min_angle) => {
+                let max_angle = lines
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(i, a)| lines[i + 1..].iter().map(move |b| a.direction.dot(&b.direction).abs()))
+                    .map(|cos| cos.min(1.0).acos())
+                    .fold(0.0, f64::max);
+                max_angle >= min_angle
+            }
+            GeometryCriterion::MinEigenvalue(min_eigenvalue) => {
+                let a: Matrix3<f64> = lines.iter().map(perpendicular_projector).sum();
+                a.symmetric_eigenvalues().min() >= min_eigenvalue
+            }
+        }
+    }
+}
+
+/// 精化后的目标位置超出关注区域（`RansacConfig::bounds`）时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutOfBounds {
+    /// 丢弃该目标（默认），其光线不再参与后续提取
+    #[default]
+    Discard,
+    /// 将位置截断到区域边界，残差等统计量按截断后的位置计算
+    Clamp,
+}
+
+/// 定位结果的排列顺序
+///
+/// 默认的 `Discovery` 与此前的行为相同。其余顺序只取决于定位结果本身，不取决于 RANSAC
+/// 的发现顺序；`find_targets` 系列函数排序后按新顺序重新编号为 `Target_1`、`Target_2`……，
+/// 相同输入、相同顺序下标识一致（`TargetLocator` 保留跨帧的标识，只排序不重新编号）。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputOrdering {
+    /// 按贪心提取发现的先后（默认），标识为发现时分配的编号
+    #[default]
+    Discovery,
+    /// 按光线数从多到少，相同时按位置
+    ByNumLinesDesc,
+    /// 按 `confidence` 从高到低，相同时按位置
+    ByConfidenceDesc,
+    /// 按位置 (x, y, z) 字典序升序
+    ByPosition,
+}
+
+/// 按位置 (x, y, z) 字典序比较
+fn compare_positions(a: &LocatedTarget, b: &LocatedTarget) -> std::cmp::Ordering {
+    a.position
+        .x
+        .total_cmp(&b.position.x)
+        .then(a.position.y.total_cmp(&b.position.y))
+        .then(a.position.z.total_cmp(&b.position.z))
+}
+
+/// 按 `ordering` 排列定位结果（不改变标识）
+pub(crate) fn order_targets(targets: &mut [LocatedTarget], ordering: OutputOrdering) {
+    match ordering {
+        OutputOrdering::Discovery => {}
+        OutputOrdering::ByNumLinesDesc => {
+            targets.sort_by(|a, b| b.num_lines.cmp(&a.num_lines).then_with(|| compare_positions(a, b)))
+        }
+        OutputOrdering::ByConfidenceDesc => {
+            targets.sort_by(|a, b| b.confidence.total_cmp(&a.confidence).then_with(|| compare_positions(a, b)))
+        }
+        OutputOrdering::ByPosition => targets.sort_by(compare_positions),
+    }
+}
+
+impl FindTargetsConfig {
+    /// 检查参数，目前为 LM 的鲁棒损失（见 `LmConfig::validate`）
+    pub fn validate(&self) -> Result<(), OptiRadarError> {
+        self.lm.validate()
+    }
+
+    /// 以给定阈值和最少光线数创建默认参数（RANSAC 上限 100 次，LM 200 次）
+    pub fn new(ransac_threshold_m: f64, min_lines_per_target: usize) -> Self {
+        FindTargetsConfig {
+            estimator: RobustEstimator::Ransac,
+            ransac: RansacConfig::new(100, ransac_threshold_m, min_lines_per_target),
+            lm: LmConfig::default(),
+            optimizer: Optimizer::LevenbergMarquardt,
+            motion_model: MotionModel::Static,
+            reassignment_passes: 0,
+            altitude_prior: None,
+            fixed_altitude: None,
+            min_separation_m: 0.0,
+            max_targets: None,
+            max_rounds: None,
+            min_inlier_quality: None,
+            output_ordering: OutputOrdering::Discovery,
+            id_prefix: "Target_".to_string(),
+            out_of_bounds: OutOfBounds::Discard,
+            min_geometry: None,
+            bearing_only_max_angle_rad: 0.02,
+            measurement_sigma: None,
+            chi_square_quantile: 0.99,
+            outlier_sigma: 3.0,
+            refit_without_outliers: false,
+            bootstrap: None,
+            em: None,
+            jlinkage_hypotheses: 500,
+            dbscan: DbscanConfig {
+                eps_m: 0.5 * ransac_threshold_m,
+                min_pts: 5,
+                max_pairs: 1_000_000,
+            },
+            progress: None,
+            progress_interval: 100,
+        }
+    }
+
+    /// 设置目标标识的前缀，例如 "UAV_" 得到 `UAV_1`、`UAV_2`……
+    pub fn with_id_prefix(mut self, id_prefix: impl Into<String>) -> Self {
+        self.id_prefix = id_prefix.into();
+        self
+    }
+
+    /// 编号为 `index` 的目标标识
+    pub fn target_id(&self, index: usize) -> String {
+        format!("{}{}", self.id_prefix, index)
+    }
+
+    /// 同时设置 RANSAC 和 LM 的射线模式
+    pub fn with_ray_mode(mut self, ray_mode: bool) -> Self {
+        self.ransac.ray_mode = ray_mode;
+        self.lm.ray_mode = ray_mode;
+        self
+    }
+
+    /// 设置贪心提取使用的稳健估计方法
+    pub fn with_estimator(mut self, estimator: RobustEstimator) -> Self {
+        self.estimator = estimator;
+        self
+    }
+
+    /// 设置关注区域及精化结果超出区域时的处理方式
+    pub fn with_bounds(mut self, bounds: BoundingBox, out_of_bounds: OutOfBounds) -> Self {
+        self.ransac.bounds = Some(bounds);
+        self.out_of_bounds = out_of_bounds;
+        self
+    }
+
+    /// 设置 RANSAC 候选模型的评分方式
+    pub fn with_scoring(mut self, scoring: RansacScoring) -> Self {
+        self.ransac.scoring = scoring;
+        self
+    }
+
+    /// 为每个目标附加 bootstrap 不确定度统计量
+    pub fn with_bootstrap(mut self, bootstrap: BootstrapConfig) -> Self {
+        self.bootstrap = Some(bootstrap);
+        self
+    }
+
+    /// 贪心提取后运行 EM 软分配精化
+    pub fn with_em(mut self, em: EmConfig) -> Self {
+        self.em = Some(em);
+        self
+    }
+
+    /// 设置进度回调：每轮贪心提取开始时及内置 RANSAC 每 `progress_interval` 次迭代调用一次，
+    /// 返回 `ControlFlow::Break(())` 时取消定位，返回已找到的目标（`StopReason::Cancelled`）
+    pub fn with_progress(
+        mut self,
+        callback: impl Fn(ProgressEvent) -> ControlFlow<()> + Send + Sync + 'static,
+    ) -> Self {
+        self.progress = Some(ProgressCallback::new(callback));
+        self
+    }
+
+    /// 同时设置 RANSAC 和 LM 的残差模型
+    ///
+    /// 角度模型下 `ransac.threshold` 及鲁棒损失参数的单位均为弧度。
+    pub fn with_residual_model(mut self, residual_model: ResidualModel) -> Self {
+        self.ransac.residual_model = residual_model;
+        self.lm.residual_model = residual_model;
+        self
+    }
+}
+
+// --- 可替换的一致集估计与精化策略 ---
+//
+// 贪心提取的每一轮先由 `ConsensusEstimator` 在剩余光线中找出一个一致集，
+// 再由 `Refiner` 以内点光线精化目标位置。`find_targets_with_config` 按配置使用
+// 内置的 RANSAC / LMedS 与 LM；`find_targets_with_strategies` 可传入自定义实现。
+
+/// 一致集估计：在光线集合中找出一个目标的候选位置及其内点
+pub trait ConsensusEstimator {
+    /// 返回候选位置与内点在 `lines` 中的索引（升序、不重复），找不到一致集时返回 None
+    ///
+    /// `rng` 由调用方提供，贪心提取的各轮共用同一个发生器：设置 `ransac.seed` 时随机数序列确定。
+    /// 内点少于 `ransac.min_lines` 的结果视为没有找到一致集。
+    fn estimate(&self, lines: &[Line], rng: &mut dyn RandomSource) -> Option<RansacModel>;
+
+    /// 同 `estimate`，并返回抽样统计（记入 `FindTargetsDiagnostics`）；默认统计全为 0
+    fn estimate_with_stats(&self, lines: &[Line], rng: &mut dyn RandomSource) -> (Option<RansacModel>, RansacStats) {
+        (self.estimate(lines, rng), RansacStats::default())
+    }
+
+    /// 只在 `active`（`lines` 中的索引，升序、不重复）列出的光线上估计，内点为 `lines` 中的索引
+    ///
+    /// `find_targets` 每轮通过此方法在未使用的光线上估计。默认实现复制出这些光线后调用
+    /// `estimate_with_stats` 再映射回原索引；内置实现直接按索引访问，不复制光线。
+    fn estimate_subset(
+        &self,
+        lines: &[Line],
+        active: &[usize],
+        rng: &mut dyn RandomSource,
+    ) -> (Option<RansacModel>, RansacStats) {
+        let subset: Vec<Line> = active.iter().map(|&i| lines[i]).collect();
+        let (model, stats) = self.estimate_with_stats(&subset, rng);
+        let model = model.map(|(position, inliers)| (position, inliers.into_iter().map(|i| active[i]).collect()));
+        (model, stats)
+    }
+}
+
+/// 位置精化：由内点光线和初值求目标位置
+pub trait Refiner {
+    /// `initial` 为内点的闭式解（奇异时为一致集估计给出的候选位置）
+    fn refine(&self, lines: &[Line], initial: Point3<f64>) -> LmReport;
+}
+
+impl<T: ConsensusEstimator + ?Sized> ConsensusEstimator for &T {
+    fn estimate(&self, lines: &[Line], rng: &mut dyn RandomSource) -> Option<RansacModel> {
+        (**self).estimate(lines, rng)
+    }
+
+    fn estimate_with_stats(&self, lines: &[Line], rng: &mut dyn RandomSource) -> (Option<RansacModel>, RansacStats) {
+        (**self).estimate_with_stats(lines, rng)
+    }
+
+    fn estimate_subset(
+        &self,
+        lines: &[Line],
+        active: &[usize],
+        rng: &mut dyn RandomSource,
+    ) -> (Option<RansacModel>, RansacStats) {
+        (**self).estimate_subset(lines, active, rng)
+    }
+}
+
+impl<T: Refiner + ?Sized> Refiner for &T {
+    fn refine(&self, lines: &[Line], initial: Point3<f64>) -> LmReport {
+        (**self).refine(lines, initial)
+    }
+}
+
+/// 内置 RANSAC 估计（`ransac_fit_lines`），随机数取自 `rng`
+#[derive(Debug, Clone)]
+pub struct RansacEstimator(pub RansacConfig);
+
+impl ConsensusEstimator for RansacEstimator {
+    fn estimate(&self, lines: &[Line], rng: &mut dyn RandomSource) -> Option<RansacModel> {
+        self.estimate_with_stats(lines, rng).0
+    }
+
+    fn estimate_with_stats(&self, lines: &[Line], rng: &mut dyn RandomSource) -> (Option<RansacModel>, RansacStats) {
+        ransac_fit_lines_with_rng(lines, &self.0, rng)
+    }
+
+    fn estimate_subset(
+        &self,
+        lines: &[Line],
+        active: &[usize],
+        rng: &mut dyn RandomSource,
+    ) -> (Option<RansacModel>, RansacStats) {
+        ransac_fit_subset(lines, active, &self.0, rng)
+    }
+}
+
+/// `Pipeline::new` 使用的内置 RANSAC：为首次传入的全部光线建立列式存储（`LineSoa`），光线数超过
+/// `spatial_index_cutoff` 时另建网格索引，之后各轮复用（一次 `find_targets` 调用只建立一次），
+/// 结果与 `RansacEstimator` 相同
+struct IndexedRansacEstimator {
+    config: RansacConfig,
+    cache: RefCell<Option<LineCache>>,
+    progress: Rc<ProgressTracker>, // 与 `Pipeline::progress` 共享
+}
+
+/// 已建立的列式存储、网格索引及其对应光线切片的地址与长度；传入其他光线时重新建立
+struct LineCache {
+    lines: (*const Line, usize),
+    soa: LineSoa,
+    grid: Option<LineGrid>,
+}
+
+impl ConsensusEstimator for IndexedRansacEstimator {
+    fn estimate(&self, lines: &[Line], rng: &mut dyn RandomSource) -> Option<RansacModel> {
+        self.estimate_with_stats(lines, rng).0
+    }
+
+    fn estimate_with_stats(&self, lines: &[Line], rng: &mut dyn RandomSource) -> (Option<RansacModel>, RansacStats) {
+        ransac_fit_lines_with_rng(lines, &self.config, rng)
+    }
+
+    fn estimate_subset(
+        &self,
+        lines: &[Line],
+        active: &[usize],
+        rng: &mut dyn RandomSource,
+    ) -> (Option<RansacModel>, RansacStats) {
+        let key = (lines.as_ptr(), lines.len());
+        let mut cache = self.cache.borrow_mut();
+        let cached = match cache.take() {
+            Some(cached) if cached.lines == key => cached,
+            _ => LineCache {
+                lines: key,
+                soa: LineSoa::new(lines),
+                grid: (lines.len() > self.config.spatial_index_cutoff)
+                    .then(|| LineGrid::build(lines, &self.config))
+                    .flatten(),
+            },
+        };
+        let cached = cache.insert(cached);
+        let progress = self.progress.active();
+        ransac_fit_subset_indexed(lines, active, &self.config, rng, cached.grid.as_ref(), Some(&cached.soa), progress)
+    }
+}
+
+/// 内置 LMedS 估计（`lmeds_fit_lines`），随机数取自 `rng`
+#[derive(Debug, Clone)]
+pub struct LmedsEstimator(pub RansacConfig);
+
+impl ConsensusEstimator for LmedsEstimator {
+    fn estimate(&self, lines: &[Line], rng: &mut dyn RandomSource) -> Option<RansacModel> {
+        self.estimate_with_stats(lines, rng).0
+    }
+
+    fn estimate_with_stats(&self, lines: &[Line], rng: &mut dyn RandomSource) -> (Option<RansacModel>, RansacStats) {
+        let active: Vec<usize> = (0..lines.len()).collect();
+        lmeds_fit_subset(lines, &active, &self.0, rng)
+    }
+
+    fn estimate_subset(
+        &self,
+        lines: &[Line],
+        active: &[usize],
+        rng: &mut dyn RandomSource,
+    ) -> (Option<RansacModel>, RansacStats) {
+        lmeds_fit_subset(lines, active, &self.0, rng)
+    }
+}
+
+/// 内置 LM（或 dogleg）精化，可附加高度先验或固定高度
+/// （见 `FindTargetsConfig::altitude_prior`、`FindTargetsConfig::fixed_altitude`、`FindTargetsConfig::optimizer`）
+#[derive(Debug, Clone)]
+pub struct LmRefiner {
+    pub config: LmConfig,
+    pub altitude_prior: Option<(f64, f64)>,
+    pub fixed_altitude: Option<f64>,
+    pub optimizer: Optimizer,
+}
+
+impl Refiner for LmRefiner {
+    fn refine(&self, lines: &[Line], initial: Point3<f64>) -> LmReport {
+        let prior = self.altitude_prior.map(|(z, std)| PriorTerm::altitude(z, std));
+        match self.optimizer {
+            Optimizer::LevenbergMarquardt => {
+                lm_solve(lines, initial, &self.config, prior.as_ref(), self.fixed_altitude)
+            }
+            Optimizer::Dogleg => dogleg_solve(lines, initial, &self.config, prior.as_ref(), self.fixed_altitude),
+        }
+    }
+}
+
+/// 一次定位使用的参数及一致集估计、精化策略
+pub(crate) struct Pipeline<'a> {
+    pub config: &'a FindTargetsConfig,
+    pub estimator: Box<dyn ConsensusEstimator + 'a>,
+    pub refiner: Box<dyn Refiner + 'a>,
+    pub refine_time: Cell<Duration>, // 精化累计耗时
+    pub progress: Rc<ProgressTracker>, // 进度回调的状态
+}
+
+impl<'a> Pipeline<'a> {
+    /// 按配置使用内置的估计方法与 LM
+    pub fn new(config: &'a FindTargetsConfig) -> Self {
+        let progress = Rc::new(ProgressTracker::new(config));
+        let estimator: Box<dyn ConsensusEstimator> = match (config.motion_model, config.estimator) {
+            (MotionModel::ConstantVelocity, _) => Box::new(ConstantVelocityEstimator {
+                config: config.ransac.clone(),
+                progress: Rc::clone(&progress),
+            }),
+            (MotionModel::Static, RobustEstimator::Ransac) => Box::new(IndexedRansacEstimator {
+                config: config.ransac.clone(),
+                cache: RefCell::new(None),
+                progress: Rc::clone(&progress),
+            }),
+            (MotionModel::Static, RobustEstimator::Lmeds) => Box::new(LmedsEstimator(config.ransac.clone())),
+        };
+        Pipeline {
+            config,
+            estimator,
+            refiner: Box::new(LmRefiner {
+                config: config.lm.clone(),
+                altitude_prior: config.altitude_prior,
+                fixed_altitude: config.fixed_altitude,
+                optimizer: config.optimizer,
+            }),
+            refine_time: Cell::new(Duration::ZERO),
+            progress,
+        }
+    }
+}
+
+/// 用给定内点拟合单个目标，并计算残差、协方差等统计量
+///
+/// 精化以内点的闭式解为初值，奇异时退回 `fallback_start`。
+/// 设置 `measurement_sigma` 时做 χ² 一致性检验（见 `check_consistency`）；
+/// 启用 `refit_without_outliers` 且有被标记的光线时，剔除这些光线后重新拟合一次，
+/// 剩余光线少于 `ransac.min_lines` 时保留原结果。设置 `bootstrap` 时最后对内点光线做 bootstrap。
+pub(crate) fn fit_target(
+    index: usize,
+    all_lines: &[Line],
+    inlier_indices: Vec<usize>,
+    fallback_start: Point3<f64>,
+    pipeline: &Pipeline,
+    station_names: &[String],
+) -> LocatedTarget {
+    let mut target = fit_checked_target(index, all_lines, inlier_indices, fallback_start, pipeline, station_names);
+    if let Some(bootstrap) = pipeline.config.bootstrap {
+        let lines: Vec<Line> = target.inlier_indices.iter().map(|&i| all_lines[i]).collect();
+        let lm = LmConfig {
+            iterations: bootstrap.lm_iterations,
+            ..pipeline.config.lm.clone()
+        };
+        let mut rng = seeded_rng(bootstrap.seed.map(|seed| seed.wrapping_add(index as u64)));
+        target.bootstrap =
+            bootstrap_uncertainty_with_config(&lines, target.position, bootstrap.num_resamples, &lm, &mut rng);
+    }
+    target
+}
+
+/// `fit_target` 中的拟合、一致性检验与剔除离群光线后的重新拟合
+fn fit_checked_target(
+    index: usize,
+    all_lines: &[Line],
+    inlier_indices: Vec<usize>,
+    fallback_start: Point3<f64>,
+    pipeline: &Pipeline,
+    station_names: &[String],
+) -> LocatedTarget {
+    let config = pipeline.config;
+    let mut target = fit_inliers(index, all_lines, inlier_indices, fallback_start, pipeline, station_names);
+    let Some(sigma) = config.measurement_sigma.filter(|_| config.motion_model == MotionModel::Static) else {
+        return target;
+    };
+    check_consistency(&mut target, all_lines, sigma, pipeline);
+    if !config.refit_without_outliers || target.outlier_indices.is_empty() {
+        return target;
+    }
+    let kept: Vec<usize> =
+        target.inlier_indices.iter().copied().filter(|i| !target.outlier_indices.contains(i)).collect();
+    if kept.len() < config.ransac.min_lines {
+        return target;
+    }
+    let mut refit = fit_inliers(index, all_lines, kept, target.position, pipeline, station_names);
+    check_consistency(&mut refit, all_lines, sigma, pipeline);
+    refit.outlier_indices.extend(target.outlier_indices);
+    refit.outlier_indices.sort_unstable();
+    refit
+}
+
+/// `fit_target` 的单次拟合，不做一致性检验
+fn fit_inliers(
+    index: usize,
+    all_lines: &[Line],
+    inlier_indices: Vec<usize>,
+    fallback_start: Point3<f64>,
+    pipeline: &Pipeline,
+    station_names: &[String],
+) -> LocatedTarget {
+    let config = pipeline.config;
+    let target_lines: Vec<_> = inlier_indices.iter().map(|&i| all_lines[i]).collect();
+
+    // LM 优化；匀速模型下同时求参考时刻（内点测量时刻的平均）的速度
+    let started = Instant::now();
+    let (report, motion) = match config.motion_model {
+        MotionModel::Static => {
+            let lm_start = linear_triangulate(&target_lines).unwrap_or(fallback_start);
+            (pipeline.refiner.refine(&target_lines, lm_start), None)
+        }
+        MotionModel::ConstantVelocity => {
+            let reference_time = motion::mean_time(&target_lines);
+            let (start, start_velocity) = motion::linear_triangulate_constant_velocity(&target_lines, reference_time)
+                .unwrap_or((fallback_start, Vector3::zeros()));
+            let (report, velocity) =
+                motion::constant_velocity_optimize(&target_lines, start, start_velocity, reference_time, &config.lm);
+            (report, Some((velocity, reference_time)))
+        }
+    };
+    pipeline.refine_time.set(pipeline.refine_time.get() + started.elapsed());
+    let final_pos = match (config.ransac.bounds, config.out_of_bounds) {
+        (Some(bounds), OutOfBounds::Clamp) => bounds.clamp(&report.position),
+        _ => report.position,
+    };
+
+    // 计算加权平均残差及每条光线的残差
+    let residuals = match motion {
+        None => line_distances(
+            &target_lines,
+            &final_pos,
+            config.lm.ray_mode,
+            config.ransac.parallel_cutoff,
+        ),
+        Some((velocity, reference_time)) => {
+            motion::line_distances(&target_lines, &final_pos, &velocity, reference_time, config.lm.ray_mode)
+        }
+    };
+    let avg_error_dist = weighted_rms(target_lines.iter().zip(&residuals).map(|(line, &r)| (line.weight, r)));
+    let max_residual = residuals.iter().copied().fold(0.0, f64::max);
+
+    let mut stations: Vec<String> = Vec::new();
+    for line in &target_lines {
+        if let Some(station) = line.station {
+            if !stations.contains(&station_names[station]) {
+                stations.push(station_names[station].clone());
+            }
+        }
+    }
+
+    let dop = geometry_dop(&target_lines);
+    let mut target = LocatedTarget {
+        id: config.target_id(index),
+        index,
+        position: final_pos,
+        num_lines: target_lines.len(),
+        avg_error_dist_m: avg_error_dist,
+        covariance: match (motion, config.fixed_altitude) {
+            (Some((velocity, reference_time)), _) => {
+                motion::estimate_constant_velocity_covariance(&target_lines, final_pos, velocity, reference_time)
+                    .map(|covariance| covariance.fixed_view::<3, 3>(0, 0).into())
+            }
+            (None, Some(_)) => estimate_horizontal_covariance(&target_lines, final_pos),
+            (None, None) => estimate_covariance(&target_lines, final_pos),
+        },
+        inlier_indices,
+        residuals,
+        max_residual_m: max_residual,
+        stations,
+        converged: report.converged,
+        final_cost: report.final_cost,
+        lm_iterations: report.iterations_used,
+        geometry_dop: dop.total,
+        horizontal_dop: dop.horizontal,
+        vertical_dop: dop.vertical,
+        timestamp: motion.map(|(_, reference_time)| reference_time),
+        low_confidence: target_lines.len() < 3,
+        confidence: 0.0,
+        chi_square: None,
+        inconsistent: false,
+        outlier_indices: Vec::new(),
+        degenerate: report.degenerate,
+        bootstrap: None,
+        velocity: motion.map(|(velocity, _)| velocity),
+    };
+    target.confidence = target_confidence(&target_lines, consensus_quality(all_lines, &target, config));
+    target
+}
+
+/// 按测角噪声 `sigma` 检验目标残差，填写 `chi_square`、`inconsistent` 与 `outlier_indices`
+///
+/// 每条内点光线的标准化残差为 √w·θ/σ，θ 为测量方向与指向目标方向的夹角（见 `ResidualModel::Angular`），
+/// w 为光线权重（视为测角方差的倒数之比）。χ² 为标准化残差的平方和，每条光线贡献 2 个自由度，
+/// 减去估计的参数个数（3，固定高度时为 2）。噪声与 σ 相符时 χ² 服从该自由度的 χ² 分布，
+/// 超过 `chi_square_quantile` 分位数的目标标记为 `inconsistent`；自由度不为正时不做检验。
+///
+/// 最小二乘会把单条错误光线的偏差分摊到其余光线上，一次按阈值标记会波及正常光线，
+/// 因此离群光线逐条识别：标记标准化残差最大且超过 `outlier_sigma` 的一条，
+/// 去掉它重新精化后再检查，直到没有超限的光线或剩余光线少于 2 条。
+fn check_consistency(target: &mut LocatedTarget, all_lines: &[Line], sigma: f64, pipeline: &Pipeline) {
+    let config = pipeline.config;
+    let standardized = |position: &Point3<f64>, i: usize| {
+        let line = &all_lines[i];
+        let angle = ResidualModel::Angular.residual(line, position, config.lm.ray_mode).norm();
+        line.weight.sqrt() * angle / sigma
+    };
+
+    let mut remaining = target.inlier_indices.clone();
+    let mut position = target.position;
+    let mut outliers = Vec::new();
+    while let Some((k, z)) =
+        remaining.iter().map(|&i| standardized(&position, i)).enumerate().max_by(|a, b| a.1.total_cmp(&b.1))
+    {
+        if z <= config.outlier_sigma {
+            break;
+        }
+        outliers.push(remaining.remove(k));
+        if remaining.len() < 2 {
+            break;
+        }
+        let lines: Vec<Line> = remaining.iter().map(|&i| all_lines[i]).collect();
+        position = pipeline.refiner.refine(&lines, position).position;
+    }
+    outliers.sort_unstable();
+    target.outlier_indices = outliers;
+
+    let parameters = if config.fixed_altitude.is_some() { 2 } else { 3 };
+    let dof = 2 * target.inlier_indices.len() as i64 - parameters;
+    if dof <= 0 {
+        target.chi_square = None;
+        target.inconsistent = false;
+        return;
+    }
+    let chi_square: f64 = target.inlier_indices.iter().map(|&i| standardized(&target.position, i).powi(2)).sum();
+    let statistic = chi_square / dof as f64;
+    target.chi_square = Some(statistic);
+    target.inconsistent = statistic > chi_square_quantile(dof as f64, config.chi_square_quantile) / dof as f64;
+}
+
+/// 自由度为 `dof` 的 χ² 分布的 `p` 分位数（Wilson–Hilferty 近似，自由度 ≥ 3 时相对误差约 1% 以内）
+fn chi_square_quantile(dof: f64, p: f64) -> f64 {
+    let a = 2.0 / (9.0 * dof);
+    let cube = 1.0 - a + normal_quantile(p) * a.sqrt();
+    dof * cube.max(0.0).powi(3)
+}
+
+/// 标准正态分布的 `p` 分位数（Acklam 有理逼近，相对误差约 1e-9），`p` 不在 (0, 1) 内时返回 ±∞ 或 NaN
+fn normal_quantile(p: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969683028665376e1,
+        2.209460984245205e2,
+        -2.759285104469687e2,
+        1.38357751867269e2,
+        -3.066479806614716e1,
+        2.506628277459239,
+    ];
+    const B: [f64; 5] = [
+        -5.447609879822406e1,
+        1.615858368580409e2,
+        -1.556989798598866e2,
+        6.680131188771972e1,
+        -1.328068155288572e1,
+    ];
+    const C: [f64; 6] = [
+        -7.784894002430293e-3,
+        -3.223964580411365e-1,
+        -2.400758277161838,
+        -2.549732539343734,
+        4.374664141464968,
+        2.938163982698783,
+    ];
+    const D: [f64; 4] = [7.784695709041462e-3, 3.224671290700398e-1, 2.445134137142996, 3.754408661907416];
+    const P_LOW: f64 = 0.02425;
+    if p.is_nan() || !(0.0..=1.0).contains(&p) {
+        return f64::NAN;
+    }
+    if p == 0.0 {
+        return f64::NEG_INFINITY;
+    }
+    if p == 1.0 {
+        return f64::INFINITY;
+    }
+    let tail = |q: f64| {
+        let q = (-2.0 * q.ln()).sqrt();
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    };
+    if p < P_LOW {
+        tail(p)
+    } else if p > 1.0 - P_LOW {
+        -tail(1.0 - p)
+    } else {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    }
+}
+
+/// 目标的综合可信度，取值 [0, 1]，越大越可信
+///
+/// 三项之积：
+/// - 光线数 n：1 - exp(-(n - 2) / 4)，2 条光线为 0，3 条约 0.22，12 条约 0.92；
+/// - 残差：一致集质量 1 - RMS / 阈值（见 `consensus_quality`），截断到 [0, 1]；
+/// - 几何：1.5 × λmin(Σ(I - d dᵀ) / n)，截断到 [0, 1]。方向两两正交或均匀分布时为 1，
+///   交会角越小越接近 0。
+fn target_confidence(lines: &[Line], quality: f64) -> f64 {
+    if lines.is_empty() {
+        return 0.0;
+    }
+    let n = lines.len() as f64;
+    let support = 1.0 - (-(n - 2.0) / 4.0).exp();
+    let residual = quality.clamp(0.0, 1.0);
+    let a: Matrix3<f64> = lines.iter().map(perpendicular_projector).sum();
+    let geometry = (1.5 * a.symmetric_eigenvalues().min() / n).clamp(0.0, 1.0);
+    (support.max(0.0) * residual * geometry).clamp(0.0, 1.0)
+}
+
+/// 贪心提取后的全局重新分配
+///
+/// 将每条光线分配给阈值内（且测距一致，见 `range_consistent`）最近的已定位目标（同一目标每个测量站只保留最近的一条），
+/// 按新分配重新运行 LM，直到分配不再变化或达到 `reassignment_passes` 轮。
+/// 光线数低于 `min_lines` 的目标被丢弃。
+fn reassign_lines(
+    all_lines: &[Line],
+    mut targets: Vec<LocatedTarget>,
+    pipeline: &Pipeline,
+    station_names: &[String],
+) -> Vec<LocatedTarget> {
+    let config = pipeline.config;
+    for _ in 0..config.reassignment_passes {
+        let mut candidates: Vec<Vec<(usize, f64)>> = vec![Vec::new(); targets.len()];
+        for (i, line) in all_lines.iter().enumerate() {
+            let nearest = targets
+                .iter()
+                .enumerate()
+                .map(|(t, target)| {
+                    let r = config.ransac.residual_model.residual(line, &target.position, config.lm.ray_mode);
+                    (t, r.norm())
+                })
+                .filter(|&(t, d)| {
+                    d < config.ransac.threshold && range_consistent(line, &targets[t].position, &config.ransac)
+                })
+                .min_by(|a, b| a.1.total_cmp(&b.1));
+            if let Some((t, d)) = nearest {
+                candidates[t].push((i, d));
+            }
+        }
+        let assignments: Vec<Vec<usize>> = candidates
+            .into_iter()
+            .map(|c| keep_closest_per_station(all_lines, c))
+            .collect();
+
+        if targets
+            .iter()
+            .zip(&assignments)
+            .all(|(target, assigned)| &target.inlier_indices == assigned)
+        {
+            break;
+        }
+
+        targets = targets
+            .into_iter()
+            .zip(assignments)
+            .filter(|(_, assigned)| assigned.len() >= config.ransac.min_lines)
+            .map(|(target, assigned)| {
+                fit_target(target.index, all_lines, assigned, target.position, pipeline, station_names)
+            })
+            .collect();
+    }
+    targets
+}
+
+/// 合并距离小于 `min_separation_m` 的定位结果
+///
+/// 每次合并当前距离最近的一对（距离相同时取索引较小的一对）：
+/// 两者内点取并集（同一测量站只保留到两者内点数加权平均位置最近的一条），
+/// 以并集重新运行 LM，结果沿用较早目标的标识和位置次序。
+/// 重复直到没有过近的目标对，因此三个及以上的目标可以级联合并，且结果确定。
+fn merge_close_targets(
+    all_lines: &[Line],
+    mut targets: Vec<LocatedTarget>,
+    pipeline: &Pipeline,
+    station_names: &[String],
+) -> Vec<LocatedTarget> {
+    let config = pipeline.config;
+    loop {
+        let mut closest: Option<(usize, usize, f64)> = None;
+        for i in 0..targets.len() {
+            for j in i + 1..targets.len() {
+                let distance = (targets[i].position - targets[j].position).norm();
+                if distance < config.min_separation_m && closest.is_none_or(|(_, _, d)| distance < d) {
+                    closest = Some((i, j, distance));
+                }
+            }
+        }
+        let Some((i, j, _)) = closest else {
+            return targets;
+        };
+
+        let second = targets.remove(j);
+        let first = &targets[i];
+        let (n1, n2) = (first.num_lines as f64, second.num_lines as f64);
+        let start = Point3::from((first.position.coords * n1 + second.position.coords * n2) / (n1 + n2));
+        let mut union: Vec<usize> = first.inlier_indices.iter().chain(&second.inlier_indices).copied().collect();
+        union.sort_unstable();
+        union.dedup();
+        let candidates = union
+            .into_iter()
+            .map(|k| {
+                let r = config.ransac.residual_model.residual(&all_lines[k], &start, config.lm.ray_mode);
+                (k, r.norm())
+            })
+            .collect();
+        let merged = keep_closest_per_station(all_lines, candidates);
+        targets[i] = fit_target(first.index, all_lines, merged, start, pipeline, station_names);
+    }
+}
+
+/// EM 软分配精化
+///
+/// E 步：光线 i 对目标 t 的残差为 dᵢₜ（按 `ransac.residual_model`，以 `ransac.threshold` 归一化），
+/// 对数得分为 -(dᵢₜ / T)² / 2，背景类的对数得分为 -(c / T)² / 2（c 为 `clutter_distance`），
+/// 对所有目标与背景类做 softmax 得到责任 rᵢₜ。M 步：每个目标以光线权重乘 rᵢₜ 的加权光线重新精化。
+/// 交替进行直到责任的最大变化小于 `tolerance` 或达到 `max_iterations` 轮。
+/// 最后每条光线硬分配给责任最大且不小于 0.5 的目标（同一目标每个测量站只保留最近的一条），
+/// 以责任加权的光线重新拟合；光线数低于 `min_lines` 的目标被丢弃。
+fn em_refine(
+    all_lines: &[Line],
+    mut targets: Vec<LocatedTarget>,
+    pipeline: &Pipeline,
+    station_names: &[String],
+    em: &EmConfig,
+) -> Vec<LocatedTarget> {
+    let config = pipeline.config;
+    if targets.is_empty() || all_lines.is_empty() {
+        return targets;
+    }
+    let scale = config.ransac.threshold * em.temperature;
+    let clutter_score = -0.5 * (em.clutter_distance / em.temperature).powi(2);
+    let distance = |line: &Line, position: &Point3<f64>| {
+        config.ransac.residual_model.residual(line, position, config.lm.ray_mode).norm()
+    };
+    // responsibilities[i][t]：光线 i 属于目标 t 的概率，其余概率属于背景类
+    let responsibilities = |positions: &[Point3<f64>]| -> Vec<Vec<f64>> {
+        all_lines
+            .iter()
+            .map(|line| {
+                let scores: Vec<f64> =
+                    positions.iter().map(|p| -0.5 * (distance(line, p) / scale).powi(2)).collect();
+                let max_score = scores.iter().copied().fold(clutter_score, f64::max);
+                let background = (clutter_score - max_score).exp();
+                let exps: Vec<f64> = scores.iter().map(|s| (s - max_score).exp()).collect();
+                let total = background + exps.iter().sum::<f64>();
+                exps.into_iter().map(|e| e / total).collect()
+            })
+            .collect()
+    };
+    let weighted_lines = |r: &[Vec<f64>], t: usize| -> Vec<Line> {
+        all_lines
+            .iter()
+            .zip(r)
+            .map(|(line, r)| Line {
+                weight: line.weight * r[t],
+                ..*line
+            })
+            .collect()
+    };
+
+    let mut positions: Vec<Point3<f64>> = targets.iter().map(|t| t.position).collect();
+    let mut r = responsibilities(&positions);
+    for _ in 0..em.max_iterations {
+        for (t, position) in positions.iter_mut().enumerate() {
+            // 责任可忽略的光线不参与精化
+            let lines: Vec<Line> = weighted_lines(&r, t).into_iter().filter(|line| line.weight > 1e-6).collect();
+            if lines.len() < 2 {
+                continue;
+            }
+            let started = Instant::now();
+            let report = pipeline.refiner.refine(&lines, *position);
+            pipeline.refine_time.set(pipeline.refine_time.get() + started.elapsed());
+            if !report.degenerate && report.position.iter().all(|v| v.is_finite()) {
+                *position = report.position;
+            }
+        }
+        let updated = responsibilities(&positions);
+        let change = r
+            .iter()
+            .flatten()
+            .zip(updated.iter().flatten())
+            .map(|(a, b)| (a - b).abs())
+            .fold(0.0, f64::max);
+        r = updated;
+        if change < em.tolerance {
+            break;
+        }
+    }
+
+    // 硬分配并以责任加权重新拟合
+    let mut candidates: Vec<Vec<(usize, f64)>> = vec![Vec::new(); targets.len()];
+    for (i, line_r) in r.iter().enumerate() {
+        let best = line_r.iter().enumerate().max_by(|a, b| a.1.total_cmp(b.1));
+        if let Some((t, &rt)) = best {
+            if rt >= 0.5 {
+                candidates[t].push((i, distance(&all_lines[i], &positions[t])));
+            }
+        }
+    }
+    targets = targets
+        .into_iter()
+        .zip(candidates)
+        .enumerate()
+        .filter_map(|(t, (target, c))| {
+            let assigned = keep_closest_per_station(all_lines, c);
+            (assigned.len() >= config.ransac.min_lines).then(|| {
+                let lines = weighted_lines(&r, t);
+                fit_target(target.index, &lines, assigned, positions[t], pipeline, station_names)
+            })
+        })
+        .collect();
+    targets
+}
+
+/// 一致集质量 1 - RMS / 阈值，RMS 为内点按权重、按残差模型计算的均方根残差
+///
+/// 取值不超过 1，残差越接近阈值越低；纯杂波拼凑出的一致集通常明显低于真实目标。
+fn consensus_quality(all_lines: &[Line], target: &LocatedTarget, config: &FindTargetsConfig) -> f64 {
+    let rms = weighted_rms(target.inlier_indices.iter().map(|&i| {
+        let line = &all_lines[i];
+        let r = config.ransac.residual_model.residual(line, &target.position_at(line.time), config.lm.ray_mode).norm();
+        (line.weight, r)
+    }));
+    1.0 - rms / config.ransac.threshold
+}
+
+/// (权重, 残差) 的加权均方根
+///
+/// 权重之和不为正（如全部为零权重，`try_into_line` 允许）时退化为不加权的均方根，没有残差时为 0。
+fn weighted_rms(weighted_residuals: impl IntoIterator<Item = (f64, f64)>) -> f64 {
+    let (mut total_error_sq, mut total_weight, mut plain_error_sq, mut count) = (0.0, 0.0, 0.0, 0usize);
+    for (weight, r) in weighted_residuals {
+        total_error_sq += weight * r * r;
+        total_weight += weight;
+        plain_error_sq += r * r;
+        count += 1;
+    }
+    if total_weight > 0.0 {
+        (total_error_sq / total_weight).sqrt()
+    } else {
+        (plain_error_sq / count.max(1) as f64).sqrt()
+    }
+}
+
+/// 综合使用 RANSAC + LM 定位多个目标
+///
+/// `min_lines_per_target` 为 2 时，RANSAC 之后剩余的光线再两两交会（见 `triangulate_pair`），
+/// 这类目标的 `low_confidence` 为 true。
+pub fn find_targets(
+    data: &[Measurement],
+    ransac_threshold_m: f64,
+    min_lines_per_target: usize,
+) -> Vec<LocatedTarget> {
+    find_targets_with_config(data, &FindTargetsConfig::new(ransac_threshold_m, min_lines_per_target))
+}
+
+/// `find_targets` 贪心提取循环的终止原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StopReason {
+    /// 剩余光线少于 `min_lines`
+    #[default]
+    InsufficientLines,
+    /// RANSAC 未找到满足 `min_lines` 的一致集
+    NoConsensus,
+    /// 一致集的拟合质量低于 `min_inlier_quality`
+    LowQuality,
+    /// 已达到 `max_targets`
+    MaxTargets,
+    /// 已达到 `max_rounds`
+    MaxRounds,
+    /// 连续多个一致集不满足 `min_geometry`
+    PoorGeometry,
+    /// 进度回调返回了 `ControlFlow::Break`
+    Cancelled,
+}
+
+/// 贪心提取的进度，由 `FindTargetsConfig::progress` 回调接收
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ProgressEvent {
+    pub round: usize,             // 当前轮次（从 1 开始）
+    pub lines_remaining: usize,   // 本轮参与提取的光线数
+    pub targets_found: usize,     // 已接受的目标数
+    pub ransac_iterations: usize, // 本轮内置 RANSAC 已运行的迭代次数，轮开始时为 0
+}
+
+/// 进度回调，返回 `ControlFlow::Break(())` 时取消定位
+///
+/// 回调在调用 `find_targets` 的线程上同步执行，应尽快返回；克隆配置时共享同一个闭包。
+/// 取消后不再提取新目标，也不再合并、重新分配或 EM 精化，直接返回已接受的目标（排序规则不变）。
+/// 自定义的一致集估计（`find_targets_with_strategies`）及 LMedS 只在每轮开始时调用。
+#[derive(Clone)]
+pub struct ProgressCallback(Arc<dyn Fn(ProgressEvent) -> ControlFlow<()> + Send + Sync>);
+
+impl ProgressCallback {
+    pub fn new(callback: impl Fn(ProgressEvent) -> ControlFlow<()> + Send + Sync + 'static) -> Self {
+        ProgressCallback(Arc::new(callback))
+    }
+}
+
+impl fmt::Debug for ProgressCallback {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("ProgressCallback(..)")
+    }
+}
+
+/// 一次定位中进度回调的状态，由 `Pipeline` 与内置 RANSAC 共享
+pub(crate) struct ProgressTracker {
+    callback: Option<ProgressCallback>,
+    pub(crate) interval: usize,
+    event: Cell<ProgressEvent>, // 当前轮的进度
+    cancelled: Cell<bool>,
+}
+
+impl ProgressTracker {
+    fn new(config: &FindTargetsConfig) -> Self {
+        ProgressTracker {
+            callback: config.progress.clone(),
+            interval: config.progress_interval.max(1),
+            event: Cell::new(ProgressEvent::default()),
+            cancelled: Cell::new(false),
+        }
+    }
+
+    /// 设置了回调时返回自身，供内置 RANSAC 在未设置回调时完全跳过进度报告
+    pub(crate) fn active(&self) -> Option<&Self> {
+        self.callback.as_ref().map(|_| self)
+    }
+
+    /// 开始新一轮提取并报告
+    pub(crate) fn start_round(&self, round: usize, lines_remaining: usize, targets_found: usize) -> ControlFlow<()> {
+        self.event.set(ProgressEvent {
+            round,
+            lines_remaining,
+            targets_found,
+            ransac_iterations: 0,
+        });
+        self.report()
+    }
+
+    /// 报告本轮 RANSAC 已运行 `iterations` 次迭代
+    pub(crate) fn ransac_progress(&self, iterations: usize) -> ControlFlow<()> {
+        self.event.set(ProgressEvent {
+            ransac_iterations: iterations,
+            ..self.event.get()
+        });
+        self.report()
+    }
+
+    /// 调用回调；一旦取消，之后不再调用回调并一直返回 `Break`
+    fn report(&self) -> ControlFlow<()> {
+        if let Some(callback) = &self.callback {
+            if !self.cancelled.get() && (callback.0)(self.event.get()).is_break() {
+                self.cancelled.set(true);
+            }
+        }
+        if self.cancelled.get() {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    }
+
+    pub(crate) fn cancelled(&self) -> bool {
+        self.cancelled.get()
+    }
+}
+
+/// `find_targets` 的运行诊断信息
+#[derive(Debug, Clone, Default)]
+pub struct FindTargetsDiagnostics {
+    pub skipped: Vec<(usize, OptiRadarError)>, // 被跳过的无效测量的索引及原因（索引升序）
+    pub rounds: usize,                         // 实际运行的 RANSAC 轮数
+    pub stop_reason: StopReason,               // 贪心提取循环的终止原因
+    pub ransac_iterations: Vec<usize>,         // 每轮的抽样次数（含未找到一致集的最后一轮）
+    pub degenerate_samples_rejected: usize,    // 各轮因退化被拒绝的样本总数
+    pub geometry_rejections: usize,            // 因不满足 `min_geometry` 被拒绝的一致集数
+    pub unassigned_lines: usize,               // 最终未分配给任何目标的有效光线数
+    pub lm_iterations: Vec<usize>,             // 每个目标最终一次 LM 的迭代次数，顺序同返回的目标
+    pub ransac_ms: f64,                        // 一致集估计累计耗时（毫秒）
+    pub lm_ms: f64,                            // LM 精化累计耗时（毫秒，含合并与重新分配中的重新拟合）
+}
+
+impl fmt::Display for FindTargetsDiagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "rounds={} stop={:?} ransac_iterations={:?} degenerate={} geometry_rejected={} unassigned={} \
+             lm_iterations={:?} skipped={} ransac_ms={:.3} lm_ms={:.3}",
+            self.rounds,
+            self.stop_reason,
+            self.ransac_iterations,
+            self.degenerate_samples_rejected,
+            self.geometry_rejections,
+            self.unassigned_lines,
+            self.lm_iterations,
+            self.skipped.len(),
+            self.ransac_ms,
+            self.lm_ms,
+        )
+    }
+}
+
+impl FindTargetsDiagnostics {
+    /// 定位是否被进度回调取消
+    pub fn cancelled(&self) -> bool {
+        self.stop_reason == StopReason::Cancelled
+    }
+
+    /// 被跳过的无效测量索引
+    pub fn skipped_indices(&self) -> Vec<usize> {
+        self.skipped.iter().map(|&(i, _)| i).collect()
+    }
+
+    /// 记录最终结果的统计：未分配光线数、各目标的 LM 迭代次数及精化耗时
+    pub(crate) fn record_results(&mut self, num_lines: usize, targets: &[LocatedTarget], pipeline: &Pipeline) {
+        let assigned: HashSet<usize> = targets.iter().flat_map(|t| t.inlier_indices.iter().copied()).collect();
+        self.unassigned_lines = num_lines - assigned.len();
+        self.lm_iterations = targets.iter().map(|t| t.lm_iterations).collect();
+        self.lm_ms = pipeline.refine_time.get().as_secs_f64() * 1e3;
+    }
+}
+
+/// 按 `FindTargetsConfig` 定位多个目标，无效测量被静默跳过
+pub fn find_targets_with_config(
+    data: &[Measurement],
+    config: &FindTargetsConfig,
+) -> Vec<LocatedTarget> {
+    find_targets_with_diagnostics(data, config).0
+}
+
+/// 同 `find_targets_with_config`，先检查参数（见 `FindTargetsConfig::validate`），无效时返回错误而不运行
+pub fn try_find_targets_with_config(
+    data: &[Measurement],
+    config: &FindTargetsConfig,
+) -> Result<Vec<LocatedTarget>, OptiRadarError> {
+    config.validate()?;
+    Ok(find_targets_with_config(data, config))
+}
+
+/// 按时间窗分组测量，逐窗定位多个目标
+///
+/// 时间窗从最早的时间戳开始，宽度为 `window_s` 秒（左闭右开）；每个非空时间窗单独运行
+/// `find_targets_with_config`，结果的 `timestamp` 为该时间窗的中心时刻。
+/// 没有时间戳（或时间戳非有限值）的测量全部归入同一个时间窗，其结果排在最前且 `timestamp` 为 None，
+/// 因此不带时间戳的数据与 `find_targets_with_config` 的结果相同。
+/// 结果按时间窗顺序排列，标识按该顺序重新编号，`inlier_indices` 为输入数据中的索引。
+/// `window_s` 不是正的有限值时返回 `InvalidParameter`。
+pub fn find_targets_windowed(
+    data: &[Measurement],
+    window_s: f64,
+    config: &FindTargetsConfig,
+) -> Result<Vec<LocatedTarget>, OptiRadarError> {
+    if !(window_s.is_finite() && window_s > 0.0) {
+        return Err(OptiRadarError::InvalidParameter {
+            name: "window_s",
+            value: window_s,
+        });
+    }
+    let timestamp = |m: &Measurement| m.timestamp.filter(|t| t.is_finite());
+    let start = data.iter().filter_map(timestamp).fold(f64::INFINITY, f64::min);
+
+    // 时间窗序号 → 测量索引；没有时间戳的测量归入 None，排在最前
+    let mut windows: BTreeMap<Option<i64>, Vec<usize>> = BTreeMap::new();
+    for (index, m) in data.iter().enumerate() {
+        let window = timestamp(m).map(|t| ((t - start) / window_s).floor() as i64);
+        windows.entry(window).or_default().push(index);
+    }
+
+    let mut located_targets = Vec::new();
+    for (window, indices) in windows {
+        let window_data: Vec<_> = indices.iter().map(|&i| data[i].clone()).collect();
+        let center = window.map(|k| start + (k as f64 + 0.5) * window_s);
+        for mut target in find_targets_with_config(&window_data, config) {
+            for i in target.inlier_indices.iter_mut().chain(&mut target.outlier_indices) {
+                *i = indices[*i];
+            }
+            target.index = located_targets.len() + 1;
+            target.id = config.target_id(target.index);
+            target.timestamp = center;
+            located_targets.push(target);
+        }
+    }
+    Ok(located_targets)
+}
+
+/// 独立定位多帧测量，结果按帧的顺序排列
+///
+/// 每帧相当于单独调用 `find_targets_with_config`，RANSAC 种子为 `ransac.seed` 加帧序号（回绕），
+/// 因此设置种子时结果与线程调度无关，且与逐帧以相应种子调用的结果相同；未设置种子时每帧随机取种。
+/// 启用 `parallel` 特性时各帧由 rayon 并行处理。某一帧 panic 时整个调用 panic
+/// （rayon 等其余帧结束后在调用方重新抛出），不会静默丢弃其他帧的结果。
+pub fn find_targets_batch(frames: &[Vec<Measurement>], config: &FindTargetsConfig) -> Vec<Vec<LocatedTarget>> {
+    map_ordered(frames.iter().enumerate().collect(), |(index, data): (usize, &Vec<Measurement>)| {
+        let seed = config.ransac.seed.map(|seed| seed.wrapping_add(index as u64));
+        run_pipeline(data.iter().enumerate(), &Pipeline::new(config), &mut seeded_rng(seed)).0
+    })
+}
+
+/// 同 `find_targets_with_config`，直接在已有光线上定位，省去 `Measurement` 转换
+///
+/// 光线须已校验（起点有限、方向为单位化的有限向量、权重非负），不会被跳过；
+/// `inlier_indices` 为 `lines` 中的索引。`station` 为 `Some(k)` 的光线归属测量站 `k`，
+/// 结果的 `stations` 中记为编号的十进制字符串。对由 `Line::from_measurement` 转换、
+/// 不含 `station_id` 的有效测量，结果与 `find_targets_with_config` 相同。
+pub fn find_targets_from_lines(lines: &[Line], config: &FindTargetsConfig) -> Vec<LocatedTarget> {
+    let num_stations = lines.iter().filter_map(|line| line.station).max().map_or(0, |k| k + 1);
+    let station_names: Vec<String> = (0..num_stations).map(|k| k.to_string()).collect();
+    let pipeline = Pipeline::new(config);
+    let mut rng = seeded_rng(config.ransac.seed);
+    run_lines_pipeline(lines, &station_names, &pipeline, &mut rng, &mut FindTargetsDiagnostics::default())
+}
+
+/// 按 `FindTargetsConfig` 定位多个目标，并返回诊断信息
+///
+/// 无法通过 `Measurement::try_into_line` 校验的测量不参与定位，
+/// 其索引记录在诊断信息中；`inlier_indices` 仍为输入数据中的索引。
+/// 贪心提取在光线不足、找不到一致集或达到 `max_targets`、`max_rounds`、
+/// `min_inlier_quality` 任一限制时停止，原因记录在 `stop_reason` 中。
+pub fn find_targets_with_diagnostics(
+    data: &[Measurement],
+    config: &FindTargetsConfig,
+) -> (Vec<LocatedTarget>, FindTargetsDiagnostics) {
+    run_pipeline(data.iter().enumerate(), &Pipeline::new(config), &mut seeded_rng(config.ransac.seed))
+}
+
+/// 同 `find_targets_with_diagnostics`，随机数取自 `rng`（忽略 `config.ransac.seed`）
+pub fn find_targets_with_rng(
+    data: &[Measurement],
+    config: &FindTargetsConfig,
+    rng: &mut dyn RandomSource,
+) -> (Vec<LocatedTarget>, FindTargetsDiagnostics) {
+    run_pipeline(data.iter().enumerate(), &Pipeline::new(config), rng)
+}
+
+/// 同 `find_targets_with_diagnostics`，测量逐条取自迭代器（例如流式解码器），不先收集为 `Vec<Measurement>`
+///
+/// 每条测量读取后立即转换为光线，只保留光线缓冲区；`inlier_indices` 和被跳过测量的索引
+/// 均为测量在迭代器中的序号。对同样的测量序列，结果与切片输入相同。
+pub fn find_targets_from_iter<I: IntoIterator<Item = Measurement>>(
+    data: I,
+    config: &FindTargetsConfig,
+) -> (Vec<LocatedTarget>, FindTargetsDiagnostics) {
+    run_pipeline(data.into_iter().enumerate(), &Pipeline::new(config), &mut seeded_rng(config.ransac.seed))
+}
+
+/// 同 `find_targets_from_iter`，接受可能解码失败的项（例如 `io::iter_measurements_csv`）
+///
+/// 解码失败的项不参与定位，也不中断读取，其序号与错误按顺序在第三项中返回；
+/// 序号计入失败的项，`inlier_indices` 和诊断信息中的索引与之一致。
+pub fn try_find_targets_from_iter<I, E>(
+    data: I,
+    config: &FindTargetsConfig,
+) -> (Vec<LocatedTarget>, FindTargetsDiagnostics, Vec<(usize, E)>)
+where
+    I: IntoIterator<Item = Result<Measurement, E>>,
+{
+    let mut errors = Vec::new();
+    let measurements = data.into_iter().enumerate().filter_map(|(index, item)| match item {
+        Ok(m) => Some((index, m)),
+        Err(error) => {
+            errors.push((index, error));
+            None
+        }
+    });
+    let (located, diagnostics) = run_pipeline(measurements, &Pipeline::new(config), &mut seeded_rng(config.ransac.seed));
+    (located, diagnostics, errors)
+}
+
+/// `find_targets_extended` 的一项结果
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DetectionResult {
+    /// 已定位的目标
+    Located(Box<LocatedTarget>),
+    /// 方向几乎相同、互不交会的一簇光线：目标远到测量站间距不足以交会定位，只知道方位
+    BearingOnly {
+        mean_direction: Vector3<f64>,  // 各光线方向按权重平均后的单位向量
+        num_lines: usize,
+        origin_centroid: Point3<f64>,  // 光线起点的平均位置
+        line_indices: Vec<usize>,      // 光线在输入测量中的索引（升序）
+    },
+}
+
+impl fmt::Display for DetectionResult {
+    /// 已定位目标同 `LocatedTarget`，仅方位聚类例如
+    /// `bearing_only direction=(0.000, 1.000, 0.000) origin=(0.000, 0.000, 0.000) lines=4`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DetectionResult::Located(target) => target.fmt(f),
+            DetectionResult::BearingOnly { mean_direction: d, num_lines, origin_centroid: o, .. } => write!(
+                f,
+                "bearing_only direction=({:.3}, {:.3}, {:.3}) origin=({:.3}, {:.3}, {:.3}) lines={}",
+                d.x, d.y, d.z, o.x, o.y, o.z, num_lines,
+            ),
+        }
+    }
+}
+
+/// 定位多个目标，并报告无法交会的近乎平行光线簇
+///
+/// 先按 `find_targets_with_config` 定位，结果依次作为 `Located` 排在前面；
+/// 未分配给任何目标的光线中，方向两两夹角不超过 `bearing_only_max_angle_rad`
+/// （同向，射线反向不算）且至少有 `ransac.min_lines` 条的光线簇作为 `BearingOnly` 排在其后。
+/// 光线簇贪心选取：每次以相似光线最多的光线为种子（相同时取索引小者），
+/// 按与种子的夹角从小到大加入与已有成员两两相似的光线。
+pub fn find_targets_extended(data: &[Measurement], config: &FindTargetsConfig) -> Vec<DetectionResult> {
+    let located = find_targets_with_config(data, config);
+    let (all_lines, _, data_indices) = prepare_lines(data.iter().enumerate(), &mut Vec::new());
+    let assigned: HashSet<usize> = located.iter().flat_map(|t| t.inlier_indices.iter().copied()).collect();
+    let remaining: Vec<usize> = (0..all_lines.len()).filter(|&k| !assigned.contains(&data_indices[k])).collect();
+
+    let bearings = bearing_clusters(&all_lines, &remaining, config).into_iter().map(|cluster| {
+        let direction_sum: Vector3<f64> = cluster.iter().map(|&k| all_lines[k].direction * all_lines[k].weight).sum();
+        let start_sum: Vector3<f64> = cluster.iter().map(|&k| all_lines[k].start.coords).sum();
+        DetectionResult::BearingOnly {
+            mean_direction: direction_sum.normalize(),
+            num_lines: cluster.len(),
+            origin_centroid: Point3::from(start_sum / cluster.len() as f64),
+            line_indices: cluster.iter().map(|&k| data_indices[k]).collect(),
+        }
+    });
+    located.into_iter().map(|target| DetectionResult::Located(Box::new(target))).chain(bearings).collect()
+}
+
+/// 在 `remaining` 中贪心寻找方向两两相似的光线簇，每簇为升序的光线索引
+fn bearing_clusters(all_lines: &[Line], remaining: &[usize], config: &FindTargetsConfig) -> Vec<Vec<usize>> {
+    let min_cos = config.bearing_only_max_angle_rad.cos();
+    let similarity = |a: usize, b: usize| all_lines[a].direction.dot(&all_lines[b].direction);
+    let mut unclustered = remaining.to_vec();
+    let mut clusters = Vec::new();
+    loop {
+        let neighbours = |seed: usize| -> Vec<usize> {
+            unclustered.iter().copied().filter(|&k| k != seed && similarity(seed, k) >= min_cos).collect()
+        };
+        let Some((seed, mut candidates)) = unclustered
+            .iter()
+            .map(|&seed| (seed, neighbours(seed)))
+            .fold(None, |best: Option<(usize, Vec<usize>)>, (seed, candidates)| match best {
+                Some(best) if best.1.len() >= candidates.len() => Some(best),
+                _ => Some((seed, candidates)),
+            })
+        else {
+            break;
+        };
+        candidates.sort_by(|&a, &b| similarity(seed, b).total_cmp(&similarity(seed, a)));
+        let mut cluster = vec![seed];
+        for k in candidates {
+            if cluster.iter().all(|&member| similarity(member, k) >= min_cos) {
+                cluster.push(k);
+            }
+        }
+        if cluster.len() < config.ransac.min_lines {
+            break;
+        }
+        cluster.sort_unstable();
+        unclustered.retain(|k| cluster.binary_search(k).is_err());
+        clusters.push(cluster);
+    }
+    clusters
+}
+
+/// 使用自定义的一致集估计与精化策略定位多个目标，并返回诊断信息
+///
+/// 流程与 `find_targets_with_diagnostics` 相同，`config.estimator`、`config.lm`
+/// 及 `config.altitude_prior`、`config.fixed_altitude` 由传入的策略取代（后者仍决定协方差
+/// 是否只估计水平分量）；`ransac` 中的最少光线数、种子、阈值等仍用于贪心提取的终止条件、
+/// 每轮的随机数及合并、重新分配等后续步骤。
+pub fn find_targets_with_strategies(
+    data: &[Measurement],
+    config: &FindTargetsConfig,
+    estimator: &dyn ConsensusEstimator,
+    refiner: &dyn Refiner,
+) -> (Vec<LocatedTarget>, FindTargetsDiagnostics) {
+    let pipeline = Pipeline {
+        config,
+        estimator: Box::new(estimator),
+        refiner: Box::new(refiner),
+        refine_time: Cell::new(Duration::ZERO),
+        progress: Rc::new(ProgressTracker::new(config)),
+    };
+    run_pipeline(data.iter().enumerate(), &pipeline, &mut seeded_rng(config.ransac.seed))
+}
+
+// --- J-linkage 多模型拟合 ---
+//
+// 贪心提取每轮取最大的一致集并移除其内点，结果与提取顺序有关：目标密集时先提取的一致集
+// 可能混入相邻目标的光线。J-linkage 一次性生成大量候选位置（假设），每条光线记录自己
+// 落在哪些假设的阈值内（偏好集），再按偏好集的 Jaccard 距离自底向上聚类光线，
+// 每个类即一个目标，与顺序无关。
+
+/// J-linkage 多模型拟合定位多个目标
+///
+/// 1. 生成 `jlinkage_hypotheses` 个假设：随机取一条光线 a，再从与 a 相容（两者最近点中点处
+///    残差均不超过阈值、且来自不同测量站）的光线中随机取 b，从在 a、b 中点处残差不超过阈值的
+///    光线中随机取 c，以三者的闭式解为假设；退化或超出关注区域的样本被跳过。
+/// 2. 每条光线的偏好集为残差不超过 `ransac.threshold` 的假设集合。
+/// 3. 每条光线起始为一类，类的偏好集为成员偏好集的交集；反复合并 Jaccard 距离最小（小于 1）的两类，
+///    含同一测量站光线的两类不合并，直到没有可合并的类。
+/// 4. 成员不少于 `ransac.min_lines` 的类按成员数从多到少编号，各以 LM 拟合（同 `find_targets`），
+///    之后的合并、重新分配、EM、关注区域与排序等后处理同 `find_targets_with_config`。
+///
+/// 随机数取自 `ransac.seed`；无效测量被忽略。聚类的代价约为 O(n² · 假设数 / 64 + n³)，适合每帧数百条光线。
+pub fn find_targets_jlinkage(data: &[Measurement], config: &FindTargetsConfig) -> Vec<LocatedTarget> {
+    let mut skipped = Vec::new();
+    let (all_lines, station_names, data_indices) = prepare_lines(data.iter().enumerate(), &mut skipped);
+    let pipeline = Pipeline::new(config);
+    let hypotheses = jlinkage_hypotheses(&all_lines, config, &mut seeded_rng(config.ransac.seed));
+    let clusters = jlinkage_clusters(&all_lines, &hypotheses, config);
+
+    let mut targets = Vec::new();
+    for members in clusters.into_iter().filter(|m| m.len() >= config.ransac.min_lines) {
+        let lines: Vec<Line> = members.iter().map(|&i| all_lines[i]).collect();
+        if config.min_geometry.is_some_and(|criterion| !criterion.accepts(&lines)) {
+            continue;
+        }
+        let start = find_closest_midpoint(&lines[0], &lines[1]);
+        targets.push(fit_target(targets.len() + 1, &all_lines, members, start, &pipeline, &station_names));
+    }
+    let mut targets = refine_targets(&all_lines, &station_names, &pipeline, targets);
+    for (k, target) in targets.iter_mut().enumerate() {
+        if config.output_ordering != OutputOrdering::Discovery {
+            target.index = k + 1;
+            target.id = config.target_id(target.index);
+        }
+        for i in target.inlier_indices.iter_mut().chain(&mut target.outlier_indices) {
+            *i = data_indices[*i];
+        }
+    }
+    targets
+}
+
+/// 按 `find_targets_jlinkage` 第 1 步生成假设位置
+fn jlinkage_hypotheses(all_lines: &[Line], config: &FindTargetsConfig, rng: &mut dyn RandomSource) -> Vec<Point3<f64>> {
+    let ransac = &config.ransac;
+    let fits = |line: &Line, p: &Point3<f64>| {
+        ransac.residual_model.residual(line, p, ransac.ray_mode).norm() <= ransac.threshold
+    };
+    let mut hypotheses = Vec::with_capacity(config.jlinkage_hypotheses);
+    if all_lines.len() < 3 {
+        return hypotheses;
+    }
+    for _ in 0..config.jlinkage_hypotheses {
+        let a = random_index(rng, all_lines.len());
+        let line_a = &all_lines[a];
+        let compatible: Vec<usize> = (0..all_lines.len())
+            .filter(|&b| b != a && (line_a.station.is_none() || all_lines[b].station != line_a.station))
+            .filter(|&b| {
+                let midpoint = find_closest_midpoint(line_a, &all_lines[b]);
+                fits(line_a, &midpoint) && fits(&all_lines[b], &midpoint)
+            })
+            .collect();
+        if compatible.is_empty() {
+            continue;
+        }
+        let b = compatible[random_index(rng, compatible.len())];
+        let midpoint = find_closest_midpoint(line_a, &all_lines[b]);
+        let third: Vec<usize> =
+            compatible.iter().copied().filter(|&c| c != b && fits(&all_lines[c], &midpoint)).collect();
+        if third.is_empty() {
+            continue;
+        }
+        let c = third[random_index(rng, third.len())];
+        let sample = [*line_a, all_lines[b], all_lines[c]];
+        if is_degenerate_sample(&sample, ransac) {
+            continue;
+        }
+        if let Some(p) = linear_triangulate(&sample).filter(|p| within_bounds(ransac, p)) {
+            hypotheses.push(p);
+        }
+    }
+    hypotheses
+}
+
+/// 按 `find_targets_jlinkage` 第 2、3 步聚类光线，返回各类的光线索引（升序）
+fn jlinkage_clusters(all_lines: &[Line], hypotheses: &[Point3<f64>], config: &FindTargetsConfig) -> Vec<Vec<usize>> {
+    let ransac = &config.ransac;
+    let words = hypotheses.len().div_ceil(64);
+    struct Cluster {
+        members: Vec<usize>,
+        stations: Vec<usize>, // 成员的测量站编号（升序、去重）
+        preference: Vec<u64>, // 偏好集位图
+    }
+    let mut clusters: Vec<Cluster> = all_lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| {
+            let mut preference = vec![0u64; words];
+            for (h, p) in hypotheses.iter().enumerate() {
+                if ransac.residual_model.residual(line, p, ransac.ray_mode).norm() <= ransac.threshold {
+                    preference[h / 64] |= 1 << (h % 64);
+                }
+            }
+            Cluster {
+                members: vec![i],
+                stations: line.station.into_iter().collect(),
+                preference,
+            }
+        })
+        .collect();
+
+    // Jaccard 距离；偏好集的交集为空或两类含同一测量站的光线时为 1（不可合并）
+    let distance = |a: &Cluster, b: &Cluster| -> f64 {
+        if a.stations.iter().any(|s| b.stations.binary_search(s).is_ok()) {
+            return 1.0;
+        }
+        let (mut both, mut either) = (0, 0);
+        for (x, y) in a.preference.iter().zip(&b.preference) {
+            both += (x & y).count_ones();
+            either += (x | y).count_ones();
+        }
+        if both == 0 {
+            1.0
+        } else {
+            1.0 - both as f64 / either as f64
+        }
+    };
+    let n = clusters.len();
+    let mut distances = vec![vec![1.0; n]; n];
+    for i in 0..n {
+        for j in i + 1..n {
+            distances[i][j] = distance(&clusters[i], &clusters[j]);
+        }
+    }
+    // alive[k]：第 k 类尚未被合并；合并后的类保存在较小的下标处
+    let mut alive = vec![true; n];
+    loop {
+        let mut closest: Option<(usize, usize, f64)> = None;
+        for i in (0..n).filter(|&i| alive[i]) {
+            for j in (i + 1..n).filter(|&j| alive[j]) {
+                if distances[i][j] < 1.0 && closest.is_none_or(|(_, _, d)| distances[i][j] < d) {
+                    closest = Some((i, j, distances[i][j]));
+                }
+            }
+        }
+        let Some((i, j, _)) = closest else {
+            break;
+        };
+        alive[j] = false;
+        let merged = std::mem::replace(
+            &mut clusters[j],
+            Cluster {
+                members: Vec::new(),
+                stations: Vec::new(),
+                preference: Vec::new(),
+            },
+        );
+        let target = &mut clusters[i];
+        target.members.extend(merged.members);
+        target.stations.extend(merged.stations);
+        target.stations.sort_unstable();
+        for (x, y) in target.preference.iter_mut().zip(&merged.preference) {
+            *x &= y;
+        }
+        for k in (0..n).filter(|&k| alive[k] && k != i) {
+            let d = distance(&clusters[i], &clusters[k]);
+            let (a, b) = if k < i { (k, i) } else { (i, k) };
+            distances[a][b] = d;
+        }
+    }
+
+    let mut result: Vec<Vec<usize>> = clusters
+        .into_iter()
+        .zip(alive)
+        .filter(|(_, alive)| *alive)
+        .map(|(mut cluster, _)| {
+            cluster.members.sort_unstable();
+            cluster.members
+        })
+        .collect();
+    // 成员数从多到少，相同时按最小光线索引，结果与合并顺序无关
+    result.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a[0].cmp(&b[0])));
+    result
+}
+
+// --- 交会点 DBSCAN 预分组 ---
+//
+// 同一目标的光线两两交会于目标附近，交会点在空间中聚成密集的一团；不同目标的光线偶然交会的点则较分散。
+// 对交会点做 DBSCAN，每个类直接给出一个目标的候选光线，不需要 RANSAC 随机抽样，
+// 场景中目标与光线很多时比逐轮 RANSAC 快得多。
+
+/// 先以交会点 DBSCAN 预分组、再以 RANSAC 处理剩余光线的多目标定位
+///
+/// 1. 两两检验光线（同一测量站的光线不配对）：交会点在关注区域内、且到两条光线的残差都在
+///    `ransac.threshold` 内时记录该交会点。光线对数超过 `dbscan.max_pairs` 时改为随机抽取这么多对：
+///    交会点数随光线数平方增长，抽样使目标处与偶然交会处的点数按同一比例减少，光线很少的目标
+///    可能不成类，留给第 4 步。
+/// 2. 对交会点做 DBSCAN（`dbscan.eps_m`、`dbscan.min_pts`）。
+/// 3. 按交会点数从多到少处理各类：在类中交会点最密集处收集参与交会、尚未使用且残差在阈值内的光线
+///    （每个测量站只保留最近的一条），以 LM 拟合后在拟合位置处重新收集一次；光线数、交会几何、
+///    一致集质量与关注区域的检验同贪心提取。一个类中可能依次得到多个目标。
+/// 4. 未被任何类吸收的光线按 `find_targets_with_config` 的贪心提取继续，之后的后处理也相同。
+///
+/// 随机数取自 `ransac.seed`（抽取光线对后继续用于 RANSAC）；无效测量被忽略。
+/// 没有任何类时结果与 `find_targets_with_config` 相同。
+pub fn find_targets_clustered(data: &[Measurement], config: &FindTargetsConfig) -> Vec<LocatedTarget> {
+    let mut diagnostics = FindTargetsDiagnostics::default();
+    let (all_lines, station_names, data_indices) = prepare_lines(data.iter().enumerate(), &mut diagnostics.skipped);
+    let pipeline = Pipeline::new(config);
+    let mut rng = seeded_rng(config.ransac.seed);
+    let mut located_targets = Vec::new();
+    let mut used_line_indices = HashSet::new();
+    let mut next_id = 1;
+
+    let (points, pairs) = pair_intersections(&all_lines, config, &mut rng);
+    let labels = dbscan(&points, config.dbscan.eps_m, config.dbscan.min_pts);
+    let mut clusters: Vec<Vec<usize>> = Vec::new();
+    for (k, label) in labels.into_iter().enumerate() {
+        if let Some(label) = label {
+            if label >= clusters.len() {
+                clusters.resize(label + 1, Vec::new());
+            }
+            clusters[label].push(k);
+        }
+    }
+    // 类内交会点按下标升序，按点数从多到少、相同时按首个下标处理
+    clusters.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a[0].cmp(&b[0])));
+
+    let collect = |lines: &[usize], position: &Point3<f64>, used: &HashSet<usize>| {
+        let candidates = lines
+            .iter()
+            .filter(|i| !used.contains(i))
+            .filter_map(|&i| {
+                let r = config.ransac.residual_model.residual(&all_lines[i], position, config.lm.ray_mode).norm();
+                (r < config.ransac.threshold).then_some((i, r))
+            })
+            .collect();
+        keep_closest_per_station(&all_lines, candidates)
+    };
+    // 在 `seed` 处收集光线并拟合，拟合后在新位置重新收集一次；不满足贪心提取的各项检验时为 None
+    let fit_at = |contributing: &[usize], seed: Point3<f64>, id: usize, used: &HashSet<usize>| {
+        let inliers = collect(contributing, &seed, used);
+        if inliers.len() < config.ransac.min_lines {
+            return None;
+        }
+        let mut target = fit_target(id, &all_lines, inliers, seed, &pipeline, &station_names);
+        let recollected = collect(contributing, &target.position, used);
+        if recollected != target.inlier_indices {
+            if recollected.len() < config.ransac.min_lines {
+                return None;
+            }
+            target = fit_target(id, &all_lines, recollected, target.position, &pipeline, &station_names);
+        }
+        let lines: Vec<Line> = target.inlier_indices.iter().map(|&i| all_lines[i]).collect();
+        let rejected = config.min_geometry.is_some_and(|criterion| !criterion.accepts(&lines))
+            || config.min_inlier_quality.is_some_and(|q| consensus_quality(&all_lines, &target, config) < q)
+            || !in_bounds(&target, config);
+        (!rejected).then_some(target)
+    };
+    'clusters: for mut cluster in clusters {
+        // 一个类可能经稀疏的偶然交会点连成一串、包含多个目标：每次取交会点最密集处拟合一个目标，
+        // 去掉光线已被使用的交会点后重复，直到剩余的点不足 `min_pts`
+        loop {
+            cluster.retain(|&k| !used_line_indices.contains(&pairs[k].0) && !used_line_indices.contains(&pairs[k].1));
+            if cluster.len() < config.dbscan.min_pts.max(1) {
+                break;
+            }
+            if config.max_targets.is_some_and(|max| located_targets.len() >= max) {
+                break 'clusters;
+            }
+            let (seed, block) = densest_block(&points, &cluster, config.dbscan.eps_m);
+            let mut contributing: Vec<usize> = cluster.iter().flat_map(|&k| [pairs[k].0, pairs[k].1]).collect();
+            contributing.sort_unstable();
+            contributing.dedup();
+            match fit_at(&contributing, seed, next_id, &used_line_indices) {
+                Some(target) => {
+                    used_line_indices.extend(target.inlier_indices.iter().copied());
+                    located_targets.push(target);
+                    next_id += 1;
+                }
+                // 该处不构成目标：丢弃这些交会点，继续处理类中的其余部分
+                None => cluster.retain(|k| !block.contains(k)),
+            }
+        }
+    }
+
+    extract_targets(
+        &all_lines,
+        &station_names,
+        &pipeline,
+        &mut used_line_indices,
+        &mut located_targets,
+        &mut next_id,
+        &mut diagnostics,
+        &mut rng,
+    );
+    let mut located_targets = refine_targets(&all_lines, &station_names, &pipeline, located_targets);
+    for (k, target) in located_targets.iter_mut().enumerate() {
+        if config.output_ordering != OutputOrdering::Discovery {
+            target.index = k + 1;
+            target.id = config.target_id(target.index);
+        }
+        for i in target.inlier_indices.iter_mut().chain(&mut target.outlier_indices) {
+            *i = data_indices[*i];
+        }
+    }
+    located_targets
+}
+
+/// 按 `find_targets_clustered` 第 1 步收集交会点，返回交会点及对应的光线对
+fn pair_intersections(
+    all_lines: &[Line],
+    config: &FindTargetsConfig,
+    rng: &mut dyn RandomSource,
+) -> (Vec<Point3<f64>>, Vec<(usize, usize)>) {
+    let ransac = &config.ransac;
+    let intersect = |i: usize, j: usize| -> Option<(Point3<f64>, (usize, usize))> {
+        let (first, second) = (&all_lines[i], &all_lines[j]);
+        if first.station.is_some() && first.station == second.station {
+            return None;
+        }
+        let midpoint = triangulate_pair(first, second)?.midpoint;
+        let consistent = within_bounds(ransac, &midpoint)
+            && [first, second].iter().all(|line| {
+                ransac.residual_model.residual(line, &midpoint, config.lm.ray_mode).norm() < ransac.threshold
+            });
+        consistent.then_some((midpoint, (i, j)))
+    };
+    let n = all_lines.len();
+    let num_pairs = n * n.saturating_sub(1) / 2;
+    let found: Vec<_> = if num_pairs <= config.dbscan.max_pairs {
+        map_ordered((0..n).collect(), |i| (i + 1..n).filter_map(|j| intersect(i, j)).collect::<Vec<_>>())
+            .into_iter()
+            .flatten()
+            .collect()
+    } else {
+        (0..config.dbscan.max_pairs)
+            .filter_map(|_| {
+                let (i, j) = (random_index(rng, n), random_index(rng, n));
+                (i != j).then(|| intersect(i.min(j), i.max(j))).flatten()
+            })
+            .collect()
+    };
+    found.into_iter().unzip()
+}
+
+/// `cluster` 列出的交会点中最密集的位置：以边长 `eps` 的网格计数，取点数最多的格子
+/// （相同时取最先出现的）及其周围 26 格，返回这些点的均值与下标
+fn densest_block(points: &[Point3<f64>], cluster: &[usize], eps: f64) -> (Point3<f64>, Vec<usize>) {
+    let cell = |p: &Point3<f64>| ((p.x / eps).floor() as i64, (p.y / eps).floor() as i64, (p.z / eps).floor() as i64);
+    let mut counts: HashMap<(i64, i64, i64), usize> = HashMap::new();
+    let mut best = (0, cell(&points[cluster[0]]));
+    for &k in cluster {
+        let c = cell(&points[k]);
+        let count = counts.entry(c).or_default();
+        *count += 1;
+        if *count > best.0 {
+            best = (*count, c);
+        }
+    }
+    let (bx, by, bz) = best.1;
+    let block: Vec<usize> = cluster
+        .iter()
+        .copied()
+        .filter(|&k| {
+            let (x, y, z) = cell(&points[k]);
+            (x - bx).abs() <= 1 && (y - by).abs() <= 1 && (z - bz).abs() <= 1
+        })
+        .collect();
+    let mean = block.iter().map(|&k| points[k].coords).sum::<Vector3<f64>>() / block.len() as f64;
+    (Point3::from(mean), block)
+}
+
+/// DBSCAN 聚类：返回每个点所属类的编号（从 0 开始，按发现顺序），噪声点为 None
+///
+/// 距离不超过 `eps` 的点互为邻居，邻域内（含自身）不少于 `min_pts` 个点的为核心点；
+/// 从核心点出发可经核心点链到达的点属于同一类。以边长 `eps` 的网格划分空间查询邻域。
+/// 点按下标顺序处理，边界点归属于最先到达它的类，结果确定。
+fn dbscan(points: &[Point3<f64>], eps: f64, min_pts: usize) -> Vec<Option<usize>> {
+    let mut labels = vec![None; points.len()];
+    if points.is_empty() || eps.is_nan() || eps <= 0.0 {
+        return labels;
+    }
+    let cell = |p: &Point3<f64>| ((p.x / eps).floor() as i64, (p.y / eps).floor() as i64, (p.z / eps).floor() as i64);
+    let mut grid: HashMap<(i64, i64, i64), Vec<usize>> = HashMap::new();
+    for (k, p) in points.iter().enumerate() {
+        grid.entry(cell(p)).or_default().push(k);
+    }
+    let eps_sq = eps * eps;
+    let neighbors = |k: usize| -> Vec<usize> {
+        let (cx, cy, cz) = cell(&points[k]);
+        let mut result = Vec::new();
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                for dz in -1..=1 {
+                    if let Some(members) = grid.get(&(cx + dx, cy + dy, cz + dz)) {
+                        result.extend(members.iter().filter(|&&m| (points[m] - points[k]).norm_squared() <= eps_sq));
+                    }
+                }
+            }
+        }
+        result
+    };
+
+    let mut visited = vec![false; points.len()];
+    let mut num_clusters = 0;
+    for start in 0..points.len() {
+        if visited[start] {
+            continue;
+        }
+        visited[start] = true;
+        let seeds = neighbors(start);
+        if seeds.len() < min_pts {
+            continue;
+        }
+        let label = num_clusters;
+        num_clusters += 1;
+        // 点在入队时即标记，每个点最多入队一次；已访问过的点（此前判为噪声的边界点）只标记不扩展
+        let mut queue = Vec::new();
+        let mut enqueue = |members: Vec<usize>, queue: &mut Vec<usize>| {
+            for m in members {
+                if labels[m].is_none() {
+                    labels[m] = Some(label);
+                    queue.push(m);
+                }
+            }
+        };
+        enqueue(seeds, &mut queue);
+        while let Some(k) = queue.pop() {
+            if visited[k] {
+                continue;
+            }
+            visited[k] = true;
+            let expansion = neighbors(k);
+            if expansion.len() >= min_pts {
+                enqueue(expansion, &mut queue);
+            }
+        }
+    }
+    labels
+}
+
+/// 完整流程：`data` 为 (输入索引, 测量)，索引须严格递增
+#[cfg_attr(
+    feature = "trace",
+    tracing::instrument(name = "find_targets", skip_all, fields(measurements = tracing::field::Empty))
+)]
+pub(crate) fn run_pipeline<M: Borrow<Measurement>>(
+    data: impl IntoIterator<Item = (usize, M)>,
+    pipeline: &Pipeline,
+    rng: &mut dyn RandomSource,
+) -> (Vec<LocatedTarget>, FindTargetsDiagnostics) {
+    let mut diagnostics = FindTargetsDiagnostics::default();
+    let (all_lines, station_names, data_indices) = prepare_lines(data, &mut diagnostics.skipped);
+    #[cfg(feature = "trace")]
+    tracing::Span::current().record("measurements", all_lines.len() + diagnostics.skipped.len());
+    let mut located_targets = run_lines_pipeline(&all_lines, &station_names, pipeline, rng, &mut diagnostics);
+
+    // 光线索引 → 输入数据索引（映射单调，保持升序）
+    for target in &mut located_targets {
+        for i in target.inlier_indices.iter_mut().chain(&mut target.outlier_indices) {
+            *i = data_indices[*i];
+        }
+    }
+
+    (located_targets, diagnostics)
+}
+
+/// 在已转换的光线上运行完整流程（提取、精化、排序编号），`inlier_indices` 为光线索引
+fn run_lines_pipeline(
+    all_lines: &[Line],
+    station_names: &[String],
+    pipeline: &Pipeline,
+    rng: &mut dyn RandomSource,
+    diagnostics: &mut FindTargetsDiagnostics,
+) -> Vec<LocatedTarget> {
+    let mut located_targets = Vec::new();
+    let mut used_line_indices = HashSet::new();
+    let mut next_id = 1;
+
+    extract_targets(
+        all_lines,
+        station_names,
+        pipeline,
+        &mut used_line_indices,
+        &mut located_targets,
+        &mut next_id,
+        diagnostics,
+        rng,
+    );
+    located_targets = refine_targets(all_lines, station_names, pipeline, located_targets);
+    // 排序后按新顺序重新编号，使标识只取决于所选顺序
+    if pipeline.config.output_ordering != OutputOrdering::Discovery {
+        for (k, target) in located_targets.iter_mut().enumerate() {
+            target.index = k + 1;
+            target.id = pipeline.config.target_id(target.index);
+        }
+    }
+    diagnostics.record_results(all_lines.len(), &located_targets, pipeline);
+    #[cfg(feature = "trace")]
+    for target in &located_targets {
+        tracing::info!(
+            id = %target.id,
+            x = target.position.x,
+            y = target.position.y,
+            z = target.position.z,
+            lines = target.num_lines,
+            avg_residual = target.avg_error_dist_m,
+            max_residual = target.max_residual_m,
+            converged = target.converged,
+            "目标"
+        );
+    }
+    #[cfg(feature = "trace")]
+    tracing::info!(%diagnostics, "定位完成");
+
+    located_targets
+}
+
+/// 将测量转换为光线并为测量站标识分配编号
+///
+/// `data` 为 (输入索引, 测量)，只遍历一次，测量可以是借用或逐条产生的值。
+/// 返回光线、测量站名称及每条光线对应的测量索引；无效测量及原因追加到 `skipped`。
+pub(crate) fn prepare_lines<M: Borrow<Measurement>>(
+    data: impl IntoIterator<Item = (usize, M)>,
+    skipped: &mut Vec<(usize, OptiRadarError)>,
+) -> (Vec<Line>, Vec<String>, Vec<usize>) {
+    let data = data.into_iter();
+    let mut station_names: Vec<String> = Vec::new();
+    let mut station_lookup: HashMap<String, usize> = HashMap::new();
+    let mut data_indices = Vec::with_capacity(data.size_hint().0);
+    let mut all_lines = Vec::with_capacity(data.size_hint().0);
+    for (index, m) in data {
+        let m = m.borrow();
+        let mut line = match m.try_into_line() {
+            Ok(line) => line,
+            Err(error) => {
+                skipped.push((index, error));
+                continue;
+            }
+        };
+        line.station = m.station_id.as_deref().map(|id| match station_lookup.get(id) {
+            Some(&station) => station,
+            None => {
+                station_names.push(id.to_string());
+                station_lookup.insert(id.to_string(), station_names.len() - 1);
+                station_names.len() - 1
+            }
+        });
+        data_indices.push(index);
+        all_lines.push(line);
+    }
+    (all_lines, station_names, data_indices)
+}
+
+/// 贪心提取：在未使用的光线上反复运行一致集估计 + 精化，直到满足终止条件
+///
+/// `located_targets` 中已有的目标计入 `max_targets`，其光线须已记入 `used_line_indices`；
+/// 新目标的编号依次为 `next_id`，标识见 `FindTargetsConfig::target_id`。轮数与终止原因记录在 `diagnostics` 中。
+/// 各轮的一致集估计依次从 `rng` 取随机数。
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn extract_targets(
+    all_lines: &[Line],
+    station_names: &[String],
+    pipeline: &Pipeline,
+    used_line_indices: &mut HashSet<usize>,
+    located_targets: &mut Vec<LocatedTarget>,
+    next_id: &mut usize,
+    diagnostics: &mut FindTargetsDiagnostics,
+    rng: &mut dyn RandomSource,
+) {
+    let config = pipeline.config;
+    let mut round = 0;
+    // 未使用的光线索引（升序），每轮只剔除新目标的内点，不再复制剩余光线
+    let mut active: Vec<usize> = (0..all_lines.len()).filter(|i| !used_line_indices.contains(i)).collect();
+    // 因交会几何被拒绝的一致集的光线：暂不参与提取，下一个目标被接受后放回
+    let mut deferred: Vec<usize> = Vec::new();
+    let mut consecutive_rejections = 0;
+
+    diagnostics.stop_reason = loop {
+        if config.max_targets.is_some_and(|max| located_targets.len() >= max) {
+            break StopReason::MaxTargets;
+        }
+        if config.max_rounds.is_some_and(|max| round >= max) {
+            break StopReason::MaxRounds;
+        }
+
+        if active.len() < config.ransac.min_lines {
+            break StopReason::InsufficientLines;
+        }
+        if pipeline.progress.start_round(round + 1, active.len(), located_targets.len()).is_break() {
+            break StopReason::Cancelled;
+        }
+
+        round += 1;
+        diagnostics.rounds += 1;
+        #[cfg(feature = "trace")]
+        let _round = tracing::debug_span!("round", round, remaining = active.len()).entered();
+
+        let started = Instant::now();
+        let (model, stats) = pipeline.estimator.estimate_subset(all_lines, &active, rng);
+        diagnostics.ransac_ms += started.elapsed().as_secs_f64() * 1e3;
+        diagnostics.ransac_iterations.push(stats.iterations_run);
+        diagnostics.degenerate_samples_rejected += stats.degenerate_samples_rejected;
+        #[cfg(feature = "trace")]
+        tracing::debug!(inliers = model.as_ref().map_or(0, |(_, inliers)| inliers.len()), "一致集");
+        if pipeline.progress.cancelled() {
+            break StopReason::Cancelled;
+        }
+        let Some((initial_guess, actual_inliers_indices)) =
+            model.filter(|(_, inliers)| inliers.len() >= config.ransac.min_lines)
+        else {
+            break StopReason::NoConsensus;
+        };
+        if let Some(criterion) = &config.min_geometry {
+            let inlier_lines: Vec<_> = actual_inliers_indices.iter().map(|&i| all_lines[i]).collect();
+            if !criterion.accepts(&inlier_lines) {
+                diagnostics.geometry_rejections += 1;
+                consecutive_rejections += 1;
+                if consecutive_rejections > MAX_CONSECUTIVE_GEOMETRY_REJECTIONS {
+                    break StopReason::PoorGeometry;
+                }
+                active.retain(|i| actual_inliers_indices.binary_search(i).is_err());
+                deferred.extend(actual_inliers_indices);
+                continue;
+            }
+        }
+        consecutive_rejections = 0;
+        let target = fit_target(
+            *next_id,
+            all_lines,
+            actual_inliers_indices.clone(),
+            initial_guess,
+            pipeline,
+            station_names,
+        );
+        if let Some(min_quality) = config.min_inlier_quality {
+            if consensus_quality(all_lines, &target, config) < min_quality {
+                break StopReason::LowQuality;
+            }
+        }
+        // 精化后漂出关注区域的目标被丢弃，其光线同样不再参与提取，避免反复得到同一个模型
+        if in_bounds(&target, config) {
+            located_targets.push(target);
+            *next_id += 1;
+        }
+
+        for &i in &actual_inliers_indices {
+            used_line_indices.insert(i);
+        }
+        active.retain(|i| !used_line_indices.contains(i));
+        if !deferred.is_empty() {
+            active.extend(deferred.drain(..).filter(|i| !used_line_indices.contains(i)));
+            active.sort_unstable();
+        }
+    };
+
+    if config.ransac.min_lines <= 2
+        && matches!(diagnostics.stop_reason, StopReason::InsufficientLines | StopReason::NoConsensus)
+    {
+        extract_pairs(all_lines, station_names, pipeline, used_line_indices, located_targets, next_id, diagnostics);
+    }
+}
+
+/// 贪心提取中连续因交会几何被拒绝的一致集数上限，超过后停止提取
+const MAX_CONSECUTIVE_GEOMETRY_REJECTIONS: usize = 3;
+
+/// `min_lines` 不超过 2 时，RANSAC 结束后对剩余光线的两线交会回退
+///
+/// RANSAC 的最小样本为 3 条光线，只被两个测量站观测到的目标无法由其提取。
+/// 这里穷举剩余光线的两两组合（同一测量站的光线不配对），交会点到两条光线的残差
+/// 都在阈值内的作为候选，按两线最短距离从小到大贪心选取互不共用光线的配对。
+/// 得到的目标 `low_confidence` 为 true。
+fn extract_pairs(
+    all_lines: &[Line],
+    station_names: &[String],
+    pipeline: &Pipeline,
+    used_line_indices: &mut HashSet<usize>,
+    located_targets: &mut Vec<LocatedTarget>,
+    next_id: &mut usize,
+    diagnostics: &mut FindTargetsDiagnostics,
+) {
+    let config = pipeline.config;
+    let remaining: Vec<usize> = (0..all_lines.len()).filter(|i| !used_line_indices.contains(i)).collect();
+    let mut candidates = Vec::new();
+    for (k, &i) in remaining.iter().enumerate() {
+        for &j in &remaining[k + 1..] {
+            let (first, second) = (&all_lines[i], &all_lines[j]);
+            if first.station.is_some() && first.station == second.station {
+                continue;
+            }
+            let Some(fix) = triangulate_pair(first, second) else {
+                continue;
+            };
+            let consistent = within_bounds(&config.ransac, &fix.midpoint)
+                && config.min_geometry.is_none_or(|criterion| criterion.accepts(&[*first, *second]))
+                && [first, second].iter().all(|line| {
+                    config.ransac.residual_model.residual(line, &fix.midpoint, config.lm.ray_mode).norm()
+                        < config.ransac.threshold
+                });
+            if consistent {
+                candidates.push((fix.miss_distance, i, j, fix.midpoint));
+            }
+        }
+    }
+    // 稳定排序，距离相同时保持索引顺序，结果确定
+    candidates.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    for (_, i, j, midpoint) in candidates {
+        if used_line_indices.contains(&i) || used_line_indices.contains(&j) {
+            continue;
+        }
+        if config.max_targets.is_some_and(|max| located_targets.len() >= max) {
+            diagnostics.stop_reason = StopReason::MaxTargets;
+            break;
+        }
+        let target = fit_target(*next_id, all_lines, vec![i, j], midpoint, pipeline, station_names);
+        if let Some(min_quality) = config.min_inlier_quality {
+            if consensus_quality(all_lines, &target, config) < min_quality {
+                continue;
+            }
+        }
+        if !in_bounds(&target, config) {
+            continue;
+        }
+        located_targets.push(target);
+        *next_id += 1;
+        used_line_indices.insert(i);
+        used_line_indices.insert(j);
+    }
+}
+
+/// 以 EM 软分配精化 `initial_targets`（通常为 `find_targets` 的结果，`inlier_indices` 为 `measurements` 中的索引）
+///
+/// 贪心提取按硬阈值逐个移除内点，相互靠近的目标争夺同一批光线时，先提取的目标容易吞掉另一目标的光线。
+/// EM 只使用初始目标的位置，对全部光线重新做软分配（见 `em_refine`），参数取 `config.em`，未设置时取默认值。
+/// 结果保持输入顺序与标识，精化后超出关注区域或光线数不足的目标被丢弃；无效测量被忽略。
+pub fn refine_targets_em(
+    measurements: &[Measurement],
+    initial_targets: &[LocatedTarget],
+    config: &FindTargetsConfig,
+) -> Vec<LocatedTarget> {
+    let mut skipped = Vec::new();
+    let (all_lines, station_names, data_indices) = prepare_lines(measurements.iter().enumerate(), &mut skipped);
+    let pipeline = Pipeline::new(config);
+    let em = config.em.unwrap_or_default();
+    let mut targets = em_refine(&all_lines, initial_targets.to_vec(), &pipeline, &station_names, &em);
+    targets.retain(|target| in_bounds(target, config));
+    for target in &mut targets {
+        for i in target.inlier_indices.iter_mut().chain(&mut target.outlier_indices) {
+            *i = data_indices[*i];
+        }
+    }
+    targets
+}
+
+/// 目标是否保留：未设置关注区域、选择截断，或位置在区域内
+fn in_bounds(target: &LocatedTarget, config: &FindTargetsConfig) -> bool {
+    config.out_of_bounds == OutOfBounds::Clamp || within_bounds(&config.ransac, &target.position)
+}
+
+/// 贪心提取后的后处理：按配置合并过近的目标、全局重新分配光线、EM 软分配精化、丢弃漂出关注区域的目标并排序
+///
+/// 进度回调已取消定位时跳过合并、重新分配与 EM。
+pub(crate) fn refine_targets(
+    all_lines: &[Line],
+    station_names: &[String],
+    pipeline: &Pipeline,
+    mut located_targets: Vec<LocatedTarget>,
+) -> Vec<LocatedTarget> {
+    // 取消后或匀速运动模型下（这些步骤按静止模型计算残差）不做合并、重新分配与 EM 精化
+    let skip = pipeline.progress.cancelled() || pipeline.config.motion_model != MotionModel::Static;
+    if pipeline.config.min_separation_m > 0.0 && !skip {
+        located_targets = merge_close_targets(all_lines, located_targets, pipeline, station_names);
+    }
+
+    if pipeline.config.reassignment_passes > 0 && !skip {
+        located_targets = reassign_lines(all_lines, located_targets, pipeline, station_names);
+    }
+    if let Some(em) = pipeline.config.em.as_ref().filter(|_| !skip) {
+        located_targets = em_refine(all_lines, located_targets, pipeline, station_names, em);
+    }
+    located_targets.retain(|target| in_bounds(target, pipeline.config));
+    order_targets(&mut located_targets, pipeline.config.output_ordering);
+    located_targets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra::{DMatrix, DVector, UnitQuaternion};
+    use rand::prelude::*;
+    use rand_distr::{Distribution, Normal};
+
+    /// 构造完整 3n×3 雅可比的 LM 参考实现，用于验证 3×3 累加版本
+    fn dense_reference_lm(lines: &[Line], initial_guess: Point3<f64>, config: &LmConfig) -> Point3<f64> {
+        let mut current_pos = initial_guess;
+        let mut lambda = config.initial_lambda;
+        for _ in 0..config.iterations {
+            let n = lines.len();
+            let mut j = DMatrix::zeros(3 * n, 3);
+            let mut e = DVector::zeros(3 * n);
+            let mut current_error_sq = 0.0;
+            for (i, line) in lines.iter().enumerate() {
+                let raw_vec = residual_vector(line, &current_pos, config.ray_mode);
+                let r = raw_vec.norm();
+                current_error_sq += line.weight * config.robust_loss.cost(r);
+                let sqrt_w = (line.weight * config.robust_loss.weight(r)).sqrt();
+                e.rows_mut(3 * i, 3)
+                    .copy_from(&DVector::from_column_slice((raw_vec * sqrt_w).as_slice()));
+                j.view_mut((3 * i, 0), (3, 3))
+                    .copy_from(&(residual_jacobian(line, &current_pos, config.ray_mode) * sqrt_w));
+            }
+            let j_t = j.transpose();
+            let h_lm = &j_t * &j + Matrix3::identity() * lambda;
+            let b = &j_t * &e;
+            let delta = match h_lm.try_inverse() {
+                Some(inv_h) => inv_h * -b,
+                None => {
+                    lambda *= 10.0;
+                    continue;
+                }
+            };
+            let new_pos = current_pos + Vector3::new(delta[0], delta[1], delta[2]);
+            let new_error_sq: f64 = lines
+                .iter()
+                .map(|l| l.weight * config.robust_loss.cost(residual_vector(l, &new_pos, config.ray_mode).norm()))
+                .sum();
+            if new_error_sq < current_error_sq {
+                current_pos = new_pos;
+                lambda *= 0.1;
+            } else {
+                lambda *= 10.0;
+            }
+        }
+        current_pos
+    }
+
+    #[test]
+    fn test_line_from_measurement_normalization() {
+        let measurement = Measurement::new(0.0, 0.0, 0.0, 3.0, 4.0, 0.0);
+        let line = Line::from_measurement(&measurement);
+        assert!((line.direction.norm() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_array_conversions_round_trip() {
+        let m = Measurement::from_arrays([1.0, -2.0, 3.5], [0.0, 3.0, 4.0]);
+        let values: [f64; 6] = (&m).into();
+        assert_eq!(values, [1.0, -2.0, 3.5, 0.0, 3.0, 4.0]);
+        let back = Measurement::try_from(&values[..]).unwrap();
+        assert_eq!(<[f64; 6]>::from(&back), values);
+        let from_tuple: Measurement = ([1.0, -2.0, 3.5], [0.0, 3.0, 4.0]).into();
+        assert_eq!(<[f64; 6]>::from(&from_tuple), values);
+        assert!(matches!(
+            Measurement::try_from(&values[..5]),
+            Err(OptiRadarError::InvalidParameter { name: "values.len", value }) if value == 5.0
+        ));
+
+        // 两点确定的光线与对应测量转换得到的光线相同
+        let a = Point3::new(1.0, -2.0, 3.5);
+        let line = Line::from_points(a, Point3::new(1.0, 1.0, 7.5)).unwrap();
+        assert_eq!(line.start, a);
+        assert_eq!(line.direction, Line::from_measurement(&m).direction);
+        assert_eq!(Line::from_points(a, a).unwrap_err(), OptiRadarError::ZeroDirection);
+        assert_eq!(
+            Line::from_points(Point3::new(f64::NAN, 0.0, 0.0), a).unwrap_err(),
+            OptiRadarError::NonFiniteStation
+        );
+        assert_eq!(
+            Line::from_points(a, Point3::new(0.0, f64::INFINITY, 0.0)).unwrap_err(),
+            OptiRadarError::NonFiniteDirection
+        );
+    }
+
+    #[test]
+    fn test_az_el_round_trip() {
+        // 方位角 90°（正东），俯仰角 0°
+        let east = Line::from_measurement(&Measurement::from_az_el_deg(0.0, 0.0, 0.0, 90.0, 0.0));
+        assert!((east.direction - Vector3::new(1.0, 0.0, 0.0)).norm() < 1e-12);
+
+        for &(az, el) in &[(0.0, 0.0), (45.0, 10.0), (200.0, -30.0), (359.5, 60.0)] {
+            let line = Line::from_measurement(&Measurement::from_az_el_deg(1.0, 2.0, 3.0, az, el));
+            let (az_back, el_back) = line.azimuth_elevation_deg();
+            assert!((az_back - az).abs() < 1e-9, "{} vs {}", az_back, az);
+            assert!((el_back - el).abs() < 1e-9);
+        }
+
+        // 方位角超过 360° 时按周期回绕
+        let wrapped = Line::from_measurement(&Measurement::from_az_el_deg(0.0, 0.0, 0.0, 370.0, 5.0));
+        let (az_back, _) = wrapped.azimuth_elevation_deg();
+        assert!((az_back - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_az_el_vertical() {
+        for &el in &[90.0, -90.0] {
+            let line = Line::from_measurement(&Measurement::from_az_el_deg(0.0, 0.0, 0.0, 123.0, el));
+            assert!(line.direction.iter().all(|v| v.is_finite()));
+            assert!((line.direction.z - el.signum()).abs() < 1e-12);
+            let (az_back, el_back) = line.azimuth_elevation_deg();
+            assert!(az_back.is_finite());
+            assert!((el_back - el).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_from_geodetic_wide_baseline() {
+        // 测量站相距 30~60 公里，平面近似在此尺度下误差可达数百米
+        let origin = Geodetic::from_degrees(30.0, 120.0, 0.0);
+        let target = Geodetic::from_degrees(30.1, 120.2, 5000.0);
+        let stations = [(29.8, 120.0, 10.0), (30.3, 119.9, 200.0), (30.0, 120.5, 50.0), (30.4, 120.4, 0.0)];
+        let data: Vec<Measurement> = stations
+            .iter()
+            .map(|&(lat, lon, alt)| {
+                // 目标在测量站当地 ENU 中的方位角/俯仰角
+                let local = coords::geodetic_to_enu(&target, &Geodetic::from_degrees(lat, lon, alt));
+                let azimuth = local.x.atan2(local.y).to_degrees();
+                let elevation = local.z.atan2(local.x.hypot(local.y)).to_degrees();
+                Measurement::from_geodetic_deg(lat, lon, alt, azimuth, elevation, &origin)
+            })
+            .collect();
+
+        let mut config = FindTargetsConfig::new(1.0, 3);
+        config.ransac.seed = Some(1);
+        let located = find_targets_with_config(&data, &config);
+        assert_eq!(located.len(), 1);
+        let expected = coords::geodetic_to_enu(&target, &origin);
+        assert!((located[0].position - expected).norm() < 1e-3, "{}", (located[0].position - expected).norm());
+
+        let back = located[0].to_geodetic(&origin);
+        assert!((back.latitude_deg() - 30.1).abs() < 1e-9);
+        assert!((back.longitude_deg() - 120.2).abs() < 1e-9);
+        assert!((back.altitude - 5000.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_body_frame_measurements() {
+        // 偏航 90°：本体前向指向正北
+        let yaw = std::f64::consts::FRAC_PI_2;
+        let mount = StationMount::from_yaw_pitch_roll(yaw, 0.0, 0.0);
+        let forward = Line::from_measurement(&Measurement::from_mounted(Point3::origin(), Vector3::x(), &mount));
+        assert!((forward.direction - Vector3::y()).norm() < 1e-12);
+        assert!(mount.attitude.angle_to(&UnitQuaternion::from_axis_angle(&Vector3::z_axis(), yaw)) < 1e-12);
+
+        // 各测量站偏航 90° 且带杆臂，按本体系给出的方向生成的光线须经过目标
+        let target = Point3::new(300.0, 800.0, 120.0);
+        let mount = mount.with_lever_arm(Vector3::new(0.5, -0.2, 1.5));
+        let stations = [Point3::new(0.0, 0.0, 0.0), Point3::new(1000.0, 0.0, 5.0), Point3::new(0.0, 1500.0, 0.0)];
+        let body_directions: Vec<Vector3<f64>> = stations
+            .iter()
+            .map(|&station| mount.attitude.inverse() * (target - (station + mount.attitude * mount.lever_arm)))
+            .collect();
+        let data: Vec<Measurement> = stations
+            .iter()
+            .zip(&body_directions)
+            .map(|(&station, &body_direction)| Measurement::from_mounted(station, body_direction, &mount))
+            .collect();
+        for m in &data {
+            assert!(Line::from_measurement(m).distance_to_point(&target) < 1e-9);
+        }
+        // 忽略杆臂时光线偏离目标
+        let without = Measurement::from_body_frame(stations[0], body_directions[0], mount.attitude);
+        assert!(Line::from_measurement(&without).distance_to_point(&target) > 0.1);
+
+        let mut config = FindTargetsConfig::new(0.1, 3);
+        config.ransac.seed = Some(1);
+        let located = find_targets_with_config(&data, &config);
+        assert_eq!(located.len(), 1);
+        assert!((located[0].position - target).norm() < 1e-6);
+    }
+
+    #[test]
+    fn test_find_closest_midpoint() {
+        let line1 = Line::new(Point3::new(0.0, 5.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+        let line2 = Line::new(Point3::new(5.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0));
+        let midpoint = find_closest_midpoint(&line1, &line2);
+        let epsilon = 1e-6;
+        assert!((midpoint.x - 5.0).abs() < epsilon);
+        assert!((midpoint.y - 5.0).abs() < epsilon);
+        assert!((midpoint.z - 0.0).abs() < epsilon);
+    }
+
+    #[test]
+    fn test_line_point_utilities() {
+        let line = Line::new(Point3::new(1.0, 2.0, 3.0), Vector3::new(0.0, 3.0, 4.0));
+        let point = Point3::new(4.0, 2.0, 8.0);
+        let t = line.closest_parameter(&point);
+        assert!((t - 4.0).abs() < 1e-12);
+        assert!((line.point_at(t) - Point3::new(1.0, 4.4, 6.2)).norm() < 1e-12);
+        let perpendicular = line.perpendicular_vector_to(&point);
+        assert!((perpendicular - Vector3::new(3.0, -2.4, 1.8)).norm() < 1e-12);
+        assert!(perpendicular.dot(&line.direction).abs() < 1e-12);
+        assert!((line.distance_to_point(&point) - perpendicular.norm()).abs() < 1e-12);
+        // 无限长直线：起点背后的点同样按垂直距离计算
+        assert!((line.distance_to_point(&line.point_at(-10.0)) - 0.0).abs() < 1e-12);
+
+        let mut heavy = Line::new(Point3::origin(), Vector3::x());
+        heavy.weight = 2.0;
+        let lines = [line, heavy];
+        let expected = perpendicular.norm_squared() + 2.0 * (2.0f64 * 2.0 + 8.0 * 8.0);
+        assert!((sum_squared_distance(&lines, &point) - expected).abs() < 1e-9);
+        // 线性三角定位的解使距离平方和最小
+        let lines = [line, heavy, Line::new(Point3::new(0.0, 0.0, 10.0), Vector3::y())];
+        let best = linear_triangulate(&lines).unwrap();
+        for step in [Vector3::x(), Vector3::y(), Vector3::z()] {
+            assert!(sum_squared_distance(&lines, &(best + step * 0.1)) > sum_squared_distance(&lines, &best));
+        }
+    }
+
+    #[test]
+    fn test_closest_point_between() {
+        let line1 = Line::new(Point3::new(0.0, 5.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+        let line2 = Line::new(Point3::new(5.0, 0.0, 2.0), Vector3::new(0.0, 1.0, 0.0));
+        match line1.closest_point_between(&line2) {
+            ClosestApproach::Points { midpoint, point_on_self, point_on_other, s, t, gap } => {
+                let epsilon = 1e-9;
+                assert!((point_on_self - Point3::new(5.0, 5.0, 0.0)).norm() < epsilon);
+                assert!((point_on_other - Point3::new(5.0, 5.0, 2.0)).norm() < epsilon);
+                assert!((midpoint - Point3::new(5.0, 5.0, 1.0)).norm() < epsilon);
+                assert!((s - 5.0).abs() < epsilon);
+                assert!((t - 5.0).abs() < epsilon);
+                assert!((gap - 2.0).abs() < epsilon);
+            }
+            ClosestApproach::Parallel { .. } => panic!("非平行线被判为平行"),
+        }
+
+        let line3 = Line::new(Point3::new(7.0, 8.0, 4.0), Vector3::new(-2.0, 0.0, 0.0));
+        assert_eq!(line1.closest_point_between(&line3), ClosestApproach::Parallel { gap: 5.0 });
+    }
+
+    #[test]
+    fn test_triangulate_pair() {
+        let line1 = Line::new(Point3::new(0.0, 5.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+        let line2 = Line::new(Point3::new(5.0, 0.0, 2.0), Vector3::new(0.0, 1.0, 0.0));
+        let fix = triangulate_pair(&line1, &line2).unwrap();
+        let epsilon = 1e-9;
+        assert!((fix.midpoint - Point3::new(5.0, 5.0, 1.0)).norm() < epsilon);
+        assert!((fix.point_on_first - Point3::new(5.0, 5.0, 0.0)).norm() < epsilon);
+        assert!((fix.point_on_second - Point3::new(5.0, 5.0, 2.0)).norm() < epsilon);
+        assert!((fix.miss_distance - 2.0).abs() < epsilon);
+        assert!((fix.crossing_angle - std::f64::consts::FRAC_PI_2).abs() < epsilon);
+
+        // 夹角取锐角，与方向正负无关
+        let line3 = Line::new(Point3::new(0.0, 0.0, 0.0), Vector3::new(-1.0, -1.0, 0.0));
+        let fix = triangulate_pair(&line1, &line3).unwrap();
+        assert!((fix.crossing_angle - std::f64::consts::FRAC_PI_4).abs() < epsilon);
+        assert!((fix.midpoint - Point3::new(5.0, 5.0, 0.0)).norm() < epsilon);
+        assert!(fix.miss_distance < epsilon);
+
+        let parallel = Line::new(Point3::new(7.0, 8.0, 4.0), Vector3::new(-2.0, 0.0, 0.0));
+        assert_eq!(triangulate_pair(&line1, &parallel), None);
+    }
+
+    #[test]
+    fn test_ransac_fit_lines() {
+        let mut lines = Vec::new();
+        for _ in 0..10 {
+            let start = Point3::new(
+                thread_rng().gen_range(0.0..5.0),
+                thread_rng().gen_range(15.0..25.0),
+                thread_rng().gen_range(25.0..35.0),
+            );
+            let direction = (Point3::new(10.0, 20.0, 30.0) - start).normalize();
+            lines.push(Line::new(start, direction));
+        }
+        for _ in 0..5 {
+            let start = Point3::new(
+                thread_rng().gen_range(-50.0..50.0),
+                thread_rng().gen_range(-50.0..50.0),
+                thread_rng().gen_range(-50.0..50.0),
+            );
+            let direction =
+                Vector3::new(thread_rng().gen(), thread_rng().gen(), thread_rng().gen()).normalize();
+            lines.push(Line::new(start, direction));
+        }
+
+        let (result, stats) = ransac_fit_lines(&lines, &RansacConfig::new(100, 1.0, 3));
+        assert!(result.is_some());
+        assert!(stats.iterations_run <= 100);
+
+        if let Some((initial_guess, inliers_indices)) = result {
+            assert!(inliers_indices.len() >= 8);
+            let epsilon = 1e-1;
+            assert!((initial_guess.x - 10.0).abs() < epsilon);
+            assert!((initial_guess.y - 20.0).abs() < epsilon);
+            assert!((initial_guess.z - 30.0).abs() < epsilon);
+        }
+    }
+
+    #[test]
+    fn test_ransac_rejects_degenerate_samples() {
+        // 10 条紧密排列的平行光线：若不拒绝退化样本，其平均点会收集到 10 个“内点”
+        let mut lines = Vec::new();
+        for i in 0..10 {
+            let start = Point3::new(0.0, 100.0 + 0.2 * i as f64, 50.0);
+            lines.push(Line::new(start, Vector3::new(1.0, 0.0, 0.0)));
+        }
+        // 5 条交于 (10, 20, 30) 的光线
+        let target = Point3::new(10.0, 20.0, 30.0);
+        for start in [
+            Point3::new(-40.0, 0.0, 0.0),
+            Point3::new(60.0, -10.0, 0.0),
+            Point3::new(0.0, 80.0, 0.0),
+            Point3::new(30.0, 60.0, -20.0),
+            Point3::new(-20.0, -50.0, 10.0),
+        ] {
+            lines.push(Line::new(start, target - start));
+        }
+
+        let mut config = RansacConfig::new(1000, 1.0, 3);
+        config.seed = Some(5);
+        let (result, stats) = ransac_fit_lines(&lines, &config);
+        let (position, inliers) = result.unwrap();
+        assert_eq!(inliers, vec![10, 11, 12, 13, 14]);
+        assert!((position - target).norm() < 1e-6);
+        assert!(stats.degenerate_samples_rejected > 0);
+
+        // 关闭退化检测时平行光线束占优
+        config.max_parallel_cos = f64::INFINITY;
+        config.max_condition_number = f64::INFINITY;
+        let (result, stats) = ransac_fit_lines(&lines, &config);
+        assert!(result.unwrap().1.len() > 5);
+        assert_eq!(stats.degenerate_samples_rejected, 0);
+    }
+
+    #[test]
+    fn test_ransac_adaptive_termination() {
+        // 所有光线都经过同一点，首个样本即为全内点，应远早于上限终止
+        let target = Point3::new(10.0, 20.0, 30.0);
+        let mut lines = Vec::new();
+        for i in 0..20 {
+            let angle = i as f64 * 0.3;
+            let start = Point3::new(angle.cos() * 50.0, angle.sin() * 50.0, 0.0);
+            lines.push(Line::new(start, target - start));
+        }
+
+        let (result, stats) = ransac_fit_lines(&lines, &RansacConfig::new(1000, 1.0, 3));
+        assert!(result.is_some());
+        assert_eq!(stats.required_iterations, 1);
+        assert_eq!(stats.iterations_run, 1);
+    }
+
+    #[test]
+    fn test_draw_three_distinct_uniform() {
+        // 5 选 3 共 10 种组合，每种出现的频率应接近 1/10
+        let mut rng = SplitMix64::new(1);
+        let mut counts = HashMap::new();
+        let draws = 100_000;
+        for _ in 0..draws {
+            let sample = draw_three_distinct(&mut rng, 5);
+            assert!(sample[0] < sample[1] && sample[1] < sample[2] && sample[2] < 5);
+            *counts.entry(sample).or_insert(0) += 1;
+        }
+        assert_eq!(counts.len(), 10);
+        for (sample, count) in counts {
+            let frequency = count as f64 / draws as f64;
+            assert!((frequency - 0.1).abs() < 0.005, "{:?}: {}", sample, frequency);
+        }
+        assert_eq!(draw_three_distinct(&mut rng, 3), [0, 1, 2]);
+    }
+
+    #[test]
+    fn test_ransac_exhaustive() {
+        // 5 条交于一点的光线 + 3 条平行的杂波光线，共 C(8,3) = 56 个三元组
+        let target = Point3::new(10.0, 20.0, 30.0);
+        let mut lines = Vec::new();
+        for i in 0..5 {
+            let angle = i as f64 * 1.2;
+            let start = Point3::new(angle.cos() * 50.0, angle.sin() * 50.0, 0.0);
+            lines.push(Line::new(start, target - start));
+        }
+        for i in 0..3 {
+            let start = Point3::new(0.0, -100.0 + 5.0 * i as f64, 10.0);
+            lines.push(Line::new(start, Vector3::new(1.0, 0.0, 0.0)));
+        }
+
+        let mut config = RansacConfig::new(10, 1.0, 3);
+        let (result, stats) = ransac_fit_lines(&lines, &config);
+        let (position, inliers) = result.unwrap();
+        assert_eq!(inliers, vec![0, 1, 2, 3, 4]);
+        assert!((position - target).norm() < 1e-6);
+        // 穷举不受 max_iterations 限制，且与退化检测共用同一判据
+        assert_eq!((stats.iterations_run, stats.required_iterations), (56, 56));
+        assert_eq!(stats.degenerate_samples_rejected, 1);
+
+        // 与种子无关
+        for seed in [1, 2] {
+            config.seed = Some(seed);
+            let (other, _) = ransac_fit_lines(&lines, &config);
+            assert_eq!(other.unwrap(), (position, inliers.clone()));
+        }
+
+        // 超过阈值时回到随机抽样，显式放宽阈值时强制穷举
+        let mut config = RansacConfig::new(10, 1.0, 3);
+        config.exhaustive_max_lines = 7;
+        assert!(ransac_fit_lines(&lines, &config).1.iterations_run <= 10);
+        config.exhaustive_max_lines = usize::MAX;
+        lines.extend(lines.clone());
+        assert_eq!(ransac_fit_lines(&lines, &config).1.iterations_run, 560);
+    }
+
+    #[test]
+    fn test_ransac_msac_scoring() {
+        // 前 4 条光线各自偏离 q 约 8 米，拼凑出一个松散的四内点候选；
+        // 后 4 条光线精确交于 target。两个候选的内点数相同（阈值 20 米）
+        let q = Point3::new(-2000.0, 0.0, 100.0);
+        let target = Point3::new(2000.0, 0.0, 100.0);
+        let offsets = [
+            Vector3::new(0.0, 8.0, 0.0),
+            Vector3::new(0.0, 0.0, 8.0),
+            Vector3::new(0.0, -8.0, 0.0),
+            Vector3::new(0.0, 0.0, -8.0),
+        ];
+        let mut lines = Vec::new();
+        for (i, offset) in offsets.iter().enumerate() {
+            let angle = i as f64 * 1.5;
+            let start = q + Vector3::new(300.0 * angle.cos(), 300.0 * angle.sin(), -100.0);
+            lines.push(Line::new(start, q + offset - start));
+        }
+        for i in 0..4 {
+            let angle = i as f64 * 1.5;
+            let start = target + Vector3::new(300.0 * angle.cos(), 300.0 * angle.sin(), -100.0);
+            lines.push(Line::new(start, target - start));
+        }
+        let config = RansacConfig::new(100, 20.0, 3);
+
+        // 内点计数在穷举时保留先找到的松散候选
+        let (result, _) = ransac_fit_lines(&lines, &config);
+        let (counted, inliers) = result.unwrap();
+        assert_eq!(inliers, vec![0, 1, 2, 3]);
+        assert!((counted - q).norm() < 20.0);
+
+        // MSAC 按残差区分，选中精确交会的候选
+        let mut msac = config.clone();
+        msac.scoring = RansacScoring::Msac;
+        let (result, _) = ransac_fit_lines(&lines, &msac);
+        let (scored, inliers) = result.unwrap();
+        assert_eq!(inliers, vec![4, 5, 6, 7]);
+        assert!((scored - target).norm() < 1e-6);
+        assert!(
+            model_score(&lines, lines.len(), &(scored, inliers), &msac)
+                < model_score(&lines, lines.len(), &(counted, vec![0, 1, 2, 3]), &msac)
+        );
+
+        let config = FindTargetsConfig::new(20.0, 3).with_scoring(RansacScoring::Msac);
+        assert_eq!(config.ransac.scoring, RansacScoring::Msac);
+    }
+
+    #[test]
+    fn test_lmeds_on_clutter_heavy_data() {
+        // 14 条带约 1 米噪声的目标光线 + 10 条从目标旁 50~300 米处经过的杂波光线
+        let target = Point3::new(0.0, 0.0, 500.0);
+        let mut rng = StdRng::seed_from_u64(4);
+        let normal = Normal::new(0.0, 1.0).unwrap();
+        let mut data = Vec::new();
+        for i in 0..24 {
+            let angle = i as f64 * 0.7;
+            let start = Point3::new(1500.0 * angle.cos(), 1500.0 * angle.sin(), 0.0);
+            let aim = if i < 14 {
+                target + Vector3::from_fn(|_, _| normal.sample(&mut rng))
+            } else {
+                let offset = Vector3::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0));
+                target + offset.normalize() * rng.gen_range(50.0..300.0)
+            };
+            let d = aim - start;
+            data.push(Measurement::new(start.x, start.y, start.z, d.x, d.y, d.z));
+        }
+        let lines: Vec<_> = data.iter().map(Line::from_measurement).collect();
+
+        // 阈值取得过大时 RANSAC 把杂波当作内点
+        let mut config = RansacConfig::new(200, 400.0, 3);
+        config.seed = Some(1);
+        let (ransac, _) = ransac_fit_lines(&lines, &config);
+        assert!(ransac.unwrap().1.iter().any(|&i| i >= 14));
+
+        // LMedS 不使用阈值，内点全部来自目标
+        let (lmeds, stats) = lmeds_fit_lines(&lines, &config);
+        let (position, inliers) = lmeds.unwrap();
+        assert!(inliers.iter().all(|&i| i < 14), "{:?}", inliers);
+        assert!(inliers.len() >= 12, "{:?}", inliers);
+        assert!((position - target).norm() < 5.0);
+        assert_eq!(stats.iterations_run, 200);
+
+        // 经 find_targets 比较两种估计方法的定位误差
+        let mut config = FindTargetsConfig::new(400.0, 3);
+        config.ransac.seed = Some(1);
+        config.max_targets = Some(1);
+        let ransac = find_targets_with_config(&data, &config);
+        let lmeds = find_targets_with_config(&data, &config.clone().with_estimator(RobustEstimator::Lmeds));
+        let ransac_error = (ransac[0].position - target).norm();
+        let lmeds_error = (lmeds[0].position - target).norm();
+        assert!(lmeds_error < 1.0, "{}", lmeds_error);
+        assert!(ransac_error > 5.0 * lmeds_error, "{} vs {}", ransac_error, lmeds_error);
+    }
+
+    #[test]
+    fn test_required_iterations() {
+        // 内点率 0.5，样本大小 3，置信度 0.99：ln(0.01)/ln(0.875) ≈ 34.5
+        assert_eq!(required_iterations(0.5, 0.99, 3, 1000), 35);
+        assert_eq!(required_iterations(0.5, 0.99, 3, 20), 20);
+        assert_eq!(required_iterations(0.0, 0.99, 3, 100), 100);
+    }
+
+    #[test]
+    fn test_find_targets_residual_breakdown() {
+        // 四条光线交于一点，另一条光线带有约 2 米的偏差
+        let target = Point3::new(0.0, 0.0, 10.0);
+        let mut measurements = Vec::new();
+        for i in 0..5 {
+            let angle = i as f64 * 1.2;
+            let start = Point3::new(100.0 * angle.cos(), 100.0 * angle.sin(), 0.0);
+            let aim = if i == 4 { target + Vector3::new(0.0, 0.0, 2.0) } else { target };
+            let direction = aim - start;
+            measurements.push(Measurement::new(
+                start.x,
+                start.y,
+                start.z,
+                direction.x,
+                direction.y,
+                direction.z,
+            ));
+        }
+
+        let located = find_targets(&measurements, 5.0, 3);
+        assert_eq!(located.len(), 1);
+        let target = &located[0];
+        assert_eq!(target.residuals.len(), target.num_lines);
+        let max = target.residuals.iter().copied().fold(0.0, f64::max);
+        assert_eq!(target.max_residual_m, max);
+        assert!(target.max_residual_m >= target.avg_error_dist_m);
+
+        // 最大残差对应偏差的那条光线
+        let worst = target
+            .residuals
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .map(|(i, _)| target.inlier_indices[i]);
+        assert_eq!(worst, Some(4));
+    }
+
+    #[test]
+    fn test_confidence_score() {
+        // 光线从四周指向目标点附近，偏离量为 offset（垂直于光线的随机方向）
+        let mut rng = StdRng::seed_from_u64(11);
+        let mut rays = |target: Point3<f64>, n: usize, offset: f64| -> Vec<Measurement> {
+            (0..n)
+                .map(|k| {
+                    let angle = k as f64 * TAU / n as f64 + 0.3;
+                    let start = target + Vector3::new(100.0 * angle.cos(), 100.0 * angle.sin(), -target.z);
+                    let d = (target - start).normalize();
+                    let side = d.cross(&Vector3::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0), 1.0));
+                    let aim = target + side.normalize() * offset;
+                    let d = aim - start;
+                    Measurement::new(start.x, start.y, start.z, d.x, d.y, d.z)
+                })
+                .collect()
+        };
+        let tight = Point3::new(0.0, 0.0, 30.0);
+        let marginal = Point3::new(500.0, 500.0, 30.0);
+        let mut data = rays(tight, 12, 0.02);
+        data.extend(rays(marginal, 3, 0.7));
+
+        let mut config = FindTargetsConfig::new(1.0, 3);
+        config.ransac.seed = Some(1);
+        let located = find_targets_with_config(&data, &config);
+        assert_eq!(located.len(), 2);
+        let score = |p: Point3<f64>| located.iter().find(|t| (t.position - p).norm() < 5.0).unwrap().confidence;
+        let (tight_score, marginal_score) = (score(tight), score(marginal));
+        assert!(tight_score > 0.6 && tight_score <= 1.0, "{}", tight_score);
+        assert!((0.0..0.2).contains(&marginal_score), "{}", marginal_score);
+
+        // 6 条勉强相交的光线先被发现，按可信度排序时排在 4 条精确光线之后
+        let mut data = rays(tight, 4, 0.02);
+        data.extend(rays(marginal, 6, 0.7));
+        let discovery = find_targets_with_config(&data, &config);
+        assert_eq!(discovery.iter().map(|t| t.num_lines).collect::<Vec<_>>(), vec![6, 4]);
+        config.output_ordering = OutputOrdering::ByConfidenceDesc;
+        let sorted = find_targets_with_config(&data, &config);
+        assert_eq!(sorted.iter().map(|t| t.num_lines).collect::<Vec<_>>(), vec![4, 6]);
+        assert!(sorted[0].confidence > sorted[1].confidence);
+    }
+
+    #[test]
+    fn test_output_ordering() {
+        // 4 个目标，光线数各不相同
+        let targets = [
+            (Point3::new(300.0, 0.0, 20.0), 4),
+            (Point3::new(-200.0, 100.0, 25.0), 7),
+            (Point3::new(0.0, 0.0, 30.0), 5),
+            (Point3::new(100.0, -400.0, 15.0), 6),
+        ];
+        let mut data = Vec::new();
+        for &(target, n) in &targets {
+            for k in 0..n {
+                let angle = k as f64 * TAU / n as f64;
+                let start = Point3::new(target.x + 80.0 * angle.cos(), target.y + 80.0 * angle.sin(), 0.0);
+                let d = target - start;
+                data.push(Measurement::new(start.x, start.y, start.z, d.x, d.y, d.z));
+            }
+        }
+
+        let run = |ordering: OutputOrdering, seed: u64| {
+            let mut config = FindTargetsConfig::new(1.0, 3);
+            config.ransac.seed = Some(seed);
+            config.ransac.exhaustive_max_lines = 0;
+            config.output_ordering = ordering;
+            find_targets_with_config(&data, &config)
+        };
+        let summary = |located: &[LocatedTarget]| -> Vec<(String, usize)> {
+            located.iter().map(|t| (t.id.clone(), t.num_lines)).collect()
+        };
+
+        let by_lines = run(OutputOrdering::ByNumLinesDesc, 1);
+        let expected: Vec<(String, usize)> =
+            [7, 6, 5, 4].iter().enumerate().map(|(k, &n)| (format!("Target_{}", k + 1), n)).collect();
+        assert_eq!(summary(&by_lines), expected);
+
+        // 按位置排序：与种子无关，标识随顺序分配
+        let by_position = run(OutputOrdering::ByPosition, 1);
+        assert_eq!(by_position.iter().map(|t| t.num_lines).collect::<Vec<_>>(), vec![7, 5, 6, 4]);
+        assert_eq!(by_position[0].id, "Target_1");
+        for seed in 2..6 {
+            let other = run(OutputOrdering::ByPosition, seed);
+            assert_eq!(summary(&other), summary(&by_position));
+        }
+
+        let by_confidence = run(OutputOrdering::ByConfidenceDesc, 1);
+        assert!(by_confidence.windows(2).all(|w| w[0].confidence >= w[1].confidence));
+        assert_eq!(by_confidence[0].id, "Target_1");
+
+        // 默认仍为发现顺序：光线最多的目标最先被发现
+        assert_eq!(FindTargetsConfig::new(1.0, 3).output_ordering, OutputOrdering::Discovery);
+        assert_eq!(run(OutputOrdering::Discovery, 1)[0].num_lines, 7);
+    }
+
+    #[test]
+    fn test_target_ids_and_display() {
+        let mut data = Vec::new();
+        for target in [Point3::new(10.0, 20.0, 5.0), Point3::new(-50.0, -30.0, 8.0)] {
+            for (dx, dy) in [(-30.0, 0.0), (0.0, 30.0), (30.0, 0.0), (0.0, -30.0)] {
+                let start = Point3::new(target.x + dx, target.y + dy, 0.0);
+                let d = target - start;
+                data.push(Measurement::new(start.x, start.y, start.z, d.x, d.y, d.z).with_timestamp(0.5));
+            }
+        }
+        let mut config = FindTargetsConfig::new(1.0, 3);
+        config.ransac.seed = Some(1);
+        let located = find_targets_with_config(&data, &config);
+        let ids: Vec<_> = located.iter().map(|t| (t.id.as_str(), t.index)).collect();
+        assert_eq!(ids, vec![("Target_1", 1), ("Target_2", 2)]);
+
+        let config = config.with_id_prefix("UAV_");
+        let located = find_targets_with_config(&data, &config);
+        assert_eq!((located[1].id.as_str(), located[1].index), ("UAV_2", 2));
+        let windowed = find_targets_windowed(&data, 1.0, &config).unwrap();
+        assert_eq!(windowed[0].id, "UAV_1");
+
+        let summary = located[0].to_string();
+        assert!(summary.starts_with("UAV_1 position=("), "{}", summary);
+        assert!(summary.contains("lines=4"), "{}", summary);
+        assert!(!summary.contains('\n'));
+    }
+
+    #[test]
+    fn test_merge_close_targets() {
+        // 同一目标的 9 条光线被拆成三个定位结果，另有一个远处的目标
+        let near = Point3::new(0.0, 0.0, 50.0);
+        let far = Point3::new(500.0, 0.0, 50.0);
+        let lines: Vec<_> = (0..12)
+            .map(|i| {
+                let target = if i < 9 { near } else { far };
+                let angle = i as f64 * 0.7;
+                let start = target + Vector3::new(300.0 * angle.cos(), 300.0 * angle.sin(), -40.0);
+                let offset = Vector3::new((i % 3) as f64 * 0.4 - 0.4, (i % 2) as f64 * 0.6 - 0.3, 0.0);
+                let mut line = Line::new(start, target + offset - start);
+                line.station = Some(i);
+                line
+            })
+            .collect();
+        let station_names: Vec<_> = (0..12).map(|i| format!("S{}", i)).collect();
+        let mut config = FindTargetsConfig::new(5.0, 3);
+        let fit = |index: usize, inliers: Vec<usize>, config: &FindTargetsConfig| {
+            fit_target(index, &lines, inliers, near, &Pipeline::new(config), &station_names)
+        };
+        let targets = vec![
+            fit(1, vec![0, 1, 2], &config),
+            fit(2, vec![9, 10, 11], &config),
+            fit(3, vec![3, 4, 5], &config),
+            fit(4, vec![6, 7, 8], &config),
+        ];
+
+        // 默认不合并
+        assert_eq!(merge_close_targets(&lines, targets.clone(), &Pipeline::new(&config), &station_names).len(), 4);
+
+        config.min_separation_m = 3.0;
+        let merged = merge_close_targets(&lines, targets.clone(), &Pipeline::new(&config), &station_names);
+        assert_eq!(merged.len(), 2);
+        assert_eq!((merged[0].id.as_str(), merged[1].id.as_str()), ("Target_1", "Target_2"));
+        assert_eq!(merged[0].inlier_indices, (0..9).collect::<Vec<_>>());
+        assert_eq!(merged[0].position, fit(0, (0..9).collect(), &config).position);
+        assert_eq!(merged[1].inlier_indices, vec![9, 10, 11]);
+
+        // 结果确定
+        let again = merge_close_targets(&lines, targets, &Pipeline::new(&config), &station_names);
+        assert_eq!(again[0].position, merged[0].position);
+    }
+
+    #[test]
+    fn test_refine_targets_em_resolves_contested_lines() {
+        // 两个目标相距 4 米（小于阈值 5 米），8 个测量站各看到两个目标，另有两条杂波光线；
+        // 第 0 条测量无效，检验索引映射
+        let truth = [Point3::new(0.0, 0.0, 100.0), Point3::new(4.0, 0.0, 100.0)];
+        let mut measurements = vec![Measurement::new(0.0, 0.0, 0.0, 0.0, 0.0, 0.0)];
+        for k in 0..8 {
+            let angle = k as f64 * TAU / 8.0;
+            let station = Point3::new(600.0 * angle.cos(), 600.0 * angle.sin(), 5.0);
+            for target in &truth {
+                let d = target - station;
+                let m = Measurement::new(station.x, station.y, station.z, d.x, d.y, d.z);
+                measurements.push(m.with_station_id(format!("S{}", k)));
+            }
+        }
+        measurements.push(Measurement::new(0.0, 900.0, 0.0, 1.0, 0.0, 0.3).with_station_id("C0"));
+        measurements.push(Measurement::new(-900.0, 0.0, 0.0, 0.0, 1.0, 0.2).with_station_id("C1"));
+
+        // 初始结果：第一个目标吞掉了一半测量站上另一目标的光线，位置偏向两者之间
+        let config = FindTargetsConfig::new(5.0, 3);
+        let (lines, station_names, _) = prepare_lines(measurements.iter().enumerate(), &mut Vec::new());
+        let pipeline = Pipeline::new(&config);
+        let contested: Vec<usize> = (0..8).map(|k| 2 * k + usize::from(k % 2 == 1)).collect();
+        let rest: Vec<usize> = (0..8).filter(|k| k % 2 == 0).map(|k| 2 * k + 1).collect();
+        let initial = vec![
+            fit_target(1, &lines, contested, truth[0], &pipeline, &station_names),
+            fit_target(2, &lines, rest, truth[1], &pipeline, &station_names),
+        ];
+        let initial_error = (initial[0].position - truth[0]).norm();
+        assert!(initial_error > 1.0, "{}", initial_error);
+
+        let refined = refine_targets_em(&measurements, &initial, &config);
+        assert_eq!(refined.len(), 2);
+        for (target, expected) in refined.iter().zip(&truth) {
+            let error = (target.position - expected).norm();
+            assert!(error < 0.5 * initial_error, "{} -> {}", initial_error, error);
+            assert_eq!(target.num_lines, 8);
+            assert_eq!(target.stations.len(), 8);
+        }
+        assert_eq!(refined[0].id, "Target_1");
+        let expected_inliers = |t: usize| (0..8).map(|k| 2 * k + t + 1).collect::<Vec<_>>();
+        assert_eq!(refined[0].inlier_indices, expected_inliers(0));
+        assert_eq!(refined[1].inlier_indices, expected_inliers(1));
+
+        // 作为后处理开关时与单独调用的结果相同
+        let mut em_config = config.clone().with_em(EmConfig::default());
+        em_config.ransac.seed = Some(3);
+        let greedy = find_targets_with_config(&measurements, &FindTargetsConfig { em: None, ..em_config.clone() });
+        let integrated = find_targets_with_config(&measurements, &em_config);
+        let separate = refine_targets_em(&measurements, &greedy, &em_config);
+        assert_eq!(integrated.len(), separate.len());
+        for (a, b) in integrated.iter().zip(&separate) {
+            assert_eq!(a.position, b.position);
+            assert_eq!(a.inlier_indices, b.inlier_indices);
+        }
+    }
+
+    #[test]
+    fn test_refine_targets_em_without_targets() {
+        let measurements = vec![Measurement::new(0.0, 0.0, 0.0, 1.0, 0.0, 0.0)];
+        assert!(refine_targets_em(&measurements, &[], &FindTargetsConfig::new(5.0, 3)).is_empty());
+        assert!(refine_targets_em(&[], &[], &FindTargetsConfig::new(5.0, 3)).is_empty());
+    }
+
+    #[test]
+    fn test_find_targets_jlinkage() {
+        // 8 个共享测量站观测 3 个目标，另有 4 条杂波光线；第 0 条测量无效
+        let truth = [Point3::new(0.0, 0.0, 100.0), Point3::new(60.0, 20.0, 80.0), Point3::new(-40.0, 50.0, 120.0)];
+        let mut measurements = vec![Measurement::new(f64::NAN, 0.0, 0.0, 1.0, 0.0, 0.0)];
+        let mut expected_inliers = vec![Vec::new(); truth.len()];
+        for k in 0..8 {
+            let angle = k as f64 * TAU / 8.0;
+            let station = Point3::new(700.0 * angle.cos(), 700.0 * angle.sin(), 5.0);
+            for (t, target) in truth.iter().enumerate() {
+                let d = target - station + Vector3::new(0.3 * (k % 3) as f64, -0.2 * (k % 2) as f64, 0.1);
+                expected_inliers[t].push(measurements.len());
+                let m = Measurement::new(station.x, station.y, station.z, d.x, d.y, d.z);
+                measurements.push(m.with_station_id(format!("S{}", k)));
+            }
+        }
+        for k in 0..4 {
+            let angle = 0.5 + k as f64 * 1.3;
+            let m = Measurement::new(900.0 * angle.cos(), 900.0 * angle.sin(), 0.0, angle.sin(), -angle.cos(), 0.05);
+            measurements.push(m.with_station_id(format!("C{}", k)));
+        }
+
+        let mut config = FindTargetsConfig::new(5.0, 3);
+        config.ransac.seed = Some(7);
+        let located = find_targets_jlinkage(&measurements, &config);
+        assert_eq!(located.len(), 3);
+        for target in &located {
+            let t = truth.iter().position(|p| (p - target.position).norm() < 1.0).unwrap();
+            assert_eq!(target.inlier_indices, expected_inliers[t]);
+            assert_eq!(target.stations.len(), 8);
+        }
+        assert_eq!(
+            located.iter().map(|t| t.id.as_str()).collect::<Vec<_>>(),
+            vec!["Target_1", "Target_2", "Target_3"]
+        );
+
+        // 结果确定；排序后重新编号
+        let again = find_targets_jlinkage(&measurements, &config);
+        assert_eq!(again[0].position, located[0].position);
+        config.output_ordering = OutputOrdering::ByPosition;
+        let ordered = find_targets_jlinkage(&measurements, &config);
+        assert!(ordered.windows(2).all(|w| w[0].position.x <= w[1].position.x));
+        assert_eq!(ordered[0].index, 1);
+
+        // 光线不足时没有目标
+        assert!(find_targets_jlinkage(&measurements[..3], &config).is_empty());
+        config.jlinkage_hypotheses = 0;
+        assert!(find_targets_jlinkage(&measurements, &config).is_empty());
+    }
+
+    #[test]
+    fn test_dbscan() {
+        // 两团点与一个离群点；第二团经一条核心点链连接
+        let mut points: Vec<Point3<f64>> = (0..5).map(|k| Point3::new(0.1 * k as f64, 0.0, 0.0)).collect();
+        points.extend((0..6).map(|k| Point3::new(10.0 + 0.8 * k as f64, 5.0, 0.0)));
+        points.push(Point3::new(-20.0, 0.0, 0.0));
+        let labels = dbscan(&points, 1.0, 3);
+        assert!(labels[..5].iter().all(|&l| l == Some(0)));
+        assert!(labels[5..11].iter().all(|&l| l == Some(1)));
+        assert_eq!(labels[11], None);
+
+        // 邻域点数不足时全部为噪声
+        assert!(dbscan(&points, 0.05, 2).iter().all(Option::is_none));
+        assert!(dbscan(&points, 0.0, 1).iter().all(Option::is_none));
+        assert!(dbscan(&[], 1.0, 1).is_empty());
+    }
+
+    #[test]
+    fn test_find_targets_clustered() {
+        // 8 个共享测量站观测 3 个目标，另有 4 条杂波光线；第 0 条测量无效
+        let truth = [Point3::new(0.0, 0.0, 100.0), Point3::new(60.0, 20.0, 80.0), Point3::new(-40.0, 50.0, 120.0)];
+        let mut measurements = vec![Measurement::new(f64::NAN, 0.0, 0.0, 1.0, 0.0, 0.0)];
+        for k in 0..8 {
+            let angle = k as f64 * TAU / 8.0;
+            let station = Point3::new(700.0 * angle.cos(), 700.0 * angle.sin(), 5.0);
+            for target in &truth {
+                let d = target - station + Vector3::new(0.3 * (k % 3) as f64, -0.2 * (k % 2) as f64, 0.1);
+                let m = Measurement::new(station.x, station.y, station.z, d.x, d.y, d.z);
+                measurements.push(m.with_station_id(format!("S{}", k)));
+            }
+        }
+        for k in 0..4 {
+            let angle = 0.5 + k as f64 * 1.3;
+            let m = Measurement::new(900.0 * angle.cos(), 900.0 * angle.sin(), 0.0, angle.sin(), -angle.cos(), 0.05);
+            measurements.push(m.with_station_id(format!("C{}", k)));
+        }
+
+        let mut config = FindTargetsConfig::new(5.0, 3);
+        config.ransac.seed = Some(7);
+        let clustered = find_targets_clustered(&measurements, &config);
+        let sequential = find_targets_with_config(&measurements, &config);
+        assert_eq!(clustered.len(), 3);
+        for target in &clustered {
+            assert!(truth.iter().any(|p| (p - target.position).norm() < 1.0));
+            assert_eq!(target.num_lines, 8);
+            let reference = sequential.iter().find(|t| t.inlier_indices == target.inlier_indices).unwrap();
+            assert!((reference.position - target.position).norm() < 1e-6);
+        }
+
+        // 随机抽取部分光线对时仍能分出 3 个目标
+        config.dbscan.max_pairs = 250;
+        assert_eq!(find_targets_clustered(&measurements, &config).len(), 3);
+
+        // 没有任何类时全部交给贪心提取，结果与 find_targets_with_config 相同
+        config.dbscan.max_pairs = usize::MAX;
+        config.dbscan.min_pts = 1000;
+        let fallback = find_targets_clustered(&measurements, &config);
+        assert_eq!(fallback.len(), sequential.len());
+        for (a, b) in fallback.iter().zip(&sequential) {
+            assert_eq!(a.position, b.position);
+            assert_eq!(a.inlier_indices, b.inlier_indices);
+        }
+    }
+
+    #[test]
+    fn test_line_grid_matches_brute_force() {
+        // 随机光线与随机候选点（部分在区域外），比较网格索引与逐条检验的内点集合
+        let mut rng = StdRng::seed_from_u64(31);
+        let lines: Vec<Line> = (0..3000)
+            .map(|k| {
+                let start = Point3::new(
+                    rng.gen_range(-800.0..800.0),
+                    rng.gen_range(-800.0..800.0),
+                    rng.gen_range(0.0..30.0),
+                );
+                let direction = Vector3::new(
+                    rng.gen_range(-1.0..1.0),
+                    rng.gen_range(-1.0..1.0),
+                    rng.gen_range(-0.2..1.0),
+                );
+                let mut line = Line::new(start, direction);
+                line.station = Some(k % 500);
+                line
+            })
+            .collect();
+        let active: Vec<usize> = (0..lines.len()).filter(|i| i % 7 != 3).collect();
+        let focus = BoundingBox::new(Point3::new(-300.0, -300.0, 0.0), Point3::new(300.0, 300.0, 200.0)).unwrap();
+        for (ray_mode, bounds) in [(true, None), (false, None), (true, Some(focus))] {
+            let mut config = RansacConfig::new(100, 15.0, 3);
+            config.ray_mode = ray_mode;
+            config.bounds = bounds;
+            let grid = LineGrid::build(&lines, &config).unwrap();
+            let mut members = vec![false; lines.len()];
+            for &i in &active {
+                members[i] = true;
+            }
+            let index = SubsetIndex {
+                grid: Some(&grid),
+                soa: None,
+                active: members,
+            };
+            let mut nonempty = 0;
+            for _ in 0..500 {
+                let point = Point3::new(
+                    rng.gen_range(-1000.0..1000.0),
+                    rng.gen_range(-1000.0..1000.0),
+                    rng.gen_range(-50.0..400.0),
+                );
+                let brute = within_threshold_in(&lines, &active, &point, &config, None);
+                let indexed = within_threshold_in(&lines, &active, &point, &config, Some(&index));
+                assert_eq!(brute, indexed, "{:?}", point);
+                nonempty += usize::from(!brute.is_empty());
+            }
+            assert!(nonempty > 50, "{}", nonempty);
+        }
+
+        // 角度残差模型不建立索引
+        let config = RansacConfig::new(100, 0.01, 3);
+        assert!(LineGrid::build(&lines, &RansacConfig { residual_model: ResidualModel::Angular, ..config }).is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "simulation")]
+    fn test_spatial_index_gives_identical_targets() {
+        let generator = crate::data_generator::GeneratorConfig::builder()
+            .num_targets(20)
+            .target_x_range(-1000.0, 1000.0)
+            .target_y_range(-1000.0, 1000.0)
+            .num_stations_per_target_range(20, 40)
+            .clutter_fraction(0.1)
+            .seed(12)
+            .build()
+            .unwrap();
+        let (_, data) = crate::data_generator::generate_data_from_config(&generator);
+        let mut config = FindTargetsConfig::new(20.0, 3);
+        config.ransac.seed = Some(4);
+        config.ransac.spatial_index_cutoff = usize::MAX;
+        let brute = find_targets_with_config(&data, &config);
+        config.ransac.spatial_index_cutoff = 0;
+        let indexed = find_targets_with_config(&data, &config);
+        assert!(brute.len() >= 15);
+        assert_eq!(brute.len(), indexed.len());
+        for (a, b) in brute.iter().zip(&indexed) {
+            assert_eq!(a.position, b.position);
+            assert_eq!(a.inlier_indices, b.inlier_indices);
+        }
+    }
+
+    #[test]
+    fn test_line_soa_scoring_matches_scalar() {
+        // 列式存储（及网格索引）统计内点的 RANSAC 与逐条计算的结果逐位相同，射线模式与角度残差模型下同样如此
+        let mut rng = StdRng::seed_from_u64(601);
+        let targets = [Point3::new(0.0, 0.0, 100.0), Point3::new(800.0, -300.0, 60.0)];
+        let mut lines = Vec::new();
+        for (k, target) in targets.iter().cycle().take(60).enumerate() {
+            let start = Point3::new(rng.gen_range(-1500.0..1500.0), rng.gen_range(-1500.0..1500.0), 0.0);
+            let aim = target + Vector3::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0), 0.0);
+            let mut line = Line::new(start, aim - start);
+            line.station = Some(k);
+            lines.push(line);
+        }
+        let soa = LineSoa::new(&lines);
+        let active: Vec<usize> = (0..lines.len()).filter(|i| i % 5 != 2).collect();
+        for (ray_mode, residual_model, threshold) in
+            [(true, ResidualModel::Metric, 5.0), (false, ResidualModel::Angular, 0.005)]
+        {
+            let mut config = RansacConfig::new(50, threshold, 3);
+            config.ray_mode = ray_mode;
+            config.residual_model = residual_model;
+            config.exhaustive_max_lines = 0;
+            let grid = LineGrid::build(&lines, &config);
+            let scalar = ransac_fit_subset(&lines, &active, &config, &mut SplitMix64::new(3));
+            let mut rng = SplitMix64::new(3);
+            let columnar = ransac_fit_subset_indexed(&lines, &active, &config, &mut rng, grid.as_ref(), Some(&soa), None);
+            let (position, inliers) = scalar.0.unwrap();
+            assert_eq!(columnar.0, Some((position, inliers)));
+            assert_eq!(columnar.1.iterations_run, scalar.1.iterations_run);
+        }
+    }
+
+    #[test]
+    fn test_custom_refiner() {
+        // 不做任何优化、直接返回初值（内点的闭式解）的精化器
+        struct ClosedForm {
+            calls: std::cell::Cell<usize>,
+        }
+        impl Refiner for ClosedForm {
+            fn refine(&self, _lines: &[Line], initial: Point3<f64>) -> LmReport {
+                self.calls.set(self.calls.get() + 1);
+                LmReport {
+                    position: initial,
+                    iterations_used: 0,
+                    initial_cost: 0.0,
+                    final_cost: 0.0,
+                    converged: true,
+                    final_lambda: 0.0,
+                    degenerate: false,
+                }
+            }
+        }
+
+        let targets = [Point3::new(0.0, 0.0, 100.0), Point3::new(600.0, 200.0, 80.0)];
+        let mut data = Vec::new();
+        for (t, target) in targets.iter().enumerate() {
+            for k in 0..5 {
+                let angle = k as f64 * 1.3 + t as f64;
+                let start = target + Vector3::new(400.0 * angle.cos(), 400.0 * angle.sin(), -80.0);
+                let aim = target + Vector3::new(0.0, 0.0, (k % 2) as f64);
+                let d = aim - start;
+                data.push(Measurement::new(start.x, start.y, start.z, d.x, d.y, d.z));
+            }
+        }
+        let mut config = FindTargetsConfig::new(5.0, 3);
+        config.ransac.seed = Some(2);
+
+        let refiner = ClosedForm { calls: std::cell::Cell::new(0) };
+        let estimator = RansacEstimator(config.ransac.clone());
+        let (located, diagnostics) = find_targets_with_strategies(&data, &config, &estimator, &refiner);
+        assert_eq!(located.len(), 2);
+        assert_eq!(refiner.calls.get(), 2);
+        assert_eq!(diagnostics.stop_reason, StopReason::InsufficientLines);
+        for target in &located {
+            let lines: Vec<_> = target.inlier_indices.iter().map(|&i| Line::from_measurement(&data[i])).collect();
+            assert_eq!(target.position, linear_triangulate(&lines).unwrap());
+            assert_eq!(target.final_cost, 0.0);
+        }
+
+        // 内置策略与 find_targets_with_config 一致
+        let lm = LmRefiner {
+            config: config.lm.clone(),
+            altitude_prior: None,
+            fixed_altitude: None,
+            optimizer: Optimizer::LevenbergMarquardt,
+        };
+        let (builtin, _) = find_targets_with_strategies(&data, &config, &estimator, &lm);
+        let default = find_targets_with_config(&data, &config);
+        assert_eq!(
+            builtin.iter().map(|t| t.position).collect::<Vec<_>>(),
+            default.iter().map(|t| t.position).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_estimate_subset_matches_copy() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let targets = [Point3::new(0.0, 0.0, 100.0), Point3::new(800.0, -300.0, 60.0), Point3::new(-500.0, 700.0, 90.0)];
+        let mut lines = Vec::new();
+        for target in &targets {
+            for _ in 0..8 {
+                let start = Point3::new(rng.gen_range(-1500.0..1500.0), rng.gen_range(-1500.0..1500.0), 0.0);
+                let aim = target + Vector3::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0), 0.0);
+                lines.push(Line::new(start, aim - start));
+            }
+        }
+        let mut config = RansacConfig::new(200, 5.0, 3);
+        config.seed = Some(9);
+
+        // 按索引在子集上运行，与复制出子集后运行再映射回原索引的结果相同（随机抽样与穷举两种路径）
+        let active: Vec<usize> = (0..lines.len()).filter(|i| i % 3 != 1).collect();
+        let subset: Vec<Line> = active.iter().map(|&i| lines[i]).collect();
+        for exhaustive_max_lines in [0, usize::MAX] {
+            config.exhaustive_max_lines = exhaustive_max_lines;
+            for (by_index, copied) in [
+                (
+                    ransac_fit_subset(&lines, &active, &config, &mut SplitMix64::new(9)),
+                    ransac_fit_lines(&subset, &config),
+                ),
+                (
+                    lmeds_fit_subset(&lines, &active, &config, &mut SplitMix64::new(9)),
+                    lmeds_fit_lines(&subset, &config),
+                ),
+            ] {
+                let (position, inliers) = by_index.0.unwrap();
+                let (expected_position, expected_inliers) = copied.0.unwrap();
+                assert_eq!(position, expected_position);
+                assert_eq!(inliers, expected_inliers.iter().map(|&i| active[i]).collect::<Vec<_>>());
+                assert_eq!(by_index.1.iterations_run, copied.1.iterations_run);
+            }
+        }
+
+        // 只实现 estimate_with_stats 的估计器走默认的复制路径，find_targets 的结果不变
+        struct CopyOnly(RansacEstimator);
+        impl ConsensusEstimator for CopyOnly {
+            fn estimate(&self, lines: &[Line], rng: &mut dyn RandomSource) -> Option<RansacModel> {
+                self.0.estimate(lines, rng)
+            }
+        }
+        let data: Vec<_> = lines
+            .iter()
+            .map(|l| Measurement::new(l.start.x, l.start.y, l.start.z, l.direction.x, l.direction.y, l.direction.z))
+            .collect();
+        let mut find_config = FindTargetsConfig::new(5.0, 3);
+        find_config.ransac.seed = Some(3);
+        let lm = LmRefiner {
+            config: find_config.lm.clone(),
+            altitude_prior: None,
+            fixed_altitude: None,
+            optimizer: Optimizer::LevenbergMarquardt,
+        };
+        let builtin = RansacEstimator(find_config.ransac.clone());
+        let (expected, _) = find_targets_with_strategies(&data, &find_config, &builtin, &lm);
+        let (copied, _) = find_targets_with_strategies(&data, &find_config, &CopyOnly(builtin.clone()), &lm);
+        assert_eq!(expected.len(), 3);
+        assert_eq!(
+            copied.iter().map(|t| (t.position, t.inlier_indices.clone())).collect::<Vec<_>>(),
+            expected.iter().map(|t| (t.position, t.inlier_indices.clone())).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_injected_rng() {
+        let targets = [Point3::new(0.0, 0.0, 100.0), Point3::new(500.0, 300.0, 80.0), Point3::new(-400.0, 600.0, 120.0)];
+        let mut data = Vec::new();
+        for (t, target) in targets.iter().enumerate() {
+            for k in 0..6 {
+                let angle = k as f64 * 1.1 + t as f64;
+                let start = target + Vector3::new(700.0 * angle.cos(), 700.0 * angle.sin(), -90.0);
+                let d = target - start;
+                data.push(Measurement::new(start.x, start.y, start.z, d.x, d.y, d.z));
+            }
+        }
+        let mut config = FindTargetsConfig::new(5.0, 3);
+        config.ransac.exhaustive_max_lines = 0;
+        config.ransac.seed = Some(11);
+
+        // 种子等价于以该种子初始化的 SplitMix64；传入发生器时忽略 config 中的种子
+        let (seeded, _) = find_targets_with_diagnostics(&data, &config);
+        config.ransac.seed = None;
+        let (injected, diagnostics) = find_targets_with_rng(&data, &config, &mut SplitMix64::new(11));
+        assert_eq!(seeded.len(), 3);
+        assert_eq!(diagnostics.stop_reason, StopReason::InsufficientLines);
+        assert_eq!(
+            injected.iter().map(|t| (t.position, t.inlier_indices.clone())).collect::<Vec<_>>(),
+            seeded.iter().map(|t| (t.position, t.inlier_indices.clone())).collect::<Vec<_>>()
+        );
+
+        let lines: Vec<_> = data.iter().map(Line::from_measurement).collect();
+        let mut ransac = config.ransac.clone();
+        ransac.seed = Some(4);
+        let (expected, _) = ransac_fit_lines(&lines, &ransac);
+        let (model, _) = ransac_fit_lines_with_rng(&lines, &config.ransac, &mut SplitMix64::new(4));
+        assert_eq!(model, expected);
+
+        // 启用 simulation 特性时 rand 的发生器可直接传入
+        #[cfg(feature = "simulation")]
+        {
+            let (located, _) = find_targets_with_rng(&data, &config, &mut StdRng::seed_from_u64(1));
+            assert_eq!(located.len(), 3);
+        }
+    }
+
+    #[test]
+    fn test_find_targets_pair_fallback() {
+        let stations = [
+            ("S1", Point3::new(-500.0, -500.0, 0.0)),
+            ("S2", Point3::new(500.0, -600.0, 0.0)),
+            ("S3", Point3::new(600.0, 500.0, 0.0)),
+            ("S4", Point3::new(-400.0, 600.0, 0.0)),
+        ];
+        // 目标 0 被四个测量站观测到，目标 1、2 各只被两个测量站观测到
+        let targets = [
+            (Point3::new(0.0, 0.0, 100.0), vec![0, 1, 2, 3]),
+            (Point3::new(1000.0, 0.0, 150.0), vec![0, 1]),
+            (Point3::new(0.0, 1000.0, 200.0), vec![2, 3]),
+        ];
+        let mut data = Vec::new();
+        for (target, observers) in &targets {
+            for &k in observers {
+                let (name, start) = stations[k];
+                let d = target - start;
+                data.push(Measurement::new(start.x, start.y, start.z, d.x, d.y, d.z).with_station_id(name));
+            }
+        }
+
+        let mut config = FindTargetsConfig::new(5.0, 3);
+        config.ransac.seed = Some(3);
+        let located = find_targets_with_config(&data, &config);
+        assert_eq!(located.len(), 1);
+        assert!(!located[0].low_confidence);
+
+        config.ransac.min_lines = 2;
+        let mut located = find_targets_with_config(&data, &config);
+        assert_eq!(located.len(), 3);
+        located.sort_by_key(|t| t.inlier_indices[0]);
+        for (target, (truth, observers)) in located.iter().zip(&targets) {
+            assert!((target.position - truth).norm() < 1e-6, "{:?}", target.position);
+            assert_eq!(target.num_lines, observers.len());
+            assert_eq!(target.low_confidence, observers.len() < 3);
+        }
+        assert_eq!(located[1].inlier_indices, vec![4, 5]);
+        assert_eq!(located[2].inlier_indices, vec![6, 7]);
+
+        // 两线交会的目标同样受 max_targets 限制
+        config.max_targets = Some(2);
+        let (located, diagnostics) = find_targets_with_diagnostics(&data, &config);
+        assert_eq!((located.len(), diagnostics.stop_reason), (2, StopReason::MaxTargets));
+    }
+
+    #[test]
+    fn test_find_targets_diagnostics_counters() {
+        // 两个目标各 6 条光线，另有 3 条互相平行的杂波光线
+        let targets = [Point3::new(0.0, 0.0, 100.0), Point3::new(800.0, 300.0, 120.0)];
+        let mut data = Vec::new();
+        for (t, target) in targets.iter().enumerate() {
+            for k in 0..6 {
+                let angle = k as f64 * 1.1 + t as f64 * 0.5;
+                let start = target + Vector3::new(350.0 * angle.cos(), 350.0 * angle.sin(), -90.0);
+                let aim = target + Vector3::new(0.0, (k % 3) as f64 * 0.5, 0.0);
+                let d = aim - start;
+                data.push(Measurement::new(start.x, start.y, start.z, d.x, d.y, d.z));
+            }
+        }
+        for k in 0..3 {
+            data.push(Measurement::new(-2000.0, 50.0 * k as f64, 0.0, 0.0, 0.0, 1.0));
+        }
+        data.push(Measurement::new(0.0, 0.0, 0.0, 0.0, 0.0, 0.0));
+        let mut config = FindTargetsConfig::new(5.0, 3);
+        config.ransac.seed = Some(8);
+
+        let (located, diagnostics) = find_targets_with_diagnostics(&data, &config);
+        assert_eq!(located.len(), 2);
+        assert_eq!(diagnostics.stop_reason, StopReason::NoConsensus);
+        assert_eq!(diagnostics.rounds, 3);
+        assert_eq!(diagnostics.ransac_iterations.len(), 3);
+        assert!(diagnostics.ransac_iterations.iter().all(|&n| n > 0));
+        assert_eq!(diagnostics.unassigned_lines, 3);
+        assert_eq!(diagnostics.lm_iterations, located.iter().map(|t| t.lm_iterations).collect::<Vec<_>>());
+        assert!(located.iter().all(|t| t.lm_iterations > 0));
+        assert!(diagnostics.ransac_ms >= 0.0 && diagnostics.lm_ms >= 0.0);
+
+        // 最后一轮只剩 3 条平行光线，穷举时唯一的三元组退化
+        assert_eq!(*diagnostics.ransac_iterations.last().unwrap(), 1);
+        assert!(diagnostics.degenerate_samples_rejected >= 1);
+
+        let line = diagnostics.to_string();
+        assert!(line.starts_with("rounds=3 stop=NoConsensus"), "{}", line);
+        assert!(line.contains("unassigned=3") && line.contains("skipped=1"), "{}", line);
+    }
+
+    #[test]
+    fn test_find_targets_stops_on_pure_clutter() {
+        // 400 米见方空间内的 600 条随机光线，不对应任何真实目标
+        let mut rng = StdRng::seed_from_u64(0);
+        let clutter: Vec<_> = (0..600)
+            .map(|_| {
+                Measurement::new(
+                    rng.gen_range(-200.0..200.0),
+                    rng.gen_range(-200.0..200.0),
+                    rng.gen_range(0.0..50.0),
+                    rng.gen_range(-1.0..1.0),
+                    rng.gen_range(-1.0..1.0),
+                    rng.gen_range(0.0..1.0),
+                )
+            })
+            .collect();
+        let mut config = FindTargetsConfig::new(20.0, 3);
+        config.ransac.seed = Some(0);
+
+        // 不设限制时贪心循环从杂波中拼凑出大量目标
+        let (located, diagnostics) = find_targets_with_diagnostics(&clutter, &config);
+        assert!(located.len() > 20, "{}", located.len());
+        assert_eq!(diagnostics.stop_reason, StopReason::NoConsensus);
+        assert_eq!(diagnostics.rounds, located.len() + 1);
+
+        let mut limited = config.clone();
+        limited.max_targets = Some(5);
+        let (located, diagnostics) = find_targets_with_diagnostics(&clutter, &limited);
+        assert_eq!((located.len(), diagnostics.stop_reason), (5, StopReason::MaxTargets));
+
+        let mut limited = config.clone();
+        limited.max_rounds = Some(3);
+        let (located, diagnostics) = find_targets_with_diagnostics(&clutter, &limited);
+        assert_eq!((located.len(), diagnostics.rounds), (3, 3));
+        assert_eq!(diagnostics.stop_reason, StopReason::MaxRounds);
+
+        // 杂波一致集的残差接近阈值，质量下限将其全部拒绝
+        let mut limited = config.clone();
+        limited.min_inlier_quality = Some(0.5);
+        let (located, diagnostics) = find_targets_with_diagnostics(&clutter, &limited);
+        assert!(located.is_empty());
+        assert_eq!((diagnostics.rounds, diagnostics.stop_reason), (1, StopReason::LowQuality));
+
+        // 真实目标不受质量下限影响
+        let target = Point3::new(0.0, 0.0, 10.0);
+        let measurements: Vec<_> = (0..5)
+            .map(|i| {
+                let angle = i as f64 * 1.2;
+                let start = Point3::new(100.0 * angle.cos(), 100.0 * angle.sin(), 0.0);
+                let d = target + Vector3::new(0.0, 0.0, (i % 2) as f64) - start;
+                Measurement::new(start.x, start.y, start.z, d.x, d.y, d.z)
+            })
+            .collect();
+        let (located, diagnostics) = find_targets_with_diagnostics(&measurements, &limited);
+        assert_eq!(located.len(), 1);
+        assert_eq!(diagnostics.stop_reason, StopReason::InsufficientLines);
+    }
+
+    #[test]
+    fn test_progress_callback_and_cancellation() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let clutter: Vec<_> = (0..600)
+            .map(|_| {
+                Measurement::new(
+                    rng.gen_range(-200.0..200.0),
+                    rng.gen_range(-200.0..200.0),
+                    rng.gen_range(0.0..50.0),
+                    rng.gen_range(-1.0..1.0),
+                    rng.gen_range(-1.0..1.0),
+                    rng.gen_range(0.0..1.0),
+                )
+            })
+            .collect();
+        let mut config = FindTargetsConfig::new(20.0, 3);
+        config.ransac.seed = Some(0);
+        config.progress_interval = 25;
+        let (expected, expected_diagnostics) = find_targets_with_diagnostics(&clutter, &config);
+
+        // 记录全部事件：回调不改变结果，每轮开始时报告一次，RANSAC 的迭代数在轮内递增
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorder = Arc::clone(&events);
+        let observed = config.clone().with_progress(move |event| {
+            recorder.lock().unwrap().push(event);
+            ControlFlow::Continue(())
+        });
+        let (located, diagnostics) = find_targets_with_diagnostics(&clutter, &observed);
+        assert_eq!(located.len(), expected.len());
+        assert!(located.iter().zip(&expected).all(|(a, b)| a.position == b.position));
+        assert_eq!(diagnostics.ransac_iterations, expected_diagnostics.ransac_iterations);
+        assert!(!diagnostics.cancelled());
+        let events = events.lock().unwrap();
+        assert_eq!(
+            events[0],
+            ProgressEvent {
+                round: 1,
+                lines_remaining: 600,
+                targets_found: 0,
+                ransac_iterations: 0,
+            }
+        );
+        let starts: Vec<_> = events.iter().filter(|event| event.ransac_iterations == 0).collect();
+        assert_eq!(starts.len(), diagnostics.rounds);
+        assert!(starts.iter().enumerate().all(|(k, event)| event.round == k + 1));
+        assert!(starts.windows(2).all(|w| w[1].lines_remaining < w[0].lines_remaining));
+        assert!(events.len() > 2 * starts.len());
+        assert!(events.windows(2).all(|w| w[1].round > w[0].round || w[1].ransac_iterations > w[0].ransac_iterations));
+
+        // 在第 3 轮开始时取消：返回前两轮的目标
+        let cancel_after_two = config.clone().with_progress(|event| {
+            if event.targets_found >= 2 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        });
+        let (located, diagnostics) = find_targets_with_diagnostics(&clutter, &cancel_after_two);
+        assert_eq!((located.len(), diagnostics.rounds), (2, 2));
+        assert_eq!(diagnostics.stop_reason, StopReason::Cancelled);
+        assert!(diagnostics.cancelled());
+        assert!(located.iter().zip(&expected).all(|(a, b)| a.position == b.position));
+
+        // 在第一轮的 RANSAC 中取消：抽样提前停止，不接受该轮的一致集
+        let cancel_in_ransac = config.clone().with_progress(|event| {
+            if event.ransac_iterations >= 50 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        });
+        let (located, diagnostics) = find_targets_with_diagnostics(&clutter, &cancel_in_ransac);
+        assert!(located.is_empty());
+        assert_eq!(diagnostics.rounds, 1);
+        assert!(diagnostics.cancelled());
+        assert!(diagnostics.ransac_iterations[0] < expected_diagnostics.ransac_iterations[0]);
+    }
+
+    #[test]
+    fn test_try_into_line_rejects_invalid_measurements() {
+        assert!(Measurement::new(1.0, 2.0, 3.0, 0.0, 0.0, 2.0).try_into_line().is_ok());
+        assert_eq!(
+            Measurement::new(1.0, 2.0, 3.0, 0.0, 0.0, 0.0).try_into_line().unwrap_err(),
+            OptiRadarError::ZeroDirection
+        );
+        assert_eq!(
+            Measurement::new(1.0, 2.0, 3.0, f64::NAN, 0.0, 1.0).try_into_line().unwrap_err(),
+            OptiRadarError::NonFiniteDirection
+        );
+        assert_eq!(
+            Measurement::new(f64::INFINITY, 2.0, 3.0, 0.0, 0.0, 1.0).try_into_line().unwrap_err(),
+            OptiRadarError::NonFiniteStation
+        );
+        assert!(matches!(
+            Measurement::new(1.0, 2.0, 3.0, 0.0, 0.0, 1.0).with_weight(f64::NAN).try_into_line(),
+            Err(OptiRadarError::InvalidParameter { name: "weight", .. })
+        ));
+        assert!(Measurement::new(1.0, 2.0, 3.0, 0.0, 0.0, 1.0).with_weight(-1.0).try_into_line().is_err());
+        assert!(Measurement::new(1.0, 2.0, 3.0, 0.0, 0.0, 1.0).with_weight(0.0).try_into_line().is_ok());
+    }
+
+    #[test]
+    fn test_levenberg_marquardt_rejects_non_finite_input() {
+        let target = Point3::new(0.0, 0.0, 10.0);
+        let mut lines: Vec<_> = (0..4)
+            .map(|i| {
+                let angle = i as f64 * 1.5;
+                let start = Point3::new(100.0 * angle.cos(), 100.0 * angle.sin(), 0.0);
+                Line::new(start, target - start)
+            })
+            .collect();
+        let guess = Point3::new(5.0, -5.0, 20.0);
+
+        // 任一光线含 NaN 时不迭代，返回初值并标记为未收敛
+        for poison in [
+            Line::new(Point3::new(f64::NAN, 0.0, 0.0), Vector3::z()),
+            Line::from_parts(Point3::origin(), Vector3::new(f64::NAN, 0.0, 1.0)),
+            Line { weight: f64::NAN, ..lines[0] },
+        ] {
+            let mut poisoned = lines.clone();
+            poisoned.push(poison);
+            let report = levenberg_marquardt_optimize_detailed(&poisoned, guess, &LmConfig::default());
+            assert_eq!(report.position, guess);
+            assert!(!report.converged);
+            assert_eq!(report.iterations_used, 0);
+            assert_eq!(levenberg_marquardt_optimize(&poisoned, guess, 200, 0.001), guess);
+        }
+        let nan_guess =
+            levenberg_marquardt_optimize_detailed(&lines, Point3::new(f64::NAN, 0.0, 0.0), &LmConfig::default());
+        assert!(!nan_guess.converged);
+        assert_eq!(nan_guess.iterations_used, 0);
+
+        // 代价溢出为无穷大时每一步都被拒绝，连续多次后放弃并返回最后的有限位置
+        lines.push(Line::new(Point3::new(1e200, 0.0, 0.0), Vector3::z()));
+        let report = levenberg_marquardt_optimize_detailed(&lines, guess, &LmConfig::default());
+        assert!(report.position.iter().all(|v| v.is_finite()));
+        assert!(!report.converged);
+        assert_eq!(report.iterations_used, MAX_NON_FINITE_STEPS);
+    }
+
+    #[test]
+    fn test_find_targets_skips_invalid_measurements() {
+        let target = Point3::new(0.0, 0.0, 10.0);
+        let mut measurements = vec![Measurement::new(5.0, 5.0, 0.0, 0.0, 0.0, 0.0)];
+        for i in 0..4 {
+            let angle = i as f64 * 1.5;
+            let start = Point3::new(100.0 * angle.cos(), 100.0 * angle.sin(), 0.0);
+            let direction = target - start;
+            measurements.push(Measurement::new(
+                start.x,
+                start.y,
+                start.z,
+                direction.x,
+                direction.y,
+                direction.z,
+            ));
+        }
+        measurements.insert(3, Measurement::new(f64::NAN, 0.0, 0.0, 1.0, 0.0, 0.0));
+
+        let (located, diagnostics) =
+            find_targets_with_diagnostics(&measurements, &FindTargetsConfig::new(1.0, 3));
+        assert_eq!(diagnostics.skipped_indices(), vec![0, 3]);
+        assert_eq!(diagnostics.skipped[1].1, OptiRadarError::NonFiniteStation);
+        assert_eq!(located.len(), 1);
+        assert!((located[0].position - target).norm() < 1e-6);
+        // 内点索引指向输入数据
+        assert_eq!(located[0].inlier_indices, vec![1, 2, 4, 5]);
+    }
+
+    #[test]
+    fn test_one_inlier_per_station() {
+        // 测量站 A 的两条光线都经过目标附近，只应保留更近的一条。
+        // 第二条光线沿竖直方向偏离目标，而其他测量站的光线都不沿竖直方向，含第二条光线的样本给出的候选点
+        // 同样离第一条光线更近，结果与 RANSAC 抽到哪些样本无关
+        let target = Point3::new(0.0, 0.0, 10.0);
+        let aims = [
+            ("A", Point3::new(100.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 0.0)),
+            ("A", Point3::new(100.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 0.5)),
+            ("B", Point3::new(0.0, 100.0, 0.0), Vector3::new(0.0, 0.0, 0.0)),
+            ("C", Point3::new(-100.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 0.0)),
+            ("D", Point3::new(0.0, -100.0, 0.0), Vector3::new(0.0, 0.0, 0.0)),
+        ];
+        let measurements: Vec<_> = aims
+            .iter()
+            .map(|(id, start, offset)| {
+                let d = target + offset - start;
+                Measurement::new(start.x, start.y, start.z, d.x, d.y, d.z).with_station_id(*id)
+            })
+            .collect();
+
+        let located = find_targets(&measurements, 2.0, 3);
+        assert_eq!(located.len(), 1);
+        assert_eq!(located[0].num_lines, 4);
+        assert!(!located[0].inlier_indices.contains(&1));
+        assert_eq!(located[0].stations, vec!["A", "B", "C", "D"]);
+    }
+
+    #[test]
+    fn test_ray_mode_rejects_points_behind_stations() {
+        // 三个测量站背对背朝外，反向延长线交于 (0, 50, 0)，该点位于所有测量站背后
+        let phantom = Point3::new(0.0, 50.0, 0.0);
+        let starts = [
+            Point3::new(-10.0, 40.0, 0.0),
+            Point3::new(10.0, 40.0, 0.0),
+            Point3::new(0.0, 35.0, 0.0),
+        ];
+        let measurements: Vec<_> = starts
+            .iter()
+            .map(|start| {
+                let d = start - phantom;
+                Measurement::new(start.x, start.y, start.z, d.x, d.y, d.z)
+            })
+            .collect();
+
+        let config = FindTargetsConfig::new(1.0, 3);
+        assert!(find_targets_with_config(&measurements, &config).is_empty());
+
+        // 关闭射线模式时会在测量站背后凭空得到一个目标
+        let infinite = find_targets_with_config(&measurements, &config.with_ray_mode(false));
+        assert_eq!(infinite.len(), 1);
+        assert!((infinite[0].position - phantom).norm() < 1e-3);
+    }
+
+    #[test]
+    fn test_angular_residual_model() {
+        // 近处（100 米）与远处（5000 米）的测量站，方向均偏转 0.002 弧度：
+        // 垂直距离分别约为 0.2 米和 10 米，角度残差相同
+        let target = Point3::new(0.0, 0.0, 100.0);
+        let lines: Vec<_> = [100.0, 100.0, 100.0, 5000.0, 5000.0]
+            .iter()
+            .enumerate()
+            .map(|(i, &range)| {
+                let azimuth = i as f64 * 1.3;
+                let start = target + Vector3::new(azimuth.cos(), azimuth.sin(), -0.2).normalize() * range;
+                let rotation = UnitQuaternion::from_axis_angle(&Vector3::z_axis(), 0.002);
+                let mut line = Line::new(start, rotation * (target - start));
+                line.station = Some(i);
+                line
+            })
+            .collect();
+
+        let mut config = RansacConfig::new(100, 2.0, 3);
+        assert_eq!(collect_inliers(&lines, &target, &config), vec![0, 1, 2]);
+        config.residual_model = ResidualModel::Angular;
+        config.threshold = 0.005;
+        assert_eq!(collect_inliers(&lines, &target, &config), vec![0, 1, 2, 3, 4]);
+
+        // 雅可比与数值差分一致（含射线模式下位于测量站背后的点）
+        let model = ResidualModel::Angular;
+        for point in [Point3::new(30.0, -20.0, 80.0), lines[0].start + Vector3::new(-5.0, 3.0, 1.0)] {
+            let jacobian = model.jacobian(&lines[0], &point, true);
+            for k in 0..3 {
+                let mut step = Vector3::zeros();
+                step[k] = 1e-6;
+                let numeric = (model.residual(&lines[0], &(point + step), true)
+                    - model.residual(&lines[0], &(point - step), true))
+                    / 2e-6;
+                assert!((jacobian.column(k) - numeric).norm() < 1e-7, "{} {}", k, numeric);
+            }
+        }
+
+        // 完整流程：角度模型保留远处测量站的光线
+        let measurements: Vec<_> = lines
+            .iter()
+            .enumerate()
+            .map(|(i, line)| {
+                let (s, d) = (line.start, line.direction);
+                Measurement::new(s.x, s.y, s.z, d.x, d.y, d.z).with_station_id(format!("S{}", i))
+            })
+            .collect();
+        let mut metric = FindTargetsConfig::new(2.0, 3);
+        metric.ransac.seed = Some(3);
+        let mut angular = FindTargetsConfig::new(0.005, 3).with_residual_model(ResidualModel::Angular);
+        angular.ransac.seed = Some(3);
+        let located = find_targets_with_config(&measurements, &metric);
+        assert_eq!(located.len(), 1);
+        assert_eq!(located[0].num_lines, 3);
+        let located = find_targets_with_config(&measurements, &angular);
+        assert_eq!(located.len(), 1);
+        assert_eq!(located[0].num_lines, 5);
+        assert!((located[0].position - target).norm() < 1.0, "{}", located[0].position);
+    }
+
+    #[test]
+    fn test_ransac_seeded_reproducible() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let target = Point3::new(10.0, 20.0, 30.0);
+        let mut lines = Vec::new();
+        for _ in 0..10 {
+            let start = Point3::new(rng.gen_range(-50.0..50.0), rng.gen_range(-50.0..50.0), 0.0);
+            let noise = Vector3::new(rng.gen_range(-0.5..0.5), rng.gen_range(-0.5..0.5), 0.0);
+            lines.push(Line::new(start, target + noise - start));
+        }
+        for _ in 0..30 {
+            let start = Point3::new(rng.gen_range(-50.0..50.0), rng.gen_range(-50.0..50.0), 0.0);
+            let direction = Vector3::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0), rng.gen_range(0.1..1.0));
+            lines.push(Line::new(start, direction));
+        }
+
+        let mut config = RansacConfig::new(500, 2.0, 3);
+        config.seed = Some(42);
+        let (first, first_stats) = ransac_fit_lines(&lines, &config);
+        let (second, second_stats) = ransac_fit_lines(&lines, &config);
+        let (first, second) = (first.unwrap(), second.unwrap());
+        assert_eq!(first.0, second.0);
+        assert_eq!(first.1, second.1);
+        assert_eq!(first_stats.iterations_run, second_stats.iterations_run);
+
+        // 并行评估时结果与线程数无关
+        #[cfg(feature = "parallel")]
+        for threads in [1, 4] {
+            let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+            let (parallel, parallel_stats) = pool.install(|| ransac_fit_lines(&lines, &config));
+            let parallel = parallel.unwrap();
+            assert_eq!(parallel.0, first.0);
+            assert_eq!(parallel.1, first.1);
+            assert_eq!(parallel_stats.iterations_run, first_stats.iterations_run);
+        }
+    }
+
+    #[test]
+    fn test_collect_inliers_parallel_cutoff() {
+        let mut rng = StdRng::seed_from_u64(11);
+        let point = Point3::new(0.0, 0.0, 10.0);
+        let lines: Vec<_> = (0..2000)
+            .map(|_| {
+                let start = Point3::new(rng.gen_range(-100.0..100.0), rng.gen_range(-100.0..100.0), 0.0);
+                let aim = point + Vector3::new(rng.gen_range(-5.0..5.0), rng.gen_range(-5.0..5.0), 0.0);
+                Line::new(start, aim - start)
+            })
+            .collect();
+
+        let mut serial = RansacConfig::new(100, 2.0, 3);
+        serial.parallel_cutoff = usize::MAX;
+        let mut parallel = serial.clone();
+        parallel.parallel_cutoff = 0;
+
+        let expected = collect_inliers(&lines, &point, &serial);
+        assert!(!expected.is_empty());
+        assert!(expected.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(collect_inliers(&lines, &point, &parallel), expected);
+        assert_eq!(
+            line_distances(&lines, &point, true, 0),
+            line_distances(&lines, &point, true, usize::MAX)
+        );
+    }
+
+    #[test]
+    fn test_estimate_covariance() {
+        let target = Point3::new(0.0, 0.0, 10.0);
+        let starts = [
+            Point3::new(100.0, 0.0, 0.0),
+            Point3::new(0.0, 100.0, 0.0),
+            Point3::new(-100.0, 0.0, 0.0),
+            Point3::new(0.0, -100.0, 0.0),
+        ];
+        let lines: Vec<_> = starts
+            .iter()
+            .map(|&start| Line::new(start, target - start))
+            .collect();
+
+        // 偏离交点后残差非零，协方差应为正定
+        let cov = estimate_covariance(&lines, Point3::new(0.5, -0.3, 10.2)).unwrap();
+        assert!(cov.symmetric_eigenvalues().min() > 0.0);
+
+        let located = LocatedTarget {
+            id: "Target_1".to_string(),
+            index: 1,
+            position: target,
+            num_lines: lines.len(),
+            avg_error_dist_m: 0.0,
+            covariance: Some(cov),
+            inlier_indices: vec![0, 1, 2, 3],
+            residuals: vec![0.0; 4],
+            max_residual_m: 0.0,
+            stations: Vec::new(),
+            converged: true,
+            final_cost: 0.0,
+            lm_iterations: 0,
+            geometry_dop: 1.0,
+            horizontal_dop: 1.0,
+            vertical_dop: 1.0,
+            timestamp: None,
+            low_confidence: false,
+            confidence: 0.0,
+            chi_square: None,
+            inconsistent: false,
+            outlier_indices: Vec::new(),
+            degenerate: false,
+            bootstrap: None,
+            velocity: None,
+        };
+        let std_devs = located.std_devs().unwrap();
+        assert!((std_devs.x - cov[(0, 0)].sqrt()).abs() < 1e-12);
+        let (sigmas, axes) = located.principal_axes().unwrap();
+        assert!(sigmas.iter().all(|&s| s > 0.0));
+        assert!((axes.column(0).norm() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_geometry_dop() {
+        // 3 条两两正交的光线
+        let orthogonal = [
+            Line::new(Point3::new(-10.0, 0.0, 0.0), Vector3::x()),
+            Line::new(Point3::new(0.0, -10.0, 0.0), Vector3::y()),
+            Line::new(Point3::new(0.0, 0.0, -10.0), Vector3::z()),
+        ];
+        let dop = geometry_dop(&orthogonal);
+        assert!((dop.total - 1.5f64.sqrt()).abs() < 1e-12);
+        assert!((dop.horizontal - 1.0).abs() < 1e-12);
+        assert!((dop.vertical - 0.5f64.sqrt()).abs() < 1e-12);
+
+        // 残差相同（均精确过目标）时，相互交会角仅 2° 的光线束 DOP 远大于方向分散的光线
+        let target = Point3::new(0.0, 0.0, 50.0);
+        let measurements_from = |azimuths_deg: &[f64]| -> Vec<Measurement> {
+            azimuths_deg
+                .iter()
+                .map(|az| {
+                    let a = az.to_radians();
+                    let start = Point3::new(300.0 * a.sin(), 300.0 * a.cos(), 0.0);
+                    let d = target - start;
+                    Measurement::new(start.x, start.y, start.z, d.x, d.y, d.z)
+                })
+                .collect()
+        };
+        let mut config = FindTargetsConfig::new(1.0, 3);
+        config.ransac.seed = Some(1);
+        let spread = find_targets_with_config(&measurements_from(&[0.0, 90.0, 180.0, 270.0]), &config);
+        let bundle = find_targets_with_config(&measurements_from(&[0.0, 1.0, 2.0, 3.0]), &config);
+        assert_eq!((spread.len(), bundle.len()), (1, 1));
+        assert!(spread[0].avg_error_dist_m < 1e-6 && bundle[0].avg_error_dist_m < 1e-6);
+        assert!(bundle[0].geometry_dop > 10.0 * spread[0].geometry_dop);
+        // 近乎平行的水平光线束主要放大水平（沿光线方向）误差
+        assert!(bundle[0].horizontal_dop > bundle[0].vertical_dop);
+
+        let parallel = [
+            Line::new(Point3::new(0.0, 0.0, 0.0), Vector3::x()),
+            Line::new(Point3::new(0.0, 1.0, 0.0), Vector3::x()),
+        ];
+        assert!(geometry_dop(&parallel).total.is_infinite());
+    }
+
+    /// 从均匀分布在目标周围的 n 个测量站指向目标的光线，测量站位置带 σ = 2 米的噪声
+    fn noisy_ring_lines(target: &Point3<f64>, n: usize, rng: &mut StdRng) -> Vec<Line> {
+        let noise = Normal::new(0.0, 2.0).unwrap();
+        (0..n)
+            .map(|k| {
+                let angle = k as f64 * TAU / n as f64;
+                let station = Point3::new(target.x + 500.0 * angle.cos(), target.y + 500.0 * angle.sin(), 10.0);
+                let direction = target - station;
+                let start = station + Vector3::from_fn(|_, _| noise.sample(rng));
+                Line::new(start, direction)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_bootstrap_spread_grows_with_fewer_lines() {
+        let target = Point3::new(10.0, -20.0, 150.0);
+        let mut rng = StdRng::seed_from_u64(23);
+        let mut spread = |n: usize| {
+            // 多组噪声实现的平均方差，减小单组数据的偶然性
+            (0..5)
+                .map(|run| {
+                    let lines = noisy_ring_lines(&target, n, &mut rng);
+                    let position = linear_triangulate(&lines).unwrap();
+                    let result = bootstrap_uncertainty(&lines, position, 300, &mut SplitMix64::new(run)).unwrap();
+                    assert_eq!(result.positions.len() + result.num_degenerate, 300);
+                    for k in 0..3 {
+                        assert!(result.lower[k] <= result.median[k] && result.median[k] <= result.upper[k]);
+                    }
+                    result.covariance.trace()
+                })
+                .sum::<f64>()
+                / 5.0
+        };
+        let (five, twenty) = (spread(5), spread(20));
+        assert!(five > 2.0 * twenty, "5 条光线 {:.3} m², 20 条光线 {:.3} m²", five, twenty);
+    }
+
+    #[test]
+    fn test_bootstrap_result_statistics() {
+        let target = Point3::new(0.0, 0.0, 100.0);
+        let lines = noisy_ring_lines(&target, 6, &mut StdRng::seed_from_u64(5));
+        let position = linear_triangulate(&lines).unwrap();
+        let result = bootstrap_uncertainty(&lines, position, 100, &mut SplitMix64::new(1)).unwrap();
+        assert_eq!(result, bootstrap_uncertainty(&lines, position, 100, &mut SplitMix64::new(1)).unwrap());
+
+        let n = result.positions.len() as f64;
+        let mean = result.positions.iter().map(|p| p.coords).sum::<Vector3<f64>>() / n;
+        assert!((result.mean.coords - mean).norm() < 1e-9);
+        let var_x = result.positions.iter().map(|p| (p.x - mean.x).powi(2)).sum::<f64>() / (n - 1.0);
+        assert!((result.covariance[(0, 0)] - var_x).abs() < 1e-9 * var_x.max(1.0));
+        assert!((result.std_devs().x - var_x.sqrt()).abs() < 1e-9);
+        let min_x = result.positions.iter().map(|p| p.x).fold(f64::INFINITY, f64::min);
+        assert_eq!(result.percentile(0.0).unwrap().x, min_x);
+        assert_eq!(result.percentile(2.5).unwrap(), result.lower);
+        for percent in [-0.1, 100.5, f64::NAN] {
+            assert!(matches!(
+                result.percentile(percent),
+                Err(OptiRadarError::InvalidParameter { name: "percent", .. })
+            ));
+        }
+        assert!(result.percentile(100.0).is_ok());
+
+        // 少于 2 条光线或有效重采样不足时没有结果
+        assert!(bootstrap_uncertainty(&lines[..1], position, 100, &mut SplitMix64::new(1)).is_none());
+        assert!(bootstrap_uncertainty(&lines, position, 1, &mut SplitMix64::new(1)).is_none());
+    }
+
+    #[test]
+    fn test_find_targets_attaches_bootstrap() {
+        let target = Point3::new(0.0, 0.0, 100.0);
+        let lines = noisy_ring_lines(&target, 6, &mut StdRng::seed_from_u64(7));
+        let data: Vec<Measurement> = lines
+            .iter()
+            .map(|l| Measurement::from_arrays(l.start.into(), l.direction.into()))
+            .collect();
+        let mut config = FindTargetsConfig::new(10.0, 3);
+        config.ransac.seed = Some(1);
+        let plain = find_targets_with_config(&data, &config);
+        assert_eq!(plain.len(), 1);
+        assert!(plain[0].bootstrap.is_none());
 
-/// Measurement → Line
-fn get_line(m: &Measurement) -> Line {
-    let start_point = Point3::new(m.x, m.y, m.z);
-    let direction = Vector3::new(m.direction_x, m.direction_y, m.direction_z).normalize();
-    Line {
-        start: start_point,
-        direction,
+        let config = config.with_bootstrap(BootstrapConfig {
+            num_resamples: 50,
+            seed: Some(9),
+            ..BootstrapConfig::default()
+        });
+        let with_bootstrap = find_targets_with_config(&data, &config);
+        assert_eq!(with_bootstrap[0].position, plain[0].position);
+        let bootstrap = with_bootstrap[0].bootstrap.as_ref().unwrap();
+        assert!(bootstrap.positions.len() > 40);
+        assert!(bootstrap.std_devs().iter().all(|&s| s > 0.0 && s < 10.0));
+        assert_eq!(find_targets_with_config(&data, &config)[0].bootstrap.as_ref(), Some(bootstrap));
     }
-}
 
-/// 求两条光线之间的最近点中点
-fn find_closest_midpoint(line1: &Line, line2: &Line) -> Point3<f64> {
-    let w0 = line1.start - line2.start;
-    let a = line1.direction.dot(&line1.direction);
-    let b = line1.direction.dot(&line2.direction);
-    let c = line2.direction.dot(&line2.direction);
-    let d = line1.direction.dot(&w0);
-    let e = line2.direction.dot(&w0);
-    let denom = a * c - b * b;
-    if denom.abs() < 1e-6 {
-        // 平行或接近平行，直接返回起点平均
-        return Point3::from((line1.start.coords + line2.start.coords) * 0.5);
+    #[test]
+    fn test_estimate_covariance_parallel_lines() {
+        let lines: Vec<_> = (0..4)
+            .map(|i| Line::new(Point3::new(0.0, i as f64, 0.0), Vector3::new(1.0, 0.0, 0.0)))
+            .collect();
+        assert!(estimate_covariance(&lines, Point3::new(5.0, 1.5, 0.0)).is_none());
     }
-    let s = (b * e - c * d) / denom;
-    let t = (a * e - b * d) / denom;
-    let closest_point1 = line1.start + line1.direction * s;
-    let closest_point2 = line2.start + line2.direction * t;
-    Point3::from((closest_point1.coords + closest_point2.coords) * 0.5)
-}
 
-/// 使用 Levenberg-Marquardt 优化点到多条光线的残差
-///
-/// 残差定义为：点到每条光线的垂直向量 `distance_vec`
-/// 维度为 `3n`，LM 会最小化所有残差向量的平方和。
-pub fn levenberg_marquardt_optimize(
-    lines: &[Line],
-    initial_guess: Point3<f64>,
-    iterations: usize,
-    initial_lambda: f64,
-) -> Point3<f64> {
-    let mut current_pos = initial_guess;
-    let mut lambda = initial_lambda;
-    let lambda_factor_up = 10.0;
-    let lambda_factor_down = 0.1;
+    #[test]
+    fn test_levenberg_marquardt_weights() {
+        // 两条精确光线交于 (0, 0, 10)，第三条光线偏离 5 米
+        let target = Point3::new(0.0, 0.0, 10.0);
+        let accurate = [Point3::new(100.0, 0.0, 0.0), Point3::new(0.0, 100.0, 0.0)];
+        let mut lines: Vec<_> = accurate
+            .iter()
+            .map(|&start| Line::new(start, target - start))
+            .collect();
+        let noisy_start = Point3::new(-100.0, -100.0, 0.0);
+        lines.push(Line::new(noisy_start, target + Vector3::new(0.0, 0.0, 5.0) - noisy_start));
 
-    for _ in 0..iterations {
-        let n = lines.len();
-        let mut j = DMatrix::zeros(3 * n, 3);
-        let mut e = DVector::zeros(3 * n);
+        let initial_guess = Point3::new(1.0, 1.0, 11.0);
+        let unweighted = levenberg_marquardt_optimize(&lines, initial_guess, 200, 0.001);
 
-        // 构建残差向量 e 和雅可比矩阵 J
-        for (i, line) in lines.iter().enumerate() {
-            let pa = current_pos - line.start;
-            let proj = pa.dot(&line.direction);
-            let distance_vec = pa - line.direction * proj; // 垂直分量
+        // 默认权重 1.0 与显式设置 1.0 完全一致
+        let mut ones = lines.clone();
+        ones.iter_mut().for_each(|l| l.weight = 1.0);
+        assert_eq!(levenberg_marquardt_optimize(&ones, initial_guess, 200, 0.001), unweighted);
 
-            // 残差
-            e.rows_mut(3 * i, 3).copy_from(&DVector::from_column_slice(distance_vec.as_slice()));
+        let mut weighted_lines = lines.clone();
+        weighted_lines[0].weight = 100.0;
+        weighted_lines[1].weight = 100.0;
+        let weighted = levenberg_marquardt_optimize(&weighted_lines, initial_guess, 200, 0.001);
 
-            // 雅可比：残差 = (p - start) - d ( (p - start)·d )
-            // 对 p 的导数 ≈ I - d dᵀ
-            let jac_block = Matrix3::identity() - line.direction * line.direction.transpose();
-            j
-                .view_mut((3 * i, 0), (3, 3))
-                .copy_from(&jac_block);
-        }
+        assert!((weighted - target).norm() < 0.1 * (unweighted - target).norm());
+    }
 
-        let j_t = j.transpose();
-        let h_approx = &j_t * &j;
-        let b = &j_t * &e;
+    #[test]
+    fn test_zero_weight_inliers_statistics() {
+        // 权重全为零（try_into_line 允许）时加权平均无定义，残差统计退化为不加权的均方根
+        let target = Point3::new(0.0, 0.0, 10.0);
+        let measurements: Vec<_> = [(100.0, 0.0), (0.0, 100.0), (-100.0, 0.0), (0.0, -100.0), (70.0, 70.0)]
+            .iter()
+            .map(|&(x, y)| Measurement {
+                weight: 0.0,
+                ..Measurement::new(x, y, 0.0, -x, -y + 0.1, 10.0)
+            })
+            .collect();
+        let mut config = FindTargetsConfig::new(2.0, 3);
+        config.ransac.seed = Some(511);
+        let located = find_targets_with_config(&measurements, &config);
+        assert_eq!(located.len(), 1);
+        assert!((located[0].position - target).norm() < 1.0);
+        assert!(located[0].avg_error_dist_m.is_finite() && located[0].avg_error_dist_m > 0.0);
+        assert!(located[0].confidence.is_finite() && located[0].confidence > 0.0);
 
-        // LM 更新： (H + λI) Δp = -b
-        let h_lm = h_approx + Matrix3::identity() * lambda;
-        let delta = match h_lm.try_inverse() {
-            Some(inv_h) => inv_h * -b,
-            None => {
-                lambda *= lambda_factor_up;
-                continue;
-            }
-        };
+        assert_eq!(weighted_rms([(0.0, 3.0), (0.0, 4.0)]), 12.5_f64.sqrt());
+        assert_eq!(weighted_rms([(1.0, 3.0), (0.0, 4.0)]), 3.0);
+        assert_eq!(weighted_rms([]), 0.0);
+    }
 
-        let delta_vec = Vector3::new(delta[0], delta[1], delta[2]);
-        let new_pos = current_pos + delta_vec;
+    #[test]
+    fn test_levenberg_marquardt_huber_outlier() {
+        // 十条精确光线加一条偏离 30 米的粗差光线
+        let target = Point3::new(5.0, -3.0, 20.0);
+        let mut lines: Vec<_> = (0..10)
+            .map(|i| {
+                let angle = i as f64 * 0.6;
+                let start = Point3::new(200.0 * angle.cos(), 200.0 * angle.sin(), 0.0);
+                Line::new(start, target - start)
+            })
+            .collect();
+        let outlier_start = Point3::new(150.0, 150.0, 0.0);
+        lines.push(Line::new(outlier_start, target + Vector3::new(30.0, -30.0, 0.0) - outlier_start));
+
+        let initial_guess = Point3::new(0.0, 0.0, 10.0);
+        let plain = levenberg_marquardt_optimize(&lines, initial_guess, 200, 0.001);
 
-        // 计算误差平方和
-        let mut new_error_sq = 0.0;
-        for line in lines.iter() {
-            let pa = new_pos - line.start;
-            let proj = pa.dot(&line.direction);
-            let dist_vec = pa - line.direction * proj;
-            new_error_sq += dist_vec.norm_squared();
+        for loss in [RobustLoss::Huber(0.5), RobustLoss::Cauchy(0.5)] {
+            let config = LmConfig {
+                robust_loss: loss,
+                ..LmConfig::default()
+            };
+            let robust = levenberg_marquardt_optimize_with_config(&lines, initial_guess, &config);
+            assert!((robust - target).norm() < 0.5, "{:?}: {}", loss, (robust - target).norm());
+            assert!((robust - target).norm() < (plain - target).norm());
         }
-        let current_error_sq: f64 = e.norm_squared();
+    }
 
-        // 接受或拒绝更新
-        if new_error_sq < current_error_sq {
-            current_pos = new_pos;
-            lambda *= lambda_factor_down; // 更接近高斯牛顿
-        } else {
-            lambda *= lambda_factor_up; // 更接近梯度下降
+    #[test]
+    fn test_robust_loss_rejects_invalid_delta() {
+        // Cauchy(0) 的代价为 0·ln(∞) = NaN，须在进入 LM 前报错而不是静默地不收敛
+        assert!(RobustLoss::Cauchy(0.0).cost(1.0_f64).is_nan());
+        let invalid = [
+            RobustLoss::Cauchy(0.0),
+            RobustLoss::Huber(-1.0),
+            RobustLoss::Huber(f64::NAN),
+            RobustLoss::Cauchy(f64::INFINITY),
+        ];
+        for loss in invalid {
+            assert!(matches!(
+                loss.validate(),
+                Err(OptiRadarError::InvalidParameter { name: "robust_loss.delta", .. })
+            ));
         }
+        assert!(RobustLoss::None.validate().is_ok() && RobustLoss::Huber(0.5).validate().is_ok());
+
+        let target = Point3::new(0.0, 0.0, 10.0);
+        let data: Vec<Measurement> = [(100.0, 0.0), (0.0, 100.0), (-100.0, 0.0), (0.0, -100.0)]
+            .iter()
+            .map(|&(x, y)| Measurement::new(x, y, 0.0, -x, -y, 10.0))
+            .collect();
+        let mut config = FindTargetsConfig::new(2.0, 3);
+        config.ransac.seed = Some(512);
+        config.lm.robust_loss = RobustLoss::Cauchy(0.0);
+        assert_eq!(
+            try_find_targets_with_config(&data, &config).unwrap_err(),
+            OptiRadarError::InvalidParameter { name: "robust_loss.delta", value: 0.0 }
+        );
+        let lines: Vec<Line> = data.iter().map(|m| m.try_into_line().unwrap()).collect();
+        assert!(levenberg_marquardt_optimize_with_prior(&lines, target, &Matrix3::identity(), &config.lm).is_err());
+        // 不返回错误的入口不迭代，原样返回初值并标记为未收敛
+        let guess = Point3::new(1.0, 1.0, 11.0);
+        let report = levenberg_marquardt_optimize_detailed(&lines, guess, &config.lm);
+        assert_eq!((report.position, report.iterations_used, report.converged), (guess, 0, false));
+
+        config.lm.robust_loss = RobustLoss::Cauchy(0.5);
+        let located = try_find_targets_with_config(&data, &config).unwrap();
+        assert!((located[0].position - target).norm() < 1e-6);
     }
-    current_pos
-}
 
-/// RANSAC 拟合光线集合，寻找最大内点集
-pub fn ransac_fit_lines(
-    all_lines: &[Line],
-    ransac_iterations: usize,
-    ransac_threshold: f64,
-    min_lines: usize,
-) -> Option<(Point3<f64>, Vec<usize>)> {
-    let mut rng = thread_rng();
-    let mut best_inliers_indices = Vec::new();
-    let mut best_model_pos = Point3::new(0.0, 0.0, 0.0);
+    #[test]
+    fn test_altitude_prior() {
+        // 两个相距 50 米的地面测量站观测 2 公里外的目标，光线近乎平行，
+        // 沿视线方向（含较大的竖直分量）几乎不可观测
+        let target = Point3::new(25.0, 2000.0, 400.0);
+        let stations = [Point3::new(0.0, 0.0, 0.0), Point3::new(50.0, 0.0, 2.0)];
+        let errors = [0.008, 0.0, 0.004, 0.0];
+        let measurements: Vec<_> = errors
+            .iter()
+            .enumerate()
+            .map(|(i, &error)| {
+                let start = stations[i % 2];
+                let rotation = UnitQuaternion::from_axis_angle(&Vector3::z_axis(), error);
+                let d = rotation * (target - start);
+                Measurement::new(start.x, start.y, start.z, d.x, d.y, d.z)
+            })
+            .collect();
 
-    if all_lines.len() < 3 {
-        return None;
+        let mut config = FindTargetsConfig::new(100.0, 3);
+        config.ransac.seed = Some(1);
+        let located = find_targets_with_config(&measurements, &config);
+        assert_eq!(located[0].num_lines, 4);
+        let free_z_error = (located[0].position.z - target.z).abs();
+
+        config.altitude_prior = Some((400.0, 1.0));
+        let located = find_targets_with_config(&measurements, &config);
+        assert_eq!(located[0].num_lines, 4);
+        let prior_z_error = (located[0].position.z - target.z).abs();
+        assert!(free_z_error > 50.0, "{}", free_z_error);
+        assert!(prior_z_error < 10.0, "{} vs {}", prior_z_error, free_z_error);
+    }
+
+    #[test]
+    fn test_fixed_altitude() {
+        // 无噪声光线：固定为真实高度时精确收敛，z 不参与优化
+        let target = Point3::new(40.0, -25.0, 12.0);
+        let lines: Vec<_> = [Point3::new(0.0, 0.0, 0.0), Point3::new(300.0, 50.0, 5.0), Point3::new(-80.0, 400.0, 2.0)]
+            .iter()
+            .map(|s| Line::new(*s, target - s))
+            .collect();
+        let report =
+            levenberg_marquardt_optimize_fixed_altitude(&lines, Point3::new(0.0, 0.0, 500.0), 12.0, &LmConfig::default());
+        assert_eq!(report.position.z, 12.0);
+        assert!((report.position - target).norm() < 1e-6, "{}", report.position);
+        assert!(report.converged);
+
+        // 5 个共面测量站位于 1.5 公里外 0.3 弧度的弧上，目标比测量站高 300 米：
+        // 自由求解时沿视线方向的误差很大，已知高度时俯仰角提供距离信息
+        let mut rng = StdRng::seed_from_u64(5);
+        let noise = Normal::new(0.0, 0.002).unwrap();
+        let mut config = FindTargetsConfig::new(50.0, 3);
+        config.ransac.seed = Some(5);
+        let (mut free_error, mut fixed_error) = (0.0, 0.0);
+        for _ in 0..30 {
+            let target = Point3::new(rng.gen_range(-100.0..100.0), rng.gen_range(-100.0..100.0), 300.0);
+            let measurements: Vec<_> = (0..5)
+                .map(|k| {
+                    let angle = -0.15 + 0.075 * k as f64;
+                    let start = Point3::new(1500.0 * angle.sin(), -1500.0 * angle.cos(), 0.0);
+                    let d = target - start;
+                    let azimuth = d.x.atan2(d.y) + noise.sample(&mut rng);
+                    let elevation = (d.z / d.norm()).asin() + noise.sample(&mut rng);
+                    Measurement::from_az_el(start.x, start.y, start.z, azimuth, elevation)
+                })
+                .collect();
+
+            config.fixed_altitude = None;
+            let free = find_targets_with_config(&measurements, &config);
+            config.fixed_altitude = Some(300.0);
+            let fixed = find_targets_with_config(&measurements, &config);
+            assert_eq!((free.len(), fixed.len()), (1, 1));
+            assert_eq!(fixed[0].position.z, 300.0);
+            let cov = fixed[0].covariance.unwrap();
+            assert_eq!((cov[(2, 2)], cov[(0, 2)]), (0.0, 0.0));
+            assert!(cov[(0, 0)] > 0.0 && cov[(1, 1)] > 0.0);
+            free_error += (free[0].position - target).xy().norm();
+            fixed_error += (fixed[0].position - target).xy().norm();
+        }
+        assert!(fixed_error < 0.7 * free_error, "{} vs {}", fixed_error, free_error);
     }
 
-    for _ in 0..ransac_iterations {
-        // 随机选取 3 条线
-        let mut sample_indices = HashSet::new();
-        while sample_indices.len() < 3 {
-            sample_indices.insert(rng.gen_range(0..all_lines.len()));
+    #[test]
+    fn test_bounding_box_rejects_far_field_phantom() {
+        // 3 个目标位于关注区域内，由 8 个环形测量站观测；另有 4 条杂波光线恰好交会于 25 公里外
+        let mut rng = StdRng::seed_from_u64(12);
+        let noise = Normal::new(0.0, 0.001).unwrap();
+        let targets = [Point3::new(-300.0, 200.0, 150.0), Point3::new(250.0, -100.0, 300.0), Point3::new(50.0, 400.0, 80.0)];
+        let mut measurements = Vec::new();
+        for (k, target) in targets.iter().enumerate() {
+            for s in 0..8 {
+                let angle = s as f64 * TAU / 8.0 + 0.1 * k as f64;
+                let start = Point3::new(1500.0 * angle.cos(), 1500.0 * angle.sin(), 10.0);
+                let d = target - start;
+                let azimuth = d.x.atan2(d.y) + noise.sample(&mut rng);
+                let elevation = (d.z / d.norm()).asin() + noise.sample(&mut rng);
+                measurements.push(Measurement::from_az_el(start.x, start.y, start.z, azimuth, elevation));
+            }
+        }
+        let phantom = Point3::new(25_000.0, 3_000.0, 800.0);
+        for s in 0..4 {
+            let start = Point3::new(-1800.0 + 1200.0 * s as f64, -1800.0 + 900.0 * s as f64, 0.0);
+            let d = phantom - start;
+            measurements.push(Measurement::new(start.x, start.y, start.z, d.x, d.y, d.z));
         }
-        let sample_indices_vec: Vec<_> = sample_indices.iter().copied().collect();
-        let sample_lines: Vec<_> = sample_indices_vec.iter().map(|&i| all_lines[i]).collect();
 
-        // 初始猜测：3 条光线两两最近点的平均
-        let initial_guess = (find_closest_midpoint(&sample_lines[0], &sample_lines[1]).coords
-            + find_closest_midpoint(&sample_lines[0], &sample_lines[2]).coords
-            + find_closest_midpoint(&sample_lines[1], &sample_lines[2]).coords)
-            / 3.0;
-        let initial_guess = Point3::from(initial_guess);
+        let mut config = FindTargetsConfig::new(20.0, 3);
+        config.ransac.seed = Some(12);
+        config.output_ordering = OutputOrdering::ByPosition;
+        let free = find_targets_with_config(&measurements, &config);
+        assert_eq!(free.len(), 4);
+        assert!(free.iter().any(|t| (t.position - phantom).norm() < 100.0));
 
-        // 统计内点
-        let mut current_inliers_indices = Vec::new();
-        for (i, line) in all_lines.iter().enumerate() {
-            let pa = initial_guess - line.start;
-            let proj = pa.dot(&line.direction);
-            let distance = (pa - line.direction * proj).norm();
-            if distance < ransac_threshold {
-                current_inliers_indices.push(i);
+        let bounds = BoundingBox::new(Point3::new(-1000.0, -1000.0, 0.0), Point3::new(1000.0, 1000.0, 1000.0)).unwrap();
+        let bounded = find_targets_with_config(&measurements, &config.clone().with_bounds(bounds, OutOfBounds::Discard));
+        assert_eq!(bounded.len(), 3);
+        // 区域内的目标不受影响
+        let in_box: Vec<_> = free.iter().filter(|t| bounds.contains(&t.position)).collect();
+        for (a, b) in in_box.iter().zip(&bounded) {
+            assert_eq!(a.inlier_indices, b.inlier_indices);
+            assert!((a.position - b.position).norm() < 1e-6);
+        }
+        for (target, truth) in bounded.iter().zip([targets[0], targets[2], targets[1]]) {
+            assert!((target.position - truth).norm() < 5.0, "{} vs {}", target.position, truth);
+        }
+    }
+
+    #[test]
+    fn test_bounding_box() {
+        let bounds = BoundingBox::new(Point3::new(-1.0, -2.0, 0.0), Point3::new(1.0, 2.0, 3.0)).unwrap();
+        assert!(bounds.contains(&Point3::new(1.0, -2.0, 0.0)));
+        assert!(!bounds.contains(&Point3::new(0.0, 0.0, 3.5)));
+        assert!(bounds.contains(&Point3::new(0.5f32, 1.5, 2.5)));
+        assert_eq!(bounds.clamp(&Point3::new(5.0, -5.0, 1.0)), Point3::new(1.0, -2.0, 1.0));
+        assert!(matches!(
+            BoundingBox::new(Point3::new(2.0, 0.0, 0.0), Point3::new(1.0, 1.0, 1.0)),
+            Err(OptiRadarError::InvalidParameter { name: "bounds.x", .. })
+        ));
+        assert!(BoundingBox::new(Point3::new(0.0, 0.0, f64::NAN), Point3::new(1.0, 1.0, 1.0)).is_err());
+    }
+
+    #[test]
+    fn test_min_geometry_rejects_grazing_consensus() {
+        // 6 个测量站排成一条直线；(3000, 100, 200) 处的一致集光线全部以掠射角交会，
+        // 光线最多而最先被找到，(0, 700, 150) 处的目标由前 4 个测量站观测，交会良好
+        let stations: Vec<_> = (0..6).map(|k| Point3::new(-1000.0 + 400.0 * k as f64, 0.0, 0.0)).collect();
+        let observe = |target: Point3<f64>, observers: &[Point3<f64>]| -> Vec<Measurement> {
+            observers
+                .iter()
+                .map(|start| {
+                    let d = target - start;
+                    Measurement::new(start.x, start.y, start.z, d.x, d.y, d.z)
+                })
+                .collect()
+        };
+        let grazing = Point3::new(3000.0, 100.0, 200.0);
+        let good = Point3::new(0.0, 700.0, 150.0);
+        let mut data = observe(grazing, &stations);
+        data.extend(observe(good, &stations[..4]));
+
+        let mut config = FindTargetsConfig::new(5.0, 3);
+        config.ransac.seed = Some(4);
+        let (free, _) = find_targets_with_diagnostics(&data, &config);
+        assert_eq!(free.len(), 2);
+
+        config.min_geometry = Some(GeometryCriterion::MinCrossingAngle(0.1));
+        let (located, diagnostics) = find_targets_with_diagnostics(&data, &config);
+        assert_eq!(located.len(), 1);
+        assert!((located[0].position - good).norm() < 1e-6);
+        assert_eq!(located[0].inlier_indices, vec![6, 7, 8, 9]);
+        // 被拒绝的光线未被消耗：好目标被接受后放回，再次被拒绝
+        assert_eq!(diagnostics.geometry_rejections, 2);
+        assert_eq!(diagnostics.unassigned_lines, 6);
+        assert!(diagnostics.to_string().contains("geometry_rejected=2"));
+
+        // 特征值判据同样拒绝掠射一致集
+        config.min_geometry = Some(GeometryCriterion::MinEigenvalue(0.05));
+        assert_eq!(find_targets_with_config(&data, &config).len(), 1);
+
+        // 只有掠射一致集时，连续拒绝超过上限即停止
+        let mut data = Vec::new();
+        for target in [grazing, Point3::new(3000.0, -150.0, 300.0), Point3::new(-3000.0, 120.0, 250.0), Point3::new(-3000.0, -100.0, 150.0)] {
+            data.extend(observe(target, &stations));
+        }
+        config.min_geometry = Some(GeometryCriterion::MinCrossingAngle(0.1));
+        let (located, diagnostics) = find_targets_with_diagnostics(&data, &config);
+        assert!(located.is_empty());
+        assert_eq!(diagnostics.stop_reason, StopReason::PoorGeometry);
+        assert_eq!(diagnostics.geometry_rejections, MAX_CONSECUTIVE_GEOMETRY_REJECTIONS + 1);
+    }
+
+    #[test]
+    fn test_find_targets_extended_reports_bearing_only_bundle() {
+        // 5 个测量站沿 x 轴相距 1 千米；近处目标交会良好，
+        // 远处目标约 2000 千米外，光线间夹角远小于测角噪声，无法交会定位
+        let stations: Vec<_> = (0..5).map(|k| Point3::new(1000.0 * k as f64, 0.0, 0.0)).collect();
+        let near = Point3::new(2000.0, 3000.0, 300.0);
+        let far = Point3::new(400_000.0, 2_000_000.0, 30_000.0);
+        let mut rng = StdRng::seed_from_u64(7);
+        let noise = Normal::new(0.0, 1e-3).unwrap();
+        let mut data = Vec::new();
+        for target in [near, far] {
+            for start in &stations {
+                let d = (target - start).normalize();
+                let (dx, dy, dz) = (d.x + noise.sample(&mut rng), d.y + noise.sample(&mut rng), d.z + noise.sample(&mut rng));
+                data.push(Measurement::new(start.x, start.y, start.z, dx, dy, dz));
             }
         }
 
-        if current_inliers_indices.len() > best_inliers_indices.len()
-            && current_inliers_indices.len() >= min_lines
-        {
-            best_inliers_indices = current_inliers_indices;
-            best_model_pos = initial_guess;
+        let mut config = FindTargetsConfig::new(20.0, 3);
+        config.ransac.seed = Some(3);
+        let results = find_targets_extended(&data, &config);
+        assert_eq!(results.len(), 2);
+        match &results[0] {
+            DetectionResult::Located(target) => {
+                assert!((target.position - near).norm() < 20.0);
+                assert_eq!(target.inlier_indices, vec![0, 1, 2, 3, 4]);
+            }
+            other => panic!("应先报告已定位目标：{}", other),
+        }
+        match &results[1] {
+            DetectionResult::BearingOnly { mean_direction, num_lines, origin_centroid, line_indices } => {
+                assert_eq!(*num_lines, 5);
+                assert_eq!(line_indices, &vec![5, 6, 7, 8, 9]);
+                let truth = (far - Point3::new(2000.0, 0.0, 0.0)).normalize();
+                assert!(mean_direction.angle(&truth) < 1e-3);
+                assert!((origin_centroid - Point3::new(2000.0, 0.0, 0.0)).norm() < 1e-9);
+            }
+            other => panic!("远处目标应为仅方位聚类：{}", other),
         }
+        assert!(results[1].to_string().ends_with("origin=(2000.000, 0.000, 0.000) lines=5"));
+
+        // 夹角上限过小时光线不成簇
+        config.bearing_only_max_angle_rad = 1e-4;
+        assert_eq!(find_targets_extended(&data, &config).len(), 1);
     }
 
-    if best_inliers_indices.len() >= min_lines {
-        Some((best_model_pos, best_inliers_indices))
-    } else {
-        None
+    #[test]
+    fn test_dedupe_measurements() {
+        // 测量站 A 的测向偏离目标约 40 米，且重传 10 次（部分为毫秒级内的近乎重复测向），
+        // B、C、D 各观测一次且准确
+        let target = Point3::new(1000.0, 2000.0, 500.0);
+        let biased = target + Vector3::new(40.0, -20.0, 0.0);
+        let observe = |start: Point3<f64>, aim: Point3<f64>, station: &str| {
+            let d = aim - start;
+            Measurement::new(start.x, start.y, start.z, d.x, d.y, d.z).with_station_id(station)
+        };
+        let mut data: Vec<_> = (0..10)
+            .map(|k| {
+                let jitter = Vector3::new(0.3, -0.2, 0.1) * (k % 3) as f64;
+                observe(Point3::new(0.01 * (k % 2) as f64, 0.0, 0.0), biased + jitter, "A").with_timestamp(0.001 * k as f64)
+            })
+            .collect();
+        data.push(observe(Point3::new(3000.0, 0.0, 0.0), target, "B"));
+        data.push(observe(Point3::new(2500.0, 3500.0, 0.0), target, "C"));
+        data.push(observe(Point3::new(-1500.0, 2500.0, 0.0), target, "D"));
+
+        let fit = |measurements: &[Measurement]| {
+            let lines: Vec<_> = measurements.iter().map(Line::from_measurement).collect();
+            let init = linear_triangulate(&lines).unwrap();
+            levenberg_marquardt_optimize(&lines, init, 200, 0.001)
+        };
+        let ray_a = Line::from_measurement(&data[0]);
+        let raw = fit(&data);
+        let deduped = dedupe_measurements(&data, 0.1, 1e-3);
+        assert_eq!(deduped.len(), 4);
+        assert_eq!(deduped[0].timestamp, Some(0.0));
+        assert_eq!(deduped[0].weight, 1.0);
+        let fixed = fit(&deduped);
+        // 去重前估计被拉向 A 的光线，去重后回到目标附近
+        assert!(ray_a.distance_to_point(&raw) < 5.0);
+        assert!(ray_a.distance_to_point(&fixed) > 20.0);
+        assert!((fixed - target).norm() < 0.6 * (raw - target).norm());
+
+        // 累加权重的合并：A 的权重为 10，方向取平均
+        let weighted = dedupe_measurements_weighted(&data, 0.1, 1e-3);
+        assert_eq!(weighted.len(), 4);
+        assert_eq!(weighted[0].weight, 10.0);
+        assert_eq!(weighted[3].weight, 1.0);
+        assert!((Line::from_measurement(&weighted[0]).direction - ray_a.direction).norm() < 1e-3);
+        // 组内权重全为零时取不加权的平均，而不是 0/0
+        let mut unweighted = data.clone();
+        unweighted[..10].iter_mut().for_each(|m| m.weight = 0.0);
+        let merged = &dedupe_measurements_weighted(&unweighted, 0.1, 1e-3)[0];
+        assert_eq!(merged.weight, 0.0);
+        assert!((merged.x - 0.005).abs() < 1e-12 && merged.y == 0.0 && merged.z == 0.0);
+        assert!((Line::from_measurement(merged).direction - ray_a.direction).norm() < 1e-3);
+
+        // 阈值不满足或测量站标识不同时不合并
+        assert_eq!(dedupe_measurements(&data, 0.001, 1e-3).len(), 5);
+        assert_eq!(dedupe_measurements(&data, 0.1, 1e-5).len(), 6);
+        let mut relabeled = data.clone();
+        relabeled[1].station_id = Some("E".to_string());
+        assert_eq!(dedupe_measurements(&relabeled, 0.1, 1e-3).len(), 5);
+        // 无效测量原样保留
+        relabeled.push(Measurement::new(0.0, 0.0, 0.0, 0.0, 0.0, 0.0));
+        assert_eq!(dedupe_measurements(&relabeled, 0.1, 1e-3).len(), 6);
     }
-}
 
-/// 综合使用 RANSAC + LM 定位多个目标
-pub fn find_targets(
-    data: &[Measurement],
-    ransac_threshold_m: f64,
-    min_lines_per_target: usize,
-) -> Vec<LocatedTarget> {
-    let all_lines: Vec<_> = data.iter().map(get_line).collect();
-    let mut located_targets = Vec::new();
-    let mut used_line_indices = HashSet::new();
-    let mut target_id_counter = 1;
+    #[test]
+    fn test_chi_square_quantile() {
+        assert!(normal_quantile(0.5).abs() < 1e-12);
+        assert!((normal_quantile(0.975) - 1.959964).abs() < 1e-6);
+        assert!((normal_quantile(0.001) + 3.090232).abs() < 1e-6);
+        assert!((chi_square_quantile(10.0, 0.99) - 23.209).abs() < 0.05);
+        assert!((chi_square_quantile(3.0, 0.95) - 7.815).abs() < 0.05);
+        assert!(normal_quantile(1.5).is_nan());
+    }
+
+    #[test]
+    fn test_consistency_check_flags_biased_station() {
+        // 7 个测量站环绕目标，测角噪声 σ = 1e-4 弧度；S3 的测向偏差 1e-3 弧度（10σ）
+        let target = Point3::new(200.0, -100.0, 800.0);
+        let sigma = 1e-4;
+        let mut rng = StdRng::seed_from_u64(11);
+        let noise = Normal::new(0.0, sigma).unwrap();
+        let measure = |bias: f64, rng: &mut StdRng| -> Vec<Measurement> {
+            (0..7)
+                .map(|k| {
+                    let angle = k as f64 * TAU / 7.0;
+                    let start = Point3::new(3000.0 * angle.cos(), 3000.0 * angle.sin(), 0.0);
+                    let mut d = (target - start).normalize();
+                    if k == 3 {
+                        d = UnitQuaternion::from_axis_angle(&Vector3::z_axis(), bias) * d;
+                    }
+                    let d = d + Vector3::from_fn(|_, _| noise.sample(rng));
+                    Measurement::new(start.x, start.y, start.z, d.x, d.y, d.z).with_station_id(format!("S{}", k))
+                })
+                .collect()
+        };
+        let mut config = FindTargetsConfig::new(20.0, 3);
+        config.ransac.seed = Some(2);
+        config.measurement_sigma = Some(sigma);
+
+        // 无偏差时残差与噪声相符
+        let located = find_targets_with_config(&measure(0.0, &mut rng), &config);
+        assert_eq!(located.len(), 1);
+        assert!(located[0].chi_square.unwrap() < 2.0);
+        assert!(!located[0].inconsistent);
+        assert!(located[0].outlier_indices.is_empty());
+
+        let data = measure(1e-3, &mut rng);
+        let located = find_targets_with_config(&data, &config);
+        assert_eq!(located.len(), 1);
+        let flagged = &located[0];
+        assert_eq!(flagged.num_lines, 7);
+        assert!(flagged.chi_square.unwrap() > 3.0);
+        assert!(flagged.inconsistent);
+        assert_eq!(flagged.outlier_indices, vec![3]);
+        assert!(flagged.to_string().ends_with(" inconsistent"));
 
-    if all_lines.len() < min_lines_per_target {
-        return located_targets;
+        // 剔除被标记的光线后重新拟合
+        config.refit_without_outliers = true;
+        let refit = &find_targets_with_config(&data, &config)[0];
+        assert_eq!(refit.inlier_indices, vec![0, 1, 2, 4, 5, 6]);
+        assert_eq!(refit.outlier_indices, vec![3]);
+        assert!(!refit.inconsistent);
+        assert!(refit.chi_square.unwrap() < 2.0);
+        assert!((refit.position - target).norm() < 0.7 * (flagged.position - target).norm());
+
+        // 未设置测角噪声时不做检验
+        config.measurement_sigma = None;
+        let unchecked = &find_targets_with_config(&data, &config)[0];
+        assert_eq!(unchecked.chi_square, None);
+        assert!(unchecked.outlier_indices.is_empty());
     }
 
-    loop {
-        // 筛选未使用的光线
-        let remaining_lines_map: Vec<_> = all_lines
-            .iter()
-            .enumerate()
-            .filter(|(i, _)| !used_line_indices.contains(i))
+    #[test]
+    fn test_find_targets_batch() {
+        // 6 帧，每帧 2 个目标各由 5 个测量站观测，另有 4 条杂波光线；目标逐帧移动
+        let mut rng = StdRng::seed_from_u64(21);
+        let frames: Vec<Vec<Measurement>> = (0..6)
+            .map(|frame| {
+                let shift = Vector3::new(10.0 * frame as f64, 0.0, 0.0);
+                let mut data = Vec::new();
+                for target in [Point3::new(0.0, 0.0, 300.0) + shift, Point3::new(800.0, 500.0, 200.0) + shift] {
+                    for k in 0..5 {
+                        let angle = k as f64 * 1.25;
+                        let start = Point3::new(1500.0 * angle.cos(), 1500.0 * angle.sin(), 0.0);
+                        let d = target + Vector3::from_fn(|_, _| rng.gen_range(-0.5..0.5)) - start;
+                        data.push(Measurement::new(start.x, start.y, start.z, d.x, d.y, d.z));
+                    }
+                }
+                for _ in 0..4 {
+                    let start = Point3::new(rng.gen_range(-1500.0..1500.0), rng.gen_range(-1500.0..1500.0), 0.0);
+                    data.push(Measurement::new(start.x, start.y, start.z, rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0), 1.0));
+                }
+                data
+            })
             .collect();
-        let remaining_lines: Vec<_> = remaining_lines_map.iter().map(|(_, l)| **l).collect();
 
-        if remaining_lines.len() < min_lines_per_target {
-            break;
+        let mut config = FindTargetsConfig::new(5.0, 3);
+        config.ransac.seed = Some(u64::MAX - 2);
+        let batch = find_targets_batch(&frames, &config);
+        assert_eq!(batch.len(), frames.len());
+        for (index, (located, data)) in batch.iter().zip(&frames).enumerate() {
+            // 与逐帧以种子加帧序号（回绕）调用的结果相同
+            let mut frame_config = config.clone();
+            frame_config.ransac.seed = Some((u64::MAX - 2).wrapping_add(index as u64));
+            let expected = find_targets_with_config(data, &frame_config);
+            assert_eq!(located.len(), 2);
+            assert_eq!(
+                located.iter().map(|t| (t.position, t.inlier_indices.clone())).collect::<Vec<_>>(),
+                expected.iter().map(|t| (t.position, t.inlier_indices.clone())).collect::<Vec<_>>()
+            );
         }
+        // 重复运行结果一致
+        let again = find_targets_batch(&frames, &config);
+        assert!(batch.iter().flatten().zip(again.iter().flatten()).all(|(a, b)| a.position == b.position));
+        assert!(find_targets_batch(&[], &config).is_empty());
+    }
 
-        if let Some((initial_guess, inliers_indices)) = ransac_fit_lines(
-            &remaining_lines,
-            100,
-            ransac_threshold_m,
-            min_lines_per_target,
-        ) {
-            let actual_inliers_indices: Vec<_> = inliers_indices
-                .iter()
-                .map(|&i| remaining_lines_map[i].0)
-                .collect();
-            let target_lines: Vec<_> = actual_inliers_indices
-                .iter()
-                .map(|&i| all_lines[i])
-                .collect();
+    #[test]
+    fn test_find_targets_from_lines_matches_measurements() {
+        // 2 个目标各由 5 个测量站观测（方向未单位化、权重不同）+ 4 条杂波光线
+        let mut rng = StdRng::seed_from_u64(23);
+        let mut data = Vec::new();
+        for target in [Point3::new(0.0, 0.0, 300.0), Point3::new(800.0, 500.0, 200.0)] {
+            for k in 0..5 {
+                let angle = k as f64 * 1.25;
+                let start = Point3::new(1500.0 * angle.cos(), 1500.0 * angle.sin(), 0.0);
+                let d = (target + Vector3::from_fn(|_, _| rng.gen_range(-0.5..0.5)) - start) * 3.0;
+                data.push(Measurement::new(start.x, start.y, start.z, d.x, d.y, d.z).with_weight(1.0 + k as f64));
+            }
+        }
+        for _ in 0..4 {
+            let start = Point3::new(rng.gen_range(-1500.0..1500.0), rng.gen_range(-1500.0..1500.0), 0.0);
+            data.push(Measurement::new(start.x, start.y, start.z, rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0), 1.0));
+        }
+        let mut config = FindTargetsConfig::new(5.0, 3);
+        config.ransac.seed = Some(9);
 
-            // LM 优化
-            let final_pos = levenberg_marquardt_optimize(&target_lines, initial_guess, 200, 0.001);
+        let lines: Vec<_> = data.iter().map(Line::from_measurement).collect();
+        assert!(lines.iter().all(|line| (line.direction.norm() - 1.0).abs() < 1e-12));
+        let from_lines = find_targets_from_lines(&lines, &config);
+        let from_data = find_targets_with_config(&data, &config);
+        assert_eq!(from_lines.len(), 2);
+        assert_eq!(from_lines.len(), from_data.len());
+        for (a, b) in from_lines.iter().zip(&from_data) {
+            assert_eq!(a.id, b.id);
+            assert_eq!(a.position, b.position);
+            assert_eq!(a.inlier_indices, b.inlier_indices);
+            assert_eq!(a.avg_error_dist_m, b.avg_error_dist_m);
+        }
+
+        // 光线的测量站编号在结果中记为十进制字符串
+        let mut stationed = lines.clone();
+        for (k, line) in stationed.iter_mut().enumerate().take(10) {
+            line.station = Some(k % 5 + 2);
+        }
+        let located = find_targets_from_lines(&stationed, &config);
+        assert_eq!(located[0].stations.len(), 5);
+        assert!(located[0].stations.iter().all(|name| ["2", "3", "4", "5", "6"].contains(&name.as_str())));
+        assert!(find_targets_from_lines(&[], &config).is_empty());
+    }
 
-            // 计算平均残差
-            let mut total_error_sq = 0.0;
-            for line in &target_lines {
-                let pa = final_pos - line.start;
-                let proj = pa.dot(&line.direction);
-                let dist_vec = pa - line.direction * proj;
-                total_error_sq += dist_vec.norm_squared();
+    #[test]
+    fn test_find_targets_from_iter_matches_slice() {
+        // 两个目标各 4 条光线 + 一条零方向的无效测量，测量站标识重复出现
+        let mut data = Vec::new();
+        for target in [Point3::new(0.0, 0.0, 300.0), Point3::new(900.0, -400.0, 150.0)] {
+            for (k, station) in [(0.0, 0.0), (1200.0, 0.0), (0.0, 1200.0), (-800.0, -900.0)].iter().enumerate() {
+                let start = Point3::new(station.0, station.1, 0.0);
+                let d = target - start;
+                data.push(Measurement::new(start.x, start.y, start.z, d.x, d.y, d.z).with_station_id(format!("S{}", k)));
             }
-            let avg_error_dist = (total_error_sq / target_lines.len() as f64).sqrt();
+        }
+        data.insert(3, Measurement::new(0.0, 0.0, 0.0, 0.0, 0.0, 0.0));
+        let config = FindTargetsConfig::new(1.0, 3);
 
-            located_targets.push(LocatedTarget {
-                id: format!("Target_{}", target_id_counter),
-                position: final_pos,
-                num_lines: target_lines.len(),
-                avg_error_dist_m: avg_error_dist,
-            });
-            target_id_counter += 1;
+        let (from_slice, slice_diagnostics) = find_targets_with_diagnostics(&data, &config);
+        let (from_iter, iter_diagnostics) = find_targets_from_iter(data.clone(), &config);
+        assert_eq!(from_iter.len(), 2);
+        assert_eq!(iter_diagnostics.skipped_indices(), vec![3]);
+        assert_eq!(iter_diagnostics.skipped_indices(), slice_diagnostics.skipped_indices());
+        for (a, b) in from_iter.iter().zip(&from_slice) {
+            assert_eq!(a.position, b.position);
+            assert_eq!(a.inlier_indices, b.inlier_indices);
+            assert_eq!(a.stations, b.stations);
+        }
 
-            for &i in &actual_inliers_indices {
-                used_line_indices.insert(i);
-            }
-        } else {
-            break;
+        // 解码失败的项计入序号，不影响其余测量
+        let items = data.iter().cloned().map(Ok).chain(std::iter::once(Err("截断")));
+        let (located, diagnostics, errors) = try_find_targets_from_iter(items, &config);
+        assert_eq!(errors, vec![(data.len(), "截断")]);
+        assert_eq!(diagnostics.skipped_indices(), vec![3]);
+        assert_eq!(located[0].inlier_indices, from_slice[0].inlier_indices);
+        let items = std::iter::once(Err("坏帧")).chain(data.iter().cloned().map(Ok));
+        let (located, _, errors) = try_find_targets_from_iter(items, &config);
+        assert_eq!(errors, vec![(0, "坏帧")]);
+        assert_eq!(located[0].inlier_indices, from_slice[0].inlier_indices.iter().map(|i| i + 1).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_levenberg_marquardt_with_prior() {
+        // 单条光线沿 x 轴方向无约束，由先验补足；其余方向按信息量加权
+        let lines = [Line::new(Point3::new(-10.0, 0.0, 10.0), Vector3::new(1.0, 0.0, 0.0))];
+        let prior_mean = Point3::new(5.0, 3.0, 12.0);
+        let prior_cov = Matrix3::from_diagonal(&Vector3::new(4.0, 4.0, 4.0));
+        let report =
+            levenberg_marquardt_optimize_with_prior(&lines, prior_mean, &prior_cov, &LmConfig::default()).unwrap();
+        // y = (0·1 + 3·¼) / 1.25，z = (10·1 + 12·¼) / 1.25
+        assert!((report.position - Point3::new(5.0, 0.6, 10.4)).norm() < 1e-6, "{}", report.position);
+        assert!(report.converged);
+        assert!(report.final_cost < report.initial_cost);
+
+        // 相关的先验协方差：y 由光线与先验按方差融合，x 按回归系数 cov(x,y)/var(y) 随 y 偏移
+        let prior_cov = Matrix3::new(4.0, 2.0, 0.0, 2.0, 4.0, 0.0, 0.0, 0.0, 4.0);
+        let report =
+            levenberg_marquardt_optimize_with_prior(&lines, prior_mean, &prior_cov, &LmConfig::default()).unwrap();
+        let expected_y = 3.0 * 1.0 / (1.0 + 4.0);
+        let expected_x = 5.0 + 0.5 * (expected_y - 3.0);
+        assert!((report.position.y - expected_y).abs() < 1e-6, "{}", report.position);
+        assert!((report.position.x - expected_x).abs() < 1e-6, "{}", report.position);
+
+        // 没有光线时直接返回先验均值
+        let report = levenberg_marquardt_optimize_with_prior(&[], prior_mean, &prior_cov, &LmConfig::default()).unwrap();
+        assert_eq!(report.position, prior_mean);
+
+        for bad in [Matrix3::zeros(), Matrix3::new(1.0, 2.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0), -Matrix3::identity()] {
+            assert_eq!(
+                levenberg_marquardt_optimize_with_prior(&lines, prior_mean, &bad, &LmConfig::default()).unwrap_err(),
+                OptiRadarError::InvalidCovariance
+            );
         }
     }
 
-    located_targets
-}
+    #[test]
+    fn test_linear_triangulate() {
+        let target = Point3::new(10.0, 20.0, 30.0);
+        let lines: Vec<_> = (0..6)
+            .map(|i| {
+                let angle = i as f64;
+                let start = Point3::new(50.0 * angle.cos(), 50.0 * angle.sin(), 0.0);
+                Line::new(start, target - start)
+            })
+            .collect();
+        let p = linear_triangulate(&lines).unwrap();
+        assert!((p - target).norm() < 1e-9);
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        // 与 LM 收敛到同一最优点
+        let lm = levenberg_marquardt_optimize(&lines, Point3::origin(), 200, 0.001);
+        assert!((p - lm).norm() < 1e-6);
+
+        // 平行光线无解
+        let parallel: Vec<_> = (0..3)
+            .map(|i| Line::new(Point3::new(0.0, i as f64, 0.0), Vector3::new(1.0, 0.0, 0.0)))
+            .collect();
+        assert!(linear_triangulate(&parallel).is_none());
+        assert!(linear_triangulate(&lines[..1]).is_none());
+    }
 
     #[test]
-    fn test_get_line_normalization() {
-        let measurement = Measurement {
-            x: 0.0,
-            y: 0.0,
-            z: 0.0,
-            direction_x: 3.0,
-            direction_y: 4.0,
-            direction_z: 0.0,
+    fn test_levenberg_marquardt_matches_dense_reference() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let target = Point3::new(10.0, 20.0, 30.0);
+        let lines: Vec<_> = (0..30)
+            .map(|_| {
+                let start = Point3::new(
+                    rng.gen_range(-100.0..100.0),
+                    rng.gen_range(-100.0..100.0),
+                    rng.gen_range(0.0..5.0),
+                );
+                let noise = Vector3::new(rng.gen_range(-2.0..2.0), rng.gen_range(-2.0..2.0), rng.gen_range(-2.0..2.0));
+                let mut line = Line::new(start, target + noise - start);
+                line.weight = rng.gen_range(0.5..2.0);
+                line
+            })
+            .collect();
+
+        for robust_loss in [RobustLoss::None, RobustLoss::Huber(1.0)] {
+            // 参考实现不提前终止，比较时关闭终止条件
+            let config = LmConfig {
+                robust_loss,
+                xtol: 0.0,
+                ftol: 0.0,
+                max_lambda: f64::INFINITY,
+                ..LmConfig::default()
+            };
+            let guess = Point3::new(0.0, 0.0, 0.0);
+            let fast = levenberg_marquardt_optimize_with_config(&lines, guess, &config);
+            let dense = dense_reference_lm(&lines, guess, &config);
+            assert!((fast - dense).norm() < 1e-12, "{}", (fast - dense).norm());
+        }
+    }
+
+    #[test]
+    fn test_levenberg_marquardt_early_termination() {
+        let target = Point3::new(10.0, 20.0, 30.0);
+        let lines: Vec<_> = (0..8)
+            .map(|i| {
+                let angle = i as f64 * 0.8;
+                let start = Point3::new(100.0 * angle.cos(), 100.0 * angle.sin(), 0.0);
+                let offset = Vector3::new(0.0, 0.0, if i % 2 == 0 { 0.5 } else { -0.5 });
+                Line::new(start, target + offset - start)
+            })
+            .collect();
+
+        let config = LmConfig::default();
+        let report = levenberg_marquardt_optimize_detailed(&lines, Point3::origin(), &config);
+        assert!(report.iterations_used < config.iterations);
+
+        // 与跑满全部迭代的结果一致
+        let full = LmConfig {
+            xtol: 0.0,
+            ftol: 0.0,
+            max_lambda: f64::INFINITY,
+            ..LmConfig::default()
         };
-        let line = get_line(&measurement);
-        assert!((line.direction.norm() - 1.0).abs() < 1e-9);
+        let full_report = levenberg_marquardt_optimize_detailed(&lines, Point3::origin(), &full);
+        assert_eq!(full_report.iterations_used, full.iterations);
+        assert!((report.position - full_report.position).norm() < 1e-6);
     }
 
     #[test]
-    fn test_find_closest_midpoint() {
-        let line1 = Line {
-            start: Point3::new(0.0, 5.0, 0.0),
-            direction: Vector3::new(1.0, 0.0, 0.0).normalize(),
+    fn test_levenberg_marquardt_multi_start() {
+        // 基线很短的 4 个测量站观测 2 千米外的目标；角度残差、射线模式下，
+        // 位于所有测量站背后的初值处各光线残差均为单位长度，梯度为零，LM 原地停滞
+        let target = Point3::new(300.0, 2000.0, 400.0);
+        let starts = [
+            Point3::new(-200.0, 0.0, 0.0),
+            Point3::new(0.0, 0.0, 10.0),
+            Point3::new(250.0, 0.0, -5.0),
+            Point3::new(400.0, 10.0, 0.0),
+        ];
+        let lines: Vec<_> = starts.iter().map(|&start| Line::new(start, target - start)).collect();
+        let guess = Point3::new(0.0, -50.0, 0.0);
+        let single = LmConfig {
+            residual_model: ResidualModel::Angular,
+            ..LmConfig::default()
         };
-        let line2 = Line {
-            start: Point3::new(5.0, 0.0, 0.0),
-            direction: Vector3::new(0.0, 1.0, 0.0).normalize(),
+        let stalled = levenberg_marquardt_optimize_detailed(&lines, guess, &single);
+        assert!((stalled.position - target).norm() > 10.0);
+
+        let multi = LmConfig {
+            multi_start: 4,
+            ..single.clone()
         };
-        let midpoint = find_closest_midpoint(&line1, &line2);
-        let epsilon = 1e-6;
-        assert!((midpoint.x - 5.0).abs() < epsilon);
-        assert!((midpoint.y - 5.0).abs() < epsilon);
-        assert!((midpoint.z - 0.0).abs() < epsilon);
+        let report = levenberg_marquardt_optimize_detailed(&lines, guess, &multi);
+        assert!((report.position - target).norm() < 1e-3);
+        assert!(report.converged);
+        assert_eq!(report.initial_cost, stalled.initial_cost);
+        assert!(report.final_cost < 1e-12 && stalled.final_cost > 1.0);
+
+        // 起点：给定初值、线性解、两两最近点中点的平均，其余为沿弱方向的正负偏移
+        let guesses = multi_start_guesses(&lines, guess, 6);
+        assert_eq!(guesses.len(), 6);
+        assert_eq!(guesses[0], guess);
+        assert!((guesses[1] - target).norm() < 1e-6);
+        assert!((guesses[2] - target).norm() < 1e-6);
+        assert!(((guesses[3] - guess) + (guesses[4] - guess)).norm() < 1e-9);
+        assert_eq!(multi_start_guesses(&lines, guess, 1), vec![guess]);
+
+        // K = 1 时与原来的单起点结果相同
+        let default_config = LmConfig {
+            multi_start: 1,
+            ..single
+        };
+        let default_report = levenberg_marquardt_optimize_detailed(&lines, guess, &default_config);
+        assert_eq!(default_report.position, stalled.position);
+        assert_eq!(default_report.iterations_used, stalled.iterations_used);
     }
 
     #[test]
-    fn test_ransac_fit_lines() {
-        let mut lines = Vec::new();
-        for _ in 0..10 {
-            let start = Point3::new(
-                thread_rng().gen_range(0.0..5.0),
-                thread_rng().gen_range(15.0..25.0),
-                thread_rng().gen_range(25.0..35.0),
-            );
-            let direction = (Point3::new(10.0, 20.0, 30.0) - start).normalize();
-            lines.push(Line { start, direction });
+    fn test_levenberg_marquardt_fuses_range() {
+        // 相距 50 米的两个测量站观测 3 千米外的目标，交会角约 0.017 弧度；两条光线各偏 1 毫弧度，
+        // 只用测向时误差沿视线方向被放大到上百米，加入 σ = 20 米的测距后沿视线方向由测距约束
+        let target = Point3::new(0.0, 3000.0, 500.0);
+        let stations = [Point3::new(-25.0, 0.0, 0.0), Point3::new(25.0, 0.0, 0.0)];
+        let (offsets, range_errors) = ([3.0, -3.0], [15.0, -10.0]);
+        let measurements: Vec<Measurement> = stations
+            .iter()
+            .zip(offsets.iter().zip(range_errors))
+            .map(|(station, (&offset, range_error))| {
+                let direction = target - station + Vector3::new(offset, 0.0, 0.0);
+                Measurement::from_arrays(station.coords.into(), direction.into())
+                    .with_range((target - station).norm() + range_error, 20.0)
+            })
+            .collect();
+        let lines: Vec<Line> = measurements.iter().map(|m| m.try_into_line().unwrap()).collect();
+        assert_eq!(lines[0].range, measurements[0].range);
+        let guess = Point3::new(0.0, 2000.0, 300.0);
+
+        let bearings = levenberg_marquardt_optimize_detailed(&lines, guess, &LmConfig::default());
+        let fused_config = LmConfig {
+            use_range: true,
+            ..LmConfig::default()
+        };
+        let fused = levenberg_marquardt_optimize_detailed(&lines, guess, &fused_config);
+        assert!((bearings.position - target).norm() > 100.0, "{}", bearings.position);
+        assert!((fused.position - target).norm() < 15.0, "{}", fused.position);
+        assert!(fused.converged && fused.final_cost < fused.initial_cost);
+        let dogleg = dogleg_optimize(&lines, guess, &fused_config);
+        assert!((dogleg.position - fused.position).norm() < 1e-3);
+
+        // 未启用 use_range 时测距不影响结果
+        let bare: Vec<Line> = lines.iter().map(|line| Line { range: None, ..*line }).collect();
+        for config in [LmConfig::default(), fused_config] {
+            assert_eq!(levenberg_marquardt_optimize_detailed(&bare, guess, &config).position, bearings.position);
         }
-        for _ in 0..5 {
-            let start = Point3::new(
-                thread_rng().gen_range(-50.0..50.0),
-                thread_rng().gen_range(-50.0..50.0),
-                thread_rng().gen_range(-50.0..50.0),
-            );
-            let direction =
-                Vector3::new(thread_rng().gen(), thread_rng().gen(), thread_rng().gen()).normalize();
-            lines.push(Line { start, direction });
+
+        for (range, sigma, name) in [(-1.0, 20.0, "range"), (f64::NAN, 20.0, "range"), (100.0, 0.0, "range.sigma")] {
+            let err = measurements[0].clone().with_range(range, sigma).try_into_line().unwrap_err();
+            assert!(matches!(err, OptiRadarError::InvalidParameter { name: n, .. } if n == name), "{:?}", err);
         }
+    }
 
-        let result = ransac_fit_lines(&lines, 100, 1.0, 3);
-        assert!(result.is_some());
+    #[test]
+    fn test_range_gate_rejects_inconsistent_lines() {
+        // 5 条光线都精确通过目标，第 2 条的测距偏差 300 米（σ = 10 米），只在设置 range_gate 时被排除
+        let target = Point3::new(100.0, 200.0, 300.0);
+        let data: Vec<Measurement> = (0..5)
+            .map(|i| {
+                let angle = i as f64 * 1.3;
+                let station = Point3::new(800.0 * angle.cos(), 800.0 * angle.sin(), 0.0);
+                let range = (target - station).norm() + if i == 2 { 300.0 } else { 2.0 };
+                Measurement::from_arrays(station.coords.into(), (target - station).into())
+                    .with_station_id(format!("S{}", i))
+                    .with_range(range, 10.0)
+                    .with_timestamp(i as f64)
+            })
+            .collect();
+        let mut config = FindTargetsConfig::new(1.0, 3);
+        config.ransac.seed = Some(605);
+        config.lm.use_range = true;
+        let located = find_targets_with_config(&data, &config);
+        assert_eq!(located.len(), 1);
+        assert_eq!(located[0].inlier_indices, vec![0, 1, 2, 3, 4]);
 
-        if let Some((initial_guess, inliers_indices)) = result {
-            assert!(inliers_indices.len() >= 8);
-            let epsilon = 1e-1;
-            assert!((initial_guess.x - 10.0).abs() < epsilon);
-            assert!((initial_guess.y - 20.0).abs() < epsilon);
-            assert!((initial_guess.z - 30.0).abs() < epsilon);
-        }
+        config.ransac.range_gate = Some(3.0);
+        let located = find_targets_with_config(&data, &config);
+        assert_eq!(located.len(), 1);
+        assert_eq!(located[0].inlier_indices, vec![0, 1, 3, 4]);
+        assert!((located[0].position - target).norm() < 1.0, "{}", located[0].position);
+        config.motion_model = MotionModel::ConstantVelocity;
+        assert_eq!(find_targets_with_config(&data, &config)[0].inlier_indices, vec![0, 1, 3, 4]);
     }
 
     #[test]
-    fn test_levenberg_marquardt_with_perfect_data() {
-        let line1 = Line {
-            start: Point3::new(-10.0, 0.0, 10.0),
-            direction: Vector3::new(1.0, 0.0, 0.0),
+    fn test_levenberg_marquardt_flags_degenerate_geometry() {
+        // 两条光线以 1e-4 弧度的交会角相交，沿视线方向几乎不可观测
+        let target = Point3::new(0.0, 1000.0, 0.0);
+        let lines = [
+            Line::new(Point3::new(-0.05, 0.0, 0.0), target - Point3::new(-0.05, 0.0, 0.0)),
+            Line::new(Point3::new(0.05, 0.0, 0.0), target - Point3::new(0.05, 0.0, 0.0)),
+        ];
+        let guess = Point3::new(1.0, 900.0, 1.0);
+        let report = levenberg_marquardt_optimize_detailed(&lines, guess, &LmConfig::default());
+        assert!(report.degenerate);
+        assert!(report.position.iter().all(|v| v.is_finite()));
+
+        // 交会良好时不标记；放宽阈值后同样不标记近乎平行的光线
+        let crossing = [
+            Line::new(Point3::new(-800.0, 0.0, 0.0), target - Point3::new(-800.0, 0.0, 0.0)),
+            Line::new(Point3::new(800.0, 0.0, 0.0), target - Point3::new(800.0, 0.0, 0.0)),
+        ];
+        let report = levenberg_marquardt_optimize_detailed(&crossing, guess, &LmConfig::default());
+        assert!(!report.degenerate);
+        assert!((report.position - target).norm() < 1e-6);
+        let lenient = LmConfig {
+            min_rcond: 1e-12,
+            ..LmConfig::default()
         };
-        let line2 = Line {
-            start: Point3::new(0.0, -10.0, 10.0),
-            direction: Vector3::new(0.0, 1.0, 0.0),
+        assert!(!levenberg_marquardt_optimize_detailed(&lines, guess, &lenient).degenerate);
+
+        // 固定高度时只检查水平方向：竖直排列的两个测量站在水平面内交会良好
+        let stacked = [
+            Line::new(Point3::new(-800.0, 0.0, 0.0), target - Point3::new(-800.0, 0.0, 0.0)),
+            Line::new(Point3::new(0.0, 0.0, -500.0), target - Point3::new(0.0, 0.0, -500.0)),
+        ];
+        let fixed = levenberg_marquardt_optimize_fixed_altitude(&stacked, guess, 0.0, &LmConfig::default());
+        assert!(!fixed.degenerate);
+
+        // 结果标记传到定位结果
+        let data: Vec<_> = [Point3::new(-800.0, 0.0, 0.0), Point3::new(800.0, 0.0, 0.0), Point3::new(0.0, -600.0, 0.0)]
+            .iter()
+            .map(|start| {
+                let d = target - start;
+                Measurement::new(start.x, start.y, start.z, d.x, d.y, d.z)
+            })
+            .collect();
+        let mut config = FindTargetsConfig::new(5.0, 3);
+        let located = find_targets_with_config(&data, &config);
+        assert_eq!(located.len(), 1);
+        assert!(!located[0].degenerate);
+        config.lm.min_rcond = 0.9;
+        let located = find_targets_with_config(&data, &config);
+        assert!(located[0].degenerate);
+        assert!(located[0].to_string().ends_with(" degenerate"));
+    }
+
+    #[test]
+    fn test_dogleg_matches_levenberg_marquardt() {
+        let mut rng = StdRng::seed_from_u64(8);
+        let target = Point3::new(10.0, 20.0, 30.0);
+        // 基准测试中的 10 条光线场景（测量站集中在目标一侧）及带噪声、不同权重的 30 条光线
+        let clustered: Vec<_> = (0..10)
+            .map(|_| {
+                let start = Point3::new(rng.gen_range(0.0..5.0), rng.gen_range(15.0..25.0), rng.gen_range(25.0..35.0));
+                Line::new(start, target - start)
+            })
+            .collect();
+        let noisy: Vec<_> = (0..30)
+            .map(|_| {
+                let start = Point3::new(rng.gen_range(-100.0..100.0), rng.gen_range(-100.0..100.0), 0.0);
+                let noise = Vector3::from_fn(|_, _| rng.gen_range(-2.0..2.0));
+                let mut line = Line::new(start, target + noise - start);
+                line.weight = rng.gen_range(0.5..2.0);
+                line
+            })
+            .collect();
+
+        for lines in [&clustered, &noisy] {
+            for config in [
+                LmConfig::default(),
+                LmConfig {
+                    robust_loss: RobustLoss::Huber(1.0),
+                    ..LmConfig::default()
+                },
+                LmConfig {
+                    residual_model: ResidualModel::Angular,
+                    ..LmConfig::default()
+                },
+            ] {
+                let guess = Point3::new(9.0, 19.0, 29.0);
+                let lm = levenberg_marquardt_optimize_detailed(lines, guess, &config);
+                let dogleg = dogleg_optimize(lines, guess, &config);
+                assert!(dogleg.converged);
+                assert!((dogleg.position - lm.position).norm() < 1e-6, "{}", (dogleg.position - lm.position).norm());
+                assert!(dogleg.final_cost <= lm.final_cost * (1.0 + 1e-9) + 1e-18);
+            }
+        }
+        // 从远处出发同样收敛到同一最优点
+        let far = dogleg_optimize(&noisy, Point3::new(-500.0, 800.0, 300.0), &LmConfig::default());
+        let lm = levenberg_marquardt_optimize_detailed(&noisy, Point3::new(-500.0, 800.0, 300.0), &LmConfig::default());
+        assert!((far.position - lm.position).norm() < 1e-6);
+
+        // 通过配置在定位流程中选用 dogleg
+        let data: Vec<_> = noisy
+            .iter()
+            .map(|line| {
+                let d = line.direction;
+                Measurement::new(line.start.x, line.start.y, line.start.z, d.x, d.y, d.z)
+            })
+            .collect();
+        let mut config = FindTargetsConfig::new(10.0, 3);
+        config.ransac.seed = Some(1);
+        let with_lm = find_targets_with_config(&data, &config);
+        config.optimizer = Optimizer::Dogleg;
+        let with_dogleg = find_targets_with_config(&data, &config);
+        assert_eq!(with_lm.len(), with_dogleg.len());
+        for (a, b) in with_lm.iter().zip(&with_dogleg) {
+            assert_eq!(a.inlier_indices, b.inlier_indices);
+            assert!((a.position - b.position).norm() < 1e-6);
+        }
+        config.fixed_altitude = Some(30.0);
+        let fixed = find_targets_with_config(&data, &config);
+        assert!(fixed.iter().all(|t| t.position.z == 30.0));
+    }
+
+    #[test]
+    fn test_levenberg_marquardt_report_flags_non_convergence() {
+        // 测量站相距 100 米、方向几乎平行，最优点远在 10⁷ 米外
+        let target = Point3::new(1e7, 0.0, 100.0);
+        let lines: Vec<_> = (0..4)
+            .map(|i| {
+                let start = Point3::new(0.0, i as f64 * 100.0, 0.0);
+                let offset = Vector3::new(0.0, 0.0, if i % 2 == 0 { 1.0 } else { -1.0 });
+                Line::new(start, target + offset - start)
+            })
+            .collect();
+        let guess = Point3::new(500.0, 100.0, 0.0);
+
+        // 迭代预算不足以沿弱方向走完，应返回当前位置并标记未收敛
+        let short = LmConfig {
+            iterations: 5,
+            ..LmConfig::default()
         };
+        let report = levenberg_marquardt_optimize_detailed(&lines, guess, &short);
+        assert!(!report.converged);
+        assert_eq!(report.iterations_used, 5);
+        assert!(report.position.iter().all(|v| v.is_finite()));
+        assert!(report.final_cost < report.initial_cost);
+
+        let report = levenberg_marquardt_optimize_detailed(&lines, guess, &LmConfig::default());
+        assert!(report.converged);
+        assert!(report.final_cost <= report.initial_cost);
+        assert!(report.final_lambda > 0.0);
+    }
+
+    #[test]
+    fn test_levenberg_marquardt_with_perfect_data() {
+        let line1 = Line::new(Point3::new(-10.0, 0.0, 10.0), Vector3::new(1.0, 0.0, 0.0));
+        let line2 = Line::new(Point3::new(0.0, -10.0, 10.0), Vector3::new(0.0, 1.0, 0.0));
         let lines = vec![line1, line2];
 
         let initial_guess = Point3::new(100.0, 100.0, 100.0);
@@ -361,4 +7887,31 @@ mod tests {
         assert!((final_pos.y - 0.0).abs() < epsilon);
         assert!((final_pos.z - 10.0).abs() < epsilon);
     }
+
+    #[test]
+    fn test_levenberg_marquardt_with_perfect_data_f32() {
+        // 同上，标量为 f32：容差按 f32 的精度放宽，且须在迭代上限前收敛
+        let line1 = Line32::from_parts(Point3::new(-10.0, 0.0, 10.0), Vector3::new(1.0, 0.0, 0.0));
+        let line2: Line32 = Measurement::new(0.0, -10.0, 10.0, 0.0, 1.0, 0.0).try_into_line_as().unwrap();
+        let lines = vec![line1, line2];
+        assert_eq!(line2.weight, 1.0f32);
+
+        let report =
+            levenberg_marquardt_optimize_detailed(&lines, Point3::new(100.0f32, 100.0, 100.0), &LmConfig::default());
+        assert!(report.converged);
+        assert!((report.position - Point3::new(0.0, 0.0, 10.0)).norm() < 1e-3);
+
+        // 与 f64 结果一致，RANSAC 也可直接处理 f32 光线
+        let lines64: Vec<Line> = lines.iter().map(Line::cast).collect();
+        let final_pos = levenberg_marquardt_optimize(&lines64, Point3::new(100.0, 100.0, 100.0), 200, 0.01);
+        assert!((report.position.cast::<f64>() - final_pos).norm() < 1e-3);
+        let mut config = RansacConfig::new(100, 0.1, 2);
+        config.seed = Some(1);
+        config.exhaustive_max_lines = 0;
+        let extra = Line32::from_parts(Point3::new(5.0, 5.0, 0.0), Vector3::new(-5.0, -5.0, 10.0));
+        let (model, _) = ransac_fit_lines(&[line1, line2, extra], &config);
+        let (position, inliers) = model.unwrap();
+        assert_eq!(inliers, vec![0, 1, 2]);
+        assert!((position - Point3::new(0.0, 0.0, 10.0)).norm() < 1e-3);
+    }
 }
@@ -0,0 +1,739 @@
+// src/tracking.rs
+
+use crate::evaluation::min_cost_assignment;
+use crate::target_processor::{
+    fit_target, keep_closest_per_station, prepare_lines, run_pipeline, seeded_rng, FindTargetsConfig, Line,
+    LocatedTarget, Measurement, Pipeline, ResidualModel,
+};
+use nalgebra::{Matrix3, Matrix6, Point3, Vector3, Vector6};
+use std::collections::{HashSet, VecDeque};
+
+// --- 帧间航迹关联 ---
+//
+// 每帧的定位结果按门限距离关联到已有航迹：未关联的定位结果产生暂定航迹，
+// 暂定航迹在最近 N 帧内命中至少 M 次时确认，否则在存在 N 帧后删除；
+// 任何航迹连续 K 帧未命中时删除。航迹编号从 1 开始递增，不会复用。
+//
+// 每条航迹以匀速模型的卡尔曼滤波估计位置和速度（状态 [p; v]，6 维），
+// 过程噪声为白噪声加速度；关联门限以航迹预测到本帧时刻的位置为中心。
+//
+// `update` 接收已定位的目标；`process_frame` 直接接收一帧测量，先把预测位置门限内的光线
+// 分给已有航迹并逐条航迹重新运行 LM，只在剩余光线上运行 RANSAC 寻找新目标（同 `locator`）。
+
+/// 关联方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Association {
+    /// 依次关联距离最近的（航迹, 定位结果）对
+    NearestNeighbor,
+    /// 全局最近邻：使门限内关联对的距离之和最小（匈牙利算法）
+    #[default]
+    GlobalNearestNeighbor,
+}
+
+/// 航迹管理参数
+#[derive(Debug, Clone)]
+pub struct TrackerConfig {
+    pub gate_distance: f64,       // 关联门限（米），距离超过该值的定位结果不关联
+    pub association: Association, // 关联方式
+    pub confirm_hits: usize,      // 确认所需的命中次数 M
+    pub confirm_window: usize,    // 确认窗口的帧数 N
+    pub max_misses: usize,        // 连续未命中 K 帧后删除航迹
+    pub process_noise: f64,       // 白噪声加速度的功率谱密度 q（m²/s³）
+    pub measurement_std: f64,     // 定位结果没有协方差（或不使用）时的位置标准差（米）
+    pub use_target_covariance: bool, // 以定位结果的协方差作为量测噪声
+    pub initial_velocity_std: f64,   // 新航迹初始速度（取 0）的标准差（米/秒）
+    pub locate: FindTargetsConfig,   // `process_frame` 的定位参数
+}
+
+impl TrackerConfig {
+    /// 以给定门限创建参数：全局最近邻关联，5 帧内命中 3 次确认，连续 3 帧未命中删除；
+    /// 过程噪声 q = 1 m²/s³，量测使用定位结果的协方差（缺省时标准差 5 米），初始速度标准差 50 米/秒；
+    /// `process_frame` 以门限为 RANSAC 阈值，每个目标至少 3 条光线
+    pub fn new(gate_distance: f64) -> Self {
+        TrackerConfig {
+            gate_distance,
+            association: Association::GlobalNearestNeighbor,
+            confirm_hits: 3,
+            confirm_window: 5,
+            max_misses: 3,
+            process_noise: 1.0,
+            measurement_std: 5.0,
+            use_target_covariance: true,
+            initial_velocity_std: 50.0,
+            locate: FindTargetsConfig::new(gate_distance, 3),
+        }
+    }
+
+    /// 定位结果作为量测时的噪声协方差
+    fn measurement_covariance(&self, detection: &LocatedTarget) -> Matrix3<f64> {
+        match detection.covariance {
+            Some(covariance) if self.use_target_covariance => covariance,
+            _ => Matrix3::identity() * self.measurement_std.powi(2),
+        }
+    }
+}
+
+/// 航迹状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrackStatus {
+    /// 尚未满足 M/N 确认条件
+    Tentative,
+    /// 已确认
+    Confirmed,
+}
+
+/// 航迹
+#[derive(Debug, Clone)]
+pub struct Track {
+    pub id: u64,                 // 航迹编号，从 1 开始，不复用
+    pub status: TrackStatus,     // 航迹状态
+    pub position: Point3<f64>,   // 滤波位置（`state_time` 时刻）
+    pub velocity: Vector3<f64>,  // 滤波速度（米/秒）
+    pub covariance: Matrix6<f64>, // 状态 [位置; 速度] 的协方差
+    pub state_time: f64,         // 滤波状态对应的时刻（秒），即最近处理的帧时刻
+    pub created_at: f64,         // 创建时刻（秒）
+    pub last_update: f64,        // 最近一次关联的时刻（秒）
+    pub hits: usize,             // 累计命中帧数（含创建帧）
+    pub misses: usize,           // 当前连续未命中帧数
+    pub age: usize,              // 存在的帧数（含创建帧）
+    recent: VecDeque<bool>,      // 最近 N 帧的命中记录
+}
+
+impl Track {
+    /// 是否已确认
+    pub fn is_confirmed(&self) -> bool {
+        self.status == TrackStatus::Confirmed
+    }
+
+    /// 位置协方差
+    pub fn position_covariance(&self) -> Matrix3<f64> {
+        self.covariance.fixed_view::<3, 3>(0, 0).into()
+    }
+
+    /// 速度协方差
+    pub fn velocity_covariance(&self) -> Matrix3<f64> {
+        self.covariance.fixed_view::<3, 3>(3, 3).into()
+    }
+
+    /// 按匀速模型外推到时刻 `time` 的状态与协方差
+    fn predict(&self, time: f64, process_noise: f64) -> (Vector6<f64>, Matrix6<f64>) {
+        let dt = time - self.state_time;
+        let mut f = Matrix6::identity();
+        f.fixed_view_mut::<3, 3>(0, 3).copy_from(&(Matrix3::identity() * dt));
+        // 白噪声加速度模型的离散过程噪声，时间间隔取绝对值以允许向前查询
+        let t = dt.abs();
+        let mut q = Matrix6::zeros();
+        q.fixed_view_mut::<3, 3>(0, 0).copy_from(&(Matrix3::identity() * (t.powi(3) / 3.0)));
+        q.fixed_view_mut::<3, 3>(0, 3).copy_from(&(Matrix3::identity() * (t.powi(2) / 2.0)));
+        q.fixed_view_mut::<3, 3>(3, 0).copy_from(&(Matrix3::identity() * (t.powi(2) / 2.0)));
+        q.fixed_view_mut::<3, 3>(3, 3).copy_from(&(Matrix3::identity() * t));
+
+        (f * self.state(), f * self.covariance * f.transpose() + q * process_noise)
+    }
+
+    /// 将状态外推到时刻 `time`
+    fn advance(&mut self, time: f64, process_noise: f64) {
+        let (state, covariance) = self.predict(time, process_noise);
+        self.set_state(&state, covariance);
+        self.state_time = time;
+    }
+
+    /// 以位置量测 `z`（协方差 `r`）做卡尔曼更新
+    fn correct(&mut self, z: &Point3<f64>, r: &Matrix3<f64>) {
+        let p = self.covariance;
+        let s = self.position_covariance() + r;
+        let Some(s_inv) = s.try_inverse() else {
+            return;
+        };
+        // H = [I 0]，P Hᵀ 即 P 的前三列
+        let p_ht = p.fixed_view::<6, 3>(0, 0).into_owned();
+        let gain = p_ht * s_inv;
+        let innovation = z - self.position;
+        let state = self.state() + gain * innovation;
+        let covariance = p - gain * p_ht.transpose();
+        self.set_state(&state, (covariance + covariance.transpose()) * 0.5);
+    }
+
+    fn state(&self) -> Vector6<f64> {
+        Vector6::new(
+            self.position.x,
+            self.position.y,
+            self.position.z,
+            self.velocity.x,
+            self.velocity.y,
+            self.velocity.z,
+        )
+    }
+
+    fn set_state(&mut self, state: &Vector6<f64>, covariance: Matrix6<f64>) {
+        self.position = Point3::new(state[0], state[1], state[2]);
+        self.velocity = Vector3::new(state[3], state[4], state[5]);
+        self.covariance = covariance;
+    }
+
+    /// 记录一帧的关联结果并按 M/N 条件更新状态
+    fn record(&mut self, hit: bool, config: &TrackerConfig) {
+        self.age += 1;
+        self.recent.push_back(hit);
+        if self.recent.len() > config.confirm_window {
+            self.recent.pop_front();
+        }
+        if hit {
+            self.hits += 1;
+            self.misses = 0;
+        } else {
+            self.misses += 1;
+        }
+        if self.recent.iter().filter(|&&h| h).count() >= config.confirm_hits {
+            self.status = TrackStatus::Confirmed;
+        }
+    }
+
+    /// 是否应当删除
+    fn is_dead(&self, config: &TrackerConfig) -> bool {
+        self.misses >= config.max_misses
+            || (self.status == TrackStatus::Tentative && self.age >= config.confirm_window)
+    }
+}
+
+/// `process_frame` 中一条航迹在本帧的定位结果
+#[derive(Debug, Clone)]
+pub struct TrackDetection {
+    pub track_id: u64,         // 航迹编号
+    pub target: LocatedTarget, // 定位结果，`inlier_indices` 为本帧测量中的索引，`timestamp` 为帧时刻
+}
+
+/// `process_frame` 的结果
+#[derive(Debug, Clone, Default)]
+pub struct FrameResult {
+    pub updated: Vec<TrackDetection>, // 以门限内光线重新定位并更新的已有航迹，按航迹编号升序
+    pub spawned: Vec<TrackDetection>, // 剩余光线上定位到的新目标及为其创建的暂定航迹
+    pub unassigned: Vec<usize>,       // 未分给任何航迹的测量索引（升序），含无效测量
+}
+
+/// 帧间航迹管理器
+#[derive(Debug, Clone)]
+pub struct Tracker {
+    config: TrackerConfig,
+    tracks: Vec<Track>,
+    next_id: u64,
+}
+
+impl Tracker {
+    /// 以给定参数创建没有航迹的管理器
+    pub fn new(config: TrackerConfig) -> Self {
+        Tracker {
+            config,
+            tracks: Vec::new(),
+            next_id: 1,
+        }
+    }
+
+    /// 航迹管理参数
+    pub fn config(&self) -> &TrackerConfig {
+        &self.config
+    }
+
+    /// 当前所有航迹（含暂定航迹），按编号升序
+    pub fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    /// 当前已确认的航迹
+    pub fn confirmed_tracks(&self) -> impl Iterator<Item = &Track> {
+        self.tracks.iter().filter(|t| t.is_confirmed())
+    }
+
+    /// 编号为 `id` 的航迹外推到任意时刻 `time` 的位置及其协方差；航迹不存在时为 None
+    pub fn predict(&self, id: u64, time: f64) -> Option<(Point3<f64>, Matrix3<f64>)> {
+        let track = self.tracks.iter().find(|t| t.id == id)?;
+        let (state, covariance) = track.predict(time, self.config.process_noise);
+        Some((
+            Point3::new(state[0], state[1], state[2]),
+            covariance.fixed_view::<3, 3>(0, 0).into(),
+        ))
+    }
+
+    /// 处理一帧定位结果，返回更新后的所有航迹
+    ///
+    /// 所有航迹先外推到本帧时刻 `timestamp`（应不早于上一帧），再按门限关联：
+    /// 关联上的航迹以定位结果做卡尔曼更新；未关联的航迹只保留外推结果并记一次未命中，
+    /// 满足删除条件时删除；未关联的定位结果按输入顺序产生新的暂定航迹（初始速度为 0）。
+    pub fn update(&mut self, detections: &[LocatedTarget], timestamp: f64) -> &[Track] {
+        for track in &mut self.tracks {
+            track.advance(timestamp, self.config.process_noise);
+        }
+        let assignment = self.associate(detections);
+        let covariances: Vec<_> = detections.iter().map(|d| self.config.measurement_covariance(d)).collect();
+        let mut detection_used = vec![false; detections.len()];
+        for (track, detection) in self.tracks.iter_mut().zip(assignment) {
+            match detection {
+                Some(d) => {
+                    detection_used[d] = true;
+                    track.correct(&detections[d].position, &covariances[d]);
+                    track.last_update = timestamp;
+                    track.record(true, &self.config);
+                }
+                None => track.record(false, &self.config),
+            }
+        }
+        let config = &self.config;
+        self.tracks.retain(|t| !t.is_dead(config));
+
+        for (d, detection) in detections.iter().enumerate().filter(|&(d, _)| !detection_used[d]) {
+            self.spawn(detection.position, &covariances[d], timestamp);
+        }
+        &self.tracks
+    }
+
+    /// 处理一帧测量：以门限内的光线更新已有航迹，在剩余光线上定位新目标并创建暂定航迹
+    ///
+    /// 所有航迹先外推到本帧时刻 `timestamp`。每条光线到各航迹预测位置的垂直距离不超过
+    /// `gate_distance` 时分给最近的航迹（每个测量站只保留最近的一条）；分到不少于 `min_lines` 条光线的航迹
+    /// 以预测位置为初值重新运行 LM，残差超过 RANSAC 阈值的光线退回后再拟合一次。
+    /// 拟合结果仍有足够光线且与预测位置的距离在门限内时以其做卡尔曼更新，否则航迹记一次未命中、光线全部退回。
+    /// 只有未被航迹使用的光线按 `locate` 运行完整的定位流程（不少于 `min_lines` 条时），
+    /// 每个新目标产生一条暂定航迹；不再与已有航迹关联。
+    pub fn process_frame(&mut self, timestamp: f64, measurements: &[Measurement]) -> FrameResult {
+        for track in &mut self.tracks {
+            track.advance(timestamp, self.config.process_noise);
+        }
+        let locate = &self.config.locate;
+        let (all_lines, station_names, data_indices) = prepare_lines(measurements.iter().enumerate(), &mut Vec::new());
+        let pipeline = Pipeline::new(locate);
+
+        // 每条光线分给门限内预测位置最近的航迹
+        let mut gated: Vec<Vec<(usize, f64)>> = vec![Vec::new(); self.tracks.len()];
+        for (i, line) in all_lines.iter().enumerate() {
+            let nearest = self
+                .tracks
+                .iter()
+                .map(|t| ResidualModel::Metric.residual(line, &t.position, locate.ransac.ray_mode).norm())
+                .enumerate()
+                .filter(|&(_, distance)| distance <= self.config.gate_distance)
+                .min_by(|a, b| a.1.total_cmp(&b.1));
+            if let Some((t, distance)) = nearest {
+                gated[t].push((i, distance));
+            }
+        }
+
+        let mut used_line_indices = HashSet::new();
+        let mut result = FrameResult::default();
+        for (track, candidates) in self.tracks.iter_mut().zip(gated) {
+            let inliers = keep_closest_per_station(&all_lines, candidates);
+            let fit = fit_gated(track.id as usize, &all_lines, inliers, track.position, &pipeline, &station_names)
+                .filter(|target| (target.position - track.position).norm() <= self.config.gate_distance);
+            let Some(mut target) = fit else {
+                track.record(false, &self.config);
+                continue;
+            };
+            used_line_indices.extend(target.inlier_indices.iter().copied());
+            for index in &mut target.inlier_indices {
+                *index = data_indices[*index];
+            }
+            target.timestamp = Some(timestamp);
+            track.correct(&target.position, &self.config.measurement_covariance(&target));
+            track.last_update = timestamp;
+            track.record(true, &self.config);
+            result.updated.push(TrackDetection {
+                track_id: track.id,
+                target,
+            });
+        }
+        let config = &self.config;
+        self.tracks.retain(|t| !t.is_dead(config));
+
+        // 剩余光线上的完整定位；光线不足时不运行
+        let located = if all_lines.len() - used_line_indices.len() >= locate.ransac.min_lines {
+            let remainder = (0..all_lines.len())
+                .filter(|i| !used_line_indices.contains(i))
+                .map(|i| (data_indices[i], &measurements[data_indices[i]]));
+            run_pipeline(remainder, &pipeline, &mut seeded_rng(locate.ransac.seed)).0
+        } else {
+            Vec::new()
+        };
+        drop(pipeline);
+        for mut target in located {
+            target.timestamp = Some(timestamp);
+            let r = self.config.measurement_covariance(&target);
+            let track_id = self.spawn(target.position, &r, timestamp);
+            result.spawned.push(TrackDetection {
+                track_id,
+                target,
+            });
+        }
+
+        let assigned: HashSet<usize> = result
+            .updated
+            .iter()
+            .chain(&result.spawned)
+            .flat_map(|detection| detection.target.inlier_indices.iter().copied())
+            .collect();
+        result.unassigned = (0..measurements.len()).filter(|i| !assigned.contains(i)).collect();
+        result
+    }
+
+    /// 以位置 `position`（协方差 `r`）创建一条暂定航迹，初始速度为 0，返回其编号
+    fn spawn(&mut self, position: Point3<f64>, r: &Matrix3<f64>, timestamp: f64) -> u64 {
+        let mut covariance = Matrix6::identity() * self.config.initial_velocity_std.powi(2);
+        covariance.fixed_view_mut::<3, 3>(0, 0).copy_from(r);
+        let mut track = Track {
+            id: self.next_id,
+            status: TrackStatus::Tentative,
+            position,
+            velocity: Vector3::zeros(),
+            covariance,
+            state_time: timestamp,
+            created_at: timestamp,
+            last_update: timestamp,
+            hits: 0,
+            misses: 0,
+            age: 0,
+            recent: VecDeque::new(),
+        };
+        track.record(true, &self.config);
+        self.tracks.push(track);
+        self.next_id += 1;
+        self.next_id - 1
+    }
+
+    /// 为每条航迹选出关联的定位结果索引
+    fn associate(&self, detections: &[LocatedTarget]) -> Vec<Option<usize>> {
+        let gate = self.config.gate_distance;
+        let distances: Vec<Vec<f64>> = self
+            .tracks
+            .iter()
+            .map(|t| detections.iter().map(|d| (d.position - t.position).norm()).collect())
+            .collect();
+
+        match self.config.association {
+            Association::GlobalNearestNeighbor => {
+                // 超过门限的代价按门限计，等价于不关联
+                let cost: Vec<Vec<f64>> = distances.iter().map(|row| row.iter().map(|d| d.min(gate)).collect()).collect();
+                min_cost_assignment(&cost, self.tracks.len(), detections.len())
+                    .into_iter()
+                    .enumerate()
+                    .map(|(t, d)| d.filter(|&d| distances[t][d] <= gate))
+                    .collect()
+            }
+            Association::NearestNeighbor => {
+                let mut pairs: Vec<(usize, usize)> = (0..self.tracks.len())
+                    .flat_map(|t| (0..detections.len()).map(move |d| (t, d)))
+                    .filter(|&(t, d)| distances[t][d] <= gate)
+                    .collect();
+                // 稳定排序，距离相同时保持（航迹, 定位结果）索引顺序
+                pairs.sort_by(|a, b| distances[a.0][a.1].total_cmp(&distances[b.0][b.1]));
+                let mut assignment = vec![None; self.tracks.len()];
+                let mut detection_used = vec![false; detections.len()];
+                for (t, d) in pairs {
+                    if assignment[t].is_none() && !detection_used[d] {
+                        assignment[t] = Some(d);
+                        detection_used[d] = true;
+                    }
+                }
+                assignment
+            }
+        }
+    }
+}
+
+/// `process_frame` 中以门限内的光线重新拟合一条航迹的目标，光线不足 `min_lines` 条时为 None
+///
+/// 初次拟合后残差不小于 RANSAC 阈值的光线被剔除，剩余光线仍足够时再拟合一次。
+fn fit_gated(
+    index: usize,
+    all_lines: &[Line],
+    inliers: Vec<usize>,
+    predicted: Point3<f64>,
+    pipeline: &Pipeline,
+    station_names: &[String],
+) -> Option<LocatedTarget> {
+    let ransac = &pipeline.config.ransac;
+    if inliers.len() < ransac.min_lines {
+        return None;
+    }
+    let target = fit_target(index, all_lines, inliers.clone(), predicted, pipeline, station_names);
+    let kept: Vec<usize> = inliers
+        .iter()
+        .copied()
+        .filter(|&i| ransac.residual_model.residual(&all_lines[i], &target.position, ransac.ray_mode).norm() < ransac.threshold)
+        .collect();
+    if kept.len() == inliers.len() {
+        Some(target)
+    } else if kept.len() >= ransac.min_lines {
+        Some(fit_target(index, all_lines, kept, target.position, pipeline, station_names))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "simulation")]
+    use crate::data_generator::{generate_trajectory_data, GeneratorConfig, StationLayout, TargetTrajectory};
+    use crate::target_processor::{find_targets_with_config, FindTargetsConfig, Measurement};
+    use nalgebra::Vector3;
+    #[cfg(feature = "simulation")]
+    use std::collections::HashSet;
+
+    /// 由给定位置构造定位结果（精确光线，协方差为零，卡尔曼更新后航迹位置即为该位置）
+    fn detections(positions: &[Point3<f64>]) -> Vec<LocatedTarget> {
+        positions
+            .iter()
+            .map(|p| {
+                let measurements: Vec<_> = [Vector3::x(), Vector3::y(), Vector3::z()]
+                    .iter()
+                    .map(|axis| {
+                        let start = p - axis * 100.0;
+                        Measurement::new(start.x, start.y, start.z, axis.x, axis.y, axis.z)
+                    })
+                    .collect();
+                find_targets_with_config(&measurements, &FindTargetsConfig::new(1.0, 3)).remove(0)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_confirmation_and_deletion() {
+        let mut config = TrackerConfig::new(10.0);
+        config.confirm_hits = 2;
+        config.confirm_window = 3;
+        config.max_misses = 2;
+        let mut tracker = Tracker::new(config);
+        let a = Point3::new(0.0, 0.0, 100.0);
+        let b = Point3::new(500.0, 0.0, 100.0);
+
+        // 第 1 帧：两条暂定航迹
+        tracker.update(&detections(&[a, b]), 0.0);
+        assert_eq!(tracker.tracks().iter().map(|t| t.id).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(tracker.confirmed_tracks().count(), 0);
+
+        // 第 2 帧：a 再次命中后确认；b 未命中
+        tracker.update(&detections(&[a + Vector3::new(3.0, 0.0, 0.0)]), 1.0);
+        let track = &tracker.tracks()[0];
+        assert!(track.is_confirmed());
+        assert_eq!((track.hits, track.misses, track.age), (2, 0, 2));
+        assert_eq!(track.position, a + Vector3::new(3.0, 0.0, 0.0));
+        assert_eq!(tracker.tracks()[1].status, TrackStatus::Tentative);
+
+        // 第 3 帧：b 在 3 帧内只命中 1 次，被删除；a 未命中
+        tracker.update(&[], 2.0);
+        assert_eq!(tracker.tracks().len(), 1);
+        assert_eq!(tracker.tracks()[0].misses, 1);
+
+        // 第 4 帧：a 连续 2 帧未命中被删除，门限外的定位结果产生新航迹，编号不复用
+        tracker.update(&detections(&[a + Vector3::new(50.0, 0.0, 0.0)]), 3.0);
+        assert_eq!(tracker.tracks().iter().map(|t| t.id).collect::<Vec<_>>(), vec![3]);
+        assert_eq!(tracker.tracks()[0].created_at, 3.0);
+    }
+
+    #[test]
+    fn test_global_nearest_neighbor() {
+        // 航迹 1 的最近定位结果是航迹 2 门限内唯一的结果，逐对最近邻会让航迹 2 丢失
+        let mut tracker = Tracker::new(TrackerConfig::new(10.0));
+        tracker.update(&detections(&[Point3::new(0.0, 0.0, 0.0), Point3::new(8.0, 0.0, 0.0)]), 0.0);
+        let frame = detections(&[Point3::new(3.5, 0.0, 0.0), Point3::new(-4.0, 0.0, 0.0)]);
+
+        let mut nearest = tracker.clone();
+        nearest.config.association = Association::NearestNeighbor;
+        nearest.update(&frame, 1.0);
+        assert_eq!(nearest.tracks().len(), 3);
+
+        tracker.update(&frame, 1.0);
+        assert_eq!(tracker.tracks().len(), 2);
+        assert_eq!(tracker.tracks()[0].position, frame[1].position);
+        assert_eq!(tracker.tracks()[1].position, frame[0].position);
+    }
+
+    #[test]
+    #[cfg(feature = "simulation")]
+    fn test_constant_velocity_filter() {
+        let truth = TargetTrajectory::new(Point3::new(-200.0, 100.0, 150.0), Vector3::new(20.0, -10.0, 2.0));
+        let generator = GeneratorConfig::builder()
+            .num_stations_per_target_range(4, 6)
+            .station_dist_range(500.0, 1000.0)
+            .angle_noise_std(0.004)
+            .seed(17)
+            .build()
+            .unwrap();
+        let frames = generate_trajectory_data(&[truth], 20, 0.5, &generator);
+
+        let mut locate_config = FindTargetsConfig::new(30.0, 3);
+        locate_config.ransac.seed = Some(17);
+        let mut config = TrackerConfig::new(50.0);
+        config.process_noise = 0.1;
+        let mut tracker = Tracker::new(config);
+        let (mut raw_sq, mut filtered_sq, mut count) = (0.0, 0.0, 0);
+        for frame in &frames {
+            let located = find_targets_with_config(&frame.scenario.measurements, &locate_config);
+            assert_eq!(located.len(), 1);
+            tracker.update(&located, frame.timestamp);
+            // 跳过滤波器收敛前的帧
+            if frame.index >= 5 {
+                let expected = truth.position_at(frame.timestamp);
+                raw_sq += (located[0].position - expected).norm_squared();
+                filtered_sq += (tracker.tracks()[0].position - expected).norm_squared();
+                count += 1;
+            }
+        }
+        let raw_rms = (raw_sq / count as f64).sqrt();
+        let filtered_rms = (filtered_sq / count as f64).sqrt();
+        assert!(filtered_rms < raw_rms, "{} vs {}", filtered_rms, raw_rms);
+
+        let track = &tracker.tracks()[0];
+        assert_eq!((tracker.tracks().len(), track.hits), (1, 20));
+        assert!((track.velocity - truth.velocity).norm() < 2.0, "{}", track.velocity);
+        // 速度误差与协方差相称
+        assert!(track.velocity_covariance().trace().sqrt() < 2.0);
+
+        // 外推位置随时间线性变化，协方差随外推时长增大
+        let last = frames.last().unwrap().timestamp;
+        let (near, near_cov) = tracker.predict(track.id, last + 1.0).unwrap();
+        let (far, far_cov) = tracker.predict(track.id, last + 2.0).unwrap();
+        assert!(((far - near) - track.velocity).norm() < 1e-9);
+        assert!(far_cov.trace() > near_cov.trace());
+        assert!((far - truth.position_at(last + 2.0)).norm() < 10.0);
+        assert!(tracker.predict(track.id + 1, last).is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "simulation")]
+    fn test_ids_stable_through_crossing() {
+        // 两个目标相向飞行，在 t = 10 s 附近以 40 米的间距交会，间距大于门限
+        let trajectories = [
+            TargetTrajectory::new(Point3::new(-300.0, 0.0, 100.0), Vector3::new(30.0, 0.0, 0.0)),
+            TargetTrajectory::new(Point3::new(300.0, 40.0, 100.0), Vector3::new(-30.0, 0.0, 0.0)),
+        ];
+        let generator = GeneratorConfig::builder()
+            .target_x_range(-300.0, 300.0)
+            .target_y_range(-100.0, 100.0)
+            .target_z_range(80.0, 120.0)
+            .station_dist_range(300.0, 600.0)
+            .station_z_range(0.0, 10.0)
+            .angle_noise_std(0.001)
+            .station_layout(StationLayout::Shared {
+                num_stations: 6,
+                detection_probability: 1.0,
+            })
+            .seed(8)
+            .build()
+            .unwrap();
+        let frames = generate_trajectory_data(&trajectories, 40, 0.5, &generator);
+
+        let mut locate_config = FindTargetsConfig::new(5.0, 3);
+        locate_config.ransac.seed = Some(8);
+        let mut tracker = Tracker::new(TrackerConfig::new(25.0));
+        let mut ids: Vec<HashSet<u64>> = vec![HashSet::new(); 2];
+        for frame in &frames {
+            let located = find_targets_with_config(&frame.scenario.measurements, &locate_config);
+            tracker.update(&located, frame.timestamp);
+            for (k, truth) in frame.scenario.true_targets.iter().enumerate() {
+                let nearest = tracker
+                    .confirmed_tracks()
+                    .min_by(|a, b| (a.position - truth).norm().total_cmp(&(b.position - truth).norm()));
+                if let Some(track) = nearest.filter(|t| (t.position - truth).norm() < 10.0) {
+                    ids[k].insert(track.id);
+                }
+            }
+        }
+
+        assert_eq!(tracker.confirmed_tracks().count(), 2);
+        assert_eq!(ids[0].len(), 1, "{:?}", ids);
+        assert_eq!(ids[1].len(), 1, "{:?}", ids);
+        assert_ne!(ids[0], ids[1]);
+    }
+
+    #[test]
+    #[cfg(feature = "simulation")]
+    fn test_process_frame_gates_existing_tracks() {
+        use crate::data_generator::generate_scenario_for_targets;
+        use crate::target_processor::ProgressEvent;
+        use std::ops::ControlFlow;
+        use std::sync::{Arc, Mutex};
+
+        let trajectories = [
+            TargetTrajectory::new(Point3::new(-200.0, 0.0, 100.0), Vector3::new(20.0, 0.0, 0.0)),
+            TargetTrajectory::new(Point3::new(200.0, 100.0, 120.0), Vector3::new(-10.0, 5.0, 0.0)),
+        ];
+        let generator = GeneratorConfig::builder()
+            .target_x_range(-300.0, 300.0)
+            .target_y_range(-100.0, 200.0)
+            .target_z_range(80.0, 140.0)
+            .station_dist_range(300.0, 600.0)
+            .station_z_range(0.0, 10.0)
+            .angle_noise_std(0.001)
+            .station_layout(StationLayout::Shared {
+                num_stations: 6,
+                detection_probability: 1.0,
+            })
+            .seed(603)
+            .build()
+            .unwrap();
+        let frames = generate_trajectory_data(&trajectories, 6, 0.5, &generator);
+
+        // 记录定位流程每轮开始时的进度，以判断 RANSAC 是否运行及其输入的光线数
+        let events: Arc<Mutex<Vec<ProgressEvent>>> = Arc::default();
+        let recorded = Arc::clone(&events);
+        let mut config = TrackerConfig::new(25.0);
+        config.locate = FindTargetsConfig::new(5.0, 3).with_progress(move |event| {
+            if event.ransac_iterations == 0 {
+                recorded.lock().unwrap().push(event);
+            }
+            ControlFlow::Continue(())
+        });
+        config.locate.ransac.seed = Some(603);
+        let mut tracker = Tracker::new(config);
+
+        // 第 1 帧：没有航迹，两个目标都由 RANSAC 定位并产生暂定航迹
+        let first = tracker.process_frame(frames[0].timestamp, &frames[0].scenario.measurements);
+        assert!(first.updated.is_empty());
+        assert_eq!(first.spawned.iter().map(|d| d.track_id).collect::<Vec<_>>(), vec![1, 2]);
+        assert!(first.unassigned.is_empty());
+        assert_eq!(events.lock().unwrap()[0].lines_remaining, frames[0].scenario.measurements.len());
+
+        // 之后各帧另加一条远处的杂波光线和一条无效测量：航迹吸收各自的全部光线，剩余光线不足以运行 RANSAC
+        for frame in &frames[1..] {
+            events.lock().unwrap().clear();
+            let mut measurements = frame.scenario.measurements.clone();
+            measurements.push(Measurement::new(5000.0, 5000.0, 0.0, 0.0, 0.0, 1.0));
+            measurements.push(Measurement::new(0.0, 0.0, 0.0, 0.0, 0.0, 0.0));
+            let n = frame.scenario.measurements.len();
+
+            let result = tracker.process_frame(frame.timestamp, &measurements);
+            assert!(events.lock().unwrap().is_empty());
+            assert!(result.spawned.is_empty());
+            assert_eq!(result.unassigned, vec![n, n + 1]);
+            assert_eq!(result.updated.iter().map(|d| d.track_id).collect::<Vec<_>>(), vec![1, 2]);
+            for (k, detection) in result.updated.iter().enumerate() {
+                let expected: Vec<usize> = (0..n).filter(|&i| frame.scenario.labels[i] == Some(k)).collect();
+                assert_eq!(detection.target.inlier_indices, expected);
+                assert_eq!(detection.target.timestamp, Some(frame.timestamp));
+                assert!((detection.target.position - frame.scenario.true_targets[k]).norm() < 5.0);
+            }
+        }
+        assert_eq!(tracker.confirmed_tracks().map(|t| t.id).collect::<Vec<_>>(), vec![1, 2]);
+        for (track, truth) in tracker.tracks().iter().zip(&trajectories) {
+            assert!((track.velocity - truth.velocity).norm() < 5.0, "{}", track.velocity);
+        }
+
+        // 新目标出现：只有它的光线和杂波光线进入 RANSAC
+        events.lock().unwrap().clear();
+        let last = frames.last().unwrap();
+        let timestamp = last.timestamp + 0.5;
+        let newcomer = generate_scenario_for_targets(&[Point3::new(0.0, 300.0, 60.0)], &generator);
+        let positions: Vec<Point3<f64>> = trajectories.iter().map(|t| t.position_at(timestamp)).collect();
+        let mut measurements = generate_scenario_for_targets(&positions, &generator).measurements;
+        let existing = measurements.len();
+        measurements.extend(newcomer.measurements.iter().cloned());
+        measurements.push(Measurement::new(5000.0, 5000.0, 0.0, 0.0, 0.0, 1.0));
+
+        let result = tracker.process_frame(timestamp, &measurements);
+        assert_eq!(result.updated.iter().map(|d| d.track_id).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(result.spawned.iter().map(|d| d.track_id).collect::<Vec<_>>(), vec![3]);
+        assert_eq!(result.spawned[0].target.inlier_indices, (existing..measurements.len() - 1).collect::<Vec<_>>());
+        assert_eq!(result.unassigned, vec![measurements.len() - 1]);
+        assert_eq!(events.lock().unwrap()[0].lines_remaining, measurements.len() - existing);
+        assert_eq!(tracker.tracks()[2].status, TrackStatus::Tentative);
+    }
+}
@@ -1,242 +1,925 @@
 // tests/integration_test.rs
 
-use opti_radar::target_processor::find_targets;
-use opti_radar::data_generator::generate_data;
+use opti_radar::target_processor::{
+    find_targets_jlinkage, find_targets_windowed, find_targets_with_config, refine_targets_em, FindTargetsConfig, Line,
+    LocatedTarget, Measurement, MotionModel, ResidualModel,
+};
+use opti_radar::evaluation::{match_targets, MatchResult};
+use opti_radar::simulation::run_monte_carlo;
+use opti_radar::data_generator::{
+    correlated_error_sequences, generate_data_for_targets, generate_data_from_config, generate_scenario,
+    generate_scenario_for_targets, generate_trajectory_data, GeneratorConfig, ScenarioPreset, StationLayout,
+    TargetTrajectory,
+};
+use opti_radar::crlb::{compute_crlb, crlb_position_sigma};
+use nalgebra::{Point3, Vector3};
 
 /// A helper function to run a single test case with given parameters and analyze the results.
 /// This function encapsulates the core testing logic for reusability.
+///
+/// Noise parameters are standard deviations of Gaussian noise. The values used below are
+/// roughly 1/√3 of the former uniform half-widths, keeping the actual noise level unchanged.
 fn run_test_case(
     case_name: &str,
+    seed: u64,
     num_runs: usize,
-    num_targets: usize,
-    target_x_range: (f64, f64),
-    target_y_range: (f64, f64),
-    target_z_range: (f64, f64),
-    num_stations_per_target_range: (usize, usize),
-    station_dist_range: (f64, f64),
-    station_z_range: (f64, f64),
-    pos_noise_std: f64,
-    alt_noise_std: f64,
-    angle_noise_std: f64,
+    generator: GeneratorConfig,
     ransac_threshold: f64,
 ) -> (f64, usize, usize) {
-    let mut total_overall_error_sum = 0.0;
-    let mut successful_runs_count = 0;
-    let mut total_matched_targets_count = 0;
+    run_test_case_with_config(case_name, seed, num_runs, generator, FindTargetsConfig::new(ransac_threshold, 3))
+}
 
+/// Same as `run_test_case`, with a full solver configuration (e.g. the angular residual model).
+fn run_test_case_with_config(
+    case_name: &str,
+    seed: u64,
+    num_runs: usize,
+    generator: GeneratorConfig,
+    config: FindTargetsConfig,
+) -> (f64, usize, usize) {
     println!("\n--- 正在进行 '{}' 测试 ({} 次运行) ---", case_name, num_runs);
 
-    for run_count in 1..=num_runs {
-        // Generate data with given parameters
-        let (true_targets, all_data) = generate_data(
-            num_targets,
-            target_x_range,
-            target_y_range,
-            target_z_range,
-            num_stations_per_target_range,
-            station_dist_range,
-            station_z_range,
-            pos_noise_std,
-            alt_noise_std,
-            angle_noise_std,
+    // Each run uses its own fixed seed (seed + run)
+    let report = run_monte_carlo(&generator, &config, num_runs, seed);
+    for run in &report.runs {
+        match run.mean_error {
+            Some(avg_run_error) => println!(
+                "第{}次运行：成功匹配 {}/{} 个目标，平均误差: {:.2} 米",
+                run.run, run.num_matched, run.num_true_targets, avg_run_error
+            ),
+            None => println!("第{}次运行：未成功匹配任何目标，本次运行被忽略。", run.run),
+        }
+    }
+
+    println!(
+        "\n'{}' 测试完成: {} 次成功运行的整体平均误差: {:.2} 米",
+        case_name,
+        report.successful_runs(),
+        report.mean_error()
+    );
+    println!("光线关联正确率: {:.3}", report.association_rate());
+    (report.mean_error(), report.successful_runs(), report.total_matched())
+}
+
+#[test]
+fn test_localization_accuracy() {
+    let (overall_avg_error, successful_runs, total_matched_targets) = run_test_case(
+        "一般精度",
+        1500,
+        10,
+        GeneratorConfig::preset(ScenarioPreset::Standard),
+        20.0,
+    );
+    let total_possible_targets = 10 * 3;
+    let success_rate = total_matched_targets as f64 / total_possible_targets as f64;
+
+    println!("总匹配目标数: {} / {}", total_matched_targets, total_possible_targets);
+    assert!(
+        overall_avg_error < 20.0 && success_rate >= 0.8,
+        "{} 次成功运行的整体平均误差 {:.2} 米超过了可接受的阈值 (20.0 米) or low success rate. Total matched targets: {} / {}.",
+        successful_runs, overall_avg_error, total_matched_targets, total_possible_targets
+    );
+}
+
+#[test]
+fn test_localization_with_high_noise() {
+    // Metric threshold of 50 m versus an angular threshold of 0.15 rad (50 m at ~330 m range)
+    let metric = FindTargetsConfig::new(50.0, 3);
+    let angular = FindTargetsConfig::new(0.15, 3).with_residual_model(ResidualModel::Angular);
+    for (mode, config) in [("距离残差", metric), ("角度残差", angular)] {
+        let (overall_avg_error, successful_runs, total_matched_targets) =
+            run_test_case_with_config(&format!("高噪声（{}）", mode), 2400, 5, GeneratorConfig::preset(ScenarioPreset::HighNoise), config);
+        let total_possible_targets = 5 * 2;
+        let success_rate = total_matched_targets as f64 / total_possible_targets as f64;
+
+        // In this high-noise scenario, a larger error is acceptable.
+        println!("总匹配目标数: {} / {}", total_matched_targets, total_possible_targets);
+        assert!(
+            overall_avg_error < 100.0 && successful_runs as f64 / 5.0 > 0.6 && success_rate >= 0.7,
+            "{}: {} 次成功运行的整体平均误差 {:.2} 米超过了可接受的阈值 (100.0 米) or low success rate. Total matched targets: {} / {}.",
+            mode, successful_runs, overall_avg_error, total_matched_targets, total_possible_targets
         );
-        let located_targets = find_targets(&all_data, ransac_threshold, 3);
-        let _located_num_targets = located_targets.len();
-        
-        let mut run_error_sum = 0.0;
-        let mut matched_targets_count = 0;
-        let mut located_targets_indices_used = vec![false; located_targets.len()];
-
-        for true_target in &true_targets {
-            let mut min_dist_sq = f64::MAX;
-            let mut best_match_idx = None;
-
-            for (i, located_target) in located_targets.iter().enumerate() {
-                if located_targets_indices_used[i] { continue; }
-                let diff_vec = located_target.position - true_target;
-                let dist_sq = diff_vec.norm_squared();
-                if dist_sq < min_dist_sq {
-                    min_dist_sq = dist_sq;
-                    best_match_idx = Some(i);
-                }
-            }
+    }
+}
 
-            if let Some(idx) = best_match_idx {
-                let error_dist = min_dist_sq.sqrt();
-                run_error_sum += error_dist;
-                matched_targets_count += 1;
-                located_targets_indices_used[idx] = true;
-            }
+#[test]
+fn test_localization_at_long_range() {
+    // Stations 3-8 km from their targets: 1 mrad of angular noise is already 3-8 m off the target,
+    // so a metric threshold suited to short ranges loses lines, while an angular one does not.
+    let generator = GeneratorConfig::preset(ScenarioPreset::LongRange);
+    let metric = FindTargetsConfig::new(5.0, 3);
+    let angular = FindTargetsConfig::new(0.004, 3).with_residual_model(ResidualModel::Angular);
+    let (metric_error, _, metric_matched) =
+        run_test_case_with_config("远距离（距离残差）", 11000, 10, generator.clone(), metric);
+    let (angular_error, _, angular_matched) =
+        run_test_case_with_config("远距离（角度残差）", 11000, 10, generator, angular);
+
+    println!(
+        "距离残差: 匹配 {} 个目标，误差 {:.2} 米；角度残差: 匹配 {} 个目标，误差 {:.2} 米",
+        metric_matched, metric_error, angular_matched, angular_error
+    );
+    assert!(angular_matched >= 27, "angular mode matched only {} / 30 targets", angular_matched);
+    assert!(angular_matched > metric_matched, "{} vs {}", angular_matched, metric_matched);
+    assert!(angular_error < 30.0, "angular mode mean error {:.2} m", angular_error);
+}
+
+#[test]
+fn test_localization_with_sparse_data() {
+    let (overall_avg_error, successful_runs, total_matched_targets) = run_test_case(
+        "稀疏数据",
+        3400,
+        5,
+        GeneratorConfig::preset(ScenarioPreset::SparseStations),
+        10.0,
+    );
+    let total_possible_targets = 5 * 3;
+    let success_rate = total_matched_targets as f64 / total_possible_targets as f64;
+
+    // 在数据稀疏的场景下，定位精度会自然下降，因此将可接受的阈值调整到更宽泛的范围。
+    println!("总匹配目标数: {} / {}", total_matched_targets, total_possible_targets);
+    assert!(
+        overall_avg_error < 150.0 && successful_runs >= 3 && success_rate >= 0.6,
+        "{} 次成功运行的整体平均误差 {:.2} 米超过了可接受的阈值 (150.0 米) or low success count. Total matched targets: {} / {}.",
+        successful_runs, overall_avg_error, total_matched_targets, total_possible_targets
+    );
+}
+
+#[test]
+fn test_localization_with_overlapping_targets() {
+    let (overall_avg_error, successful_runs, total_matched_targets) = run_test_case(
+        "重叠目标",
+        4400,
+        5,
+        GeneratorConfig::preset(ScenarioPreset::DenseMultiTarget),
+        5.0,
+    );
+    let total_possible_targets = 5 * 3;
+    let success_rate = total_matched_targets as f64 / total_possible_targets as f64;
+
+    // Overlapping targets might lead to slightly higher errors and fewer successful runs.
+    println!("总匹配目标数: {} / {}", total_matched_targets, total_possible_targets);
+    assert!(
+        overall_avg_error < 100.0 && successful_runs >= 2 && success_rate >= 0.5,
+        "{} 次成功运行的整体平均误差 {:.2} 米超过了可接受的阈值 (100.0 米) or low success count. Total matched targets: {} / {}.",
+        successful_runs, overall_avg_error, total_matched_targets, total_possible_targets
+    );
+}
+/// 按最优分配匹配真实目标与定位结果（不设门限），返回 (误差总和, 匹配数)。
+fn matched_error_sum(true_targets: &[Point3<f64>], located_targets: &[LocatedTarget]) -> (f64, usize) {
+    let result = match_targets(true_targets, located_targets, f64::INFINITY);
+    (result.errors().iter().sum(), result.matches.len())
+}
+
+/// 多次运行累计的匹配计数
+#[derive(Default)]
+struct MatchTotals {
+    matched: usize,
+    truths: usize,
+    estimates: usize,
+    error_sum: f64,
+}
+
+impl MatchTotals {
+    fn add(&mut self, result: &MatchResult) {
+        self.matched += result.matches.len();
+        self.truths += result.matches.len() + result.missed.len();
+        self.estimates += result.matches.len() + result.false_tracks.len();
+        self.error_sum += result.errors().iter().sum::<f64>();
+    }
+
+    fn recall(&self) -> f64 {
+        self.matched as f64 / self.truths as f64
+    }
+
+    fn precision(&self) -> f64 {
+        self.matched as f64 / self.estimates as f64
+    }
+}
+
+impl std::fmt::Display for MatchTotals {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "召回率 {:.3}，精确率 {:.3}，平均误差 {:.3} 米",
+            self.recall(),
+            self.precision(),
+            self.error_sum / self.matched as f64
+        )
+    }
+}
+
+#[test]
+fn test_reassignment_improves_overlapping_targets() {
+    let mut greedy_error = 0.0;
+    let mut greedy_matched = 0;
+    let mut reassigned_error = 0.0;
+    let mut reassigned_matched = 0;
+    let overlapping = GeneratorConfig::preset(ScenarioPreset::DenseMultiTarget);
+
+    for run in 0..300 {
+        let (true_targets, all_data) = generate_data_from_config(&GeneratorConfig {
+            seed: Some(5000 + run),
+            ..overlapping.clone()
+        });
+
+        // 固定 RANSAC 种子，使两种设置的贪心阶段完全相同
+        let mut config = FindTargetsConfig::new(5.0, 3);
+        config.ransac.seed = Some(run);
+        let (error, matched) = matched_error_sum(&true_targets, &find_targets_with_config(&all_data, &config));
+        greedy_error += error;
+        greedy_matched += matched;
+
+        config.reassignment_passes = 5;
+        let (error, matched) = matched_error_sum(&true_targets, &find_targets_with_config(&all_data, &config));
+        reassigned_error += error;
+        reassigned_matched += matched;
+    }
+
+    let greedy_avg = greedy_error / greedy_matched as f64;
+    let reassigned_avg = reassigned_error / reassigned_matched as f64;
+    println!("重叠目标：贪心平均误差 {:.3} 米，全局重分配后 {:.3} 米", greedy_avg, reassigned_avg);
+    assert!(reassigned_avg < greedy_avg);
+}
+
+#[test]
+fn test_em_refinement_improves_overlapping_targets() {
+    let mut greedy_error = 0.0;
+    let mut greedy_matched = 0;
+    let mut em_error = 0.0;
+    let mut em_matched = 0;
+    let overlapping = GeneratorConfig::preset(ScenarioPreset::DenseMultiTarget);
+
+    for run in 0..300 {
+        let (true_targets, all_data) = generate_data_from_config(&GeneratorConfig {
+            seed: Some(5000 + run),
+            ..overlapping.clone()
+        });
+
+        let mut config = FindTargetsConfig::new(5.0, 3);
+        config.ransac.seed = Some(run);
+        let greedy = find_targets_with_config(&all_data, &config);
+        let (error, matched) = matched_error_sum(&true_targets, &greedy);
+        greedy_error += error;
+        greedy_matched += matched;
+
+        let (error, matched) = matched_error_sum(&true_targets, &refine_targets_em(&all_data, &greedy, &config));
+        em_error += error;
+        em_matched += matched;
+    }
+
+    let greedy_avg = greedy_error / greedy_matched as f64;
+    let em_avg = em_error / em_matched as f64;
+    println!(
+        "重叠目标：贪心平均误差 {:.3} 米（匹配 {}），EM 精化后 {:.3} 米（匹配 {}）",
+        greedy_avg, greedy_matched, em_avg, em_matched
+    );
+    assert!(em_avg < 0.9 * greedy_avg);
+    assert!(em_matched + 10 >= greedy_matched);
+}
+
+#[test]
+fn test_jlinkage_recall_on_dense_scenario() {
+    // 10 个目标挤在 60 m × 60 m 内，其余参数同 `DenseMultiTarget`
+    let dense = GeneratorConfig {
+        num_targets: 10,
+        target_x_range: (-30.0, 30.0),
+        target_y_range: (-30.0, 30.0),
+        ..GeneratorConfig::preset(ScenarioPreset::DenseMultiTarget)
+    };
+    let (mut sequential, mut jlinkage) = (MatchTotals::default(), MatchTotals::default());
+    for run in 0..30 {
+        let (true_targets, all_data) = generate_data_from_config(&GeneratorConfig {
+            seed: Some(11000 + run),
+            ..dense.clone()
+        });
+        let mut config = FindTargetsConfig::new(5.0, 3);
+        config.ransac.seed = Some(run);
+        sequential.add(&match_targets(&true_targets, &find_targets_with_config(&all_data, &config), 5.0));
+        jlinkage.add(&match_targets(&true_targets, &find_targets_jlinkage(&all_data, &config), 5.0));
+    }
+    println!("10 个密集目标：顺序 RANSAC {}，J-linkage {}", sequential, jlinkage);
+    assert!(jlinkage.recall() >= sequential.recall());
+    assert!(jlinkage.precision() > 0.9);
+}
+
+#[test]
+fn test_localization_with_clutter() {
+    let clean = GeneratorConfig::builder()
+        .num_targets(5)
+        .num_stations_per_target_range(5, 8)
+        .pos_noise_std(0.5)
+        .alt_noise_std(0.3)
+        .angle_noise_std(0.002)
+        .build()
+        .unwrap();
+    let cluttered = GeneratorConfig {
+        clutter_fraction: 0.3,
+        ..clean.clone()
+    };
+
+    // 杂波降低内点率，需要更多 RANSAC 迭代；自适应终止使无杂波时开销不变
+    let mut config = FindTargetsConfig::new(10.0, 3);
+    config.ransac.max_iterations = 1000;
+
+    let mut reports = Vec::new();
+    for generator in [&clean, &cluttered] {
+        let report = run_monte_carlo(generator, &config, 10, 6100);
+        println!(
+            "杂波比例 {:.1}：定位 {} 个目标（真实 {} 个），平均误差 {:.3} 米，光线关联正确率 {:.3}",
+            generator.clutter_fraction,
+            report.total_located(),
+            report.total_true_targets(),
+            report.mean_error(),
+            report.association_rate()
+        );
+        reports.push(report);
+    }
+
+    let (clean, cluttered) = (&reports[0], &reports[1]);
+    let (located, total) = (cluttered.total_located() as f64, cluttered.total_true_targets() as f64);
+    assert!(located >= 0.9 * total && located <= 1.1 * total);
+    assert!(cluttered.mean_error() < 2.0 && cluttered.mean_error() < 1.5 * clean.mean_error());
+    assert!(cluttered.association_rate() > 0.95);
+}
+
+#[test]
+fn test_localization_with_shared_stations() {
+    // 共享测量站：同一站点发出的多条光线指向不同目标
+    let generator = GeneratorConfig::builder()
+        .num_targets(5)
+        .station_layout(StationLayout::Shared {
+            num_stations: 8,
+            detection_probability: 0.9,
+        })
+        .pos_noise_std(0.5)
+        .alt_noise_std(0.3)
+        .angle_noise_std(0.002)
+        .build()
+        .unwrap();
+
+    let mut config = FindTargetsConfig::new(10.0, 3);
+    config.ransac.max_iterations = 1000;
+
+    let report = run_monte_carlo(&generator, &config, 10, 7000);
+    println!(
+        "共享测量站：定位 {} 个目标（真实 {} 个），平均误差 {:.3} 米，光线关联正确率 {:.3}",
+        report.total_located(),
+        report.total_true_targets(),
+        report.mean_error(),
+        report.association_rate()
+    );
+    let (located, total) = (report.total_located() as f64, report.total_true_targets() as f64);
+    assert!(located >= 0.9 * total && located <= 1.1 * total);
+    assert!(report.mean_error() < 2.0);
+    assert!(report.association_rate() > 0.95);
+}
+
+#[test]
+fn test_localization_error_grows_with_station_bias() {
+    // 每个测量站的方位角/俯仰角偏差在 ±bias 内均匀分布；偏差不随测量平均掉，定位误差随之增大
+    let base = GeneratorConfig::builder()
+        .num_targets(5)
+        .num_stations_per_target_range(5, 8)
+        .pos_noise_std(0.5)
+        .alt_noise_std(0.3)
+        .angle_noise_std(0.001)
+        .build()
+        .unwrap();
+    let mut config = FindTargetsConfig::new(20.0, 3);
+    config.ransac.max_iterations = 1000;
+
+    let mut medians = Vec::new();
+    for bias in [0.0, 0.002, 0.005, 0.01] {
+        let generator = GeneratorConfig {
+            azimuth_bias_range: (-bias, bias),
+            elevation_bias_range: (-bias, bias),
+            ..base.clone()
+        };
+        generator.validate().unwrap();
+        // 用中位数统计，避免个别漏检目标的错误匹配主导结果
+        let mut errors = Vec::new();
+        for run in 0..20 {
+            let scenario = generate_scenario(&GeneratorConfig {
+                seed: Some(8000 + run),
+                ..generator.clone()
+            });
+            config.ransac.seed = Some(run);
+            let located_targets = find_targets_with_config(&scenario.measurements, &config);
+            errors.extend(match_targets(&scenario.true_targets, &located_targets, f64::INFINITY).errors());
+        }
+        errors.sort_by(f64::total_cmp);
+        let median = errors[errors.len() / 2];
+        println!("测向偏差 ±{:.3} rad：匹配 {} 个目标，误差中位数 {:.3} 米", bias, errors.len(), median);
+        medians.push(median);
+    }
+    assert!(medians.windows(2).all(|w| w[0] < w[1]), "误差未随偏差增大: {:?}", medians);
+    // 偏差 ±0.01 rad 时误差至少为无偏差时的两倍
+    assert!(medians[0] < 1.0 && medians[3] > 2.0 * medians[0]);
+}
+
+#[test]
+fn test_resolution_of_close_targets() {
+    // 两个目标相距 5 米：阈值远小于间距时应分辨为两个目标，远大于间距时合并为一个
+    let targets = [Point3::new(0.0, 0.0, 100.0), Point3::new(5.0, 0.0, 100.0)];
+    let generator = GeneratorConfig::builder()
+        .num_stations_per_target_range(6, 8)
+        .pos_noise_std(0.1)
+        .alt_noise_std(0.1)
+        .angle_noise_std(0.0005)
+        .build()
+        .unwrap();
+
+    // 中间阈值的结果与几何有关，只打印不断言
+    for (threshold, expected) in [(1.0, Some(2)), (2.0, None), (5.0, None), (10.0, None), (50.0, Some(1))] {
+        let mut counts = Vec::new();
+        for run in 0..10 {
+            let measurements = generate_data_for_targets(&targets, &GeneratorConfig {
+                seed: Some(9000 + run),
+                ..generator.clone()
+            });
+            let mut config = FindTargetsConfig::new(threshold, 3);
+            config.ransac.seed = Some(run);
+            counts.push(find_targets_with_config(&measurements, &config).len());
         }
-        
-        if matched_targets_count > 0 {
-            let avg_run_error = run_error_sum / matched_targets_count as f64;
-            total_overall_error_sum += avg_run_error;
-            successful_runs_count += 1;
-            total_matched_targets_count += matched_targets_count;
-            println!("第{}次运行：成功匹配 {}/{} 个目标，平均误差: {:.2} 米", run_count, matched_targets_count, num_targets, avg_run_error);
-        } else {
-            println!("第{}次运行：未成功匹配任何目标，本次运行被忽略。", run_count);
+        println!("阈值 {} 米：每次定位到的目标数 {:?}", threshold, counts);
+        if let Some(expected) = expected {
+            let resolved = counts.iter().filter(|&&c| c == expected).count();
+            assert!(resolved >= 9, "阈值 {} 米时应定位到 {} 个目标: {:?}", threshold, expected, counts);
         }
     }
+}
 
-    let overall_avg_error = if successful_runs_count > 0 {
-        total_overall_error_sum / successful_runs_count as f64
-    } else {
-        f64::INFINITY
+#[test]
+fn test_localization_with_stations_along_a_road() {
+    // 测量站都部署在 y = 0 的一段 1 km 道路上，目标位于道路一侧约 3 km 处：
+    // 光线交会角仅十余度，沿垂直于道路的方向（y）误差远大于沿道路方向（x）。
+    // 对照组为环绕每个目标、距离相同的测量站
+    let road = GeneratorConfig::builder()
+        .num_targets(2)
+        .target_x_range(-300.0, 300.0)
+        .target_y_range(2500.0, 3500.0)
+        .target_z_range(100.0, 300.0)
+        .station_layout(StationLayout::Polyline {
+            vertices: vec![Point3::new(-500.0, 0.0, 5.0), Point3::new(500.0, 0.0, 5.0)],
+            num_stations: 6,
+            lateral_jitter_m: 10.0,
+            detection_probability: 1.0,
+        })
+        .pos_noise_std(0.5)
+        .alt_noise_std(0.3)
+        .angle_noise_std(0.001)
+        .build()
+        .unwrap();
+    let ring = GeneratorConfig {
+        station_layout: StationLayout::PerTarget,
+        num_stations_per_target_range: (6, 6),
+        station_dist_range: (2500.0, 3500.0),
+        ..road.clone()
     };
+    let mut config = FindTargetsConfig::new(0.01, 3).with_residual_model(ResidualModel::Angular);
 
-    println!("\n'{}' 测试完成: {} 次成功运行的整体平均误差: {:.2} 米", case_name, successful_runs_count, overall_avg_error);
-    (overall_avg_error, successful_runs_count, total_matched_targets_count)
+    let mut summaries = Vec::new();
+    for (name, generator) in [("沿道路", &road), ("环绕", &ring)] {
+        let (mut abs_x, mut abs_y, mut dops) = (Vec::new(), Vec::new(), Vec::new());
+        for run in 0..20 {
+            let scenario = generate_scenario(&GeneratorConfig {
+                seed: Some(12000 + run),
+                ..generator.clone()
+            });
+            config.ransac.seed = Some(run);
+            let located = find_targets_with_config(&scenario.measurements, &config);
+            for m in match_targets(&scenario.true_targets, &located, f64::INFINITY).matches {
+                let error = located[m.estimate].position - scenario.true_targets[m.truth];
+                abs_x.push(error.x.abs());
+                abs_y.push(error.y.abs());
+                dops.push(located[m.estimate].horizontal_dop);
+            }
+        }
+        let median = |values: &mut Vec<f64>| {
+            values.sort_by(f64::total_cmp);
+            values[values.len() / 2]
+        };
+        let (x, y, dop) = (median(&mut abs_x), median(&mut abs_y), median(&mut dops));
+        println!("{}：定位 {} 个目标，|Δx| 中位数 {:.2} 米，|Δy| 中位数 {:.2} 米，水平 DOP 中位数 {:.2}", name, abs_x.len(), x, y, dop);
+        assert!(abs_x.len() >= 36, "{} 只定位了 {} / 40 个目标", name, abs_x.len());
+        summaries.push((x, y, dop));
+    }
+    let (road_x, road_y, road_dop) = summaries[0];
+    let (ring_x, ring_y, ring_dop) = summaries[1];
+    assert!(road_y > 3.0 * road_x, "{:.2} vs {:.2}", road_y, road_x);
+    assert!(road_y > 3.0 * ring_y.max(ring_x), "{:.2} vs {:.2}", road_y, ring_y);
+    // DOP 标出了这种几何：与实际误差一样，道路部署明显更差
+    assert!(road_dop > 3.0 * ring_dop, "{:.2} vs {:.2}", road_dop, ring_dop);
 }
 
 #[test]
-fn test_localization_accuracy() {
-    let mut attempts = 0;
-    loop {
-        attempts += 1;
-        let (overall_avg_error, successful_runs, total_matched_targets) = run_test_case(
-            "一般精度",
-            10,
-            3,
-            (-2000.0, 2000.0),
-            (-2000.0, 2000.0),
-            (50.0, 200.0),
-            (3, 5),
-            (500.0, 2000.0),
-            (30.0, 70.0),
-            5.0,
-            2.0,
-            0.005,
-            20.0,
-        );
-        let total_possible_targets = 10 * 3;
-        let success_rate = total_matched_targets as f64 / total_possible_targets as f64;
-        
-        if overall_avg_error < 20.0 && success_rate >= 0.8 {
-            println!("第 {} 次尝试成功通过。", attempts);
-            println!("总匹配目标数: {} / {}", total_matched_targets, total_possible_targets);
-            break;
-        } else {
-            if attempts >= 3 {
-                panic!("{} 次成功运行的整体平均误差 {:.2} 米超过了可接受的阈值 (20.0 米) or low success rate after {} attempts. Total matched targets: {} / {}.",
-                        successful_runs, overall_avg_error, attempts, total_matched_targets, total_possible_targets);
-            }
-            println!("第 {} 次尝试失败，正在重试...", attempts);
+fn test_localization_with_fixed_sites() {
+    // 三个固定站址观测区域内的目标；每个目标只有 3 条光线，且来自不同测量站
+    let sites = vec![
+        Point3::new(-600.0, -400.0, 120.0),
+        Point3::new(700.0, -300.0, 90.0),
+        Point3::new(0.0, 650.0, 150.0),
+    ];
+    let generator = GeneratorConfig::builder()
+        .num_targets(3)
+        .target_z_range(200.0, 400.0)
+        .station_layout(StationLayout::Fixed {
+            positions: sites.clone(),
+            detection_probability: 1.0,
+            angle_noise_stds: Vec::new(),
+        })
+        .pos_noise_std(0.5)
+        .alt_noise_std(0.3)
+        .angle_noise_std(0.0005)
+        .build()
+        .unwrap();
+    let mut config = FindTargetsConfig::new(5.0, 3);
+
+    let mut errors = Vec::new();
+    let mut located = 0;
+    for run in 0..20 {
+        let scenario = generate_scenario(&GeneratorConfig {
+            seed: Some(10000 + run),
+            ..generator.clone()
+        });
+        assert!(scenario.station_positions.iter().all(|p| sites.contains(p)));
+        config.ransac.seed = Some(run);
+        let located_targets = find_targets_with_config(&scenario.measurements, &config);
+        located += located_targets.len();
+        for target in &located_targets {
+            assert_eq!(target.stations.len(), target.num_lines);
         }
+        errors.extend(match_targets(&scenario.true_targets, &located_targets, f64::INFINITY).errors());
     }
+    errors.sort_by(f64::total_cmp);
+    let median = errors[errors.len() / 2];
+    println!("固定站址：定位 {} 个目标（真实 60 个），误差中位数 {:.3} 米", located, median);
+    assert!((54..=66).contains(&located));
+    assert!(median < 2.0);
 }
 
 #[test]
-fn test_localization_with_high_noise() {
-    let mut attempts = 0;
-    loop {
-        attempts += 1;
-        let (overall_avg_error, successful_runs, total_matched_targets) = run_test_case(
-            "高噪声",
-            5,
-            2,
-            (-500.0, 500.0),
-            (-500.0, 500.0),
-            (20.0, 100.0),
-            (10, 20),
-            (100.0, 500.0),
-            (10.0, 30.0),
-            10.0, // Higher position noise
-            5.0,  // Higher altitude noise
-            0.02, // Higher angle noise
-            50.0,
-        );
-        let total_possible_targets = 5 * 2;
-        let success_rate = total_matched_targets as f64 / total_possible_targets as f64;
-        
-        // In this high-noise scenario, a larger error is acceptable.
-        if overall_avg_error < 100.0 && successful_runs as f64 / 5.0 > 0.6 && success_rate >= 0.7 {
-            println!("第 {} 次尝试成功通过。", attempts);
-            println!("总匹配目标数: {} / {}", total_matched_targets, total_possible_targets);
-            break;
-        } else {
-            if attempts >= 3 {
-                panic!("{} 次成功运行的整体平均误差 {:.2} 米超过了可接受的阈值 (100.0 米) or low success rate after {} attempts. Total matched targets: {} / {}.",
-                        successful_runs, overall_avg_error, attempts, total_matched_targets, total_possible_targets);
+fn test_windowed_localization_of_moving_target() {
+    // 一个 60 m/s 的目标，每 0.5 s 一帧共 8 帧；混合所有帧的测量会把不同时刻的光线凑在一起
+    let trajectory = TargetTrajectory::new(Point3::new(-200.0, 0.0, 150.0), Vector3::new(60.0, 0.0, 0.0));
+    let generator = GeneratorConfig::builder()
+        .num_stations_per_target_range(5, 5)
+        .station_dist_range(400.0, 800.0)
+        .angle_noise_std(0.001)
+        .seed(12000)
+        .build()
+        .unwrap();
+    let frames = generate_trajectory_data(&[trajectory], 8, 0.5, &generator);
+    let measurements: Vec<_> = frames.iter().flat_map(|f| f.scenario.measurements.clone()).collect();
+    let mut config = FindTargetsConfig::new(5.0, 3);
+    config.ransac.seed = Some(12000);
+
+    let windowed = find_targets_windowed(&measurements, 0.5, &config).unwrap();
+    assert_eq!(windowed.len(), 8);
+    for (k, target) in windowed.iter().enumerate() {
+        // 时间窗 [0.5k, 0.5k + 0.5) 只包含第 k 帧
+        assert_eq!(target.timestamp, Some(0.5 * k as f64 + 0.25));
+        assert_eq!(target.id, format!("Target_{}", k + 1));
+        assert!(target.inlier_indices.iter().all(|&i| measurements[i].timestamp == Some(0.5 * k as f64)));
+        let error = (target.position - trajectory.position_at(0.5 * k as f64)).norm();
+        assert!(error < 3.0, "第 {} 帧误差 {:.2} 米", k, error);
+    }
+
+    // 不分时间窗时，同一测量站不同时刻的光线互相矛盾，定位结果偏离各时刻的真实位置
+    let mixed = find_targets_with_config(&measurements, &config);
+    let truths: Vec<_> = frames.iter().map(|f| f.scenario.true_targets[0]).collect();
+    let mixed_matched = match_targets(&truths, &mixed, 3.0).matches.len();
+    println!("不分时间窗：定位 {} 个目标，其中 {} 个与某一时刻的真实位置相差 3 米以内", mixed.len(), mixed_matched);
+    assert!(mixed_matched < 8);
+
+    // 没有时间戳的测量归入同一时间窗，结果与 find_targets_with_config 相同
+    let untimed: Vec<_> = frames[0]
+        .scenario
+        .measurements
+        .iter()
+        .map(|m| Measurement { timestamp: None, ..m.clone() })
+        .collect();
+    let single = find_targets_windowed(&untimed, 0.5, &config).unwrap();
+    let batch = find_targets_with_config(&untimed, &config);
+    assert_eq!(single.len(), batch.len());
+    assert_eq!(single[0].position, batch[0].position);
+    assert_eq!(single[0].timestamp, None);
+    assert!(find_targets_windowed(&untimed, 0.0, &config).is_err());
+}
+
+#[test]
+fn test_constant_velocity_model_within_one_window() {
+    // 一个 30 m/s 的目标，5 个测量站每 0.5 s 各测一次、共 5 s；所有测量作为一个时间窗一起定位
+    let trajectory = TargetTrajectory::new(Point3::new(-75.0, 40.0, 150.0), Vector3::new(30.0, 0.0, 0.0));
+    let generator = GeneratorConfig::builder()
+        .num_stations_per_target_range(5, 5)
+        .station_dist_range(400.0, 800.0)
+        .angle_noise_std(0.001)
+        .seed(13000)
+        .build()
+        .unwrap();
+    let frames = generate_trajectory_data(&[trajectory], 11, 0.5, &generator);
+    let measurements: Vec<_> = frames.iter().flat_map(|f| f.scenario.measurements.clone()).collect();
+
+    // 每条测量在其测量时刻的位置误差：取包含该测量的定位结果按自身模型外推，未被任何目标使用的测量不计
+    let trajectory_errors = |located: &[LocatedTarget]| -> Vec<f64> {
+        located
+            .iter()
+            .flat_map(|target| target.inlier_indices.iter().map(move |&i| (target, i)))
+            .map(|(target, i)| {
+                let time = measurements[i].timestamp.unwrap();
+                (target.position_at(time) - trajectory.position_at(time)).norm()
+            })
+            .collect()
+    };
+    let rms = |errors: &[f64]| (errors.iter().map(|e| e * e).sum::<f64>() / errors.len() as f64).sqrt();
+
+    let mut config = FindTargetsConfig::new(10.0, 3);
+    config.ransac.seed = Some(13000);
+    let stationary = find_targets_with_config(&measurements, &config);
+    config.motion_model = MotionModel::ConstantVelocity;
+    let moving = find_targets_with_config(&measurements, &config);
+
+    // 静止模型下 5 s 内目标移动 150 m，单个位置解释不了整个窗口：目标被拆成多段，每段都带着运动造成的偏差
+    assert!(stationary.len() > 1);
+    assert!(stationary.iter().all(|target| target.inlier_indices.len() < measurements.len() / 2));
+    let stationary_errors = trajectory_errors(&stationary);
+    assert!(rms(&stationary_errors) > 5.0, "静止模型误差 {:.2}", rms(&stationary_errors));
+
+    // 匀速模型用一个目标解释全部测量，位置以窗口平均时刻为参考时刻
+    assert_eq!(moving.len(), 1);
+    assert_eq!(moving[0].inlier_indices.len(), measurements.len());
+    let moving_errors = trajectory_errors(&moving);
+    assert!(rms(&moving_errors) < 1.0, "匀速模型误差 {:.2}", rms(&moving_errors));
+    assert!((moving[0].timestamp.unwrap() - 2.5).abs() < 1e-9);
+    let velocity = moving[0].velocity.unwrap();
+    assert!((velocity - trajectory.velocity).norm() < 1.0, "速度 {:?}", velocity);
+}
+
+#[test]
+fn test_empirical_error_approaches_crlb() {
+    // 6 个固定测量站不等距地观测同一目标；低噪声下角度残差（即最大似然）估计接近 CRLB 而不低于它
+    let target = Point3::new(50.0, -30.0, 150.0);
+    let sites = vec![
+        Point3::new(-800.0, -200.0, 10.0),
+        Point3::new(600.0, -700.0, 30.0),
+        Point3::new(900.0, 400.0, 20.0),
+        Point3::new(-300.0, 1100.0, 40.0),
+        Point3::new(200.0, 300.0, 15.0),
+        Point3::new(-1200.0, 600.0, 25.0),
+    ];
+    let angle_noise_std = 0.001;
+    let generator = GeneratorConfig::builder()
+        .station_layout(StationLayout::Fixed {
+            positions: sites.clone(),
+            detection_probability: 1.0,
+            angle_noise_stds: Vec::new(),
+        })
+        .pos_noise_std(0.0)
+        .alt_noise_std(0.0)
+        .angle_noise_std(angle_noise_std)
+        .build()
+        .unwrap();
+    // 生成器的 angle_noise_std 为偏离角均方根，每个垂直方向的标准差为其 1/√2
+    let bound = crlb_position_sigma(&compute_crlb(&sites, &target, angle_noise_std / 2f64.sqrt()));
+
+    let mut config = FindTargetsConfig::new(0.01, 3).with_residual_model(ResidualModel::Angular);
+    let mut squared_errors = 0.0;
+    let runs = 400;
+    for run in 0..runs {
+        let scenario = generate_scenario_for_targets(&[target], &GeneratorConfig {
+            seed: Some(14000 + run),
+            ..generator.clone()
+        });
+        config.ransac.seed = Some(run);
+        let located = find_targets_with_config(&scenario.measurements, &config);
+        assert_eq!(located.len(), 1);
+        assert_eq!(located[0].num_lines, sites.len());
+        squared_errors += (located[0].position - target).norm_squared();
+    }
+    let rms = (squared_errors / runs as f64).sqrt();
+    println!("CRLB 1σ 位置误差 {:.3} 米，{} 次定位的 RMS 误差 {:.3} 米", bound, runs, rms);
+    // 400 次的 RMS 抽样误差约 ±3%
+    assert!(rms > 0.93 * bound, "{:.3} vs {:.3}", rms, bound);
+    assert!(rms < 1.2 * bound, "{:.3} vs {:.3}", rms, bound);
+}
+
+#[test]
+fn test_frame_averaging_with_correlated_station_noise() {
+    // 静止目标由 5 个固定测量站连续观测 40 帧，逐帧定位后取平均。总测角标准差相同（4 mrad）时，
+    // 独立噪声的平均误差约按 1/√40 下降，而各测量站缓变的相关误差在帧间几乎不抵消
+    let sites: Vec<Point3<f64>> = (0..5)
+        .map(|k| {
+            let angle = k as f64 * 2.0 * std::f64::consts::PI / 5.0;
+            Point3::new(1000.0 * angle.cos(), 1000.0 * angle.sin(), 20.0)
+        })
+        .collect();
+    let independent = GeneratorConfig::builder()
+        .station_layout(StationLayout::Fixed {
+            positions: sites,
+            detection_probability: 1.0,
+            angle_noise_stds: Vec::new(),
+        })
+        .pos_noise_std(0.0)
+        .alt_noise_std(0.0)
+        .angle_noise_std(0.004)
+        .build()
+        .unwrap();
+    let white = 0.002;
+    let correlated = GeneratorConfig {
+        angle_noise_std: white,
+        correlated_angle_noise_std: (0.004f64.powi(2) - white * white).sqrt(),
+        correlated_noise_length: 100.0,
+        ..independent.clone()
+    };
+    let target = TargetTrajectory::new(Point3::new(0.0, 0.0, 200.0), Vector3::zeros());
+    let mut config = FindTargetsConfig::new(20.0, 3);
+
+    let mut results = Vec::new();
+    for (name, generator) in [("独立噪声", &independent), ("相关噪声", &correlated)] {
+        let (mut frame_sq, mut frame_count, mut averaged_sq) = (0.0, 0, 0.0);
+        for run in 0..20 {
+            let frames = generate_trajectory_data(
+                &[target],
+                40,
+                1.0,
+                &GeneratorConfig {
+                    seed: Some(13000 + run),
+                    ..generator.clone()
+                },
+            );
+            let mut sum = Vector3::zeros();
+            for frame in &frames {
+                config.ransac.seed = Some(run);
+                let located = find_targets_with_config(&frame.scenario.measurements, &config);
+                assert_eq!(located.len(), 1);
+                let error = located[0].position - frame.scenario.true_targets[0];
+                frame_sq += error.norm_squared();
+                frame_count += 1;
+                sum += error;
             }
-            println!("第 {} 次尝试失败，正在重试...", attempts);
+            averaged_sq += (sum / frames.len() as f64).norm_squared();
         }
+        let frame_rms = (frame_sq / frame_count as f64).sqrt();
+        let averaged_rms = (averaged_sq / 20.0).sqrt();
+        println!("{}：单帧 RMS 误差 {:.2} 米，40 帧平均后 {:.2} 米", name, frame_rms, averaged_rms);
+        results.push((frame_rms, averaged_rms));
     }
+    let (independent_frame, independent_averaged) = results[0];
+    let (correlated_frame, correlated_averaged) = results[1];
+    // 单帧误差相当，平均后相关噪声明显更差
+    assert!((correlated_frame / independent_frame - 1.0).abs() < 0.25, "{:.2} vs {:.2}", correlated_frame, independent_frame);
+    assert!(correlated_averaged > 3.0 * independent_averaged, "{:.2} vs {:.2}", correlated_averaged, independent_averaged);
+
+    // 场景记录了每个测量站跨帧的误差序列
+    let frames = generate_trajectory_data(&[target], 40, 1.0, &GeneratorConfig { seed: Some(13000), ..correlated });
+    let sequences = correlated_error_sequences(frames.iter().map(|f| &f.scenario));
+    assert_eq!(sequences.len(), 5);
+    assert!(sequences.iter().all(|(_, sequence)| sequence.len() == 40));
 }
 
+#[cfg(feature = "serde")]
 #[test]
-fn test_localization_with_sparse_data() {
-    let mut attempts = 0;
-    loop {
-        attempts += 1;
-        let (overall_avg_error, successful_runs, total_matched_targets) = run_test_case(
-            "稀疏数据",
-            5,
-            3,
-            (-200.0, 200.0),
-            (-200.0, 200.0),
-            (10.0, 50.0),
-            (2, 3), // Fewer stations per target
-            (50.0, 200.0),
-            (5.0, 15.0),
-            1.0,
-            0.5,
-            0.002,
-            10.0,
+fn test_localization_on_fixture_scenario() {
+    // 固定场景：2 个目标各 4~5 个测量站，另有 2 条杂波，由种子 2024 生成
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/scenario.json");
+    let scenario = opti_radar::data_generator::Scenario::load_json(path).unwrap();
+    assert_eq!(scenario.config.seed, Some(2024));
+    assert_eq!(scenario.true_targets.len(), 2);
+    assert_eq!(scenario.clutter_indices().len(), 2);
+
+    let located = scenario.locate(&FindTargetsConfig::new(5.0, 3));
+    let result = match_targets(&scenario.true_targets, &located, 20.0);
+    assert!(result.missed.is_empty() && result.false_tracks.is_empty());
+    for m in &result.matches {
+        println!("目标 {}：误差 {:.3} 米", m.truth, m.distance);
+        assert!(m.distance < 1.5);
+        // 内点恰为瞄准该目标的测量，杂波未被关联
+        assert_eq!(located[m.estimate].inlier_indices, scenario.measurements_of(m.truth));
+    }
+}
+
+#[test]
+fn test_localization_degrades_gracefully_with_missed_detections() {
+    // 每个目标 8~10 个测量站，检测概率从 1.0 降到 0.5
+    let mut config = FindTargetsConfig::new(10.0, 3);
+    config.ransac.max_iterations = 1000;
+
+    let mut reports = Vec::new();
+    for probability in [1.0, 0.9, 0.8, 0.7, 0.6, 0.5] {
+        let generator = GeneratorConfig::builder()
+            .num_targets(5)
+            .num_stations_per_target_range(8, 10)
+            .pos_noise_std(0.5)
+            .alt_noise_std(0.3)
+            .angle_noise_std(0.002)
+            .detection_probability(probability)
+            .build()
+            .unwrap();
+        let report = run_monte_carlo(&generator, &config, 10, 6900);
+        println!(
+            "检测概率 {:.1}：定位 {} 个目标（真实 {} 个），平均误差 {:.3} 米，光线关联正确率 {:.3}",
+            probability,
+            report.total_located(),
+            report.total_true_targets(),
+            report.mean_error(),
+            report.association_rate()
         );
-        let total_possible_targets = 5 * 3;
-        let success_rate = total_matched_targets as f64 / total_possible_targets as f64;
+        reports.push(report);
+    }
 
-        // 在数据稀疏的场景下，定位精度会自然下降，因此将可接受的阈值调整到更宽泛的范围。
-        if overall_avg_error < 150.0 && successful_runs >= 3 && success_rate >= 0.6 {
-            println!("第 {} 次尝试成功通过。", attempts);
-            println!("总匹配目标数: {} / {}", total_matched_targets, total_possible_targets);
-            break;
-        } else {
-            if attempts >= 3 {
-                panic!("{} 次成功运行的整体平均误差 {:.2} 米超过了可接受的阈值 (150.0 米) or low success count after {} attempts. Total matched targets: {} / {}.",
-                        successful_runs, overall_avg_error, attempts, total_matched_targets, total_possible_targets);
-            }
-            println!("第 {} 次尝试失败，正在重试...", attempts);
+    // 漏检只减少每个目标的光线数：检出逐渐减少，误差缓慢增大，而不是整体失效
+    let clean = &reports[0];
+    assert_eq!(clean.total_located(), clean.total_true_targets());
+    for report in &reports {
+        assert!(report.total_located() as f64 >= 0.8 * report.total_true_targets() as f64);
+        assert!(report.total_located() <= clean.total_located());
+        assert!(report.mean_error() < 2.0 * clean.mean_error());
+        assert!(report.association_rate() > 0.95);
+    }
+}
+
+#[test]
+fn test_localization_error_from_angle_quantization() {
+    // 无噪声，方位角按 0.5°、俯仰角按 1° 量化输出；测量站距目标 1~1.5 千米
+    let (azimuth_step, elevation_step) = (0.5f64.to_radians(), 1f64.to_radians());
+    let generator = GeneratorConfig::builder()
+        .num_targets(1)
+        .num_stations_per_target_range(8, 8)
+        .station_dist_range(1000.0, 1500.0)
+        .pos_noise_std(0.0)
+        .alt_noise_std(0.0)
+        .angle_noise_std(0.0)
+        .angle_quantization(azimuth_step, elevation_step)
+        .build()
+        .unwrap();
+    let config = FindTargetsConfig::new(30.0, 3);
+
+    let (mut miss_sq, mut expected_sq, mut num_lines, mut errors) = (0.0, 0.0, 0, Vec::new());
+    for run in 0..20 {
+        let scenario = generate_scenario(&GeneratorConfig { seed: Some(7000 + run), ..generator.clone() });
+        let target = scenario.true_targets[0];
+        for (m, station) in scenario.measurements.iter().zip(&scenario.station_positions) {
+            // 量化误差在 ±步长/2 内均匀分布，方差为步长²/12；方位角误差按 cos(俯仰角) 折算为垂直于视线的角度
+            let line = Line::from_measurement(m);
+            let range = (target - station).norm();
+            let elevation = ((target.z - station.z) / range).asin();
+            let angular_var = ((azimuth_step * elevation.cos()).powi(2) + elevation_step.powi(2)) / 12.0;
+            miss_sq += line.distance_to_point(&target).powi(2);
+            expected_sq += range * range * angular_var;
+            num_lines += 1;
         }
+        let located = scenario.locate(&config);
+        assert_eq!(located.len(), 1);
+        errors.push((located[0].position - target).norm());
     }
+    let miss_rms = (miss_sq / num_lines as f64).sqrt();
+    let expected_rms = (expected_sq / num_lines as f64).sqrt();
+    let mean_error = errors.iter().sum::<f64>() / errors.len() as f64;
+    println!("单条光线偏离 RMS {:.2} 米（期望 {:.2} 米），定位平均误差 {:.2} 米", miss_rms, expected_rms, mean_error);
+
+    // 光线偏离与“量化角误差 × 距离”相符；8 条光线平均后定位误差约为其 1/√8，且明显不为零
+    assert!(miss_rms > 0.8 * expected_rms && miss_rms < 1.25 * expected_rms);
+    let expected_error = expected_rms / 8f64.sqrt();
+    assert!(mean_error > 0.5 * expected_error && mean_error < 2.0 * expected_error);
 }
 
 #[test]
-fn test_localization_with_overlapping_targets() {
-    let mut attempts = 0;
-    loop {
-        attempts += 1;
-        let (overall_avg_error, successful_runs, total_matched_targets) = run_test_case(
-            "重叠目标",
-            5,
-            3,
-            (-10.0, 10.0), // Smaller range to force overlap
-            (-10.0, 10.0), // Smaller range to force overlap
-            (10.0, 30.0),  // Smaller range to force overlap
-            (3, 5),
-            (50.0, 200.0),
-            (5.0, 15.0),
-            0.5,
-            0.5,
-            0.001,
-            5.0,
-        );
-        let total_possible_targets = 5 * 3;
-        let success_rate = total_matched_targets as f64 / total_possible_targets as f64;
-        
-        // Overlapping targets might lead to slightly higher errors and fewer successful runs.
-        if overall_avg_error < 100.0 && successful_runs >= 2 && success_rate >= 0.5 {
-            println!("第 {} 次尝试成功通过。", attempts);
-            println!("总匹配目标数: {} / {}", total_matched_targets, total_possible_targets);
-            break;
-        } else {
-            if attempts >= 3 {
-                panic!("{} 次成功运行的整体平均误差 {:.2} 米超过了可接受的阈值 (100.0 米) or low success count after {} attempts. Total matched targets: {} / {}.",
-                        successful_runs, overall_avg_error, attempts, total_matched_targets, total_possible_targets);
-            }
-            println!("第 {} 次尝试失败，正在重试...", attempts);
+fn test_noise_weighted_localization_beats_unweighted() {
+    // 每个目标 8 个测量站，各站测角噪声标准差在 0.5~20 毫弧度间均匀抽取
+    let generator = GeneratorConfig::builder()
+        .num_targets(3)
+        .num_stations_per_target_range(8, 8)
+        .pos_noise_std(0.0)
+        .alt_noise_std(0.0)
+        .angle_noise_std_range(0.0005, 0.02)
+        .build()
+        .unwrap();
+    let mut config = FindTargetsConfig::new(30.0, 3);
+    config.ransac.seed = Some(1);
+    // 权重是测角方差之比，LM 按角度残差拟合；RANSAC 门限仍为距离
+    config.lm.residual_model = ResidualModel::Angular;
+
+    let (mut unweighted, mut weighted) = (Vec::new(), Vec::new());
+    for run in 0..20 {
+        let scenario = generate_scenario(&GeneratorConfig { seed: Some(8100 + run), ..generator.clone() });
+        assert!(scenario.angle_noise_stds.iter().all(|s| s.is_some_and(|s| (0.0005..0.02).contains(&s))));
+        let located = scenario.locate(&config);
+        let located_weighted = find_targets_with_config(&scenario.noise_weighted_measurements(), &config);
+        for (located, errors) in [(located, &mut unweighted), (located_weighted, &mut weighted)] {
+            let result = match_targets(&scenario.true_targets, &located, 50.0);
+            errors.extend(result.matches.iter().map(|m| m.distance));
         }
     }
-}
\ No newline at end of file
+    let rms = |errors: &[f64]| (errors.iter().map(|e| e * e).sum::<f64>() / errors.len() as f64).sqrt();
+    println!(
+        "不加权：匹配 {} 个，误差 RMS {:.2} 米；按真实噪声加权：匹配 {} 个，误差 RMS {:.2} 米",
+        unweighted.len(),
+        rms(&unweighted),
+        weighted.len(),
+        rms(&weighted)
+    );
+    assert_eq!(unweighted.len(), 60);
+    assert_eq!(weighted.len(), 60);
+    assert!(rms(&weighted) < 0.85 * rms(&unweighted));
+}
@@ -0,0 +1,222 @@
+// tests/io.rs
+
+use nalgebra::Point3;
+use opti_radar::io::{
+    iter_measurements_csv, read_measurement_batches, read_measurements_csv, read_points_csv, write_measurements_csv,
+    write_points_csv, write_targets_geojson, write_targets_json,
+};
+use opti_radar::target_processor::{
+    find_targets, find_targets_with_config, try_find_targets_from_iter, FindTargetsConfig, LocatedTarget, Measurement,
+};
+use serde_json::Value;
+
+/// 两个目标、各四条精确光线
+fn located_targets() -> Vec<LocatedTarget> {
+    let mut measurements = Vec::new();
+    for target in [Point3::new(10.0, 20.0, 5.0), Point3::new(-50.0, -30.0, 8.0)] {
+        for (dx, dy) in [(-30.0, 0.0), (0.0, 30.0), (30.0, 0.0), (0.0, -30.0)] {
+            let start = Point3::new(target.x + dx, target.y + dy, 0.0);
+            let d = target - start;
+            measurements.push(Measurement::new(start.x, start.y, start.z, d.x, d.y, d.z));
+        }
+    }
+    let mut located = find_targets(&measurements, 1.0, 3);
+    located.sort_by(|a, b| b.position.x.total_cmp(&a.position.x));
+    located
+}
+
+#[test]
+fn test_write_targets_geojson_structure() {
+    let targets = located_targets();
+    let mut buffer = Vec::new();
+    write_targets_geojson(&mut buffer, &targets, None).unwrap();
+    let value: Value = serde_json::from_slice(&buffer).unwrap();
+
+    assert_eq!(value["type"], "FeatureCollection");
+    let features = value["features"].as_array().unwrap();
+    assert_eq!(features.len(), targets.len());
+    for (feature, target) in features.iter().zip(&targets) {
+        assert_eq!(feature["type"], "Feature");
+        assert_eq!(feature["geometry"]["type"], "Point");
+        let coordinates: Vec<f64> = feature["geometry"]["coordinates"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| c.as_f64().unwrap())
+            .collect();
+        assert_eq!(coordinates, vec![target.position.x, target.position.y, target.position.z]);
+        let properties = &feature["properties"];
+        assert_eq!(properties["id"], target.id.as_str());
+        assert_eq!(properties["num_lines"], target.num_lines);
+        assert_eq!(properties["avg_error_dist_m"].as_f64().unwrap(), target.avg_error_dist_m);
+    }
+}
+
+#[test]
+fn test_write_targets_geojson_lon_lat() {
+    let targets = located_targets();
+    // 简单的等距投影：原点 (116°E, 40°N)
+    let to_lon_lat = |p: &Point3<f64>| {
+        let lat0: f64 = 40.0;
+        [116.0 + p.x / (111_320.0 * lat0.to_radians().cos()), lat0 + p.y / 110_540.0]
+    };
+    let mut buffer = Vec::new();
+    write_targets_geojson(&mut buffer, &targets, Some(&to_lon_lat)).unwrap();
+    let value: Value = serde_json::from_slice(&buffer).unwrap();
+
+    for (feature, target) in value["features"].as_array().unwrap().iter().zip(&targets) {
+        let coordinates = feature["geometry"]["coordinates"].as_array().unwrap();
+        let [lon, lat] = to_lon_lat(&target.position);
+        assert_eq!(coordinates[0].as_f64().unwrap(), lon);
+        assert_eq!(coordinates[1].as_f64().unwrap(), lat);
+        assert_eq!(coordinates[2].as_f64().unwrap(), target.position.z);
+    }
+}
+
+#[test]
+fn test_write_targets_json() {
+    let targets = located_targets();
+    let mut buffer = Vec::new();
+    write_targets_json(&mut buffer, &targets).unwrap();
+    let value: Value = serde_json::from_slice(&buffer).unwrap();
+    let array = value.as_array().unwrap();
+    assert_eq!(array.len(), 2);
+    assert_eq!(array[0]["id"], targets[0].id.as_str());
+    assert_eq!(array[0]["num_lines"], 4);
+}
+
+#[test]
+fn test_measurements_csv_round_trip() {
+    let measurements = vec![
+        Measurement::new(1.0 / 3.0, -2.0, 1e-9, 0.1, 0.2, 0.3).with_station_id("A"),
+        Measurement::new(5.0, 6.0, 7.0, 0.0, 0.0, 1.0).with_weight(2.5),
+    ];
+    let mut buffer = Vec::new();
+    write_measurements_csv(&mut buffer, &measurements).unwrap();
+    let back = read_measurements_csv(buffer.as_slice()).unwrap();
+    assert_eq!(back.len(), 2);
+    assert_eq!(back[0].x, measurements[0].x);
+    assert_eq!(back[0].z, measurements[0].z);
+    assert_eq!(back[0].station_id.as_deref(), Some("A"));
+    assert_eq!(back[1].station_id, None);
+    assert_eq!(back[1].weight, 2.5);
+
+    let points = vec![Point3::new(1.5, -2.25, 1e6)];
+    let mut buffer = Vec::new();
+    write_points_csv(&mut buffer, &points).unwrap();
+    assert_eq!(read_points_csv(buffer.as_slice()).unwrap(), points);
+}
+
+#[test]
+fn test_measurements_csv_station_id_round_trip() {
+    // 含内部空格等字符的标识原样读回
+    let measurements = vec![Measurement::new(1.0, 2.0, 3.0, 0.0, 0.0, 1.0).with_station_id("site 7/北")];
+    let mut buffer = Vec::new();
+    write_measurements_csv(&mut buffer, &measurements).unwrap();
+    let back = read_measurements_csv(buffer.as_slice()).unwrap();
+    assert_eq!(back[0].station_id, measurements[0].station_id);
+
+    // 读回后会改变的标识：拒绝写出，且不输出任何内容
+    for id in ["A,B", " A", "A\t", "A\nB", ""] {
+        let measurements = vec![
+            Measurement::new(1.0, 2.0, 3.0, 0.0, 0.0, 1.0).with_station_id("A"),
+            Measurement::new(1.0, 2.0, 3.0, 0.0, 0.0, 1.0).with_station_id(id),
+        ];
+        let mut buffer = Vec::new();
+        let error = write_measurements_csv(&mut buffer, &measurements).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput, "{:?}", id);
+        assert!(buffer.is_empty());
+    }
+}
+
+#[test]
+fn test_read_measurements_csv_errors() {
+    // 缺少表头
+    assert!(read_measurements_csv("1,2,3,0,0,1,,1\n".as_bytes()).is_err());
+    // 字段数不符
+    let csv = "x,y,z,direction_x,direction_y,direction_z,station_id,weight\n1,2,3\n";
+    assert!(read_measurements_csv(csv.as_bytes()).is_err());
+    // 非数值字段
+    let csv = "x,y,z,direction_x,direction_y,direction_z,station_id,weight\n1,2,abc,0,0,1,,\n";
+    let error = read_measurements_csv(csv.as_bytes()).unwrap_err();
+    assert!(error.to_string().contains("第 2 行"));
+}
+
+#[test]
+fn test_iter_measurements_csv_into_locator() {
+    // 一个目标的四条光线，中间夹一行无法解析的数据和一个空行
+    let header = "x,y,z,direction_x,direction_y,direction_z,station_id,weight\n";
+    let csv = format!(
+        "{}0,0,0,1,1,1,A,\n100,0,0,-1,1,1,B,\n1,2,abc,0,0,1,,\n\n0,100,0,1,-1,1,C,\n100,100,0,-1,-1,1,D,2\n",
+        header
+    );
+    let items: Vec<_> = iter_measurements_csv(csv.as_bytes()).unwrap().collect();
+    assert_eq!(items.len(), 5);
+    assert!(items[2].as_ref().unwrap_err().to_string().contains("第 4 行"));
+
+    let config = FindTargetsConfig::new(1.0, 3);
+    let (located, diagnostics, errors) = try_find_targets_from_iter(iter_measurements_csv(csv.as_bytes()).unwrap(), &config);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].0, 2);
+    assert!(diagnostics.skipped.is_empty());
+    assert_eq!(located.len(), 1);
+    assert_eq!(located[0].inlier_indices, vec![0, 1, 3, 4]);
+    assert!((located[0].position - Point3::new(50.0, 50.0, 50.0)).norm() < 1e-6);
+
+    // 去掉无法解析的行后与 read_measurements_csv + 切片输入的结果相同
+    let valid = csv.replace("1,2,abc,0,0,1,,\n", "");
+    let from_slice = find_targets_with_config(&read_measurements_csv(valid.as_bytes()).unwrap(), &config);
+    assert_eq!(from_slice[0].position, located[0].position);
+    assert_eq!(from_slice[0].stations, located[0].stations);
+
+    // 表头无效时直接返回错误
+    assert!(iter_measurements_csv("1,2,3\n".as_bytes()).is_err());
+}
+
+#[test]
+fn test_measurements_csv_timestamp_column() {
+    let measurements = vec![
+        Measurement::new(1.0, 2.0, 3.0, 0.0, 0.0, 1.0).with_timestamp(0.25),
+        Measurement::new(4.0, 5.0, 6.0, 0.0, 1.0, 0.0),
+    ];
+    let mut buffer = Vec::new();
+    write_measurements_csv(&mut buffer, &measurements).unwrap();
+    let text = String::from_utf8(buffer).unwrap();
+    assert!(text.starts_with("x,y,z,direction_x,direction_y,direction_z,station_id,weight,timestamp\n"));
+    let back = read_measurements_csv(text.as_bytes()).unwrap();
+    assert_eq!(back[0].timestamp, Some(0.25));
+    assert_eq!(back[1].timestamp, None);
+}
+
+#[test]
+fn test_read_measurement_batches() {
+    let header = "x,y,z,direction_x,direction_y,direction_z,station_id,weight,timestamp\n";
+    // 空行分批；解析失败的行报告行号后跳过；末尾没有空行的一批在结束时输出
+    let csv = format!("{}0,0,0,0,0,1,,,\n0,0,0,0,1,0,,,\n\n\n1,2,abc,0,0,1,,,\n0,0,0,1,0,0,,,\n", header);
+    let mut batches = Vec::new();
+    let mut errors = Vec::new();
+    let on_batch = |b: Vec<Measurement>| {
+        batches.push(b.len());
+        Ok(())
+    };
+    read_measurement_batches(csv.as_bytes(), None, on_batch, |e| errors.push(e.to_string())).unwrap();
+    assert_eq!(batches, vec![2, 1]);
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].contains("第 6 行"), "{}", errors[0]);
+
+    // 时间窗：从第一个时间戳起每 1 秒一批，没有时间戳或乱序的测量归入当前批
+    let csv = format!(
+        "{}0,0,0,0,0,1,,,10.0\n0,0,0,0,0,1,,,10.9\n0,0,0,0,0,1,,,\n0,0,0,0,0,1,,,11.2\n0,0,0,0,0,1,,,10.5\n0,0,0,0,0,1,,,13.0\n",
+        header
+    );
+    let mut batches = Vec::new();
+    let on_batch = |b: Vec<Measurement>| {
+        batches.push(b.len());
+        Ok(())
+    };
+    read_measurement_batches(csv.as_bytes(), Some(1.0), on_batch, |_| panic!()).unwrap();
+    assert_eq!(batches, vec![3, 2, 1]);
+
+    // 表头无效时直接返回错误
+    assert!(read_measurement_batches("1,2,3\n".as_bytes(), None, |_| Ok(()), |_| {}).is_err());
+}
@@ -0,0 +1,80 @@
+// tests/prelude.rs
+
+use opti_radar::prelude::*;
+
+#[test]
+fn test_locate_through_prelude() {
+    // 四个测量站精确指向同一目标
+    let target = Point3::new(120.0, -40.0, 300.0);
+    let stations = [(0.0, 0.0, 0.0), (500.0, 0.0, 10.0), (0.0, 500.0, 5.0), (-300.0, -200.0, 0.0)];
+    let data: Vec<Measurement> = stations
+        .iter()
+        .map(|&(x, y, z)| {
+            let direction: Vector3<f64> = target - Point3::new(x, y, z);
+            Measurement::new(x, y, z, direction.x, direction.y, direction.z)
+        })
+        .collect();
+
+    let located = find_targets(&data, 1.0, 3);
+    assert_eq!(located.len(), 1);
+    let located_target: &LocatedTarget = &located[0];
+    assert!((located_target.x() - target.x).abs() < 1e-6);
+    assert!((located_target.y() - target.y).abs() < 1e-6);
+    assert!((located_target.z() - target.z).abs() < 1e-6);
+    assert_eq!(located_target.as_array(), [located_target.x(), located_target.y(), located_target.z()]);
+    assert_eq!(Point3::from(located_target.position_array()), located_target.position);
+
+    let line = Line::new(Point3::origin(), Vector3::new(0.0, 0.0, 2.0));
+    assert_eq!(line.direction, Vector3::z());
+    let mut config = FindTargetsConfig::new(1.0, 3);
+    config.ransac = RansacConfig { seed: Some(1), ..config.ransac };
+    let (same, diagnostics): (_, FindTargetsDiagnostics) = find_targets_with_diagnostics(&data, &config);
+    assert_eq!(same[0].as_array(), located_target.as_array());
+    assert!(diagnostics.skipped.is_empty());
+}
+
+#[test]
+fn test_configure_through_prelude() {
+    // 只通过 prelude 导入的类型组装一份各项均非默认的参数
+    let target = Point3::new(120.0, -40.0, 300.0);
+    let stations = [(0.0, 0.0, 0.0), (500.0, 0.0, 10.0), (0.0, 500.0, 5.0), (-300.0, -200.0, 0.0)];
+    let data: Vec<Measurement> = stations
+        .iter()
+        .map(|&(x, y, z)| {
+            let direction: Vector3<f64> = target - Point3::new(x, y, z);
+            Measurement::new(x, y, z, direction.x, direction.y, direction.z)
+        })
+        .collect();
+
+    let bounds = BoundingBox::new(Point3::new(-1000.0, -1000.0, 0.0), Point3::new(1000.0, 1000.0, 1000.0)).unwrap();
+    let mut config = FindTargetsConfig::new(1.0, 3);
+    config.estimator = RobustEstimator::Ransac;
+    config.ransac = RansacConfig {
+        seed: Some(585),
+        residual_model: ResidualModel::Metric,
+        scoring: RansacScoring::Msac,
+        bounds: Some(bounds),
+        ..config.ransac
+    };
+    config.lm = LmConfig {
+        robust_loss: RobustLoss::Huber(0.5),
+        ..config.lm
+    };
+    config.optimizer = Optimizer::Dogleg;
+    config.motion_model = MotionModel::Static;
+    config.output_ordering = OutputOrdering::ByConfidenceDesc;
+    config.out_of_bounds = OutOfBounds::Clamp;
+    config.min_geometry = Some(GeometryCriterion::MinCrossingAngle(0.1));
+    config.bootstrap = Some(BootstrapConfig {
+        num_resamples: 20,
+        seed: Some(585),
+        ..BootstrapConfig::default()
+    });
+    config.em = Some(EmConfig::default());
+    config.dbscan = DbscanConfig { min_pts: 3, ..config.dbscan };
+
+    let located = find_targets_with_config(&data, &config);
+    assert_eq!(located.len(), 1);
+    assert!((located[0].position - target).norm() < 1e-3);
+    assert!(located[0].bootstrap.is_some());
+}
@@ -0,0 +1,67 @@
+// tests/serde.rs
+
+use nalgebra::{Point3, Vector3};
+use opti_radar::target_processor::{find_targets, Line, LocatedTarget, Measurement};
+
+fn load_fixture() -> Vec<Measurement> {
+    let json = std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/measurements.json"))
+        .expect("无法读取测试数据");
+    serde_json::from_str(&json).expect("无法解析测试数据")
+}
+
+#[test]
+fn test_deserialize_fixture_and_find_targets() {
+    let measurements = load_fixture();
+    assert_eq!(measurements.len(), 8);
+    assert_eq!(measurements[0].station_id.as_deref(), Some("north"));
+    assert_eq!(measurements[1].weight, 2.0);
+    // 缺省字段
+    assert_eq!(measurements[2].station_id, None);
+    assert_eq!(measurements[2].weight, 1.0);
+
+    let mut located = find_targets(&measurements, 1.0, 3);
+    assert_eq!(located.len(), 2);
+    located.sort_by(|a, b| b.position.x.total_cmp(&a.position.x));
+    assert!((located[0].position - Point3::new(10.0, 20.0, 5.0)).norm() < 1e-6);
+    assert_eq!(located[0].inlier_indices, vec![0, 1, 2, 3]);
+    assert!((located[1].position - Point3::new(-50.0, -30.0, 8.0)).norm() < 1e-6);
+    assert_eq!(located[1].inlier_indices, vec![4, 5, 6, 7]);
+}
+
+#[test]
+fn test_round_trip_preserves_values() {
+    let measurement = Measurement::from_az_el_deg(1.0 / 3.0, -2.5e-7, 1e12, 123.456, 7.89)
+        .with_station_id("站点 A")
+        .with_weight(0.1);
+    let json = serde_json::to_string(&measurement).unwrap();
+    let back: Measurement = serde_json::from_str(&json).unwrap();
+    assert_eq!(back.x.to_bits(), measurement.x.to_bits());
+    assert_eq!(back.y.to_bits(), measurement.y.to_bits());
+    assert_eq!(back.z.to_bits(), measurement.z.to_bits());
+    assert_eq!(back.direction_x.to_bits(), measurement.direction_x.to_bits());
+    assert_eq!(back.direction_y.to_bits(), measurement.direction_y.to_bits());
+    assert_eq!(back.direction_z.to_bits(), measurement.direction_z.to_bits());
+    assert_eq!(back.station_id, measurement.station_id);
+    assert_eq!(back.weight, measurement.weight);
+
+    let line = Line::new(Point3::new(0.1, 0.2, 0.3), Vector3::new(1.0, 2.0, 3.0));
+    let json = serde_json::to_string(&line).unwrap();
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(value["start"], serde_json::json!([0.1, 0.2, 0.3]));
+    let back: Line = serde_json::from_str(&json).unwrap();
+    assert_eq!(back.start, line.start);
+    assert_eq!(back.direction, line.direction);
+
+    let located = find_targets(&load_fixture(), 1.0, 3);
+    let json = serde_json::to_string(&located).unwrap();
+    let back: Vec<LocatedTarget> = serde_json::from_str(&json).unwrap();
+    for (a, b) in located.iter().zip(&back) {
+        assert_eq!(a.id, b.id);
+        assert_eq!(a.position, b.position);
+        assert_eq!(a.covariance, b.covariance);
+        assert_eq!(a.inlier_indices, b.inlier_indices);
+        assert_eq!(a.residuals, b.residuals);
+        assert_eq!(a.avg_error_dist_m, b.avg_error_dist_m);
+        assert_eq!(a.final_cost, b.final_cost);
+    }
+}